@@ -0,0 +1,55 @@
+// A disk image bundled into the kernel, e.g. with
+// `include_bytes!("disk.swim")`. Each entry is laid out as
+//
+//   name length (1 byte) | name | contents length (4 bytes, little-endian) | contents
+//
+// and entries follow each other with nothing in between.
+pub struct Archive<'a> {
+    bytes: &'a [u8],
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ArchiveError {
+    Truncated,
+    BadName,
+}
+
+impl<'a> Archive<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ArchiveError> {
+        if self.bytes.len() < len {
+            return Err(ArchiveError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn next_entry(&mut self) -> Result<(&'a str, &'a [u8]), ArchiveError> {
+        let name_len = self.take(1)?[0] as usize;
+        let name = core::str::from_utf8(self.take(name_len)?).map_err(|_| ArchiveError::BadName)?;
+        let mut len = [0; 4];
+        len.copy_from_slice(self.take(4)?);
+        let contents = self.take(u32::from_le_bytes(len) as usize)?;
+        Ok((name, contents))
+    }
+}
+
+impl<'a> Iterator for Archive<'a> {
+    type Item = Result<(&'a str, &'a [u8]), ArchiveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bytes.is_empty() {
+            None
+        } else {
+            let entry = self.next_entry();
+            if entry.is_err() {
+                self.bytes = &[];
+            }
+            Some(entry)
+        }
+    }
+}
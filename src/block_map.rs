@@ -0,0 +1,173 @@
+use core::fmt::Write;
+
+use crate::screen::{plot, plot_str, Color, ColorCode};
+
+use simple_interp::ArrayString;
+
+use crate::storage::name_str;
+use crate::{BLOCK_SIZE, LINE_WIDTH, MAX_FILENAME_BYTES, MAX_FILES_STORED, NUM_BLOCKS};
+
+const MAP_WIDTH: usize = LINE_WIDTH + 1;
+const MAX_ROWS: usize = 8;
+const BLOCKS_PER_CELL: usize = (NUM_BLOCKS + MAP_WIDTH * MAX_ROWS - 1) / (MAP_WIDTH * MAX_ROWS);
+const NUM_CELLS: usize = (NUM_BLOCKS + BLOCKS_PER_CELL - 1) / BLOCKS_PER_CELL;
+const NUM_ROWS: usize = (NUM_CELLS + MAP_WIDTH - 1) / MAP_WIDTH;
+const FILE_COLORS: [Color; 10] = [
+    Color::Blue,
+    Color::Green,
+    Color::Cyan,
+    Color::Red,
+    Color::Magenta,
+    Color::Brown,
+    Color::LightBlue,
+    Color::LightGreen,
+    Color::LightRed,
+    Color::Pink,
+];
+// A guess at what the directory takes, from its size; FileSystem doesn't
+// say.
+const DIRECTORY_BLOCKS: usize =
+    (MAX_FILES_STORED * (MAX_FILENAME_BYTES + 1) + BLOCK_SIZE - 1) / BLOCK_SIZE;
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Block {
+    Free,
+    Directory,
+    File([u8; MAX_FILENAME_BYTES]),
+}
+
+// An estimate of where the blocks are. FileSystem does not expose where it
+// puts a file's blocks, so Storage keeps this map in step with every create
+// and write it passes through, allocating first-fit as the FileSystem's
+// free map is expected to. Nothing checks it against the disk, so once the
+// real allocator places blocks differently the map is wrong; the counts of
+// blocks per file and free blocks still follow the file sizes.
+#[derive(Copy, Clone)]
+pub struct BlockMap {
+    blocks: [Block; NUM_BLOCKS],
+}
+
+impl Default for BlockMap {
+    fn default() -> Self {
+        let mut blocks = [Block::Free; NUM_BLOCKS];
+        for block in blocks[..DIRECTORY_BLOCKS].iter_mut() {
+            *block = Block::Directory;
+        }
+        Self { blocks }
+    }
+}
+
+impl BlockMap {
+    pub fn num_free(&self) -> usize {
+        self.blocks.iter().filter(|b| **b == Block::Free).count()
+    }
+
+    pub fn num_blocks(&self, name: &[u8; MAX_FILENAME_BYTES]) -> usize {
+        self.blocks
+            .iter()
+            .filter(|b| **b == Block::File(*name))
+            .count()
+    }
+
+    pub fn allocate(&mut self, name: &[u8; MAX_FILENAME_BYTES]) {
+        if let Some(block) = self.blocks.iter_mut().find(|b| **b == Block::Free) {
+            *block = Block::File(*name);
+        }
+    }
+
+    pub fn release(&mut self, name: &[u8; MAX_FILENAME_BYTES]) {
+        for block in self.blocks.iter_mut() {
+            if *block == Block::File(*name) {
+                *block = Block::Free;
+            }
+        }
+    }
+
+    #[cfg(feature = "shell")]
+    pub fn first_block(&self, name: &[u8; MAX_FILENAME_BYTES]) -> Option<usize> {
+        self.blocks.iter().position(|b| *b == Block::File(*name))
+    }
+
+    pub fn free_runs(&self) -> usize {
+        (0..NUM_BLOCKS)
+            .filter(|&i| {
+                self.blocks[i] == Block::Free && (i == 0 || self.blocks[i - 1] != Block::Free)
+            })
+            .count()
+    }
+
+    // Draws one cell per block, or per group of blocks when the disk is too
+    // big for the window, coloring each file by its name so it keeps the same
+    // color across frames, with `highlight`'s blocks picked out in yellow.
+    // The two rows under the grid describe the highlighted file and the free
+    // space.
+    pub fn draw(
+        &self,
+        x: usize,
+        y: usize,
+        highlight: Option<&[u8; MAX_FILENAME_BYTES]>,
+        text: ColorCode,
+    ) {
+        let highlighted = highlight.map(|name| Block::File(*name));
+        for (i, cell) in self.blocks.chunks(BLOCKS_PER_CELL).enumerate() {
+            let block = match highlighted {
+                Some(block) if cell.contains(&block) => block,
+                _ => cell
+                    .iter()
+                    .copied()
+                    .find(|b| *b != Block::Free)
+                    .unwrap_or(Block::Free),
+            };
+            let (c, color) = match block {
+                Block::Free => ('.', ColorCode::new(Color::DarkGray, Color::Black)),
+                Block::Directory => ('D', ColorCode::new(Color::Black, Color::LightGray)),
+                Block::File(name) if Some(&name) == highlight => {
+                    ('#', ColorCode::new(Color::Black, Color::Yellow))
+                }
+                Block::File(name) => (' ', ColorCode::new(Color::Black, file_color(&name))),
+            };
+            plot(c, x + i % MAP_WIDTH, y + i / MAP_WIDTH, color);
+        }
+        for i in NUM_CELLS..MAP_WIDTH * NUM_ROWS {
+            plot(
+                ' ',
+                x + i % MAP_WIDTH,
+                y + i / MAP_WIDTH,
+                ColorCode::new(Color::Black, Color::Black),
+            );
+        }
+        let mut line: ArrayString<MAP_WIDTH> = ArrayString::default();
+        if let Some(name) = highlight {
+            write!(
+                line,
+                "{}: {} blk",
+                name_str(name).unwrap_or("?"),
+                self.num_blocks(name)
+            )
+            .unwrap();
+        }
+        draw_line(line.as_str().unwrap(), x, y + NUM_ROWS, text);
+        let mut line: ArrayString<MAP_WIDTH> = ArrayString::default();
+        write!(
+            line,
+            "free {}/{}, {} runs",
+            self.num_free(),
+            NUM_BLOCKS,
+            self.free_runs()
+        )
+        .unwrap();
+        draw_line(line.as_str().unwrap(), x, y + NUM_ROWS + 1, text);
+    }
+}
+
+fn file_color(name: &[u8; MAX_FILENAME_BYTES]) -> Color {
+    let hash = name.iter().fold(0usize, |hash, b| hash * 31 + *b as usize);
+    FILE_COLORS[hash % FILE_COLORS.len()]
+}
+
+fn draw_line(text: &str, x: usize, y: usize, color: ColorCode) {
+    plot_str(text, x, y, color);
+    for i in text.len()..MAP_WIDTH {
+        plot(' ', x + i, y, ColorCode::new(Color::Black, Color::Black));
+    }
+}
@@ -0,0 +1,75 @@
+use core::fmt::Write;
+use core::mem::size_of;
+
+use crate::buffer::TextEditor;
+use crate::format::Compact;
+use crate::mount::NUM_VOLUMES;
+use crate::open_files::OpenFiles;
+use crate::output::Output;
+use crate::overlay::Layers;
+use crate::process::{Process, Program, MAX_PROCESSES};
+#[cfg(feature = "serial")]
+use crate::serial;
+#[cfg(feature = "shell")]
+use crate::shell::Shell;
+use crate::storage::Storage;
+use crate::{SwimInterface, Window, DOCUMENT_LENGTH, LINE_WIDTH};
+
+// What the big structures cost in RAM, worked out by the compiler from the
+// const-generic knobs. Each row is a name, how many a SwimInterface with N
+// windows holds and the size of one; indented rows are part of the row
+// above.
+struct Budget<const N: usize>;
+
+impl<const N: usize> Budget<N> {
+    const ROWS: &'static [(&'static str, usize, usize)] = &[
+        ("interface", 1, size_of::<SwimInterface<N>>()),
+        ("window", N, size_of::<Window>()),
+        (
+            " editor",
+            1,
+            size_of::<TextEditor<LINE_WIDTH, DOCUMENT_LENGTH>>(),
+        ),
+        #[cfg(feature = "shell")]
+        (" shell", 1, size_of::<Shell>()),
+        ("process", MAX_PROCESSES, size_of::<Option<Process>>()),
+        (" program", 1, size_of::<Program>()),
+        (" output", 1, size_of::<Output>()),
+        ("volume", NUM_VOLUMES, size_of::<Storage>()),
+        ("open files", 1, size_of::<OpenFiles<N>>()),
+        ("overlays", 1, size_of::<Layers>()),
+    ];
+}
+
+// A line per structure for the shell's `mem`: count x size = total.
+pub fn write_report<const N: usize, W: Write>(out: &mut W) -> core::fmt::Result {
+    for &(name, count, size) in Budget::<N>::ROWS {
+        writeln!(
+            out,
+            "{:<10}{:>2} x {:>5} = {}",
+            name,
+            count,
+            Compact(size as u64),
+            Compact((count * size) as u64)
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "serial")]
+struct Serial;
+
+#[cfg(feature = "serial")]
+impl Write for Serial {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        s.bytes().for_each(serial::send);
+        Ok(())
+    }
+}
+
+// Sends the report to the serial port, for a boot log.
+#[cfg(feature = "serial")]
+pub fn send_report<const N: usize>() {
+    writeln!(Serial, "memory budget").unwrap();
+    write_report::<N, _>(&mut Serial).unwrap();
+}
@@ -1,6 +1,38 @@
-use pluggable_interrupt_os::vga_buffer::{is_drawable, plot, Color, ColorCode};
+use crate::screen::{is_drawable, is_extended, plot, Color, ColorCode};
 use simple_interp::ArrayString;
 
+// Room for every row of a document and its newline.
+pub type Contents = ArrayString<1280>;
+
+// How many jumps to a definition Ctrl+T can go back through.
+const MAX_JUMPS: usize = 8;
+
+// Why a rename was refused, leaving the document as it was.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RenameError {
+    NotAName,
+    // The new name would push this line, counting from 1, past the width.
+    LineTooLong(usize),
+}
+
+impl core::fmt::Display for RenameError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            RenameError::NotAName => write!(f, "not a variable name"),
+            RenameError::LineTooLong(line) => write!(f, "line {} would be too long", line),
+        }
+    }
+}
+
+// The last rename, for undo_rename: the names, and where the new one was
+// written. Any other edit drops it.
+#[derive(Copy, Clone, Eq, PartialEq)]
+struct Rename<const LINE_WIDTH: usize, const DOCUMENT_LENGTH: usize> {
+    from: ArrayString<LINE_WIDTH>,
+    to: ArrayString<LINE_WIDTH>,
+    starts: [[bool; LINE_WIDTH]; DOCUMENT_LENGTH],
+}
+
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub struct TextEditor<const LINE_WIDTH: usize, const DOCUMENT_LENGTH: usize> {
     document: [[char; LINE_WIDTH]; DOCUMENT_LENGTH],
@@ -11,6 +43,11 @@ pub struct TextEditor<const LINE_WIDTH: usize, const DOCUMENT_LENGTH: usize> {
     focus_y: usize,
     window_size_x: usize,
     window_size_y: usize,
+    select_anchor: Option<(usize, usize)>,
+    // Where each jump to a definition left from, newest last.
+    jumps: [(usize, usize); MAX_JUMPS],
+    num_jumps: usize,
+    renamed: Option<Rename<LINE_WIDTH, DOCUMENT_LENGTH>>,
     pub focused: bool,
 }
 
@@ -27,6 +64,10 @@ impl<const LINE_WIDTH: usize, const DOCUMENT_LENGTH: usize> Default
             target_col: 0,
             focus_x: 0,
             focus_y: 0,
+            select_anchor: None,
+            jumps: [(0, 0); MAX_JUMPS],
+            num_jumps: 0,
+            renamed: None,
             focused: true,
         }
     }
@@ -35,8 +76,9 @@ impl<const LINE_WIDTH: usize, const DOCUMENT_LENGTH: usize> Default
 impl<const LINE_WIDTH: usize, const DOCUMENT_LENGTH: usize>
     TextEditor<LINE_WIDTH, DOCUMENT_LENGTH>
 {
-    pub fn new(file_contents: &str, focused: bool) -> Self {
-        let file_bytes = file_contents.as_bytes();
+    // Each byte of the file is one character, so code page 437 characters
+    // above 127 load as themselves.
+    pub fn new(file_bytes: &[u8], focused: bool) -> Self {
         let mut document = [[0u8 as char; LINE_WIDTH]; DOCUMENT_LENGTH];
         let mut i = 0;
         let mut row = 0;
@@ -46,9 +88,13 @@ impl<const LINE_WIDTH: usize, const DOCUMENT_LENGTH: usize>
                 i += 1;
                 row += 1;
                 col = 0;
+                continue;
             } else if col >= LINE_WIDTH {
                 row += 1;
                 col = 0;
+                if row == DOCUMENT_LENGTH {
+                    break;
+                }
             }
             document[row][col] = file_bytes[i] as char;
             i += 1;
@@ -63,11 +109,15 @@ impl<const LINE_WIDTH: usize, const DOCUMENT_LENGTH: usize>
             target_col: 0,
             focus_x: 0,
             focus_y: 0,
+            select_anchor: None,
+            jumps: [(0, 0); MAX_JUMPS],
+            num_jumps: 0,
+            renamed: None,
             focused,
         }
     }
 
-    pub fn get_file_contents(&self) -> ArrayString<1240> {
+    pub fn get_file_contents(&self) -> Contents {
         let mut ret = ArrayString::default();
         let mut row = 0;
         let mut col = 0;
@@ -75,6 +125,13 @@ impl<const LINE_WIDTH: usize, const DOCUMENT_LENGTH: usize>
             if col >= LINE_WIDTH {
                 col = 0;
                 row += 1;
+                // A full row runs on into the next, as a long line wraps,
+                // unless the next is blank; then it needs its own newline
+                // or the blank row would load as the end of this one.
+                if row < DOCUMENT_LENGTH && self.document[row][0] == 0 as char {
+                    ret.push_char('\n');
+                }
+                continue;
             }
             if self.document[row][col] == 0 as char {
                 ret.push_char('\n');
@@ -88,7 +145,40 @@ impl<const LINE_WIDTH: usize, const DOCUMENT_LENGTH: usize>
         ret
     }
 
+    // Whether the cursor is on a cell of the document that the window can
+    // scroll to, for the soak test's invariants.
+    #[cfg(feature = "soak")]
+    pub fn cursor_in_bounds(&self) -> bool {
+        self.cursor_col < self.window_size_x.min(LINE_WIDTH)
+            && self.cursor_row < (self.window_size_y * 4).min(DOCUMENT_LENGTH)
+    }
+
+    pub fn set_selecting(&mut self, selecting: bool) {
+        if !selecting {
+            self.select_anchor = None;
+        } else if self.select_anchor.is_none() {
+            self.select_anchor = Some((self.cursor_row, self.cursor_col));
+        }
+    }
+
+    fn is_selected(&self, row: usize, col: usize) -> bool {
+        match self.select_anchor {
+            Some(anchor) => {
+                let cursor = (self.cursor_row, self.cursor_col);
+                let (start, end) = if anchor < cursor {
+                    (anchor, cursor)
+                } else {
+                    (cursor, anchor)
+                };
+                (row, col) >= start && (row, col) < end
+            }
+            None => false,
+        }
+    }
+
     pub fn push_char(&mut self, c: char) {
+        self.select_anchor = None;
+        self.renamed = None;
         self.document[self.cursor_row][self.cursor_col] = c;
         if self.cursor_col < self.window_size_x - 1 {
             self.cursor_col += 1;
@@ -100,6 +190,8 @@ impl<const LINE_WIDTH: usize, const DOCUMENT_LENGTH: usize>
     }
 
     pub fn backspace_char(&mut self) {
+        self.select_anchor = None;
+        self.renamed = None;
         if self.cursor_col != 0 || self.cursor_row != 0 {
             if self.cursor_col > 0 {
                 self.cursor_col -= 1;
@@ -121,6 +213,8 @@ impl<const LINE_WIDTH: usize, const DOCUMENT_LENGTH: usize>
     }
 
     pub fn delete_char(&mut self) {
+        self.select_anchor = None;
+        self.renamed = None;
         if self.document[self.cursor_row][0] == 0u8 as char {
             self.delete_line();
         } else {
@@ -144,6 +238,8 @@ impl<const LINE_WIDTH: usize, const DOCUMENT_LENGTH: usize>
     }
 
     pub fn newline(&mut self) {
+        self.select_anchor = None;
+        self.renamed = None;
         if self.cursor_row + 1 != self.window_size_y * 4 {
             self.cursor_row += 1;
             self.cursor_col = 0;
@@ -225,48 +321,301 @@ impl<const LINE_WIDTH: usize, const DOCUMENT_LENGTH: usize>
         self.target_col = self.cursor_col;
     }
 
-    pub fn draw_window(&mut self, window_x: usize, window_y: usize) {
+    // The cursor's row and column, and the top row in view.
+    pub fn place(&self) -> (usize, usize, usize) {
+        (self.cursor_row, self.cursor_col, self.focus_y)
+    }
+
+    // Goes back to a place, kept within the document and the row's text.
+    pub fn restore_place(&mut self, row: usize, col: usize, top: usize) {
+        let row = row.min(DOCUMENT_LENGTH - 1);
+        let len = self.document[row]
+            .iter()
+            .position(|c| *c == 0u8 as char)
+            .unwrap_or(LINE_WIDTH - 1);
+        self.move_to(row, col.min(len));
+        self.focus_y = top.min(row);
+    }
+
+    // Moves to the first `name := ...` for the variable under the cursor,
+    // remembering where it was for jump_back. Returns false, without
+    // moving, when there is no variable there or it is never assigned.
+    pub fn jump_to_definition(&mut self) -> bool {
+        let Some((start, end)) = self.word_at(self.cursor_row, self.cursor_col) else {
+            return false;
+        };
+        let word = &self.document[self.cursor_row][start..end];
+        let Some(row) = (0..DOCUMENT_LENGTH).find(|row| self.assigns(*row, word)) else {
+            return false;
+        };
+        if self.num_jumps == MAX_JUMPS {
+            self.jumps.copy_within(1.., 0);
+            self.num_jumps -= 1;
+        }
+        self.jumps[self.num_jumps] = (self.cursor_row, self.cursor_col);
+        self.num_jumps += 1;
+        let indent = self.document[row].iter().take_while(|c| **c == ' ').count();
+        self.move_to(row, indent);
+        true
+    }
+
+    // Goes back to where the last jump to a definition left from.
+    pub fn jump_back(&mut self) -> bool {
+        if self.num_jumps == 0 {
+            return false;
+        }
+        self.num_jumps -= 1;
+        let (row, col) = self.jumps[self.num_jumps];
+        self.move_to(row, col);
+        true
+    }
+
+    fn move_to(&mut self, row: usize, col: usize) {
+        self.select_anchor = None;
+        self.cursor_row = row;
+        self.cursor_col = col;
+        self.target_col = col;
+    }
+
+    // The columns of the identifier at or just before `col`.
+    fn word_at(&self, row: usize, col: usize) -> Option<(usize, usize)> {
+        let line = &self.document[row];
+        let col = if line.get(col).is_some_and(|c| is_word(*c)) {
+            col
+        } else {
+            col.checked_sub(1).filter(|col| is_word(line[*col]))?
+        };
+        let start = (0..=col).rev().take_while(|i| is_word(line[*i])).last()?;
+        let end = (col..LINE_WIDTH)
+            .find(|i| !is_word(line[*i]))
+            .unwrap_or(LINE_WIDTH);
+        (!line[start].is_ascii_digit()).then_some((start, end))
+    }
+
+    // The columns on `row` where `word` starts as a whole identifier.
+    fn occurrences<'a>(&'a self, row: usize, word: &'a [char]) -> impl Iterator<Item = usize> + 'a {
+        let line = &self.document[row];
+        (0..LINE_WIDTH).filter(move |col| is_use(line, *col, word))
+    }
+
+    // The variable under the cursor, for renaming.
+    pub fn word_under_cursor(&self) -> Option<ArrayString<LINE_WIDTH>> {
+        let (start, end) = self.word_at(self.cursor_row, self.cursor_col)?;
+        let mut word = ArrayString::default();
+        self.document[self.cursor_row][start..end]
+            .iter()
+            .for_each(|c| word.push_char(*c));
+        Some(word)
+    }
+
+    // How many times `word` is used in the whole document.
+    pub fn count_uses(&self, word: &str) -> usize {
+        let (word, len) = chars_of::<LINE_WIDTH>(word);
+        (0..DOCUMENT_LENGTH)
+            .map(|row| self.occurrences(row, &word[..len]).count())
+            .sum()
+    }
+
+    // Renames every use of `from` to `to` and returns how many there were,
+    // keeping the cursor on the same use. Nothing changes if `to` isn't a
+    // name or a line would no longer fit.
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<usize, RenameError> {
+        let starts_ok = to.chars().next().is_some_and(|c| !c.is_ascii_digit());
+        if !starts_ok || to.len() > LINE_WIDTH || !to.chars().all(is_word) {
+            return Err(RenameError::NotAName);
+        }
+        let (from_chars, from_len) = chars_of::<LINE_WIDTH>(from);
+        let (to_chars, to_len) = chars_of::<LINE_WIDTH>(to);
+        let starts = self.replace(&from_chars[..from_len], &to_chars[..to_len], |_, _| true)?;
+        let mut renamed = Rename {
+            from: ArrayString::default(),
+            to: ArrayString::default(),
+            starts,
+        };
+        from.chars().for_each(|c| renamed.from.push_char(c));
+        to.chars().for_each(|c| renamed.to.push_char(c));
+        self.renamed = Some(renamed);
+        Ok(starts.iter().flatten().filter(|s| **s).count())
+    }
+
+    // Puts back the names the last rename changed, if nothing has been
+    // typed since.
+    pub fn undo_rename(&mut self) -> bool {
+        let Some(renamed) = self.renamed.take() else {
+            return false;
+        };
+        let (from, from_len) = chars_of::<LINE_WIDTH>(renamed.from.as_str().unwrap());
+        let (to, to_len) = chars_of::<LINE_WIDTH>(renamed.to.as_str().unwrap());
+        self.replace(&to[..to_len], &from[..from_len], |row, col| {
+            renamed.starts[row][col]
+        })
+        .is_ok()
+    }
+
+    // Writes `to` over each use of `from` that `at` picks, returning where
+    // each `to` now starts.
+    fn replace(
+        &mut self,
+        from: &[char],
+        to: &[char],
+        at: impl Fn(usize, usize) -> bool,
+    ) -> Result<[[bool; LINE_WIDTH]; DOCUMENT_LENGTH], RenameError> {
+        let mut document = self.document;
+        let mut starts = [[false; LINE_WIDTH]; DOCUMENT_LENGTH];
+        let mut cursor_col = self.cursor_col;
+        for (row, line) in self.document.iter().enumerate() {
+            let mut new_line = [0u8 as char; LINE_WIDTH];
+            let (mut col, mut len) = (0, 0);
+            while col < LINE_WIDTH && line[col] != 0u8 as char {
+                let (text, step) = if at(row, col) && is_use(line, col, from) {
+                    starts[row][len] = true;
+                    (to, from.len())
+                } else {
+                    (&line[col..=col], 1)
+                };
+                if len + text.len() > LINE_WIDTH {
+                    return Err(RenameError::LineTooLong(row + 1));
+                }
+                if row == self.cursor_row && (col..col + step).contains(&self.cursor_col) {
+                    cursor_col = len;
+                }
+                new_line[len..len + text.len()].copy_from_slice(text);
+                len += text.len();
+                col += step;
+            }
+            if row == self.cursor_row && self.cursor_col >= col {
+                cursor_col = len;
+            }
+            document[row] = new_line;
+        }
+        self.document = document;
+        self.move_to(self.cursor_row, cursor_col.min(LINE_WIDTH - 1));
+        Ok(starts)
+    }
+
+    // Whether `row` assigns to `word`, as in `word := ...`.
+    fn assigns(&self, row: usize, word: &[char]) -> bool {
+        let line = &self.document[row];
+        let indent = line.iter().take_while(|c| **c == ' ').count();
+        let rest = &line[indent..];
+        if !rest.starts_with(word) {
+            return false;
+        }
+        let mut after = rest[word.len()..].iter().skip_while(|c| **c == ' ');
+        after.next() == Some(&':') && after.next() == Some(&'=')
+    }
+
+    // `cursor` is false while a blinking cursor is in its hidden phase. Only
+    // the first `rows` rows of the window are drawn, for an editor sharing
+    // its window; see Window::split.
+    // `guide`, if any, is the column shaded to show where lines should end.
+    pub fn draw_window(
+        &mut self,
+        window_x: usize,
+        window_y: usize,
+        rows: usize,
+        cursor: bool,
+        guide: Option<usize>,
+        text: ColorCode,
+    ) {
+        let rows = rows.min(self.window_size_y);
+        self.focus_y = self.focus_y.min(self.window_size_y * 4 - rows);
         if self.cursor_row < self.focus_y && self.focus_y != 0 {
             self.focus_y = self.cursor_row;
-        } else if self.cursor_row >= self.focus_y + self.window_size_y
-            && self.focus_y + self.window_size_y < self.window_size_y * 4
+        } else if self.cursor_row >= self.focus_y + rows
+            && self.focus_y + rows < self.window_size_y * 4
         {
-            self.focus_y = self.cursor_row - self.window_size_y + 1;
+            self.focus_y = self.cursor_row - rows + 1;
         }
-        for y in 0..self.window_size_y {
-            for x in 0..self.window_size_x {
-                if self.cursor_col == x && self.cursor_row == y + self.focus_y && self.focused {
-                    if is_drawable(self.document[y + self.focus_y][x]) {
-                        plot(
-                            self.document[y + self.focus_y][x],
-                            window_x + x,
-                            window_y + y,
-                            ColorCode::new(Color::Black, Color::LightCyan),
-                        );
-                    } else {
-                        plot(
-                            ' ',
-                            window_x + x,
-                            window_y + y,
-                            ColorCode::new(Color::Black, Color::LightCyan),
-                        );
+        // The identifier under the cursor, to pick out where else it is used.
+        let word = self
+            .word_at(self.cursor_row, self.cursor_col)
+            .filter(|_| self.focused)
+            .map(|(start, end)| (start, &self.document[self.cursor_row][start..end]));
+        for y in 0..rows {
+            let row = y + self.focus_y;
+            let mut uses = [false; LINE_WIDTH];
+            if let Some((start, word)) = word {
+                for col in self.occurrences(row, word) {
+                    if (row, col) != (self.cursor_row, start) {
+                        uses[col..col + word.len()].fill(true);
                     }
-                } else if is_drawable(self.document[y + self.focus_y][x]) {
-                    plot(
-                        self.document[y + self.focus_y][x],
-                        window_x + x,
-                        window_y + y,
-                        ColorCode::new(Color::LightCyan, Color::Black),
-                    );
-                } else {
-                    plot(
-                        ' ',
-                        window_x + x,
-                        window_y + y,
-                        ColorCode::new(Color::LightCyan, Color::Black),
-                    );
                 }
             }
+            for (x, used) in uses.iter().enumerate().take(self.window_size_x) {
+                let c = self.document[y + self.focus_y][x];
+                let color = if self.cursor_col == x
+                    && self.cursor_row == y + self.focus_y
+                    && self.focused
+                    && cursor
+                {
+                    ColorCode::new(Color::Black, Color::LightCyan)
+                } else if self.is_selected(y + self.focus_y, x) {
+                    ColorCode::new(Color::Black, Color::Cyan)
+                } else if *used {
+                    ColorCode::new(Color::LightCyan, Color::DarkGray)
+                } else if guide == Some(x) {
+                    guide_color()
+                } else {
+                    text
+                };
+                plot(
+                    if is_drawable(c) || is_extended(c) {
+                        c
+                    } else {
+                        ' '
+                    },
+                    window_x + x,
+                    window_y + y,
+                    color,
+                );
+            }
+        }
+    }
+
+    // A row of column numbers, every tenth column with its tens digit and
+    // every fifth with a `+`, picking out the cursor's column and the guide.
+    pub fn draw_ruler(&self, x: usize, y: usize, guide: Option<usize>, color: ColorCode) {
+        for col in 0..self.window_size_x {
+            let c = match (col + 1) % 10 {
+                0 => (b'0' + ((col + 1) / 10 % 10) as u8) as char,
+                5 => '+',
+                _ => '.',
+            };
+            let color = if col == self.cursor_col {
+                ColorCode::new(Color::Black, Color::LightCyan)
+            } else if guide == Some(col) {
+                guide_color()
+            } else {
+                color
+            };
+            plot(c, x + col, y, color);
         }
     }
 }
+
+fn guide_color() -> ColorCode {
+    ColorCode::new(Color::White, Color::DarkGray)
+}
+
+fn is_word(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+// Whether `word` starts at `col` of `line` as a whole identifier.
+fn is_use(line: &[char], col: usize, word: &[char]) -> bool {
+    line[col..].starts_with(word)
+        && (col == 0 || !is_word(line[col - 1]))
+        && !line.get(col + word.len()).is_some_and(|c| is_word(*c))
+}
+
+// `word` as the editor stores text, and its length.
+fn chars_of<const N: usize>(word: &str) -> ([char; N], usize) {
+    let mut chars = [0u8 as char; N];
+    let mut len = 0;
+    for (slot, c) in chars.iter_mut().zip(word.chars()) {
+        *slot = c;
+        len += 1;
+    }
+    (chars, len)
+}
@@ -0,0 +1,109 @@
+use crate::scheduler::SchedulerPolicy;
+use crate::selftest::SelfTest;
+use crate::splash::SPLASH_TICKS;
+use crate::theme::Theme;
+use crate::SwimInterface;
+
+// Sets up a SwimInterface: which files start on the disk (for example
+// `("hello", include_str!("hello.txt"))`, or a whole archive of them), its
+// colors, how running programs share the CPU and whether a splash screen
+// or a self-test comes first.
+pub struct SwimInterfaceBuilder<'a, const N: usize = 4> {
+    files: &'a [(&'a str, &'a str)],
+    archive: &'a [u8],
+    samples: &'static [(&'static str, &'static str)],
+    theme: Theme,
+    scheduler: SchedulerPolicy,
+    splash: usize,
+    self_test: bool,
+}
+
+impl<const N: usize> Default for SwimInterfaceBuilder<'_, N> {
+    fn default() -> Self {
+        Self {
+            files: &[],
+            archive: &[],
+            samples: &[],
+            theme: Theme::default(),
+            scheduler: SchedulerPolicy::default(),
+            splash: 0,
+            self_test: false,
+        }
+    }
+}
+
+impl<'a, const N: usize> SwimInterfaceBuilder<'a, N> {
+    pub fn files(mut self, files: &'a [(&'a str, &'a str)]) -> Self {
+        self.files = files;
+        self
+    }
+
+    pub fn archive(mut self, archive: &'a [u8]) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    // Files kept as shipped, such as SAMPLE_FILES, that the shell's
+    // `reset samples` can put back.
+    pub fn samples(mut self, samples: &'static [(&'static str, &'static str)]) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn scheduler(mut self, scheduler: SchedulerPolicy) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    pub fn splash(mut self) -> Self {
+        self.splash = SPLASH_TICKS;
+        self
+    }
+
+    // Checks the filesystem, interpreter and renderer once the files are on
+    // the disk, and shows the results instead of the splash. A failure keeps
+    // them up until a key is pressed.
+    pub fn self_test(mut self) -> Self {
+        self.self_test = true;
+        self
+    }
+
+    // How many windows the interface has; four unless set.
+    pub fn windows<const M: usize>(self) -> SwimInterfaceBuilder<'a, M> {
+        SwimInterfaceBuilder {
+            files: self.files,
+            archive: self.archive,
+            samples: self.samples,
+            theme: self.theme,
+            scheduler: self.scheduler,
+            splash: self.splash,
+            self_test: self.self_test,
+        }
+    }
+
+    pub fn build(self) -> SwimInterface<N> {
+        let mut interface = SwimInterface::new(
+            self.files,
+            self.archive,
+            self.samples,
+            self.theme,
+            self.scheduler,
+        );
+        interface.splash = self.splash;
+        if self.self_test {
+            let report = SelfTest::run(interface.mounts.volume(0));
+            interface.splash = if report.passed() {
+                SPLASH_TICKS
+            } else {
+                usize::MAX
+            };
+            interface.self_test = Some(report);
+        }
+        interface
+    }
+}
@@ -0,0 +1,87 @@
+use core::fmt::Write;
+
+use simple_interp::{ArrayString, Interpreter, InterpreterOutput, TickStatus};
+
+use crate::output::Output;
+use crate::process::Program;
+use crate::screen::{plot, Color, ColorCode};
+use crate::LINE_WIDTH;
+
+const CALCULATOR_ROWS: usize = 10;
+const PROMPT: &str = "= ";
+const MAX_EXPRESSION: usize = LINE_WIDTH - PROMPT.len() - 1;
+// Ticks an expression gets before it is given up on.
+const MAX_TICKS: usize = 100;
+
+// A scratch window for arithmetic. Each line is wrapped in print(...) and
+// run to the end by a throwaway interpreter, so it takes any expression a
+// program could print, without a file to hold it.
+#[derive(Default)]
+pub struct Calculator {
+    output: Output,
+    expression: ArrayString<MAX_EXPRESSION>,
+}
+
+impl Calculator {
+    pub fn push_char(&mut self, c: char) {
+        if c == '\u{0008}' || self.expression.len() < MAX_EXPRESSION {
+            self.expression.push_char(c);
+        }
+    }
+
+    // Echoes the expression and prints what it came to under it.
+    pub fn evaluate(&mut self) {
+        let expression = self.expression;
+        let expression = expression.as_str().unwrap().trim();
+        self.expression = ArrayString::default();
+        if expression.is_empty() {
+            return;
+        }
+        writeln!(self.output, "{}", expression).unwrap();
+        let mut program: ArrayString<LINE_WIDTH> = ArrayString::default();
+        write!(program, "print({})", expression).unwrap();
+        let mut interpreter: Program = Interpreter::new(program.as_str().unwrap());
+        write!(self.output, "{}", PROMPT).unwrap();
+        for _ in 0..MAX_TICKS {
+            match interpreter.tick(self) {
+                TickStatus::Continuing => {}
+                TickStatus::Finished => return,
+                TickStatus::AwaitInput => {
+                    writeln!(self.output, "no input here").unwrap();
+                    return;
+                }
+            }
+        }
+        writeln!(self.output, "too long").unwrap();
+    }
+
+    pub fn draw(&self, x: usize, y: usize, cursor: bool, text: ColorCode) {
+        let output_rows = CALCULATOR_ROWS - 1;
+        for row in 0..output_rows {
+            draw_row(self.output.line(output_rows - row), "", x, y + row, text);
+        }
+        let expression = self.expression.as_str().unwrap();
+        draw_row(PROMPT, expression, x, y + output_rows, text);
+        if cursor {
+            plot(
+                ' ',
+                x + PROMPT.len() + expression.len(),
+                y + output_rows,
+                ColorCode::new(Color::Black, Color::LightCyan),
+            );
+        }
+    }
+}
+
+impl InterpreterOutput for Calculator {
+    fn print(&mut self, chars: &[u8]) {
+        self.output.write_bytes(chars);
+    }
+}
+
+fn draw_row(prefix: &str, text: &str, x: usize, y: usize, color: ColorCode) {
+    let mut chars = prefix.chars().chain(text.chars());
+    for col in 0..LINE_WIDTH {
+        plot(chars.next().unwrap_or(' '), x + col, y, color);
+    }
+}
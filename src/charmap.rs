@@ -0,0 +1,94 @@
+use core::fmt::Write;
+
+use simple_interp::ArrayString;
+
+use crate::screen::{plot, ColorCode};
+use crate::theme::Palette;
+use crate::LINE_WIDTH;
+
+// Code page 437 from the space up: 224 characters, 28 to a row.
+const FIRST: usize = 32;
+const COLUMNS: usize = 28;
+const ROWS: usize = 8;
+const HELP: &str = "arrows enter:insert";
+
+// A character picker, so box-drawing and accented characters can be typed.
+// Enter puts the selected one into the window that was focused before this
+// one was opened; see SwimInterface::insert_char.
+#[derive(Copy, Clone)]
+pub struct CharMap {
+    selected: usize,
+    pub target: usize,
+}
+
+impl CharMap {
+    pub fn new(target: usize) -> Self {
+        Self {
+            selected: 0,
+            target,
+        }
+    }
+
+    pub fn up(&mut self) {
+        if self.selected >= COLUMNS {
+            self.selected -= COLUMNS;
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.selected + COLUMNS < COLUMNS * ROWS {
+            self.selected += COLUMNS;
+        }
+    }
+
+    pub fn left(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn right(&mut self) {
+        if self.selected + 1 < COLUMNS * ROWS {
+            self.selected += 1;
+        }
+    }
+
+    pub fn selected(&self) -> char {
+        ((FIRST + self.selected) as u8) as char
+    }
+
+    pub fn draw(&self, x: usize, y: usize, palette: &Palette) {
+        for row in 0..ROWS {
+            for col in 0..LINE_WIDTH {
+                let i = row * COLUMNS + col;
+                let (c, color) = if col >= COLUMNS {
+                    (' ', palette.text)
+                } else if i == self.selected {
+                    (((FIRST + i) as u8) as char, palette.highlight)
+                } else {
+                    (((FIRST + i) as u8) as char, palette.text)
+                };
+                plot(c, x + col, y + row, color);
+            }
+        }
+        let mut line: ArrayString<LINE_WIDTH> = ArrayString::default();
+        let code = FIRST + self.selected;
+        write!(
+            line,
+            "  {:>3} 0x{:02X} into F{}",
+            code,
+            code,
+            self.target + 1
+        )
+        .unwrap();
+        draw_row(line.as_str().unwrap(), x, y + ROWS, palette.text);
+        // The character itself can't go into an ArrayString above 127.
+        plot(self.selected(), x, y + ROWS, palette.text);
+        draw_row(HELP, x, y + ROWS + 1, palette.border);
+    }
+}
+
+fn draw_row(text: &str, x: usize, y: usize, color: ColorCode) {
+    let mut chars = text.chars();
+    for col in 0..LINE_WIDTH {
+        plot(chars.next().unwrap_or(' '), x + col, y, color);
+    }
+}
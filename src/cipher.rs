@@ -0,0 +1,37 @@
+// Obfuscation for protected files, not encryption. The passphrase is hashed
+// (FNV-1a) into the seed of a xorshift generator, and the bytes it makes are
+// XORed over the file as it is written and again as it is read. It keeps a
+// file from being read off the disk by eye and no more.
+#[derive(Copy, Clone)]
+pub struct Keystream {
+    state: u32,
+}
+
+// The seed a passphrase gives. It is never zero, which xorshift can't leave.
+pub fn key(passphrase: &str) -> u32 {
+    let hash = passphrase.bytes().fold(0x811c_9dc5u32, |hash, b| {
+        (hash ^ b as u32).wrapping_mul(0x0100_0193)
+    });
+    hash.max(1)
+}
+
+// A byte kept with the file to tell a wrong passphrase from the right one.
+// Mixed apart from the seed, so it says little about the keystream.
+pub fn check(key: u32) -> u8 {
+    (key.wrapping_mul(0x9e37_79b9) >> 24) as u8
+}
+
+impl Keystream {
+    pub fn new(key: u32) -> Self {
+        Self { state: key }
+    }
+
+    pub fn apply(&mut self, bytes: &mut [u8]) {
+        for b in bytes {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 17;
+            self.state ^= self.state << 5;
+            *b ^= self.state as u8;
+        }
+    }
+}
@@ -0,0 +1,22 @@
+// A cycle counter for timing work inside a single tick. Kernel builds read
+// the CPU's time-stamp counter; `std` builds use a counter that host tests
+// advance by hand, so reported timings are deterministic.
+#[cfg(not(feature = "std"))]
+pub fn cycles() -> u64 {
+    unsafe { core::arch::x86_64::_rdtsc() }
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static CYCLES: core::cell::Cell<u64> = const { core::cell::Cell::new(0) };
+}
+
+#[cfg(feature = "std")]
+pub fn cycles() -> u64 {
+    CYCLES.with(|cycles| cycles.get())
+}
+
+#[cfg(feature = "std")]
+pub fn advance(num_cycles: u64) {
+    CYCLES.with(|cycles| cycles.set(cycles.get() + num_cycles));
+}
@@ -0,0 +1,45 @@
+// Filesystem geometry, fixed at build time. Each value can be overridden by
+// setting the matching environment variable when building, e.g.
+// `SWIM_NUM_BLOCKS=512 cargo bootimage`, so a kernel built from this
+// template can resize its disk without editing the source.
+pub const BLOCK_SIZE: usize = parse(option_env!("SWIM_BLOCK_SIZE"), 256);
+pub const NUM_BLOCKS: usize = parse(option_env!("SWIM_NUM_BLOCKS"), 255);
+pub const MAX_FILE_BLOCKS: usize = parse(option_env!("SWIM_MAX_FILE_BLOCKS"), 64);
+pub const MAX_FILES_STORED: usize = parse(option_env!("SWIM_MAX_FILES_STORED"), 30);
+pub const MAX_OPEN: usize = parse(option_env!("SWIM_MAX_OPEN"), 16);
+
+const LISTING_CAPACITY: usize = 30;
+
+const _: () = assert!(
+    MAX_FILES_STORED <= LISTING_CAPACITY,
+    "SWIM_MAX_FILES_STORED must fit in the 30-file listing"
+);
+const _: () = assert!(
+    MAX_FILE_BLOCKS <= NUM_BLOCKS,
+    "SWIM_MAX_FILE_BLOCKS must not exceed SWIM_NUM_BLOCKS"
+);
+const _: () = assert!(
+    BLOCK_SIZE > 0 && MAX_OPEN > 0,
+    "geometry values must be positive"
+);
+
+const fn parse(value: Option<&str>, default: usize) -> usize {
+    match value {
+        None => default,
+        Some(value) => {
+            let digits = value.as_bytes();
+            assert!(!digits.is_empty(), "geometry values must be numbers");
+            let mut result = 0;
+            let mut i = 0;
+            while i < digits.len() {
+                assert!(
+                    digits[i].is_ascii_digit(),
+                    "geometry values must be numbers"
+                );
+                result = result * 10 + (digits[i] - b'0') as usize;
+                i += 1;
+            }
+            result
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use core::fmt::Write;
+
+use file_system_solution::FileSystemError;
+use pc_keyboard::DecodedKey;
+use simple_interp::ArrayString;
+
+use crate::mount::volume_letter;
+use crate::replay::write_key;
+use crate::storage::{name_bytes, name_str};
+use crate::{FileAction, SwimInterface};
+
+// Where a crash's state is written, and what boot looks for.
+pub const CRASHDUMP: &str = "crashdump";
+// How many of the last keys a dump lists.
+pub const KEY_HISTORY: usize = 20;
+const DUMP_BYTES: usize = 2048;
+// Lines of the crashed program's output a dump keeps.
+const OUTPUT_LINES: usize = 3;
+
+// The last KEY_HISTORY keys handled, for a crash dump to say how the
+// kernel got where it was.
+#[derive(Copy, Clone, Default)]
+pub struct KeyHistory {
+    keys: [Option<DecodedKey>; KEY_HISTORY],
+    next: usize,
+}
+
+impl KeyHistory {
+    pub fn push(&mut self, key: DecodedKey) {
+        self.keys[self.next] = Some(key);
+        self.next = (self.next + 1) % KEY_HISTORY;
+    }
+
+    // Oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = DecodedKey> + '_ {
+        (0..KEY_HISTORY).filter_map(|i| self.keys[(self.next + i) % KEY_HISTORY])
+    }
+}
+
+impl<const N: usize> SwimInterface<N> {
+    // Writes what the kernel was doing when `pid`'s program crashed to
+    // CRASHDUMP on the first volume, replacing any earlier dump: the focused
+    // window, what each window held, the scheduler's counters and every
+    // process, the status bar, the program's last output and the last keys.
+    pub(crate) fn write_crashdump(&mut self, pid: usize) -> Result<(), FileSystemError> {
+        let mut dump: ArrayString<DUMP_BYTES> = ArrayString::default();
+        self.describe_crash(&mut dump, pid).ok();
+        let storage = self.mounts.volume(0);
+        let fd = storage.open_create(CRASHDUMP)?;
+        let result = storage.write(fd, dump.as_str().unwrap().as_bytes());
+        result.and(storage.close(fd))
+    }
+
+    // A dump left by the last run opens in the last window at boot.
+    pub(crate) fn open_crashdump(&mut self) {
+        if !self.mounts.volume(0).exists(CRASHDUMP) {
+            return;
+        }
+        let name = name_bytes(CRASHDUMP);
+        if self
+            .do_file_action(N - 1, 0, name, FileAction::View)
+            .is_ok()
+        {
+            self.notifier.notify("crashdump from the last run", false);
+        }
+    }
+
+    fn describe_crash<W: Write>(&self, out: &mut W, pid: usize) -> core::fmt::Result {
+        if let Some(process) = self.processes.get(pid) {
+            write!(out, "crash: pid {} ", pid)?;
+            if let Some(window) = process.window {
+                write!(out, "in F{} ", window + 1)?;
+            }
+            writeln!(
+                out,
+                "{}:{} after {} ticks",
+                volume_letter(process.volume),
+                name_str(&process.file).unwrap_or(""),
+                process.ticks
+            )?;
+        }
+        writeln!(out, "status: {}", self.filename_message.as_str().unwrap())?;
+        writeln!(
+            out,
+            "frame {}, focus F{}, current pid {}, countdown {}",
+            self.metrics.frames,
+            self.focused_editor + 1,
+            self.current_process,
+            self.running_countdown
+        )?;
+        for (i, window) in self.windows.iter().enumerate() {
+            writeln!(
+                out,
+                "F{} {:?} {}:{}",
+                i + 1,
+                window.state,
+                volume_letter(window.volume),
+                name_str(&window.current_file).unwrap_or("")
+            )?;
+        }
+        for (i, process) in self.processes.iter() {
+            writeln!(
+                out,
+                "pid {} {} ticks {} vruntime {} nice {}",
+                i,
+                process.state.label(),
+                process.ticks,
+                process.vruntime,
+                process.nice
+            )?;
+        }
+        if let Some(process) = self.processes.get(pid) {
+            let mut kept = [""; OUTPUT_LINES];
+            let mut num_kept = 0;
+            for age in 0..OUTPUT_LINES * 2 {
+                let line = process.output.line(age);
+                if num_kept < OUTPUT_LINES && !line.is_empty() {
+                    kept[num_kept] = line;
+                    num_kept += 1;
+                }
+            }
+            writeln!(out, "output:")?;
+            for line in kept[..num_kept].iter().rev() {
+                writeln!(out, "  {}", line)?;
+            }
+        }
+        write!(out, "keys: ")?;
+        for key in self.key_history.iter() {
+            write_key(out, key)?;
+        }
+        writeln!(out)
+    }
+}
@@ -0,0 +1,49 @@
+use core::fmt::Write;
+
+use file_system_solution::FileSystemError;
+
+use crate::storage::{name_str, Storage};
+use crate::{MAX_FILES_STORED, MAX_FILE_BYTES};
+
+// Rewrites every file in order of its first block on the estimated block
+// map, naming each one in `out`, and returns how many it rewrote.
+// Truncating a file frees its blocks for the allocator to hand out again,
+// so if the allocator fills the lowest free blocks first the free space
+// ends up in one run at the end of the disk. FileSystem doesn't say where
+// blocks went, so only the rewrites are reported, not what they moved.
+pub fn defrag<W: Write>(storage: &mut Storage, out: &mut W) -> Result<usize, FileSystemError> {
+    let estimate = *storage.block_map();
+    let (num_files, files) = storage.raw_directory()?;
+    let mut order = [(0, 0); MAX_FILES_STORED];
+    let mut num_placed = 0;
+    for (i, file) in files[..num_files].iter().enumerate() {
+        if let Some(first) = estimate.first_block(file) {
+            order[num_placed] = (first, i);
+            num_placed += 1;
+        }
+    }
+    order[..num_placed].sort_unstable();
+
+    let mut contents = [0; MAX_FILE_BYTES];
+    for (_, i) in order[..num_placed].iter() {
+        let name = name_str(&files[*i]).ok_or(FileSystemError::FileNotFound)?;
+        let fd = storage.open_read(name)?;
+        let mut size = 0;
+        loop {
+            match storage.read(fd, &mut contents[size..]) {
+                Ok(0) => break,
+                Ok(num_bytes) => size += num_bytes,
+                Err(e) => {
+                    storage.close(fd)?;
+                    return Err(e);
+                }
+            }
+        }
+        storage.close(fd)?;
+        let fd = storage.open_create(name)?;
+        let result = storage.write(fd, &contents[..size]);
+        result.and(storage.close(fd))?;
+        writeln!(out, "rewrote {}", name).unwrap();
+    }
+    Ok(num_placed)
+}
@@ -0,0 +1,69 @@
+use crate::config::MAX_FILES_STORED;
+use crate::storage::name_str;
+use crate::MAX_FILENAME_BYTES;
+
+// Directories for the file lists, over a file system that has none. A file
+// named `dir/name` is in directory `dir/`, one level deep, with the prefix
+// counting toward MAX_FILENAME_BYTES. At the top a list shows each
+// directory once, then the files in none; in a directory it shows `..`,
+// then the files in it. Entries are full names, so whatever acts on the
+// focused entry uses the name as it is on the disk.
+pub type Name = [u8; MAX_FILENAME_BYTES];
+
+// How many entries there are, and the entries. A directory holding every
+// file slot has its last file left off to make room for `..`.
+pub type Entries = (usize, [Name; MAX_FILES_STORED]);
+
+// The entry that goes back up to the top.
+pub const PARENT: Name = *b"..\0\0\0\0\0\0\0\0";
+
+// The directory a file is in, slash included.
+pub fn dir_of(name: &Name) -> Option<Name> {
+    let slash = name.iter().position(|b| *b == b'/')?;
+    let mut dir = [0; MAX_FILENAME_BYTES];
+    dir[..=slash].copy_from_slice(&name[..=slash]);
+    Some(dir)
+}
+
+// True for `..` and for a directory, rather than a file.
+pub fn is_dir(entry: &Name) -> bool {
+    *entry == PARENT || name_str(entry).is_some_and(|name| name.ends_with('/'))
+}
+
+// What `files` look like from `dir`, or from the top for None.
+pub fn entries(files: &[Name], dir: Option<Name>) -> Entries {
+    let mut entries = [[0; MAX_FILENAME_BYTES]; MAX_FILES_STORED];
+    let mut len = 0;
+    let mut push = |entry: Name| {
+        if len < MAX_FILES_STORED && !entries[..len].contains(&entry) {
+            entries[len] = entry;
+            len += 1;
+        }
+    };
+    match dir {
+        Some(dir) => {
+            push(PARENT);
+            files
+                .iter()
+                .filter(|file| dir_of(file) == Some(dir))
+                .for_each(|file| push(*file));
+        }
+        None => {
+            files.iter().filter_map(dir_of).for_each(&mut push);
+            files
+                .iter()
+                .filter(|file| dir_of(file).is_none())
+                .for_each(|file| push(*file));
+        }
+    }
+    (len, entries)
+}
+
+// An entry as a list in `dir` shows it: a file without its directory.
+pub fn display_name(entry: &Name, dir: Option<Name>) -> &str {
+    let name = name_str(entry).unwrap_or("?");
+    match (dir, name.split_once('/')) {
+        (Some(_), Some((_, file))) => file,
+        _ => name,
+    }
+}
@@ -0,0 +1,167 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crossbeam::atomic::AtomicCell;
+use pc_keyboard::{DecodedKey, KeyCode};
+
+#[cfg(feature = "serial")]
+use crate::serial;
+use crate::SwimInterface;
+
+const KEY_SLOTS: usize = 32;
+#[cfg(feature = "serial")]
+const SERIAL_SLOTS: usize = 64;
+// Ticks missed while the main loop was busy are made up, but only this many
+// at once, so one slow frame cannot snowball into a run of them.
+const MAX_CATCH_UP: usize = 4;
+
+// Shared between the interrupt handlers and the kernel's main loop. The
+// handlers only record what happened, which is cheap and never blocks;
+// `SwimInterface::drain` does the drawing, interpreting and file system work
+// afterwards from the main loop. Keys and serial bytes go into rings with one
+// producer and one consumer, so ones arriving while a frame is being drawn
+// are queued rather than overwriting each other. Key presses and releases
+// share a ring, so they are delivered in the order they happened.
+pub struct Events {
+    keys: Ring<Key, KEY_SLOTS>,
+    #[cfg(feature = "serial")]
+    serial: Ring<u8, SERIAL_SLOTS>,
+    ticks: AtomicUsize,
+}
+
+impl Default for Events {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Events {
+    pub const fn new() -> Self {
+        Self {
+            keys: Ring::new(),
+            #[cfg(feature = "serial")]
+            serial: Ring::new(),
+            ticks: AtomicUsize::new(0),
+        }
+    }
+
+    // Called from the keyboard interrupt.
+    pub fn push_key(&self, key: DecodedKey) {
+        self.keys.push(Key::Pressed(key));
+    }
+
+    // Called from the keyboard interrupt for a key let go, a pc_keyboard
+    // KeyEvent in KeyState::Up. pluggable_interrupt_os's handler only passes
+    // presses on, so kernel builds fall back on InputQueue's HOLD_TICKS.
+    pub fn release_key(&self, code: KeyCode) {
+        self.keys.push(Key::Released(code));
+    }
+
+    // Called from the timer interrupt.
+    pub fn tick(&self) {
+        self.ticks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Called from the timer interrupt, standing in for the serial port's own
+    // interrupt; see serial.rs.
+    #[cfg(feature = "serial")]
+    pub fn poll_serial(&self) {
+        while let Some(byte) = serial::receive() {
+            self.serial.push(byte);
+        }
+    }
+
+    // Whether nothing has come in since the last drain. The main loop checks
+    // this with interrupts off before halting.
+    pub fn is_empty(&self) -> bool {
+        #[cfg(feature = "serial")]
+        if !self.serial.is_empty() {
+            return false;
+        }
+        self.keys.is_empty() && self.ticks.load(Ordering::Relaxed) == 0
+    }
+
+    pub fn dropped_keys(&self) -> usize {
+        self.keys.dropped.load(Ordering::Relaxed)
+    }
+
+    fn take_ticks(&self) -> usize {
+        self.ticks.swap(0, Ordering::Relaxed)
+    }
+}
+
+#[derive(Copy, Clone)]
+enum Key {
+    Pressed(DecodedKey),
+    Released(KeyCode),
+}
+
+struct Ring<T, const SLOTS: usize> {
+    slots: [AtomicCell<Option<T>>; SLOTS],
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+impl<T: Copy, const SLOTS: usize> Ring<T, SLOTS> {
+    const fn new() -> Self {
+        Self {
+            slots: [const { AtomicCell::new(None) }; SLOTS],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    fn push(&self, value: T) {
+        let tail = self.tail.load(Ordering::Relaxed);
+        if tail.wrapping_sub(self.head.load(Ordering::Acquire)) == SLOTS {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.slots[tail % SLOTS].store(Some(value));
+            self.tail.store(tail.wrapping_add(1), Ordering::Release);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Relaxed) == self.tail.load(Ordering::Acquire)
+    }
+
+    fn pop(&self) -> Option<T> {
+        let head = self.head.load(Ordering::Relaxed);
+        if head == self.tail.load(Ordering::Acquire) {
+            return None;
+        }
+        let value = self.slots[head % SLOTS].take();
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+        value
+    }
+}
+
+impl<const N: usize> SwimInterface<N> {
+    // The main loop's half of the work: delivers every queued key press,
+    // release and serial byte, then runs the ticks that have come in since the last call.
+    // Returns whether there was anything to do.
+    pub fn drain(&mut self, events: &Events) -> bool {
+        let mut worked = false;
+        while let Some(key) = events.keys.pop() {
+            match key {
+                Key::Pressed(key) => self.key(key),
+                Key::Released(code) => self.key_released(code),
+            }
+            worked = true;
+        }
+        #[cfg(feature = "serial")]
+        while let Some(byte) = events.serial.pop() {
+            self.serial_received(byte);
+            worked = true;
+        }
+        let arrived = events.take_ticks();
+        let ran = arrived.min(MAX_CATCH_UP);
+        for _ in 0..ran {
+            self.tick();
+            worked = true;
+        }
+        self.count_ticks(arrived, ran);
+        worked
+    }
+}
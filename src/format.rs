@@ -0,0 +1,107 @@
+use core::fmt::{Display, Formatter, Result, Write};
+
+use simple_interp::ArrayString;
+
+const FIELD_WIDTH: usize = 32;
+// Code page 437 has no ellipsis, so a cut is marked with its `»`.
+pub const ELLIPSIS: char = 175u8 as char;
+
+// A count shortened to at most four characters: 999, 1.2k, 45k, 3.4M, 12G.
+// Wraps the value so it can be padded and aligned like any other.
+#[derive(Copy, Clone)]
+pub struct Compact(pub u64);
+
+impl Display for Compact {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let mut text: ArrayString<8> = ArrayString::default();
+        let mut value = self.0;
+        let mut tenths = 0;
+        let mut suffix = "";
+        for unit in ["k", "M", "G", "T"] {
+            if value < 1000 {
+                break;
+            }
+            tenths = value % 1000 / 100;
+            value /= 1000;
+            suffix = unit;
+        }
+        if suffix.is_empty() || value >= 10 {
+            write!(text, "{}{}", value, suffix)?;
+        } else {
+            write!(text, "{}.{}{}", value, tenths, suffix)?;
+        }
+        f.pad(text.as_str().unwrap())
+    }
+}
+
+// `part` as a whole-number percentage of `whole`, or 0% of nothing.
+#[derive(Copy, Clone)]
+pub struct Percent(pub u64, pub u64);
+
+impl Display for Percent {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let mut text: ArrayString<8> = ArrayString::default();
+        let percent = (self.0 * 100).checked_div(self.1).unwrap_or(0);
+        write!(text, "{}%", percent)?;
+        f.pad(text.as_str().unwrap())
+    }
+}
+
+// A bar `width` characters wide, filled in proportion to `part` of `whole`.
+#[derive(Copy, Clone)]
+pub struct Bar {
+    pub part: u64,
+    pub whole: u64,
+    pub width: usize,
+}
+
+impl Display for Bar {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let filled = (self.part.min(self.whole) * self.width as u64 / self.whole.max(1)) as usize;
+        for i in 0..self.width {
+            f.write_char(if i < filled { '#' } else { '.' })?;
+        }
+        Ok(())
+    }
+}
+
+// Formats `value` right-justified in exactly `width` columns, so drawing it
+// also blanks whatever longer value was drawn there before.
+pub fn right(value: impl Display, width: usize) -> ArrayString<FIELD_WIDTH> {
+    let mut text = ArrayString::default();
+    write!(text, "{:>1$.1$}", Fit(value), width).unwrap();
+    text
+}
+
+// Formats `value` left-justified in exactly `width` columns.
+pub fn left(value: impl Display, width: usize) -> ArrayString<FIELD_WIDTH> {
+    let mut text = ArrayString::default();
+    write!(text, "{:<1$.1$}", Fit(value), width).unwrap();
+    text
+}
+
+// Formats `value` in at most `width` columns, which must be fewer than 32.
+// If it doesn't fit, one column less is kept and true is returned, leaving
+// the last column for the caller to mark with ELLIPSIS. (ArrayString only
+// holds single-byte characters, so the mark can't be part of the text.)
+pub fn clip(value: impl Display, width: usize) -> (ArrayString<FIELD_WIDTH>, bool) {
+    let text = left(value, width + 1);
+    let full = text.as_str().unwrap().trim_end();
+    if full.len() <= width {
+        (left(full, full.len()), false)
+    } else {
+        (left(full, width.saturating_sub(1)), true)
+    }
+}
+
+// Renders a value to a string first, so precision can cut it to the field
+// width even for types whose Display ignores precision.
+struct Fit<T>(T);
+
+impl<T: Display> Display for Fit<T> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let mut text: ArrayString<FIELD_WIDTH> = ArrayString::default();
+        write!(text, "{}", self.0)?;
+        f.pad(text.as_str().unwrap())
+    }
+}
@@ -0,0 +1,100 @@
+use core::fmt::Write;
+
+use crate::screen::is_drawable;
+
+use crate::storage::{name_str, Storage, DELETED, METADATA_FILE};
+use crate::{BLOCK_SIZE, MAX_FILE_BYTES, NUM_BLOCKS};
+
+#[derive(Default)]
+pub struct FsckReport {
+    pub problems: usize,
+    pub repaired: usize,
+}
+
+// Checks everything the storage layer can observe through the FileSystem
+// API: every directory entry must be nameable, unique, openable and within
+// the size limit, deleted files must not hold blocks, and every metadata
+// record must belong to a directory entry. Blocks themselves aren't
+// checked: the API gives no block lists, so blocks no file owns and blocks
+// two files share can't be found from here.
+pub fn check<W: Write>(storage: &mut Storage, repair: bool, out: &mut W) -> FsckReport {
+    let mut report = FsckReport::default();
+    let (num_files, files) = match storage.raw_directory() {
+        Ok(listing) => listing,
+        Err(e) => {
+            writeln!(out, "directory unreadable: {}", e).unwrap();
+            report.problems += 1;
+            return report;
+        }
+    };
+    let mut blocks_used = 0;
+    for (i, file) in files[..num_files].iter().enumerate() {
+        let name = match name_str(file) {
+            Some(name) if !name.is_empty() && name.chars().all(is_drawable) => name,
+            _ => {
+                writeln!(out, "entry {}: bad name", i).unwrap();
+                report.problems += 1;
+                continue;
+            }
+        };
+        if files[..i].contains(file) {
+            writeln!(out, "{}: duplicate entry", name).unwrap();
+            report.problems += 1;
+        }
+        let size = match storage.file_size(name) {
+            Ok(size) => size,
+            Err(e) => {
+                writeln!(out, "{}: {}", name, e).unwrap();
+                report.problems += 1;
+                continue;
+            }
+        };
+        if size > MAX_FILE_BYTES {
+            writeln!(out, "{}: size {} > {}", name, size, MAX_FILE_BYTES).unwrap();
+            report.problems += 1;
+        }
+        let blocks = (size + BLOCK_SIZE - 1) / BLOCK_SIZE;
+        if storage.file_flags(file) & DELETED != 0 && blocks > 0 {
+            writeln!(out, "{}: deleted, holds {} blk", name, blocks).unwrap();
+            report.problems += 1;
+            if repair && storage.truncate(name).is_ok() {
+                report.repaired += 1;
+                continue;
+            }
+        }
+        if name == METADATA_FILE && !storage.metadata_fits(size) {
+            writeln!(out, "{}: size {} not whole records", name, size).unwrap();
+            report.problems += 1;
+            if repair && storage.save_metadata().is_ok() {
+                report.repaired += 1;
+            }
+        }
+        blocks_used += blocks;
+    }
+    let mut orphans = [[0; crate::MAX_FILENAME_BYTES]; crate::MAX_FILES_STORED];
+    let mut num_orphans = 0;
+    for name in storage.metadata_names() {
+        if !files[..num_files].contains(&name) {
+            orphans[num_orphans] = name;
+            num_orphans += 1;
+        }
+    }
+    for orphan in orphans[..num_orphans].iter() {
+        writeln!(out, "{}: orphaned record", name_str(orphan).unwrap_or("?")).unwrap();
+        report.problems += 1;
+        if repair && storage.remove_record(orphan).is_ok() {
+            report.repaired += 1;
+        }
+    }
+    if blocks_used > NUM_BLOCKS {
+        writeln!(out, "{} data blocks > {} on disk", blocks_used, NUM_BLOCKS).unwrap();
+        report.problems += 1;
+    }
+    writeln!(
+        out,
+        "{} files, {}/{} blocks",
+        num_files, blocks_used, NUM_BLOCKS
+    )
+    .unwrap();
+    report
+}
@@ -0,0 +1,298 @@
+use pc_keyboard::{DecodedKey, KeyCode};
+
+use crate::clock;
+
+const QUEUE_SIZE: usize = 16;
+const HOLD_TICKS: usize = 3;
+
+#[derive(Copy, Clone, Default, Eq, PartialEq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub caps_lock: bool,
+}
+
+// The keyboard decoder always assumes a US layout. A keymap moves the
+// characters it reports to where another layout has them, so a Dvorak or
+// AZERTY typist can type on a US-decoded keyboard.
+#[derive(Copy, Clone, Default, Eq, PartialEq)]
+pub enum Keymap {
+    #[default]
+    Us,
+    Dvorak,
+    Azerty,
+}
+
+pub const KEYMAPS: [Keymap; 3] = [Keymap::Us, Keymap::Dvorak, Keymap::Azerty];
+
+const QWERTY: &str = "-=qwertyuiop[]asdfghjkl;'zxcvbnm,./_+QWERTYUIOP{}ASDFGHJKL:\"ZXCVBNM<>?";
+const DVORAK: &str = "[]',.pyfgcrl/=aoeuidhtns-;qjkxbmwvz{}\"<>PYFGCRL?+AOEUIDHTNS_:QJKXBMWVZ";
+// The French layout, for the keys that differ. Its accented letters are
+// given as their code page 437 codes, the way the editor stores them; its
+// `^` and `¨` keys are dead keys, left for Compose to combine.
+const AZERTY_KEYS: &str = "1234567890-!@#$%^&*()_qwaQWAzZ;:m,./<>'\"[]{}M";
+const AZERTY: &str =
+    "&\u{82}\"'(-\u{8a}_\u{87}\u{85})1234567890\u{f8}azqAZQwWmM,;:!./\u{97}%^$\u{a8}\u{9c}?";
+const DIAERESIS: char = '\u{a8}';
+// Letters an accent combines with, and the code page 437 codes they make.
+const CIRCUMFLEXED: [(char, u8); 5] = [('a', 131), ('e', 136), ('i', 140), ('o', 147), ('u', 150)];
+const UMLAUTED: [(char, u8); 9] = [
+    ('a', 132),
+    ('e', 137),
+    ('i', 139),
+    ('o', 148),
+    ('u', 129),
+    ('y', 152),
+    ('A', 142),
+    ('O', 153),
+    ('U', 154),
+];
+
+impl Keymap {
+    pub fn named(name: &str) -> Option<Keymap> {
+        match name {
+            "us" => Some(Keymap::Us),
+            "dvorak" => Some(Keymap::Dvorak),
+            "azerty" => Some(Keymap::Azerty),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Keymap::Us => "us",
+            Keymap::Dvorak => "dvorak",
+            Keymap::Azerty => "azerty",
+        }
+    }
+
+    pub fn translate(&self, key: DecodedKey) -> DecodedKey {
+        match (self, key) {
+            (Keymap::Dvorak, DecodedKey::Unicode(c)) => match QWERTY.find(c) {
+                Some(i) => DecodedKey::Unicode(DVORAK.as_bytes()[i] as char),
+                None => key,
+            },
+            (Keymap::Azerty, DecodedKey::Unicode(c)) => match AZERTY_KEYS.find(c) {
+                Some(i) => DecodedKey::Unicode(AZERTY.chars().nth(i).unwrap()),
+                None => key,
+            },
+            _ => key,
+        }
+    }
+
+    fn has_dead_keys(&self) -> bool {
+        *self == Keymap::Azerty
+    }
+}
+
+// Builds characters no key types by itself. Holding Alt while typing three
+// digits enters that code page 437 code, as on a PC, and on a keymap with
+// dead keys an accent waits for the letter it goes on. An accent followed
+// by a space types the accent alone; followed by a letter it can't go on,
+// only the letter is typed.
+#[derive(Copy, Clone, Default)]
+pub struct Compose {
+    code: u32,
+    digits: usize,
+    accent: Option<char>,
+}
+
+impl Compose {
+    // Takes a translated character, returning what it should type, if
+    // anything yet.
+    pub fn key(&mut self, c: char, alt: bool, keymap: Keymap) -> Option<char> {
+        if alt && c.is_ascii_digit() {
+            self.code = self.code * 10 + c.to_digit(10).unwrap();
+            self.digits += 1;
+            if self.digits < 3 {
+                return None;
+            }
+            let code = core::mem::take(&mut self.code);
+            self.digits = 0;
+            return (32..=255).contains(&code).then_some((code as u8) as char);
+        }
+        self.code = 0;
+        self.digits = 0;
+        match self.accent.take() {
+            Some(accent) => {
+                let combined = if accent == '^' {
+                    &CIRCUMFLEXED[..]
+                } else {
+                    &UMLAUTED[..]
+                };
+                match combined.iter().find(|(letter, _)| *letter == c) {
+                    Some((_, code)) => Some(*code as char),
+                    None if c == ' ' || c == accent => {
+                        Some(if accent == DIAERESIS { '"' } else { accent })
+                    }
+                    None => Some(c),
+                }
+            }
+            None if keymap.has_dead_keys() && (c == '^' || c == DIAERESIS) => {
+                self.accent = Some(c);
+                None
+            }
+            None => Some(c),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct KeyEvent {
+    pub key: DecodedKey,
+    pub modifiers: Modifiers,
+    // The cycle count when it was queued, for measuring how long it waits.
+    pub arrived: u64,
+}
+
+// The decoder only reports key presses, never releases, so a modifier counts
+// as held for HOLD_TICKS after it last arrived, pressed or repeated by the
+// keyboard. Other keys arriving don't keep it held.
+// Once the keyboard's own typematic repeat shows an arrow is held down, the
+// queue emits one repeat per tick instead of passing the hardware repeats
+// through, until the arrow's release comes (see Events::release_key) or
+// HOLD_TICKS pass without a repeat.
+pub struct InputQueue {
+    events: [Option<KeyEvent>; QUEUE_SIZE],
+    head: usize,
+    len: usize,
+    modifiers: Modifiers,
+    modifier_age: usize,
+    held: Option<KeyCode>,
+    held_age: usize,
+    repeating: bool,
+}
+
+impl Default for InputQueue {
+    fn default() -> Self {
+        Self {
+            events: [None; QUEUE_SIZE],
+            head: 0,
+            len: 0,
+            modifiers: Modifiers::default(),
+            modifier_age: 0,
+            held: None,
+            held_age: 0,
+            repeating: false,
+        }
+    }
+}
+
+impl InputQueue {
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    pub fn push(&mut self, key: DecodedKey) {
+        match key {
+            DecodedKey::RawKey(KeyCode::LShift | KeyCode::RShift) => {
+                self.modifiers.shift = true;
+                self.modifier_age = 0;
+            }
+            DecodedKey::RawKey(KeyCode::LControl | KeyCode::RControl) => {
+                self.modifiers.ctrl = true;
+                self.modifier_age = 0;
+            }
+            DecodedKey::RawKey(KeyCode::LAlt | KeyCode::RAltGr) => {
+                self.modifiers.alt = true;
+                self.modifier_age = 0;
+            }
+            DecodedKey::RawKey(KeyCode::CapsLock) => {
+                self.modifiers.caps_lock = !self.modifiers.caps_lock;
+            }
+            DecodedKey::RawKey(
+                code @ (KeyCode::ArrowUp
+                | KeyCode::ArrowDown
+                | KeyCode::ArrowLeft
+                | KeyCode::ArrowRight),
+            ) => {
+                if self.held == Some(code) && self.held_age <= HOLD_TICKS {
+                    self.repeating = true;
+                } else {
+                    self.repeating = false;
+                    self.enqueue(key);
+                }
+                self.held = Some(code);
+                self.held_age = 0;
+            }
+            DecodedKey::Unicode(c) if is_control_letter(c) => {
+                self.held = None;
+                let mut event = KeyEvent {
+                    key: DecodedKey::Unicode((c as u8 + b'a' - 1) as char),
+                    modifiers: self.modifiers,
+                    arrived: clock::cycles(),
+                };
+                event.modifiers.ctrl = true;
+                self.enqueue_event(event);
+            }
+            _ => {
+                self.held = None;
+                self.enqueue(key);
+            }
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.modifier_age += 1;
+        if self.modifier_age > HOLD_TICKS {
+            self.modifiers = Modifiers {
+                caps_lock: self.modifiers.caps_lock,
+                ..Modifiers::default()
+            };
+        }
+        if let Some(code) = self.held {
+            self.held_age += 1;
+            if self.held_age > HOLD_TICKS {
+                self.held = None;
+                self.repeating = false;
+            } else if self.repeating {
+                self.enqueue(DecodedKey::RawKey(code));
+            }
+        }
+    }
+
+    pub fn release(&mut self, code: KeyCode) {
+        if self.held == Some(code) {
+            self.held = None;
+            self.repeating = false;
+        }
+    }
+
+    // True when nothing is queued and no held key will repeat on a tick.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0 && self.held.is_none()
+    }
+
+    pub fn pop(&mut self) -> Option<KeyEvent> {
+        if self.len == 0 {
+            None
+        } else {
+            let event = self.events[self.head].take();
+            self.head = (self.head + 1) % QUEUE_SIZE;
+            self.len -= 1;
+            event
+        }
+    }
+
+    fn enqueue(&mut self, key: DecodedKey) {
+        self.enqueue_event(KeyEvent {
+            key,
+            modifiers: self.modifiers,
+            arrived: clock::cycles(),
+        });
+    }
+
+    fn enqueue_event(&mut self, event: KeyEvent) {
+        if self.len < QUEUE_SIZE {
+            self.events[(self.head + self.len) % QUEUE_SIZE] = Some(event);
+            self.len += 1;
+        }
+    }
+}
+
+// Ctrl+letter arrives as an ASCII control code when the decoder maps control
+// characters; backspace, tab, newline and carriage return keep their meaning.
+fn is_control_letter(c: char) -> bool {
+    ('\u{1}'..='\u{1a}').contains(&c) && !matches!(c, '\u{8}' | '\t' | '\n' | '\r')
+}
@@ -0,0 +1,110 @@
+use core::fmt::Write;
+
+use simple_interp::ArrayString;
+
+use crate::format::{Compact, Percent};
+use crate::process::ProcessTable;
+use crate::screen::{plot, ColorCode};
+use crate::storage::name_str;
+use crate::theme::Palette;
+use crate::wrapping_nav;
+use crate::LINE_WIDTH;
+
+const JOB_ROWS: usize = 7;
+const HEADER: &str = "# file       stat   vrun w  n g";
+const HELP: &str = "k:kill p:pause a:att d:det +/-";
+
+// A task manager: one row per process, with the selected one picked out.
+// The keys act on the selected process; see SwimInterface::handle_jobs_key.
+// Under the rows, the selected process's CPU time, the interpreter ticks it
+// has run, is set against its wall time, the frames since it started. The
+// share is of what those frames could have run, so a process that has had
+// a fair vruntime can still be seen to crawl beside busy neighbours.
+#[derive(Copy, Clone, Default)]
+pub struct Jobs {
+    selected: usize,
+}
+
+impl Jobs {
+    pub fn up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn down(&mut self, processes: &ProcessTable) {
+        if self.selected + 1 < processes.iter().count() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn selected_pid(&self, processes: &ProcessTable) -> Option<usize> {
+        processes.iter().nth(self.selected).map(|(pid, _)| pid)
+    }
+
+    pub fn draw(
+        &mut self,
+        processes: &ProcessTable,
+        now: u64,
+        ticks_per_frame: usize,
+        x: usize,
+        y: usize,
+        palette: &Palette,
+    ) {
+        let num_processes = processes.iter().count();
+        self.selected = wrapping_nav::clamp(self.selected, num_processes);
+        draw_row(HEADER, x, y, palette.border);
+        let mut rows = processes.iter();
+        for row in 0..JOB_ROWS {
+            let mut line: ArrayString<LINE_WIDTH> = ArrayString::default();
+            let mut color = palette.text;
+            if let Some((pid, process)) = rows.next() {
+                write!(
+                    line,
+                    "{} {:<10} {:<4} {:>6} ",
+                    pid,
+                    name_str(&process.file).unwrap_or("?"),
+                    process.state.label(),
+                    process.vruntime
+                )
+                .unwrap();
+                match process.window {
+                    Some(window) => write!(line, "F{} ", window + 1).unwrap(),
+                    None => write!(line, "-- ").unwrap(),
+                }
+                write!(line, "{}", process.nice).unwrap();
+                if let Some(group) = process.group {
+                    write!(line, " {}", group).unwrap();
+                }
+                if row == self.selected {
+                    color = palette.highlight;
+                }
+            }
+            draw_row(line.as_str().unwrap(), x, y + 1 + row, color);
+        }
+        let mut times: ArrayString<LINE_WIDTH> = ArrayString::default();
+        if let Some(process) = self
+            .selected_pid(processes)
+            .and_then(|pid| processes.get(pid))
+        {
+            let cpu = process.ticks as u64;
+            let wall = process.wall_ticks(now);
+            let share = Percent(cpu, wall * ticks_per_frame as u64);
+            write!(
+                times,
+                "cpu {} wall {} {:>4}",
+                Compact(cpu),
+                Compact(wall),
+                share
+            )
+            .unwrap();
+        }
+        draw_row(times.as_str().unwrap(), x, y + 1 + JOB_ROWS, palette.text);
+        draw_row(HELP, x, y + 2 + JOB_ROWS, palette.border);
+    }
+}
+
+fn draw_row(text: &str, x: usize, y: usize, color: ColorCode) {
+    let mut chars = text.chars();
+    for col in 0..LINE_WIDTH {
+        plot(chars.next().unwrap_or(' '), x + col, y, color);
+    }
+}
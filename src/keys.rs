@@ -0,0 +1,129 @@
+use core::fmt::Write;
+
+use file_system_solution::FileSystemError;
+use simple_interp::ArrayString;
+
+use crate::SwimInterface;
+
+// Where the shell's `keys` command writes the reference.
+pub const KEYS_FILE: &str = "keys.txt";
+const KEYS_BYTES: usize = 3072;
+
+// The keys each part of the kernel answers to, grouped by where they work,
+// as `keys` writes them out. The keys themselves are matched in lib.rs;
+// a binding added or changed there belongs here too.
+const SECTIONS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Anywhere",
+        &[
+            ("F1-F4", "focus a window; again for the next page"),
+            ("F6", "close the focused window"),
+            ("F7 F8", "pick a window in the task panel"),
+            ("Ctrl+B", "switch the window to bright"),
+            ("Ctrl+K", "lock the screen"),
+            ("Ctrl+L", "clear the window's output"),
+            ("Ctrl+P", "screenshot into shotN"),
+            ("Ctrl+Q", "mute windows out of focus"),
+            ("Ctrl+R", "start or stop recording the reel"),
+            ("Ctrl+V", "paste the copied line"),
+            ("Ctrl+W", "collapse the window to its header"),
+        ],
+    ),
+    (
+        "File list",
+        &[
+            ("Left Right", "move along the list"),
+            ("e", "edit the file"),
+            ("r", "run it here"),
+            ("R", "run it in another window"),
+            ("C", "run it in every free window"),
+            ("a", "run it, appending output to .out"),
+            ("v", "view it"),
+            ("f", "play a reel"),
+            ("d", "delete it, or the marked files"),
+            ("Space", "mark it"),
+            ("*", "star it"),
+            ("p", "protect it with a passphrase"),
+            ("/", "jump to a name"),
+            ("1-9", "pick a file by number"),
+            ("Enter", "go into a directory"),
+            ("Tab", "next volume"),
+            ("F5", "new file"),
+            ("Ctrl+O", "split to show the last run"),
+            ("s", "shell"),
+            ("j", "jobs"),
+            ("m", "file manager"),
+            ("o", "settings"),
+            ("b", "estimated block map"),
+            ("?", "guided tour"),
+            #[cfg(feature = "apps")]
+            ("c", "character map"),
+            #[cfg(feature = "apps")]
+            ("=", "calculator"),
+            #[cfg(feature = "apps")]
+            ("l", "Life"),
+            #[cfg(feature = "apps")]
+            ("t", "typing test"),
+            #[cfg(feature = "serial")]
+            ("u", "serial terminal"),
+        ],
+    ),
+    (
+        "Editor",
+        &[
+            ("Ctrl+S", "save"),
+            ("F5", "run the file"),
+            ("Ctrl+]", "go to the variable's definition"),
+            ("Ctrl+T", "go back from a jump"),
+            ("Ctrl+N", "rename the variable"),
+            ("Ctrl+Z", "undo the rename"),
+            ("Ctrl+U", "ruler"),
+            ("Ctrl+O", "split to show the last run"),
+        ],
+    ),
+    (
+        "Running program",
+        &[
+            ("Up Down", "pick an output line"),
+            ("Enter", "copy it, or send the input"),
+            ("Ctrl+Enter", "send several lines of input"),
+            ("PgUp PgDn", "page through the output"),
+            ("Ctrl+E", "run again on each save"),
+        ],
+    ),
+    (
+        "Shell",
+        &[
+            ("Tab", "complete a name"),
+            ("Up Down", "earlier commands"),
+            ("Ctrl+C", "drop the line or stop paging"),
+        ],
+    ),
+];
+
+impl<const N: usize> SwimInterface<N> {
+    // Writes every key binding to KEYS_FILE on `volume`, led by the keymap
+    // the keyboard is read with.
+    pub(crate) fn write_key_reference(&mut self, volume: usize) -> Result<(), FileSystemError> {
+        let mut text: ArrayString<KEYS_BYTES> = ArrayString::default();
+        write_reference(&mut text, self.settings.keymap.name()).ok();
+        let storage = self.mounts.volume(volume);
+        let fd = storage.open_create(KEYS_FILE)?;
+        let result = storage.write(fd, text.as_str().unwrap().as_bytes());
+        let result = result.and(storage.close(fd));
+        self.refresh_listing(volume);
+        result
+    }
+}
+
+fn write_reference<W: Write>(out: &mut W, keymap: &str) -> core::fmt::Result {
+    writeln!(out, "Keys, read with the {} keymap", keymap)?;
+    for (section, keys) in SECTIONS {
+        writeln!(out)?;
+        writeln!(out, "{}", section)?;
+        for (key, action) in keys.iter() {
+            writeln!(out, "{:<11}{}", key, action)?;
+        }
+    }
+    Ok(())
+}
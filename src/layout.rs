@@ -0,0 +1,77 @@
+use crate::{PAGE_WINDOWS, WINDOW_HEIGHT, WIN_REGION_WIDTH};
+
+// The part of the screen a window takes, its border included.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    // The column of the right border and the row of the bottom one.
+    pub const fn right(&self) -> usize {
+        self.x + self.width - 1
+    }
+
+    pub const fn bottom(&self) -> usize {
+        self.y + self.height - 1
+    }
+
+    // The part inside the border.
+    pub const fn interior(&self) -> Rect {
+        Rect {
+            x: self.x + 1,
+            y: self.y + 1,
+            width: self.width.saturating_sub(2),
+            height: self.height.saturating_sub(2),
+        }
+    }
+
+    pub const fn contains(&self, col: usize, row: usize) -> bool {
+        col >= self.x && col < self.x + self.width && row >= self.y && row < self.y + self.height
+    }
+}
+
+// A window takes its slot's cell of the two-by-two grid under the status
+// bar, on whichever page it is. Collapsed, it keeps only the top row of the
+// cell, for its header.
+pub const fn window_rect(window: usize, collapsed: bool) -> Rect {
+    let slot = window % PAGE_WINDOWS;
+    Rect {
+        x: (slot % 2) * (WIN_REGION_WIDTH / 2),
+        y: 1 + (slot / 2) * WINDOW_HEIGHT,
+        width: WIN_REGION_WIDTH / 2,
+        height: if collapsed { 1 } else { WINDOW_HEIGHT },
+    }
+}
+
+// A split window's interior, cut into the top two-thirds for what the
+// window holds and, under a one-row rule, the rest for the output of its
+// last run.
+pub const fn split_panes(interior: Rect) -> (Rect, Rect) {
+    let top = interior.height * 2 / 3;
+    (
+        Rect {
+            height: top,
+            ..interior
+        },
+        Rect {
+            y: interior.y + top + 1,
+            height: interior.height.saturating_sub(top + 1),
+            ..interior
+        },
+    )
+}
+
+// Screen rows each line of text takes. Text mode has no taller glyphs, so a
+// zoomed window spaces its lines out over two rows each instead, fitting
+// half as many.
+pub const fn line_spacing(zoomed: bool) -> usize {
+    if zoomed {
+        2
+    } else {
+        1
+    }
+}
@@ -1,37 +1,205 @@
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+pub use archive::{Archive, ArchiveError};
+#[cfg(feature = "serial")]
+pub use budget::send_report as send_memory_report;
 use buffer::TextEditor;
+pub use builder::SwimInterfaceBuilder;
+#[cfg(feature = "apps")]
+use calculator::Calculator;
+#[cfg(feature = "apps")]
+use charmap::CharMap;
+use config::{BLOCK_SIZE, MAX_FILES_STORED, MAX_FILE_BLOCKS, MAX_OPEN, NUM_BLOCKS};
 use core::{fmt::Write, usize};
-use file_system_solution::{FileSystem, FileSystemError};
-use gc_heap_template::GenerationalHeap;
-use num::Integer;
+use crashdump::KeyHistory;
+pub use events::Events;
+use file_system_solution::FileSystemError;
+#[cfg(feature = "shell")]
+use format::Bar;
+use format::{Compact, Percent};
+use input::{Compose, InputQueue, KeyEvent, Modifiers};
+use jobs::Jobs;
+use layout::Rect;
+#[cfg(feature = "apps")]
+use life::{Life, LIFE_PERIOD};
+use lock::Lock;
+use manager::FileManager;
+pub use metrics::{Frame, FrameMetrics, TickRate};
+use mount::{parse_path, volume_letter, MountTable, NUM_VOLUMES};
+use notify::Notifier;
+use open_files::{OpenFiles, OpenMode};
+use options::Options;
+use output::{copy_line, Output, Printer};
 use pc_keyboard::{DecodedKey, KeyCode};
-use pluggable_interrupt_os::vga_buffer::{
-        is_drawable, peek, plot, plot_num, plot_num_right_justified, plot_str, Color, ColorCode,
-    };
-use ramdisk::RamDisk;
-use simple_interp::{ArrayString, Interpreter, InterpreterOutput};
+use preview::Preview;
+use process::{InputKind, Process, ProcessState, ProcessTable, MAX_NICE, MAX_PROCESSES};
+use quota::Quota;
+use recorder::{Playback, Recorder};
+pub use replay::{InputSource, Step, StepList, TextScript};
+pub use samples::SAMPLE_FILES;
+use save::SaveTask;
+pub use scheduler::SchedulerPolicy;
+use screen::{
+    cp437_to_utf8, is_drawable, plot, plot_clipped, plot_str, plot_str_clipped, Color, ColorCode,
+    BUFFER_HEIGHT, BUFFER_WIDTH,
+};
+use selftest::SelfTest;
+#[cfg(feature = "serial")]
+pub use serial::init as init_serial;
+use session::{Session, SessionLog, SessionReplay};
+use settings::Settings;
+#[cfg(feature = "shell")]
+use shell::{Page, Shell};
+use simple_interp::ArrayString;
+pub use stack::paint as paint_stack;
+use storage::{
+    name_bytes, name_str, EditorPlace, RunStats, RunStatus, Storage, MAX_WINDOW_FILES, PROTECTED,
+    STARRED,
+};
+use stream::FileReader;
+#[cfg(feature = "serial")]
+use terminal::Terminal;
+use theme::Palette;
+pub use theme::Theme;
+use tour::Tour;
+#[cfg(feature = "apps")]
+use typing::TypingTest;
+use viewer::Viewer;
 
 use core::prelude::rust_2024::derive;
 
+mod archive;
+mod block_map;
+#[cfg(any(feature = "shell", feature = "serial"))]
+mod budget;
 mod buffer;
+mod builder;
+#[cfg(feature = "apps")]
+mod calculator;
+#[cfg(feature = "apps")]
+mod charmap;
+mod cipher;
+mod clock;
+mod config;
+mod crashdump;
+#[cfg(feature = "shell")]
+mod defrag;
+mod dirs;
+mod events;
+mod format;
+#[cfg(feature = "shell")]
+mod fsck;
+mod input;
+mod jobs;
+#[cfg(feature = "shell")]
+mod keys;
+mod layout;
+#[cfg(feature = "apps")]
+mod life;
+mod lock;
+mod manager;
+mod metrics;
+mod mount;
+mod notify;
+mod open_files;
+mod options;
+mod output;
+mod overlay;
+#[cfg(not(feature = "std"))]
+mod port;
+mod preview;
+mod process;
+mod quota;
+mod recorder;
+mod replay;
+mod samples;
+mod save;
+mod scheduler;
+mod screen;
+mod screenshot;
+mod selftest;
+#[cfg(feature = "serial")]
+mod serial;
+mod session;
+mod settings;
+#[cfg(feature = "shell")]
+mod shell;
+#[cfg(feature = "shell")]
+mod shutdown;
+#[cfg(feature = "soak")]
+mod soak;
+#[cfg(feature = "sound")]
+mod speaker;
+mod splash;
+mod stack;
+mod storage;
+mod stream;
+#[cfg(feature = "serial")]
+mod terminal;
+mod theme;
+mod timer;
+mod tour;
+#[cfg(feature = "apps")]
+mod typing;
+mod viewer;
+pub mod wrapping_nav;
+
+// Hooks for the host test suite in `tests/`; see the README.
+#[cfg(feature = "std")]
+pub mod testing {
+    pub use crate::buffer::TextEditor;
+    pub use crate::clock::advance;
+    pub use crate::format::{clip, left, right, Bar, Compact, Percent};
+    pub use crate::layout::Rect;
+    pub use crate::overlay::{close as close_overlay, draw_on, open as open_overlay};
+    pub use crate::screen::{
+        cells_presented, fake_screen, plot_str, plot_str_clipped, present, Color, ColorCode,
+        FakeScreen,
+    };
+    #[cfg(feature = "serial")]
+    pub use crate::serial::{serial_arrive, serial_sent};
+    #[cfg(feature = "shell")]
+    pub use crate::shutdown::{powered_off, was_reset};
+    #[cfg(feature = "soak")]
+    pub use crate::soak::Invariants;
+    #[cfg(feature = "sound")]
+    pub use crate::speaker::speaker_tone;
+    pub use crate::stack::use_stack;
+    pub use crate::storage::{Storage, DELETED, PROTECTED, STARRED};
+    pub use crate::timer::timer_divisor;
+
+    // The editor at the size the windows use.
+    pub type Editor = TextEditor<{ crate::LINE_WIDTH }, { crate::DOCUMENT_LENGTH }>;
+    pub const EDITOR_COLUMNS: usize = crate::LINE_WIDTH;
+    pub const EDITOR_ROWS: usize = crate::DOCUMENT_LENGTH;
+}
 
 const WIN_WIDTH: usize = (WIN_REGION_WIDTH - 4) / 2;
-const EDITOR_POSITION: [(usize, usize); 4] = [
-    (0, 1),
-    (WIN_REGION_WIDTH / 2, 1),
-    (0, 13),
-    (WIN_REGION_WIDTH / 2, 13),
-];
+// Windows tile two across and two down, filling the F1-F4 slots. A
+// SwimInterface with more than four has further pages of them in the same
+// places, and only the focused window's page is on screen.
+const PAGE_WINDOWS: usize = 4;
+const WINDOW_HEIGHT: usize = 12;
+// The rows inside a window's border, and the ones a split editor keeps
+// above its output.
+const WINDOW_ROWS: usize = WINDOW_HEIGHT - 2;
+// How many windows one frame repaints from scratch. A theme switch or page
+// change leaves the rest for the following ticks, so the frame stays
+// inside the timer period and keys are not held up behind it.
+const REPAINTS_PER_FRAME: usize = 2;
+// Frames without a key before a listing's type-ahead jump gives up, about a
+// second at the default timer rate.
+const JUMP_TIMEOUT: u64 = 18;
 const TASK_MANAGER_WIDTH: usize = 10;
 const WIN_REGION_WIDTH: usize = 80 - TASK_MANAGER_WIDTH;
-const MAX_OPEN: usize = 16;
-const BLOCK_SIZE: usize = 256;
-const NUM_BLOCKS: usize = 255;
-const MAX_FILE_BLOCKS: usize = 64;
 const MAX_FILE_BYTES: usize = MAX_FILE_BLOCKS * BLOCK_SIZE;
-const MAX_FILES_STORED: usize = 30;
 const MAX_FILENAME_BYTES: usize = 10;
+const MAX_PATH_BYTES: usize = MAX_FILENAME_BYTES + 2;
+const MAX_PASSPHRASE: usize = MAX_PATH_BYTES;
+const FILENAME_MESSAGE_WIDTH: usize = 34 - MAX_PATH_BYTES;
+// The status bar up to where the modifier keys are shown.
+const STATUS_PROMPT_WIDTH: usize = 16 + MAX_PATH_BYTES + FILENAME_MESSAGE_WIDTH;
+const LISTING_CELL_WIDTH: usize = MAX_FILENAME_BYTES + 1;
 const DOCUMENT_LENGTH: usize = 40;
 const MAX_TOKENS: usize = 100;
 const MAX_LITERAL_CHARS: usize = 15;
@@ -41,40 +209,92 @@ const HEAP_SIZE: usize = 256;
 const MAX_HEAP_BLOCKS: usize = HEAP_SIZE;
 const SCHED_LATENCY: usize = 24;
 const LINE_WIDTH: usize = WIN_WIDTH - 2;
+const OUTPUT_SUFFIX: &str = ".out";
+// The code page 437 bullet.
+const ACTIVITY_BADGE: char = 7u8 as char;
 
-pub struct SwimInterface {
-    windows: [Window; 4],
-    filesystem: FileSystem<
-        MAX_OPEN,
-        BLOCK_SIZE,
-        NUM_BLOCKS,
-        MAX_FILE_BLOCKS,
-        MAX_FILE_BYTES,
-        MAX_FILES_STORED,
-        MAX_FILENAME_BYTES,
-    >,
+pub struct SwimInterface<const N: usize = 4> {
+    windows: [Window; N],
+    mounts: MountTable,
+    open_files: OpenFiles<N>,
     focused_editor: usize,
-    num_files: usize,
+    num_files: [usize; NUM_VOLUMES],
+    processes: ProcessTable,
     running_countdown: usize,
     current_process: usize,
-    filename_input: ArrayString<MAX_FILENAME_BYTES>,
+    filename_input: ArrayString<MAX_PATH_BYTES>,
+    filename_message: ArrayString<80>,
+    overwrite_pending: bool,
     creating_file: bool,
+    confirming_delete: bool,
+    // Shift+R in a listing waits for the number of the window to run the
+    // focused file in.
+    choosing_window: bool,
+    // After `/` in a listing, the start of a name typed so far and the frame
+    // of the last key; see jump_to_prefix.
+    jump: Option<(ArrayString<MAX_FILENAME_BYTES>, u64)>,
+    // The window picked in the task panel with F7 and F8, whose process the
+    // next p, k, + or - acts on.
+    panel_selected: Option<usize>,
+    input: InputQueue,
+    compose: Compose,
+    saves: [Option<SaveTask>; N],
+    settings: Settings,
+    // The settings' theme as color codes, for the status bar and task panel.
+    palette: Palette,
+    metrics: FrameMetrics,
+    tick_rate: TickRate,
+    cursor_shown: bool,
+    // Where focus was before follow_input moved it to a program asking for
+    // input; it goes back there once the input is entered.
+    return_focus: Option<usize>,
+    // The last output line copied, for Ctrl+V to paste.
+    clipboard: ArrayString<LINE_WIDTH>,
+    // The window focused before the current one, which a character map
+    // opened now types into.
+    previous_focus: usize,
+    // Ticks left to show the boot splash; the layout is drawn once it ends.
+    splash: usize,
+    // The boot self-test's report, shown in the splash's place if it ran.
+    self_test: Option<SelfTest>,
+    // Set while the screen is locked; see Lock.
+    lock: Option<Lock>,
+    // Set by the shell's `shutdown`, after which nothing more runs.
+    shut_down: bool,
+    // Set by Ctrl+Q: only the focused window draws its program's output.
+    // The others keep collecting it and show it once unmuted or focused.
+    muted: bool,
+    // Set while Ctrl+R is recording the screen; see Recorder.
+    recorder: Option<Recorder>,
+    // A reel being played back over the screen.
+    playback: Option<Playback>,
+    // The session log `session=record` is writing, or the one
+    // `session=replay` is playing back in place of the keyboard.
+    session_log: Option<SessionLog>,
+    session_replay: Option<SessionReplay>,
+    // The last keys handled, for a crash dump.
+    key_history: KeyHistory,
+    // A passphrase being typed on the status bar for a protected file.
+    passphrase: Option<PassphrasePrompt>,
+    // A new name being typed on the status bar for an editor's variable.
+    renaming: Option<RenamePrompt>,
+    notifier: Notifier,
+    // The sample programs put on the disk at boot, for `reset samples`.
+    #[cfg(feature = "shell")]
+    samples: &'static [(&'static str, &'static str)],
 }
 
-impl Default for SwimInterface {
-    fn default() -> Self {
-        let mut filesystem = FileSystem::new(RamDisk::new());
-        create_default("hello", r#"print("Hello, world!")"#, &mut filesystem);
-        create_default(
-            "nums",
-            r#"print(1)
+pub const DEFAULT_FILES: &[(&str, &str)] = &[
+    ("hello", r#"print("Hello, world!")"#),
+    (
+        "nums",
+        r#"print(1)
 print(257)
             "#,
-            &mut filesystem,
-        );
-        create_default(
-            "average",
-            r#"sum := 0
+    ),
+    (
+        "average",
+        r#"sum := 0
 count := 0
 averaging := true
 while averaging {
@@ -88,11 +308,10 @@ while averaging {
 }
 print((sum / count))
             "#,
-            &mut filesystem,
-        );
-        create_default(
-            "pi",
-            r#"sum := 0
+    ),
+    (
+        "pi",
+        r#"sum := 0
 i := 0
 neg := false
 terms := input("Num terms:")
@@ -107,562 +326,3761 @@ while (i < terms) {
 }
 print((4 * sum))
             "#,
-            &mut filesystem,
-        );
-        let windows = [
-            Window::make(EDITOR_POSITION[0].0, EDITOR_POSITION[0].1),
-            Window::make(EDITOR_POSITION[1].0, EDITOR_POSITION[1].1),
-            Window::make(EDITOR_POSITION[2].0, EDITOR_POSITION[2].1),
-            Window::make(EDITOR_POSITION[3].0, EDITOR_POSITION[3].1),
-        ];
-        Self {
-            windows,
-            filesystem,
-            focused_editor: 0,
-            num_files: 4,
-            running_countdown: 0,
-            current_process: 0,
-            filename_input: ArrayString::default(),
-            creating_file: false,
-        }
+    ),
+];
+
+impl Default for SwimInterface {
+    fn default() -> Self {
+        SwimInterfaceBuilder::default().files(DEFAULT_FILES).build()
     }
 }
 
-fn create_default(
-    filename: &str,
-    contents: &str,
-    filesystem: &mut FileSystem<
-        MAX_OPEN,
-        BLOCK_SIZE,
-        NUM_BLOCKS,
-        MAX_FILE_BLOCKS,
-        MAX_FILE_BYTES,
-        MAX_FILES_STORED,
-        MAX_FILENAME_BYTES,
-    >,
-) {
-    if let Ok(fd) = filesystem.open_create(filename) {
-        if let Ok(()) = filesystem.write(fd, contents.as_bytes()) {
-            filesystem.close(fd).unwrap_or(());
+fn create_default(filename: &str, contents: &[u8], storage: &mut Storage) {
+    if let Ok(fd) = storage.open_create(filename) {
+        if let Ok(()) = storage.write(fd, contents) {
+            storage.close(fd).unwrap_or(());
         }
     }
 }
 
+// Whether a process's input or log, or a save under way, is using `fd` on
+// `volume`.
+fn fd_held(processes: &ProcessTable, saves: &[Option<SaveTask>], volume: usize, fd: usize) -> bool {
+    let open = Some((volume, fd));
+    processes
+        .iter()
+        .any(|(_, p)| p.stdin == open || p.log == open)
+        || saves
+            .iter()
+            .flatten()
+            .any(|s| s.volume == volume && s.fd() == Some(fd))
+}
+
 pub fn safe_add<const LIMIT: usize>(a: usize, b: usize) -> usize {
-    (a + b).mod_floor(&LIMIT)
+    wrapping_nav::offset(a, b, LIMIT)
 }
 
 pub fn add1<const LIMIT: usize>(value: usize) -> usize {
-    safe_add::<LIMIT>(value, 1)
+    wrapping_nav::next(value, LIMIT)
 }
 
 pub fn sub1<const LIMIT: usize>(value: usize) -> usize {
-    safe_add::<LIMIT>(value, LIMIT - 1)
+    wrapping_nav::prev(value, LIMIT)
 }
 
 impl SwimInterface {
+    pub fn builder<'a>() -> SwimInterfaceBuilder<'a> {
+        SwimInterfaceBuilder::default()
+    }
+}
+
+impl<const N: usize> SwimInterface<N> {
+    fn new(
+        files: &[(&str, &str)],
+        archive: &[u8],
+        samples: &'static [(&'static str, &'static str)],
+        theme: Theme,
+        scheduler: SchedulerPolicy,
+    ) -> Self {
+        let mut mounts = MountTable::default();
+        for (name, contents) in files.iter().chain(samples) {
+            create_default(name, contents.as_bytes(), mounts.volume(0));
+        }
+        for (name, contents) in Archive::new(archive).map_while(Result::ok) {
+            create_default(name, contents, mounts.volume(0));
+        }
+        Self::boot(mounts, samples, theme, scheduler)
+    }
+
+    // Starts everything but the volumes afresh: at power-on once the files
+    // are on the disk, and again on a warm `reboot`.
+    #[cfg_attr(not(feature = "shell"), allow(unused_variables))]
+    fn boot(
+        mut mounts: MountTable,
+        samples: &'static [(&'static str, &'static str)],
+        theme: Theme,
+        scheduler: SchedulerPolicy,
+    ) -> Self {
+        for volume in 0..NUM_VOLUMES {
+            mounts.volume(volume).load_metadata();
+            mounts.volume(volume).clear_save_scratch();
+        }
+        let num_files = core::array::from_fn(|volume| {
+            mounts.volume(volume).list_directory().map_or(0, |(n, _)| n)
+        });
+        let windows = core::array::from_fn(|window| {
+            let rect = layout::window_rect(window, false);
+            Window {
+                palette: theme.palette(),
+                ..Window::make(rect.x, rect.y)
+            }
+        });
+        let mut interface = Self {
+            windows,
+            mounts,
+            open_files: OpenFiles::default(),
+            focused_editor: 0,
+            num_files,
+            processes: ProcessTable::default(),
+            running_countdown: 0,
+            current_process: 0,
+            filename_input: ArrayString::default(),
+            filename_message: ArrayString::default(),
+            overwrite_pending: false,
+            creating_file: false,
+            confirming_delete: false,
+            choosing_window: false,
+            jump: None,
+            panel_selected: None,
+            input: InputQueue::default(),
+            compose: Compose::default(),
+            saves: [None; N],
+            settings: Settings::new(theme, scheduler),
+            palette: theme.palette(),
+            metrics: FrameMetrics::default(),
+            tick_rate: TickRate::default(),
+            cursor_shown: true,
+            return_focus: None,
+            clipboard: ArrayString::default(),
+            previous_focus: 0,
+            splash: 0,
+            self_test: None,
+            lock: None,
+            shut_down: false,
+            muted: false,
+            recorder: None,
+            playback: None,
+            session_log: None,
+            session_replay: None,
+            key_history: KeyHistory::default(),
+            passphrase: None,
+            renaming: None,
+            notifier: Notifier::default(),
+            #[cfg(feature = "shell")]
+            samples,
+        };
+        interface.load_config();
+        interface.open_crashdump();
+        interface.publish_outputs();
+        interface
+    }
+
     pub fn tick(&mut self) {
+        if self.shut_down {
+            return;
+        }
+        self.replay_session();
+        self.log_session(Step::Tick);
+        if self.splash > 0 {
+            match &self.self_test {
+                Some(report) => report.draw(&self.palette),
+                None => splash::draw_splash(&self.palette),
+            }
+            self.splash -= 1;
+            if self.splash == 0 {
+                self.redraw_all();
+            }
+            screen::present();
+            return;
+        }
+        let cells_before = screen::cells_plotted();
+        let written_before = screen::cells_presented();
+        self.input.tick();
+        self.mounts.set_opener(Some(self.focused_editor));
+        self.process_input();
+        self.advance_saves();
+        self.mounts.set_opener(None);
+        if self
+            .jump
+            .is_some_and(|(_, typed)| self.metrics.frames - typed > JUMP_TIMEOUT)
+        {
+            self.jump = None;
+        }
+        #[cfg(feature = "apps")]
+        self.advance_life();
+        self.notifier.tick();
+        let draw_start = clock::cycles();
+        // Windows keep drawing while locked; the lock covers them.
         self.draw_current();
-        let mut program_to_tick = 4;
-        if self.running_countdown > 0 {
-            if self.windows[self.current_process].state == WindowState::Running {
-                if let Some(interpreter) = &self.windows[self.current_process].interpreter {
-                    if !interpreter.blocked_on_input() && !interpreter.completed() {
-                        program_to_tick = self.current_process;
-                    }
+        self.draw_playback();
+        match &self.lock {
+            Some(lock) => lock.draw(&self.palette),
+            None => self.notifier.draw(),
+        }
+        let draw_cycles = clock::cycles() - draw_start;
+        self.metrics.keys.drawn(clock::cycles());
+        self.advance_recording();
+        let interp_start = clock::cycles();
+        for _ in 0..self.settings.ticks_per_frame {
+            self.run_next_process();
+        }
+        self.publish_outputs();
+        self.find_leaks();
+        screen::present();
+        self.metrics.record(Frame {
+            cells_plotted: screen::cells_plotted() - cells_before,
+            cells_written: screen::cells_presented() - written_before,
+            draw_cycles,
+            interp_cycles: clock::cycles() - interp_start,
+        });
+    }
+
+    // Keeps `winN.out` on volume a: in step with window N's program output,
+    // copying it only when it has changed. A window with no program has an
+    // empty file.
+    fn publish_outputs(&mut self) {
+        for window in 0..N.min(MAX_WINDOW_FILES) {
+            let mut name: ArrayString<MAX_FILENAME_BYTES> = ArrayString::default();
+            write!(name, "win{}.out", window + 1).unwrap();
+            let name = name.as_str().unwrap();
+            let process = self
+                .window_pid(window)
+                .and_then(|pid| self.processes.get(pid));
+            let storage = self.mounts.volume(0);
+            match process {
+                Some(process) if process.output.is_dirty() => {
+                    storage.publish(name, |out| process.output.write_text(out));
+                }
+                Some(_) => {}
+                None if !matches!(storage.file_size(name), Ok(0)) => {
+                    storage.publish(name, |_| Ok(()));
                 }
+                None => {}
+            }
+        }
+    }
+
+    fn run_next_process(&mut self) {
+        let mut program_to_tick = MAX_PROCESSES;
+        if self.running_countdown > 0 {
+            if self
+                .processes
+                .get(self.current_process)
+                .map_or(false, Process::is_ready)
+            {
+                program_to_tick = self.current_process;
             }
             self.running_countdown -= 1;
         } else {
-            let (_, p, program_count) = self.min_vruntime();
+            let (_, p, program_count) = match self.settings.scheduler {
+                SchedulerPolicy::Fair { .. } => self.min_vruntime(),
+                SchedulerPolicy::RoundRobin { .. } => self.next_in_turn(),
+            };
             program_to_tick = p;
-            if program_to_tick != 4 {
+            if program_to_tick != MAX_PROCESSES {
                 self.current_process = program_to_tick;
-                self.running_countdown = SCHED_LATENCY / program_count;
+                self.running_countdown = self.settings.scheduler.time_slice(program_count);
             }
         }
-        if program_to_tick != 4 {
-            if let Some(mut interpreter) = self.windows[program_to_tick].interpreter {
-                //print!("{}", interpreter.completed);
-                match interpreter.tick(&mut self.windows[program_to_tick]) {
+        let mut asking_window = None;
+        if let Some(process) = self.processes.get_mut(program_to_tick) {
+            if let Some(mut interpreter) = process.interpreter {
+                let mut finished = false;
+                process.mirror = self.settings.mirror;
+                match interpreter.tick(process) {
                     simple_interp::TickStatus::Continuing => {
-                        self.windows[program_to_tick].interpreter = Some(interpreter);
-                    },
+                        process.interpreter = Some(interpreter);
+                    }
                     simple_interp::TickStatus::Finished => {
-                        self.windows[program_to_tick].print("[DONE] ".as_bytes());
-                        self.windows[program_to_tick].interpreter = None;
+                        process.interpreter = None;
+                        process.state = ProcessState::Finished;
+                        finished = true;
                     }
                     simple_interp::TickStatus::AwaitInput => {
-                        self.windows[program_to_tick].input_buffer = Default::default();
-                        self.windows[program_to_tick].taking_input = true;
-                        if self.windows[program_to_tick].interpreter_print_loc == 10 {
-                            for row in self.windows[program_to_tick].window_y + 1
-                                ..self.windows[program_to_tick].interpreter_print_loc
-                                    + self.windows[program_to_tick].window_y
-                            {
-                                for col in self.windows[program_to_tick].window_x + 1
-                                    ..WIN_WIDTH + self.windows[program_to_tick].window_x
-                                {
-                                    let (c, color) = peek(col, row + 1);
-                                    plot(c, col, row, color);
-                                }
-                            }
-                            for col in self.windows[program_to_tick].window_x + 1
-                                ..WIN_WIDTH + self.windows[program_to_tick].window_x - 1
-                            {
-                                plot(
-                                    ' ',
-                                    col,
-                                    self.windows[program_to_tick].interpreter_print_loc
-                                        + self.windows[program_to_tick].window_y,
-                                    ColorCode::new(Color::Black, Color::Black),
-                                );
-                            }
-                            self.windows[program_to_tick].interpreter_print_loc -= 1;
+                        process.await_input();
+                        process.interpreter = Some(interpreter);
+                        asking_window = process.window;
+                    }
+                }
+                process.page(self.settings.more);
+                process.ticks += 1;
+                if !finished && self.settings.timeout > 0 && process.ticks >= self.settings.timeout
+                {
+                    process.output.end_line();
+                    writeln!(process.output, "timed out").unwrap();
+                    process.interpreter = None;
+                    process.crashed = true;
+                    process.state = ProcessState::Finished;
+                    finished = true;
+                }
+                // Badge the header of a window working out of sight.
+                match process.window {
+                    Some(window) if window != self.focused_editor => {
+                        if core::mem::take(&mut process.printed) || finished {
+                            self.windows[window].activity = true;
+                        }
+                    }
+                    _ => process.printed = false,
+                }
+                process.vruntime += 1 << process.nice;
+                self.flush_log(program_to_tick, finished);
+                if finished {
+                    self.close_input(program_to_tick);
+                    let crashed = self
+                        .processes
+                        .get(program_to_tick)
+                        .is_some_and(|p| p.crashed);
+                    let status = if crashed {
+                        RunStatus::TimedOut
+                    } else {
+                        RunStatus::Finished
+                    };
+                    self.record_run(program_to_tick, status);
+                    let window = self.processes.get(program_to_tick).and_then(|p| p.window);
+                    if crashed {
+                        if let Err(e) = self.write_crashdump(program_to_tick) {
+                            let mut toast: ArrayString<WIN_REGION_WIDTH> = ArrayString::default();
+                            write!(toast, "crash dump failed: {}", e).unwrap();
+                            self.notifier.notify(toast.as_str().unwrap(), false);
                         }
-                        self.windows[program_to_tick].interpreter = Some(interpreter);
+                    }
+                    if let (true, Some(window)) = (crashed, window) {
+                        self.release_descriptors(window);
+                    }
+                }
+                if let (true, Some(process)) = (finished, self.processes.get_mut(program_to_tick)) {
+                    process.output.end_line();
+                    writeln!(process.output, "[DONE]").unwrap();
+                    if let Some(window) = process.window.filter(|w| *w != self.focused_editor) {
+                        let mut toast: ArrayString<WIN_REGION_WIDTH> = ArrayString::default();
+                        write!(
+                            toast,
+                            "window {}: {} finished, {} ticks",
+                            window + 1,
+                            name_str(&process.file).unwrap_or(""),
+                            process.ticks
+                        )
+                        .unwrap();
+                        self.notifier
+                            .notify(toast.as_str().unwrap(), self.settings.beep);
                     }
                 }
-                self.windows[program_to_tick].vruntime += 1;
+                self.feed_input(program_to_tick);
+            }
+        }
+        match asking_window {
+            Some(window)
+                if self.settings.follow_input
+                    && !self.creating_file
+                    && self.passphrase.is_none()
+                    && window != self.focused_editor =>
+            {
+                let previous = self.return_focus.unwrap_or(self.focused_editor);
+                self.focus_window(window);
+                self.return_focus = Some(previous);
             }
+            _ => {}
         }
     }
 
-    fn min_vruntime(&mut self) -> (usize, usize, usize) {
+    pub fn frame_metrics(&self) -> &FrameMetrics {
+        &self.metrics
+    }
+
+    // `drain` reports how many timer interrupts came in and how many of
+    // them it ran as ticks, against the rate the config set the PIT to.
+    fn count_ticks(&mut self, arrived: usize, ran: usize) {
+        let hz = timer::hz(self.settings.pit_divisor);
+        self.tick_rate.count(arrived, ran, hz);
+    }
+
+    // Nothing to do until the next key: no program is ready to run, no key
+    // is waiting to be handled and no save is in progress. The kernel's main
+    // loop halts the CPU while this holds.
+    pub fn is_idle(&self) -> bool {
+        self.input.is_empty()
+            && self.saves.iter().all(|s| s.is_none())
+            && self
+                .processes
+                .iter()
+                .all(|(_, process)| !process.is_ready())
+    }
+
+    fn min_vruntime(&self) -> (usize, usize, usize) {
         let mut min_vruntime = usize::MAX;
-        let mut program_to_tick = 4;
+        let mut program_to_tick = MAX_PROCESSES;
         let mut num_programs = 0;
-        for i in 0..4 {
-            if self.windows[i].state == WindowState::Running {
-                if let Some(interpreter) = &self.windows[i].interpreter {
-                    if !interpreter.blocked_on_input() {
-                        if self.windows[i].vruntime < min_vruntime {
-                            min_vruntime = self.windows[i].vruntime;
-                            program_to_tick = i;
-                        }
-                        num_programs += 1;
-                    }
-                }
+        for (pid, process) in self.processes.iter().filter(|(_, p)| p.is_ready()) {
+            if process.vruntime < min_vruntime {
+                min_vruntime = process.vruntime;
+                program_to_tick = pid;
             }
+            num_programs += 1;
         }
         if min_vruntime == usize::MAX {
-            (0, 4, 0)
+            (0, MAX_PROCESSES, 0)
         } else {
             (min_vruntime, program_to_tick, num_programs)
         }
     }
 
+    fn next_in_turn(&self) -> (usize, usize, usize) {
+        let ready = |pid: usize| self.processes.get(pid).map_or(false, Process::is_ready);
+        let num_programs = (0..MAX_PROCESSES).filter(|pid| ready(*pid)).count();
+        match (1..=MAX_PROCESSES)
+            .map(|by| wrapping_nav::offset(self.current_process, by, MAX_PROCESSES))
+            .find(|pid| ready(*pid))
+        {
+            Some(program) => (0, program, num_programs),
+            None => (0, MAX_PROCESSES, 0),
+        }
+    }
+
+    // The process shown in `window`, if any.
+    fn window_pid(&self, window: usize) -> Option<usize> {
+        self.processes
+            .iter()
+            .find(|(_, p)| p.window == Some(window))
+            .map(|(pid, _)| pid)
+    }
+
+    fn window_process(&self, window: usize) -> Option<&Process> {
+        self.processes.get(self.window_pid(window)?)
+    }
+
     fn draw_current(&mut self) {
+        let cursor = self.settings.cursor_shown(self.metrics.frames);
+        if cursor != self.cursor_shown {
+            self.cursor_shown = cursor;
+            for pid in 0..MAX_PROCESSES {
+                match self.processes.get_mut(pid) {
+                    Some(process) if process.state == ProcessState::AwaitingInput => {
+                        process.output.mark_dirty()
+                    }
+                    _ => {}
+                }
+            }
+        }
         match self.windows[self.focused_editor].state {
+            WindowState::Editing if self.renaming.is_some() => {
+                let mut prompt: ArrayString<{ 2 * LINE_WIDTH + 32 }> = ArrayString::default();
+                if let Some(renaming) = &self.renaming {
+                    write!(
+                        prompt,
+                        "Rename {} ({} uses) to: {}",
+                        renaming.from.as_str().unwrap(),
+                        renaming.uses,
+                        renaming.typed.as_str().unwrap()
+                    )
+                    .unwrap();
+                }
+                let mut chars = prompt.as_str().unwrap().chars();
+                for col in 0..STATUS_PROMPT_WIDTH {
+                    let color = if cursor && col == prompt.len() {
+                        self.palette.highlight
+                    } else {
+                        self.palette.text
+                    };
+                    plot(chars.next().unwrap_or(' '), col, 0, color);
+                }
+            }
             WindowState::Editing => {
-                plot_str(
-                    "Editing ",
-                    0,
-                    0,
-                    ColorCode::new(Color::LightCyan, Color::Black),
-                );
+                plot_str("Editing ", 0, 0, self.palette.text);
                 plot_str(
                     core::str::from_utf8(&self.windows[self.focused_editor].current_file).unwrap(),
                     8,
                     0,
-                    ColorCode::new(Color::LightCyan, Color::Black),
+                    self.palette.text,
                 );
+                // Clears what a rename prompt left.
+                for col in 8 + MAX_FILENAME_BYTES..STATUS_PROMPT_WIDTH {
+                    plot(' ', col, 0, self.palette.text);
+                }
             }
-            WindowState::Running => {
-                if self.windows[self.focused_editor].taking_input {
-                    plot_str(
-                        "Awaiting Input    ",
-                        0,
-                        0,
-                        ColorCode::new(Color::LightCyan, Color::Black),
-                    );
-                } else {
-                    plot_str(
-                        "Running ",
-                        0,
-                        0,
-                        ColorCode::new(Color::LightCyan, Color::Black),
-                    );
+            WindowState::Running => match self.window_process(self.focused_editor) {
+                Some(process) if process.state == ProcessState::AwaitingInput => {
+                    plot_str("Awaiting Input    ", 0, 0, self.palette.text);
+                }
+                Some(process) => {
+                    plot_str("Running ", 0, 0, self.palette.text);
                     plot_str(
-                        core::str::from_utf8(&self.windows[self.focused_editor].current_file)
-                            .unwrap(),
+                        core::str::from_utf8(&process.file).unwrap(),
                         8,
                         0,
-                        ColorCode::new(Color::LightCyan, Color::Black),
+                        self.palette.text,
                     );
                 }
-            }
-            WindowState::Listing => {
+                None => {}
+            },
+            WindowState::Viewing => {
+                plot_str("Viewing ", 0, 0, self.palette.text);
                 plot_str(
-                    "F5 - Filename: ",
-                    0,
+                    core::str::from_utf8(&self.windows[self.focused_editor].current_file).unwrap(),
+                    8,
                     0,
-                    ColorCode::new(Color::LightCyan, Color::Black),
+                    self.palette.text,
                 );
+            }
+            #[cfg(feature = "shell")]
+            WindowState::Shell => {
+                plot_str("Shell             ", 0, 0, self.palette.text);
+            }
+            WindowState::BlockMap => {
+                plot_str("Block map (est.)  ", 0, 0, self.palette.text);
+            }
+            WindowState::Jobs => {
+                plot_str("Jobs              ", 0, 0, self.palette.text);
+            }
+            WindowState::Settings => {
+                plot_str("Settings          ", 0, 0, self.palette.text);
+            }
+            WindowState::Tour => {
+                plot_str("Guided tour       ", 0, 0, self.palette.text);
+            }
+            WindowState::FileManager => {
+                plot_str("File manager      ", 0, 0, self.palette.text);
+            }
+            #[cfg(feature = "apps")]
+            WindowState::CharMap => {
+                plot_str("Characters        ", 0, 0, self.palette.text);
+            }
+            #[cfg(feature = "apps")]
+            WindowState::Calculator => {
+                plot_str("Calculator        ", 0, 0, self.palette.text);
+            }
+            #[cfg(feature = "apps")]
+            WindowState::Life => {
+                plot_str("Life              ", 0, 0, self.palette.text);
+            }
+            #[cfg(feature = "apps")]
+            WindowState::Typing => {
+                plot_str("Typing test       ", 0, 0, self.palette.text);
+            }
+            #[cfg(feature = "serial")]
+            WindowState::Terminal => {
+                plot_str("Serial terminal   ", 0, 0, self.palette.text);
+            }
+            WindowState::Listing if self.passphrase.is_some() => {
+                let typed = self.passphrase.map_or(0, |p| p.typed.len());
+                plot_str("Passphrase:    ", 0, 0, self.palette.text);
+                for i in 0..MAX_PATH_BYTES {
+                    let c = if i < typed { '*' } else { ' ' };
+                    plot(c, 15 + i, 0, self.palette.text);
+                }
+                if cursor && typed < MAX_PASSPHRASE {
+                    plot(' ', 15 + typed, 0, self.palette.highlight);
+                }
+                for i in 0..FILENAME_MESSAGE_WIDTH {
+                    plot(' ', 16 + MAX_PATH_BYTES + i, 0, self.palette.error);
+                }
+            }
+            WindowState::Listing if self.confirming_delete => {
+                let mut prompt: ArrayString<50> = ArrayString::default();
+                write!(
+                    prompt,
+                    "Delete {} file(s)? (y/n)",
+                    self.windows[self.focused_editor]
+                        .delete_targets()
+                        .iter()
+                        .filter(|t| **t)
+                        .count()
+                )
+                .unwrap();
+                plot_str(prompt.as_str().unwrap(), 0, 0, self.palette.error);
+                for i in prompt.len()..16 + MAX_PATH_BYTES + FILENAME_MESSAGE_WIDTH {
+                    plot(' ', i, 0, self.palette.error);
+                }
+            }
+            WindowState::Listing if self.windows[self.focused_editor].typed_index.is_some() => {
+                let mut prompt: ArrayString<50> = ArrayString::default();
+                match self.windows[self.focused_editor].typed_index {
+                    Some(0) | None => write!(prompt, "File number: "),
+                    Some(index) => write!(prompt, "File number: {}", index),
+                }
+                .unwrap();
+                plot_str(prompt.as_str().unwrap(), 0, 0, self.palette.text);
+                for i in prompt.len()..STATUS_PROMPT_WIDTH {
+                    plot(' ', i, 0, self.palette.text);
+                }
+            }
+            WindowState::Listing if self.jump.is_some() => {
+                let mut prompt: ArrayString<50> = ArrayString::default();
+                if let Some((prefix, _)) = &self.jump {
+                    write!(prompt, "Jump to: {}", prefix.as_str().unwrap()).unwrap();
+                }
+                plot_str(prompt.as_str().unwrap(), 0, 0, self.palette.text);
+                for i in prompt.len()..STATUS_PROMPT_WIDTH {
+                    plot(' ', i, 0, self.palette.text);
+                }
+            }
+            WindowState::Listing if self.choosing_window => {
+                let mut prompt: ArrayString<50> = ArrayString::default();
+                write!(prompt, "Run in window? (1-{})", N.min(9)).unwrap();
+                plot_str(prompt.as_str().unwrap(), 0, 0, self.palette.text);
+                for i in prompt.len()..STATUS_PROMPT_WIDTH {
+                    plot(' ', i, 0, self.palette.text);
+                }
+            }
+            WindowState::Listing => {
+                plot_str("F5 - Filename: ", 0, 0, self.palette.text);
                 plot_str(
                     self.filename_input.as_str().unwrap(),
                     15,
                     0,
-                    ColorCode::new(Color::LightCyan, Color::Black),
+                    self.palette.text,
                 );
-                for i in self.filename_input.len()..MAX_FILENAME_BYTES {
+                for i in self.filename_input.len()..MAX_PATH_BYTES {
+                    plot(' ', 15 + i, 0, self.palette.text);
+                }
+                if cursor && self.filename_input.len() < MAX_PATH_BYTES {
                     plot(
                         ' ',
-                        15 + i,
+                        15 + self.filename_input.len(),
                         0,
-                        ColorCode::new(Color::LightCyan, Color::Black),
+                        self.palette.highlight,
                     );
                 }
+                let message = self.filename_message.as_str().unwrap();
+                let message = &message[..message.len().min(FILENAME_MESSAGE_WIDTH)];
+                plot_str(message, 16 + MAX_PATH_BYTES, 0, self.palette.error);
+                for i in message.len()..FILENAME_MESSAGE_WIDTH {
+                    plot(' ', 16 + MAX_PATH_BYTES + i, 0, self.palette.error);
+                }
             }
         }
-        for i in 0..4 {
-            self.draw_outline(
-                EDITOR_POSITION[i].0,
-                EDITOR_POSITION[i].1,
-                i == self.focused_editor,
-            );
-            plot(
-                'F',
-                EDITOR_POSITION[i].0 + 3,
-                EDITOR_POSITION[i].1,
-                ColorCode::new(Color::Green, Color::Black),
-            );
-            plot_num(
-                (i + 1) as isize,
-                EDITOR_POSITION[i].0 + 4,
-                EDITOR_POSITION[i].1,
-                ColorCode::new(Color::Green, Color::Black),
-            );
-            if i == self.focused_editor {
-                match self.windows[i].state {
-                    WindowState::Listing => {
-                        plot_str(
-                            " (e)dit (r)unÍÍÍÍÍÍÍÍÍÍÍÍÍÍÍÍ",
-                            EDITOR_POSITION[i].0 + 5,
-                            EDITOR_POSITION[i].1,
-                            ColorCode::new(Color::Green, Color::Black),
-                        );
-                    }
-                    _ => {
-                        for j in 0..10 {
-                            plot(
-                                self.windows[self.focused_editor].current_file[j] as char,
-                                EDITOR_POSITION[i].0 + 6 + j,
-                                EDITOR_POSITION[i].1,
-                                ColorCode::new(Color::Green, Color::Black),
-                            );
-                        }
-                        plot_str(
-                            " (F6 to exit)ÍÍÍÍÍ",
-                            EDITOR_POSITION[i].0 + 16,
-                            EDITOR_POSITION[i].1,
-                            ColorCode::new(Color::Green, Color::Black),
-                        );
-                    }
+        let mut repaints = 0;
+        for i in self.page() {
+            let mut header = self.windows[i].header(i == self.focused_editor);
+            header.running = self
+                .window_process(i)
+                .is_some_and(|p| p.interpreter.is_some());
+            header.alert = self.window_process(i).is_some_and(Process::alerting);
+            if self.windows[i].drawn_header.is_none() {
+                if repaints == REPAINTS_PER_FRAME {
+                    continue;
                 }
-            } else {
-                match self.windows[i].state {
-                    WindowState::Listing => {
-                        plot_str(
-                            " (e)dit (r)unÄÄÄÄÄÄÄÄÄÄÄÄÄÄÄÄ",
-                            EDITOR_POSITION[i].0 + 5,
-                            EDITOR_POSITION[i].1,
-                            ColorCode::new(Color::Green, Color::Black),
-                        );
-                    }
-                    _ => {
-                        for j in 0..10 {
-                            plot(
-                                self.windows[self.focused_editor].current_file[j] as char,
-                                EDITOR_POSITION[i].0 + 6 + j,
-                                EDITOR_POSITION[i].1,
-                                ColorCode::new(Color::Green, Color::Black),
-                            );
-                        }
-                        plot_str(
-                            " (F6 to exit)ÄÄÄÄÄ",
-                            EDITOR_POSITION[i].0 + 16,
-                            EDITOR_POSITION[i].1,
-                            ColorCode::new(Color::Green, Color::Black),
-                        );
-                    }
+                repaints += 1;
+            }
+            if self.windows[i].drawn_header != Some(header) {
+                self.draw_header(i, header);
+                self.windows[i].drawn_header = Some(header);
+            }
+            if header.collapsed {
+                continue;
+            }
+            if header.running {
+                self.draw_progress(i);
+            } else if self.windows[i].state == WindowState::Listing {
+                self.draw_run_stats(i);
+            }
+            let volume = self.windows[i].volume;
+            let guide = Some(self.settings.guide).filter(|column| *column > 0);
+            let storage = self.mounts.volume(volume);
+            if let Err(e) = self.windows[i].draw_window(storage, &self.open_files, cursor, guide) {
+                self.print_error(i, e);
+            }
+            let (x, y) = (self.windows[i].window_x, self.windows[i].window_y);
+            if self.windows[i].shows_split() {
+                self.draw_split(i);
+            }
+            let shown = !self.muted || i == self.focused_editor;
+            if let Some(pid) = self.window_pid(i).filter(|_| shown) {
+                let area = self.window_rect(i).interior();
+                let zoomed = self.settings.zoom && i == self.focused_editor;
+                if let Some(process) = self.processes.get_mut(pid) {
+                    let spacing = layout::line_spacing(zoomed);
+                    process.draw(area, &self.windows[i].palette, cursor, spacing);
+                }
+            }
+            if let Some(jobs) = &mut self.windows[i].jobs {
+                let (now, per_frame) = (self.metrics.frames, self.settings.ticks_per_frame);
+                let palette = &self.windows[i].palette;
+                jobs.draw(&self.processes, now, per_frame, x + 1, y + 1, palette);
+            }
+            if let Some(options) = &self.windows[i].options {
+                options.draw(&self.settings, x + 1, y + 1, &self.windows[i].palette);
+            }
+            if let Some(tour) = &self.windows[i].tour {
+                tour.draw(x + 1, y + 1, &self.windows[i].palette);
+            }
+            if let Some(manager) = &mut self.windows[i].manager {
+                let palette = self.windows[i].palette;
+                if let Err(e) = manager.draw(&mut self.mounts, x + 1, y + 1, &palette) {
+                    self.print_error(i, e);
                 }
             }
-            self.windows[i].draw_window(&mut self.filesystem);
+            #[cfg(feature = "apps")]
+            if let Some(charmap) = &self.windows[i].charmap {
+                charmap.draw(x + 1, y + 1, &self.windows[i].palette);
+            }
+            #[cfg(feature = "apps")]
+            if let Some(life) = &self.windows[i].life {
+                let cursor = cursor && i == self.focused_editor;
+                life.draw(x + 1, y + 1, cursor, &self.windows[i].palette);
+            }
+            #[cfg(feature = "apps")]
+            if let Some(test) = &self.windows[i].typing {
+                let cursor = cursor && i == self.focused_editor;
+                let frame = self.metrics.frames;
+                test.draw(x + 1, y + 1, frame, cursor, &self.windows[i].palette);
+            }
         }
+        self.draw_modifiers();
         self.draw_processes();
     }
 
-    fn draw_outline(&self, x: usize, y: usize, focused: bool) {
-        for i in x + 1..x + 3 {
-            if focused {
-                plot(
-                    205u8 as char,
-                    i,
-                    y,
-                    ColorCode::new(Color::Green, Color::Black),
-                );
-            } else {
-                plot(
-                    196u8 as char,
-                    i,
-                    y,
-                    ColorCode::new(Color::Green, Color::Black),
-                );
-            }
+    // Each window's border and title only change when its focus, state,
+    // volume or file does, so they are redrawn on those changes rather than
+    // every tick.
+    fn draw_header(&self, window: usize, header: Header) {
+        let rect = self.window_rect(window);
+        let (x, y) = (rect.x, rect.y);
+        let color = self.windows[window].palette.border;
+        self.draw_outline(rect, header.focused, color);
+        let (title, cut) = format::clip(header.title(window).as_str().unwrap(), LINE_WIDTH);
+        plot_str_clipped(title.as_str().unwrap(), x + 3, y, color, rect);
+        let mut title_end = x + 3 + title.len();
+        if cut {
+            plot_clipped(format::ELLIPSIS, title_end, y, color, rect);
+            title_end += 1;
         }
-        for i in x + 1..x + WIN_REGION_WIDTH / 2 - 1 {
-            if focused {
-                plot(
-                    205u8 as char,
-                    i,
-                    y + 11,
-                    ColorCode::new(Color::Green, Color::Black),
-                );
-            } else {
-                plot(
-                    196u8 as char,
-                    i,
-                    y + 11,
-                    ColorCode::new(Color::Green, Color::Black),
-                );
-            }
+        let fill = if header.focused { 205u8 } else { 196u8 };
+        for col in title_end..rect.right() {
+            plot(fill as char, col, y, color);
         }
-        for j in y + 1..y + 11 {
-            for i in [x, x + WIN_REGION_WIDTH / 2 - 1] {
-                if focused {
-                    plot(
-                        186u8 as char,
-                        i,
-                        j,
-                        ColorCode::new(Color::Green, Color::Black),
-                    );
-                } else {
-                    plot(
-                        179u8 as char,
-                        i,
-                        j,
-                        ColorCode::new(Color::Green, Color::Black),
-                    );
-                }
-            }
+        if header.alert {
+            plot_str_clipped(title.as_str().unwrap(), x + 3, y, self.palette.alert, rect);
         }
-        if focused {
+        if header.activity {
             plot(
-                201u8 as char,
-                x,
+                ACTIVITY_BADGE,
+                x + 2,
                 y,
-                ColorCode::new(Color::Green, Color::Black),
-            );
-            plot(
-                187u8 as char,
-                x + WIN_REGION_WIDTH / 2 - 1,
-                y,
-                ColorCode::new(Color::Green, Color::Black),
+                ColorCode::new(Color::Yellow, Color::Black),
             );
+        }
+        // Without blinking or color to go on, an alert needs a mark of its
+        // own.
+        if header.alert && (self.settings.reduce_motion || self.settings.theme.high_contrast()) {
+            plot('!', x + 2, y, self.palette.alert);
+        }
+    }
+
+    // A spinner and the ticks run so far, or left before the timeout, on the
+    // bottom edge of a window whose program hasn't finished. draw_header
+    // restores the edge once it has.
+    fn draw_progress(&self, window: usize) {
+        let Some(process) = self.window_process(window) else {
+            return;
+        };
+        let rect = self.window_rect(window);
+        let spinner = match self.settings.reduce_motion {
+            true => ' ',
+            false => ['|', '/', '-', '\\'][process.ticks % 4],
+        };
+        let mut text: ArrayString<20> = ArrayString::default();
+        match self.settings.timeout.checked_sub(process.ticks) {
+            Some(left) if self.settings.timeout > 0 => {
+                write!(text, " {} {} left ", spinner, Compact(left as u64))
+            }
+            _ => write!(
+                text,
+                " {} {} ticks ",
+                spinner,
+                Compact(process.ticks as u64)
+            ),
+        }
+        .unwrap();
+        let text = format::right(text.as_str().unwrap(), 16);
+        plot_str_clipped(
+            text.as_str().unwrap(),
+            rect.right() - 17,
+            rect.bottom(),
+            self.windows[window].palette.border,
+            rect,
+        );
+    }
+
+    // How the focused file's last run went, on the bottom edge of a file
+    // list, or the plain edge for a file that hasn't been run.
+    fn draw_run_stats(&mut self, window: usize) {
+        let rect = self.window_rect(window);
+        let storage = self.mounts.volume(self.windows[window].volume);
+        let run = self.windows[window]
+            .listing(storage)
+            .ok()
+            .and_then(|(num_files, files)| {
+                files[..num_files]
+                    .get(self.windows[window].focused_file)
+                    .copied()
+            })
+            .and_then(|file| storage.run_stats(name_str(&file)?));
+        let mut text: ArrayString<WIN_REGION_WIDTH> = ArrayString::default();
+        if let Some(run) = run {
+            write!(
+                text,
+                " {} {} ticks {} ins ",
+                run.status,
+                Compact(run.ticks as u64),
+                Compact(run.instructions as u64)
+            )
+            .unwrap();
+        }
+        let focused = window == self.focused_editor;
+        let fill = if focused { 205u8 } else { 196u8 };
+        let color = self.windows[window].palette.border;
+        let mut chars = text.as_str().unwrap().chars();
+        for col in rect.x + 2..rect.right() - 1 {
             plot(
-                200u8 as char,
-                x,
-                y + 11,
-                ColorCode::new(Color::Green, Color::Black),
+                chars.next().unwrap_or(fill as char),
+                col,
+                rect.bottom(),
+                color,
             );
-            plot(
-                188u8 as char,
-                x + WIN_REGION_WIDTH / 2 - 1,
-                y + 11,
-                ColorCode::new(Color::Green, Color::Black),
+        }
+    }
+
+    // The rule under a split editor or listing, and the tail of its last
+    // run's output.
+    fn draw_split(&self, window: usize) {
+        let (_, area) = layout::split_panes(self.windows[window].interior());
+        let palette = &self.windows[window].palette;
+        let mut label = " output ".chars();
+        for col in area.x..=area.right() {
+            let c = label.next().unwrap_or(196u8 as char);
+            plot(c, col, area.y - 1, palette.border);
+        }
+        match self.windows[window]
+            .run
+            .and_then(|pid| self.processes.get(pid))
+        {
+            Some(process) => process.draw_tail(area, palette.text),
+            None => {
+                for row in area.y..=area.bottom() {
+                    for col in area.x..=area.right() {
+                        plot(' ', col, row, palette.text);
+                    }
+                }
+            }
+        }
+    }
+
+    fn draw_modifiers(&self) {
+        let modifiers = self.input.modifiers();
+        for (i, (label, active)) in [
+            ("CAPS", modifiers.caps_lock),
+            ("SHFT", modifiers.shift),
+            ("CTRL", modifiers.ctrl),
+            ("ALT ", modifiers.alt),
+        ]
+        .iter()
+        .enumerate()
+        {
+            plot_str(
+                if *active { label } else { "    " },
+                WIN_REGION_WIDTH - 20 + i * 5,
+                0,
+                ColorCode::new(Color::Yellow, Color::Black),
             );
+        }
+    }
+
+    // A window's border, or just the ends of its header row when it is
+    // collapsed. The title goes over the rest of the top row.
+    fn draw_outline(&self, rect: Rect, focused: bool, color: ColorCode) {
+        let (fill, side) = if focused {
+            (205u8, 186u8)
         } else {
+            (196u8, 179u8)
+        };
+        let corners = if focused {
+            [201u8, 187, 200, 188]
+        } else {
+            [218u8, 191, 192, 217]
+        };
+        plot(corners[0] as char, rect.x, rect.y, color);
+        plot(corners[1] as char, rect.right(), rect.y, color);
+        for i in rect.x + 1..rect.x + 3 {
+            plot(fill as char, i, rect.y, color);
+        }
+        if rect.height == 1 {
+            return;
+        }
+        for j in rect.y + 1..rect.bottom() {
+            for i in [rect.x, rect.right()] {
+                plot(side as char, i, j, color);
+            }
+        }
+        for i in rect.x + 1..rect.right() {
+            plot(fill as char, i, rect.bottom(), color);
+        }
+        plot(corners[2] as char, rect.x, rect.bottom(), color);
+        plot(corners[3] as char, rect.right(), rect.bottom(), color);
+    }
+
+    // Where the window is on its page, as it is laid out now.
+    fn window_rect(&self, window: usize) -> Rect {
+        layout::window_rect(window, self.windows[window].collapsed)
+    }
+
+    pub fn draw_processes(&mut self) {
+        let page = self.page();
+        let total_ticks: usize = self.processes.iter().map(|(_, p)| p.ticks).sum();
+        for slot in 0..PAGE_WINDOWS {
+            let i = page.start + slot;
+            if !page.contains(&i) {
+                plot_str("          ", WIN_REGION_WIDTH, slot * 2, self.palette.text);
+                plot_str(
+                    "          ",
+                    WIN_REGION_WIDTH,
+                    slot * 2 + 1,
+                    self.palette.text,
+                );
+                continue;
+            }
+            let color = if self.panel_selected == Some(i) {
+                self.palette.highlight
+            } else {
+                self.palette.text
+            };
+            plot('F', WIN_REGION_WIDTH, slot * 2, color);
             plot(
-                218u8 as char,
-                x,
-                y,
-                ColorCode::new(Color::Green, Color::Black),
-            );
-            plot(
-                191u8 as char,
-                x + WIN_REGION_WIDTH / 2 - 1,
-                y,
-                ColorCode::new(Color::Green, Color::Black),
-            );
-            plot(
-                192u8 as char,
-                x,
-                y + 11,
-                ColorCode::new(Color::Green, Color::Black),
+                (i + 49) as u8 as char,
+                WIN_REGION_WIDTH + 1,
+                slot * 2,
+                color,
             );
-            plot(
-                217u8 as char,
-                x + WIN_REGION_WIDTH / 2 - 1,
-                y + 11,
-                ColorCode::new(Color::Green, Color::Black),
+            // Each window's process state and its share of the ticks run by
+            // every process, or idle if it has none. A program waiting for
+            // input shows the start of its prompt instead of its share.
+            let mut status: ArrayString<10> = ArrayString::default();
+            match self.window_process(i) {
+                Some(process)
+                    if process.state == ProcessState::AwaitingInput
+                        && !process.prompt.as_str().unwrap().trim().is_empty() =>
+                {
+                    let prompt = format::left(process.prompt.as_str().unwrap().trim(), 5);
+                    write!(
+                        status,
+                        "{:<5}{}",
+                        process.state.label(),
+                        prompt.as_str().unwrap()
+                    )
+                    .unwrap()
+                }
+                Some(process) => write!(
+                    status,
+                    "{:<5}{:>5}",
+                    process.state.label(),
+                    Percent(process.ticks as u64, total_ticks as u64)
+                )
+                .unwrap(),
+                None => write!(status, "{:<10}", "idle").unwrap(),
+            }
+            plot_str(
+                status.as_str().unwrap(),
+                WIN_REGION_WIDTH,
+                slot * 2 + 1,
+                self.palette.text,
             );
         }
+        plot_str(
+            format::left(if self.is_idle() { "idle" } else { "" }, 4)
+                .as_str()
+                .unwrap(),
+            WIN_REGION_WIDTH + 6,
+            0,
+            self.palette.text,
+        );
+        let pending: usize = self.saves.iter().flatten().map(|s| s.remaining()).sum();
+        let (label, count) = match pending {
+            0 => (format::left("", 10), format::left("", 10)),
+            _ => (
+                format::left("Saving", 10),
+                format::right(Compact(pending as u64), 10),
+            ),
+        };
+        let text = self.palette.text;
+        plot_str(label.as_str().unwrap(), WIN_REGION_WIDTH, 9, text);
+        plot_str(count.as_str().unwrap(), WIN_REGION_WIDTH, 10, text);
+        let mut rate: ArrayString<10> = ArrayString::default();
+        match self.tick_rate.per_second {
+            Some(ticks) => write!(rate, "{:>6} tps", ticks).unwrap(),
+            None => write!(rate, "{:>6} tps", "--").unwrap(),
+        }
+        plot_str(rate.as_str().unwrap(), WIN_REGION_WIDTH, 11, text);
+        let mut target: ArrayString<10> = ArrayString::default();
+        write!(target, "keys to F{}", self.focused_editor + 1).unwrap();
+        plot_str(target.as_str().unwrap(), WIN_REGION_WIDTH, 12, text);
+        plot_str(
+            format::left(self.input_mode(), 10).as_str().unwrap(),
+            WIN_REGION_WIDTH,
+            13,
+            ColorCode::new(Color::Yellow, Color::Black),
+        );
+        let mut keymap: ArrayString<10> = ArrayString::default();
+        write!(keymap, "kbd {:<6}", self.settings.keymap.name()).unwrap();
+        plot_str(
+            keymap.as_str().unwrap(),
+            WIN_REGION_WIDTH,
+            14,
+            ColorCode::new(Color::Yellow, Color::Black),
+        );
+        plot_str(
+            if self.settings.read_only {
+                "READ ONLY "
+            } else {
+                "          "
+            },
+            WIN_REGION_WIDTH,
+            15,
+            self.palette.error,
+        );
+        plot_str(
+            if self.muted {
+                "muted     "
+            } else {
+                "          "
+            },
+            WIN_REGION_WIDTH,
+            16,
+            ColorCode::new(Color::Yellow, Color::Black),
+        );
+    }
+
+    // What typing goes to in the focused window. The editor, a program's
+    // input, the filename prompt and the shell all take the same keys, so the
+    // task panel names the one that will get them.
+    fn input_mode(&self) -> &'static str {
+        if self.panel_selected.is_some() {
+            return "tasks";
+        }
+        match self.windows[self.focused_editor].state {
+            WindowState::Editing if self.renaming.is_some() => "rename",
+            WindowState::Editing => "text",
+            WindowState::Running => match self.window_process(self.focused_editor) {
+                Some(process) if process.state == ProcessState::AwaitingInput => "input",
+                Some(process) if process.state == ProcessState::More => "more",
+                _ => "none",
+            },
+            #[cfg(feature = "shell")]
+            WindowState::Shell => "shell",
+            #[cfg(feature = "apps")]
+            WindowState::Calculator => "calc",
+            #[cfg(feature = "apps")]
+            WindowState::Typing => "text",
+            #[cfg(feature = "serial")]
+            WindowState::Terminal => "serial",
+            WindowState::Listing if self.creating_file => "filename",
+            WindowState::Listing if self.passphrase.is_some() => "passphrase",
+            WindowState::Listing if self.confirming_delete => "y/n",
+            WindowState::Listing if self.choosing_window => "window",
+            WindowState::Listing if self.jump.is_some() => "jump",
+            WindowState::Listing if self.windows[self.focused_editor].typed_index.is_some() => {
+                "number"
+            }
+            WindowState::Listing
+            | WindowState::Viewing
+            | WindowState::BlockMap
+            | WindowState::Jobs
+            | WindowState::Settings
+            | WindowState::Tour
+            | WindowState::FileManager => "commands",
+            #[cfg(feature = "apps")]
+            WindowState::CharMap | WindowState::Life => "commands",
+        }
+    }
+
+    pub fn key(&mut self, key: DecodedKey) {
+        if self.shut_down {
+            return;
+        }
+        if self.session_replay.is_some() {
+            // The log types for the keyboard; Esc is the only key heard.
+            if key == DecodedKey::Unicode('\u{1b}') {
+                self.stop_replay(format_args!("replay stopped"));
+            }
+            return;
+        }
+        self.log_session(Step::Key(key));
+        self.handle_key(key);
+        screen::present();
+    }
+
+    // An arrow key let go, which ends its repeat at once.
+    pub fn key_released(&mut self, code: KeyCode) {
+        self.input.release(code);
+    }
+
+    fn handle_key(&mut self, key: DecodedKey) {
+        self.key_history.push(key);
+        if self.splash > 0 {
+            if self.settings.keymap.translate(key) == DecodedKey::Unicode('r') {
+                self.settings.read_only = true;
+                self.mounts.set_read_only();
+            }
+            self.splash = 0;
+            self.redraw_all();
+            return;
+        }
+        if let Some(lock) = &mut self.lock {
+            if let DecodedKey::Unicode(c) = self.settings.keymap.translate(key) {
+                if lock.key(c, self.settings.passcode.as_str().unwrap()) {
+                    if let Some(lock) = self.lock.take() {
+                        lock.unlock();
+                    }
+                }
+            }
+            return;
+        }
+        if let Some(playback) = &mut self.playback {
+            match self.settings.keymap.translate(key) {
+                DecodedKey::RawKey(KeyCode::ArrowLeft) => playback.step(false),
+                DecodedKey::RawKey(KeyCode::ArrowRight) => playback.step(true),
+                DecodedKey::Unicode('q' | '\u{1b}') => self.close_playback(),
+                _ => {}
+            }
+            return;
+        }
+        // Alt codes are typed on the digits a US keyboard has, whatever the
+        // keymap moves there.
+        let alt = self.input.modifiers().alt;
+        let key = match (key, self.settings.keymap.translate(key)) {
+            (DecodedKey::Unicode(typed), DecodedKey::Unicode(c)) => {
+                let c = if alt && typed.is_ascii_digit() {
+                    typed
+                } else {
+                    c
+                };
+                match self.compose.key(c, alt, self.settings.keymap) {
+                    Some(c) => DecodedKey::Unicode(c),
+                    None => return,
+                }
+            }
+            (_, key) => key,
+        };
+        self.input.push(key);
+        self.process_input();
+    }
+
+    fn process_input(&mut self) {
+        while let Some(event) = self.input.pop() {
+            self.metrics.keys.handled(event.arrived);
+            if event.modifiers.ctrl && self.handle_chord(event) {
+                continue;
+            }
+            match event.key {
+                DecodedKey::RawKey(code) => self.handle_raw(code, event.modifiers),
+                DecodedKey::Unicode(c) if self.panel_selected.is_some() => self.handle_panel_key(c),
+                DecodedKey::Unicode(c) => self.handle_unicode(c),
+            }
+        }
+    }
+
+    // With a window picked in the task panel, p, k, + and - act on its
+    // process as they do in the jobs window. Any other key puts the
+    // selection down and goes where it would have.
+    fn handle_panel_key(&mut self, key: char) {
+        let Some(window) = self.panel_selected else {
+            return;
+        };
+        match key {
+            'p' | 'k' | '+' | '-' => {
+                if let Some(pid) = self.window_pid(window) {
+                    self.control_process(pid, key);
+                }
+            }
+            _ => {
+                self.panel_selected = None;
+                if key != '\u{1b}' {
+                    self.handle_unicode(key);
+                }
+            }
+        }
+    }
+
+    // F7 and F8 move the task panel's selection up and down the page's
+    // windows, starting from the focused one.
+    fn move_panel_selection(&mut self, down: bool) {
+        let page = self.page();
+        self.panel_selected = Some(match self.panel_selected {
+            None => self.focused_editor,
+            Some(window) if down => (window + 1).min(page.end - 1),
+            Some(window) => window.saturating_sub(1).max(page.start),
+        });
+    }
+
+    fn handle_chord(&mut self, event: KeyEvent) -> bool {
+        match (&self.windows[self.focused_editor].state, event.key) {
+            (WindowState::Editing, DecodedKey::Unicode('s')) => {
+                self.save_editor(self.focused_editor);
+                true
+            }
+            (WindowState::Editing, DecodedKey::Unicode(c @ (']' | 't'))) => {
+                let window = self.focused_editor;
+                if let Some(editor) = &mut self.windows[window].editor {
+                    let moved = if c == ']' {
+                        editor.jump_to_definition()
+                    } else {
+                        editor.jump_back()
+                    };
+                    if !moved {
+                        let message = if c == ']' {
+                            "no definition here"
+                        } else {
+                            "no jump to go back from"
+                        };
+                        self.notifier.notify(message, false);
+                    }
+                }
+                true
+            }
+            (WindowState::Editing, DecodedKey::Unicode('n')) => {
+                self.start_rename();
+                true
+            }
+            (WindowState::Editing, DecodedKey::Unicode('z')) => {
+                let window = self.focused_editor;
+                let undone = self.windows[window]
+                    .editor
+                    .as_mut()
+                    .is_some_and(|editor| editor.undo_rename());
+                if !undone {
+                    self.notifier.notify("no rename to undo", false);
+                }
+                true
+            }
+            (WindowState::Editing, DecodedKey::Unicode('u')) => {
+                let window = &mut self.windows[self.focused_editor];
+                window.ruler = !window.ruler;
+                window.clear_window();
+                true
+            }
+            (WindowState::Editing | WindowState::Listing, DecodedKey::Unicode('o')) => {
+                let window = &mut self.windows[self.focused_editor];
+                window.split = !window.split;
+                window.clear_window();
+                true
+            }
+            (WindowState::Running, DecodedKey::Unicode('e')) => {
+                self.toggle_watch(self.focused_editor);
+                true
+            }
+            (_, DecodedKey::Unicode('l')) => {
+                self.clear_output(self.focused_editor);
+                true
+            }
+            (_, DecodedKey::Unicode('v')) => {
+                self.paste();
+                true
+            }
+            (_, DecodedKey::Unicode('k')) => {
+                self.lock_screen();
+                true
+            }
+            (_, DecodedKey::Unicode('p')) => {
+                self.take_screenshot();
+                true
+            }
+            (_, DecodedKey::Unicode('r')) => {
+                self.toggle_recording();
+                true
+            }
+            (_, DecodedKey::Unicode('w')) => {
+                self.toggle_collapsed(self.focused_editor);
+                true
+            }
+            (_, DecodedKey::Unicode('q')) => {
+                self.muted = !self.muted;
+                let message = if self.muted {
+                    "muted: only the focused window shows output"
+                } else {
+                    "unmuted: every window shows output"
+                };
+                self.notifier.notify(message, false);
+                true
+            }
+            (_, DecodedKey::Unicode('b')) => {
+                let window = &mut self.windows[self.focused_editor];
+                window.bright = !window.bright;
+                self.set_theme(self.settings.theme);
+                true
+            }
+            (WindowState::Running, DecodedKey::Unicode('\n')) => {
+                self.answer_input();
+                true
+            }
+            #[cfg(feature = "shell")]
+            (WindowState::Shell, DecodedKey::Unicode('c')) => {
+                if let Some(shell) = &mut self.windows[self.focused_editor].shell {
+                    shell.cancel();
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Asks on the status bar for a new name for the variable under the
+    // editor's cursor, saying how many uses it has.
+    fn start_rename(&mut self) {
+        let Some(editor) = &self.windows[self.focused_editor].editor else {
+            return;
+        };
+        match editor.word_under_cursor() {
+            Some(from) => {
+                self.renaming = Some(RenamePrompt {
+                    from,
+                    uses: editor.count_uses(from.as_str().unwrap()),
+                    typed: ArrayString::default(),
+                })
+            }
+            None => self.notifier.notify("no variable here", false),
+        }
+    }
+
+    fn submit_rename(&mut self) {
+        let Some(renaming) = self.renaming.take() else {
+            return;
+        };
+        let Some(editor) = &mut self.windows[self.focused_editor].editor else {
+            return;
+        };
+        let from = renaming.from.as_str().unwrap();
+        let mut toast: ArrayString<{ 2 * LINE_WIDTH + 32 }> = ArrayString::default();
+        match editor.rename(from, renaming.typed.as_str().unwrap()) {
+            Ok(uses) => write!(toast, "renamed {} uses of {}, ^Z undoes", uses, from),
+            Err(e) => write!(toast, "rename: {}", e),
+        }
+        .unwrap();
+        self.notifier.notify(toast.as_str().unwrap(), false);
+    }
+
+    // Blanks the screen until the configured passcode is typed. Without a
+    // passcode there would be nothing to unlock with, so it says so instead.
+    fn lock_screen(&mut self) {
+        if self.settings.passcode.len() == 0 {
+            self.set_filename_message("no passcode in config");
+            return;
+        }
+        match Lock::new(&self.palette) {
+            Some(lock) => self.lock = Some(lock),
+            None => self.set_filename_message("too many overlays"),
+        }
+    }
+
+    // Saves the screen to the focused window's volume and says where.
+    fn take_screenshot(&mut self) {
+        if self.refuse_read_only("save screenshots") {
+            return;
+        }
+        let volume = self.windows[self.focused_editor].volume;
+        let result = screenshot::capture(self.mounts.volume(volume));
+        self.refresh_listing(volume);
+        let mut toast: ArrayString<WIN_REGION_WIDTH> = ArrayString::default();
+        match result {
+            Ok(name) => write!(toast, "screenshot saved to {}", name.as_str().unwrap()),
+            Err(e) => write!(toast, "screenshot failed: {}", e),
+        }
+        .unwrap();
+        self.notifier.notify(toast.as_str().unwrap(), false);
+    }
+
+    // Starts recording the screen into the focused window's volume, or
+    // stops and says how many frames were kept.
+    fn toggle_recording(&mut self) {
+        let mut toast: ArrayString<WIN_REGION_WIDTH> = ArrayString::default();
+        match self.recorder.take() {
+            Some(recorder) => write!(
+                toast,
+                "recorded {} frames to {}",
+                recorder.frames(),
+                recorder::REEL
+            )
+            .unwrap(),
+            None if self.refuse_read_only("record the screen") => return,
+            None => {
+                let volume = self.windows[self.focused_editor].volume;
+                match Recorder::start(self.mounts.volume(volume), volume) {
+                    Ok(recorder) => self.recorder = Some(recorder),
+                    Err(e) => write!(toast, "can't record: {}", e).unwrap(),
+                }
+                self.refresh_listing(volume);
+            }
+        }
+        if toast.len() > 0 {
+            self.notifier.notify(toast.as_str().unwrap(), false);
+        }
+    }
+
+    fn advance_recording(&mut self) {
+        let Some(recorder) = &mut self.recorder else {
+            return;
+        };
+        let storage = self.mounts.volume(recorder.volume);
+        if let Err(e) = recorder.tick(storage, self.settings.record_every) {
+            self.recorder = None;
+            let mut toast: ArrayString<WIN_REGION_WIDTH> = ArrayString::default();
+            write!(toast, "recording stopped: {}", e).unwrap();
+            self.notifier.notify(toast.as_str().unwrap(), false);
+        }
+    }
+
+    fn log_session(&mut self, step: Step) {
+        let Some(log) = &mut self.session_log else {
+            return;
+        };
+        let storage = self.mounts.volume(log.volume);
+        let result = match step {
+            Step::Key(key) => log.key(storage, key),
+            Step::Tick => log.tick(storage),
+        };
+        if let Err(e) = result {
+            self.session_log = None;
+            let mut toast: ArrayString<WIN_REGION_WIDTH> = ArrayString::default();
+            write!(toast, "session log stopped: {}", e).unwrap();
+            self.notifier.notify(toast.as_str().unwrap(), false);
+        }
+    }
+
+    // Delivers the keys the replayed session logged before this tick.
+    fn replay_session(&mut self) {
+        while let Some(replay) = &mut self.session_replay {
+            match replay.next_step(self.mounts.volume(replay.volume)) {
+                Ok(Some(Step::Key(key))) => self.handle_key(key),
+                Ok(Some(Step::Tick)) => return,
+                Ok(None) => self.stop_replay(format_args!("replay finished")),
+                Err(e) => self.stop_replay(format_args!("replay stopped: {}", e)),
+            }
+        }
+    }
+
+    fn stop_replay(&mut self, why: core::fmt::Arguments) {
+        self.session_replay = None;
+        let mut toast: ArrayString<WIN_REGION_WIDTH> = ArrayString::default();
+        toast.write_fmt(why).unwrap();
+        self.notifier.notify(toast.as_str().unwrap(), false);
+    }
+
+    fn draw_playback(&mut self) {
+        let Some(playback) = &mut self.playback else {
+            return;
+        };
+        if let Err(e) = playback.draw(self.mounts.volume(playback.volume), &self.palette) {
+            self.close_playback();
+            self.print_error(self.focused_editor, e);
+        }
+    }
+
+    fn close_playback(&mut self) {
+        if let Some(playback) = self.playback.take() {
+            playback.close();
+        }
+    }
+
+    // The program shown in the focused window, whose output the arrow keys
+    // select lines from.
+    fn focused_output(&mut self) -> Option<&mut Process> {
+        if self.windows[self.focused_editor].state != WindowState::Running {
+            return None;
+        }
+        let pid = self.window_pid(self.focused_editor)?;
+        self.processes.get_mut(pid)
+    }
+
+    // Types the clipboard into the focused editor, shell or program input.
+    fn paste(&mut self) {
+        let clipboard = self.clipboard;
+        self.type_text(self.focused_editor, clipboard.as_str().unwrap());
+    }
+
+    #[cfg(feature = "apps")]
+    // Types the character selected in the focused character map into the
+    // window it was opened from.
+    fn insert_char(&mut self) {
+        let Some(charmap) = self.windows[self.focused_editor].charmap else {
+            return;
+        };
+        let mut bytes = [0; 4];
+        let text = charmap.selected().encode_utf8(&mut bytes);
+        self.type_text(charmap.target, text);
+    }
+
+    // Types `text` into `window`'s editor, shell or waiting program. Only
+    // the editor keeps characters above ASCII; the others store bytes that
+    // must stay UTF-8, so the shell drops them and a program's input field
+    // rejects them.
+    fn type_text(&mut self, window: usize, text: &str) {
+        match self.windows[window].state {
+            WindowState::Editing => {
+                let settings = self.settings;
+                if let Some(editor) = &mut self.windows[window].editor {
+                    text.chars()
+                        .filter(|c| settings.typeable(*c))
+                        .for_each(|c| editor.push_char(c));
+                }
+            }
+            #[cfg(feature = "shell")]
+            WindowState::Shell => {
+                if let Some(shell) = &mut self.windows[window].shell {
+                    text.chars()
+                        .filter(|c| is_drawable(*c))
+                        .for_each(|c| shell.push_char(c));
+                }
+            }
+            #[cfg(feature = "apps")]
+            WindowState::Calculator => {
+                if let Some(calculator) = &mut self.windows[window].calculator {
+                    text.chars()
+                        .filter(|c| is_drawable(*c))
+                        .for_each(|c| calculator.push_char(c));
+                }
+            }
+            WindowState::Running => {
+                let pid = self.window_pid(window);
+                if let Some(process) = pid.and_then(|pid| self.processes.get_mut(pid)) {
+                    if process.state == ProcessState::AwaitingInput {
+                        text.chars().for_each(|c| process.type_char(c));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Hands what has been typed to the program in the focused window, if it
+    // is waiting for input.
+    fn answer_input(&mut self) {
+        let vruntime = self.min_vruntime().0;
+        let pid = self.window_pid(self.focused_editor);
+        let Some(process) = pid.and_then(|pid| self.processes.get_mut(pid)) else {
+            return;
+        };
+        // The interpreter is answered where it lies; it is large to copy.
+        let (ProcessState::AwaitingInput, Some(interpreter)) =
+            (process.state, process.interpreter.as_mut())
+        else {
+            return;
+        };
+        // Caught up with the others, but never set back, so a program that
+        // waited can't then run ahead of its own fair share.
+        process.vruntime = process.vruntime.max(vruntime);
+        writeln!(process.output, "{}", process.input.as_str().unwrap()).unwrap();
+        process.state = ProcessState::Running;
+        if let Err(e) = interpreter.provide_input(process.input.as_str().unwrap()) {
+            writeln!(Printer(process), "{}", e).unwrap();
+        }
+        process.output.mark_dirty();
+        if let Some(window) = self.return_focus {
+            self.focus_window(window);
+        }
+    }
+
+    #[cfg(feature = "shell")]
+    fn run_shell_command(&mut self) {
+        let window = self.focused_editor;
+        if let Some(mut shell) = self.windows[window].shell.take() {
+            let command = shell.take_command();
+            shell.remember(command);
+            let command = command.as_str().unwrap();
+            writeln!(shell, "> {}", command).unwrap();
+            let Some((line, input, output)) = shell::split_redirects(command) else {
+                writeln!(shell, "bad redirect").unwrap();
+                self.windows[window].shell = Some(shell);
+                return;
+            };
+            for path in line.split_whitespace().chain(input) {
+                self.generate_stats(path, window);
+            }
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some(name)
+                    if input.is_some() && name != "run"
+                        || output.is_some() && !matches!(name, "echo" | "cat" | "run") =>
+                {
+                    writeln!(shell, "{}: can't redirect", name).unwrap();
+                }
+                Some(name)
+                    if self.settings.read_only
+                        && (output.is_some()
+                            || name == "defrag"
+                            || name == "fsck" && line.split_whitespace().any(|w| w == "-r")) =>
+                {
+                    writeln!(shell, "{}: read-only mode", name).unwrap();
+                }
+                Some("help") => {
+                    writeln!(shell, "ls [v:]  list files").unwrap();
+                    writeln!(shell, "cat file  print a file").unwrap();
+                    writeln!(shell, "head|tail [-n N] file").unwrap();
+                    writeln!(shell, "echo text [> file]").unwrap();
+                    writeln!(shell, "run prog [< in] [> out]").unwrap();
+                    writeln!(shell, "ps  list processes").unwrap();
+                    writeln!(shell, "kill id, renice id 0-{}", MAX_NICE).unwrap();
+                    writeln!(shell, "free  process memory").unwrap();
+                    writeln!(shell, "df  disk usage").unwrap();
+                    writeln!(shell, "clear  clear this window").unwrap();
+                    writeln!(shell, "reset [1-{}]  back to the list", N).unwrap();
+                    writeln!(shell, "reset samples  restore a1/.. files").unwrap();
+                    writeln!(shell, "fsck [-r] [v:]  check volume").unwrap();
+                    writeln!(shell, "defrag [v:]  rewrite every file").unwrap();
+                    writeln!(shell, "stats  frame timings").unwrap();
+                    writeln!(shell, "mem  memory budget").unwrap();
+                    writeln!(shell, "keys [v:]  write keys.txt").unwrap();
+                    writeln!(shell, "shutdown  stop and power off").unwrap();
+                    writeln!(shell, "reboot [warm]  start again").unwrap();
+                    #[cfg(feature = "serial")]
+                    writeln!(shell, "send file  out of COM1").unwrap();
+                }
+                Some("ls") => {
+                    let volume = self.shell_volume(words.next(), window, &mut shell);
+                    if let Some(volume) = volume {
+                        match self.mounts.volume(volume).list_directory() {
+                            Ok((num_files, files)) => {
+                                for file in files[..num_files].iter() {
+                                    writeln!(
+                                        shell,
+                                        "{}:{}",
+                                        volume_letter(volume),
+                                        name_str(file).unwrap_or("?")
+                                    )
+                                    .unwrap();
+                                }
+                            }
+                            Err(e) => writeln!(shell, "{}", e).unwrap(),
+                        }
+                    }
+                }
+                Some("fsck") => {
+                    let mut repair = false;
+                    let mut target = None;
+                    for word in words {
+                        if word == "-r" {
+                            repair = true;
+                        } else {
+                            target = Some(word);
+                        }
+                    }
+                    if let Some(volume) = self.shell_volume(target, window, &mut shell) {
+                        let report = fsck::check(self.mounts.volume(volume), repair, &mut shell);
+                        writeln!(
+                            shell,
+                            "{} problems, {} repaired",
+                            report.problems, report.repaired
+                        )
+                        .unwrap();
+                        self.refresh_listing(volume);
+                    }
+                }
+                Some("defrag") => {
+                    if let Some(volume) = self.shell_volume(words.next(), window, &mut shell) {
+                        let start = clock::cycles();
+                        match defrag::defrag(self.mounts.volume(volume), &mut shell) {
+                            Ok(rewritten) => {
+                                let cycles = clock::cycles() - start;
+                                writeln!(
+                                    shell,
+                                    "{} files rewritten, {} kcycles",
+                                    rewritten,
+                                    cycles / 1000
+                                )
+                                .unwrap();
+                            }
+                            Err(e) => writeln!(shell, "defrag failed: {}", e).unwrap(),
+                        }
+                    }
+                }
+                Some("echo") => {
+                    let text =
+                        words.fold(ArrayString::<LINE_WIDTH>::default(), |mut text, word| {
+                            if text.len() > 0 {
+                                text.push_char(' ');
+                            }
+                            write!(text, "{}", word).unwrap();
+                            text
+                        });
+                    let text = text.as_str().unwrap();
+                    match output.map(|path| self.shell_path(path, window, &mut shell)) {
+                        Some(Some((volume, name))) => {
+                            if let Err(e) = self.write_text_file(volume, name, text) {
+                                writeln!(shell, "{}", e).unwrap();
+                            }
+                        }
+                        Some(None) => {}
+                        None => writeln!(shell, "{}", text).unwrap(),
+                    }
+                }
+                Some("cat") if output.is_some() => {
+                    let from = words
+                        .next()
+                        .and_then(|path| self.shell_path(path, window, &mut shell));
+                    let to = output.and_then(|path| self.shell_path(path, window, &mut shell));
+                    match (from, to) {
+                        (Some(from), Some(to)) if from.0 == to.0 && from.1 == to.1 => {
+                            writeln!(shell, "cat: {} is the input", to.1).unwrap();
+                        }
+                        (Some(from), Some(to)) => {
+                            if let Err(e) = self.copy_file(from, to) {
+                                writeln!(shell, "{}", e).unwrap();
+                            }
+                        }
+                        _ => writeln!(shell, "usage: cat file > file").unwrap(),
+                    }
+                }
+                Some("run") => match words.next() {
+                    Some(program) => {
+                        if let Err(e) = self.shell_run(program, input, output, window, &mut shell) {
+                            writeln!(shell, "{}", e).unwrap();
+                        }
+                    }
+                    None => writeln!(shell, "usage: run prog [< in] [> out]").unwrap(),
+                },
+                Some("ps") => {
+                    writeln!(shell, "ID file       stat   vrun").unwrap();
+                    for (pid, process) in self.processes.iter() {
+                        writeln!(
+                            shell,
+                            "{:<2} {:<10} {:<4} {:>6}",
+                            pid,
+                            name_str(&process.file).unwrap_or("?"),
+                            process.state.label(),
+                            process.vruntime
+                        )
+                        .unwrap();
+                    }
+                }
+                // The interpreter doesn't report how much of its heap is in
+                // use, so only the process table is measured.
+                Some("free") => {
+                    let num_processes = self.processes.iter().count();
+                    writeln!(
+                        shell,
+                        "procs {}/{}, {} bytes each",
+                        num_processes,
+                        MAX_PROCESSES,
+                        core::mem::size_of::<Process>()
+                    )
+                    .unwrap();
+                    writeln!(shell, "heap use: not reported").unwrap();
+                }
+                Some("df") => {
+                    for volume in 0..NUM_VOLUMES {
+                        let blocks = self.mounts.volume(volume).block_map();
+                        let used = (NUM_BLOCKS - blocks.num_free()) as u64;
+                        let whole = NUM_BLOCKS as u64;
+                        let bar = Bar {
+                            part: used,
+                            whole,
+                            width: 10,
+                        };
+                        writeln!(
+                            shell,
+                            "{}: {} {:>4}, {} files",
+                            volume_letter(volume),
+                            bar,
+                            Percent(used, whole),
+                            self.num_files[volume]
+                        )
+                        .unwrap();
+                    }
+                }
+                Some("clear") => shell.clear(),
+                Some("reset") => match words.next() {
+                    None => self.reset_window(window),
+                    Some("samples") => self.reset_samples(&mut shell),
+                    Some(n) => match n.parse::<usize>() {
+                        Ok(n) if (1..=N).contains(&n) => self.reset_window(n - 1),
+                        _ => writeln!(shell, "usage: reset [1-{}|samples]", N).unwrap(),
+                    },
+                },
+                Some("kill") => {
+                    if let Some(pid) = self.shell_pid(words.next(), &mut shell) {
+                        self.kill(pid);
+                    }
+                }
+                Some("renice") => {
+                    if let Some(pid) = self.shell_pid(words.next(), &mut shell) {
+                        match (
+                            words.next().and_then(|n| n.parse().ok()),
+                            self.processes.get_mut(pid),
+                        ) {
+                            (Some(nice), Some(process)) if nice <= MAX_NICE => process.nice = nice,
+                            _ => writeln!(shell, "nice must be 0-{}", MAX_NICE).unwrap(),
+                        }
+                    }
+                }
+                Some(command @ ("cat" | "head" | "tail")) => {
+                    self.shell_print_file(command, words, window, &mut shell)
+                }
+                Some("stats") => {
+                    self.metrics.write_report(&mut shell).unwrap();
+                    stack::write_report(&mut shell).unwrap();
+                }
+                Some("mem") => budget::write_report::<N, _>(&mut shell).unwrap(),
+                Some("shutdown") => {
+                    self.windows[window].shell = Some(shell);
+                    self.shut_down();
+                    return;
+                }
+                Some("reboot") => match (words.next(), words.next()) {
+                    (warm @ (None | Some("warm")), None) => {
+                        self.windows[window].shell = Some(shell);
+                        self.reboot(warm.is_some());
+                        return;
+                    }
+                    _ => writeln!(shell, "usage: reboot [warm]").unwrap(),
+                },
+                Some("keys") => {
+                    if let Some(volume) = self.shell_volume(words.next(), window, &mut shell) {
+                        match self.write_key_reference(volume) {
+                            Ok(()) => {
+                                let letter = volume_letter(volume);
+                                writeln!(shell, "wrote {}:{}", letter, keys::KEYS_FILE).unwrap()
+                            }
+                            Err(e) => writeln!(shell, "{}", e).unwrap(),
+                        }
+                    }
+                }
+                #[cfg(feature = "serial")]
+                Some("send") => match words.next() {
+                    Some(path) => {
+                        if let Some(from) = self.shell_path(path, window, &mut shell) {
+                            match self.send_file(from) {
+                                Ok(sent) => writeln!(shell, "sent {} bytes", sent),
+                                Err(e) => writeln!(shell, "{}", e),
+                            }
+                            .unwrap();
+                        }
+                    }
+                    None => writeln!(shell, "usage: send file").unwrap(),
+                },
+                Some(other) => writeln!(shell, "unknown command: {}", other).unwrap(),
+                None => {}
+            }
+            if self.windows[window].state == WindowState::Shell {
+                self.windows[window].shell = Some(shell);
+            }
+        }
+    }
+
+    // Makes `stats/sched`, or `stats/heapN` for process N, when a command is
+    // about to read it, so what it reads is current. Each line is a name and
+    // its value, or one process, for programs to take apart.
+    #[cfg(feature = "shell")]
+    fn generate_stats(&mut self, path: &str, window: usize) {
+        let Some((volume, name)) = parse_path(path, self.windows[window].volume) else {
+            return;
+        };
+        let storage = self.mounts.volume(volume);
+        let processes = &self.processes;
+        if name == "stats/sched" {
+            let (policy, slice) = match self.settings.scheduler {
+                SchedulerPolicy::Fair { latency } => ("fair", latency),
+                SchedulerPolicy::RoundRobin { quantum } => ("rr", quantum),
+            };
+            let ticks_per_frame = self.settings.ticks_per_frame;
+            storage.publish_generated(name, |out| {
+                writeln!(out, "policy {}", policy)?;
+                writeln!(out, "slice {}", slice)?;
+                writeln!(out, "ticks_per_frame {}", ticks_per_frame)?;
+                writeln!(out, "processes {}", processes.iter().count())?;
+                for (pid, process) in processes.iter() {
+                    writeln!(
+                        out,
+                        "{} {} {} {} {}",
+                        pid,
+                        name_str(&process.file).unwrap_or("?"),
+                        process.state.label(),
+                        process.vruntime,
+                        process.nice
+                    )?;
+                }
+                Ok(())
+            });
+        } else if let Some(process) = name
+            .strip_prefix("stats/heap")
+            .and_then(|pid| pid.parse().ok())
+            .and_then(|pid| processes.get(pid))
+        {
+            // The interpreter doesn't say how much of its heap is in use.
+            storage.publish_generated(name, |out| {
+                writeln!(out, "file {}", name_str(&process.file).unwrap_or("?"))?;
+                writeln!(out, "state {}", process.state.label())?;
+                writeln!(out, "heap_size {}", HEAP_SIZE)?;
+                writeln!(out, "heap_blocks {}", MAX_HEAP_BLOCKS)?;
+                writeln!(out, "ticks {}", process.ticks)
+            });
+        }
+    }
+
+    // Puts the sample programs back as they shipped; see samples::restore.
+    #[cfg(feature = "shell")]
+    fn reset_samples(&mut self, shell: &mut Shell) {
+        if self.samples.is_empty() {
+            writeln!(shell, "no samples in this build").unwrap();
+            return;
+        }
+        if self.settings.read_only {
+            writeln!(shell, "reset: read-only mode").unwrap();
+            return;
+        }
+        let result = samples::restore(self.mounts.volume(0), self.samples);
+        self.refresh_listing(0);
+        match result {
+            Ok(()) => writeln!(shell, "restored {} samples", self.samples.len()).unwrap(),
+            Err(e) => writeln!(shell, "{}", e).unwrap(),
+        }
+    }
+
+    #[cfg(feature = "shell")]
+    fn shell_pid(&self, arg: Option<&str>, shell: &mut Shell) -> Option<usize> {
+        match arg.and_then(|arg| arg.parse().ok()) {
+            Some(pid) if self.processes.get(pid).is_some() => Some(pid),
+            Some(pid) => {
+                writeln!(shell, "no process {}", pid).unwrap();
+                None
+            }
+            None => {
+                writeln!(shell, "expected a process id").unwrap();
+                None
+            }
+        }
+    }
+
+    #[cfg(feature = "shell")]
+    fn shell_path<'a>(
+        &self,
+        path: &'a str,
+        window: usize,
+        shell: &mut Shell,
+    ) -> Option<(usize, &'a str)> {
+        let parsed = parse_path(path, self.windows[window].volume);
+        if parsed.is_none() {
+            writeln!(shell, "bad volume: {}", path).unwrap();
+        }
+        parsed
+    }
+
+    #[cfg(feature = "shell")]
+    fn write_text_file(
+        &mut self,
+        volume: usize,
+        name: &str,
+        text: &str,
+    ) -> Result<(), FileSystemError> {
+        let storage = self.mounts.volume(volume);
+        let fd = storage.open_create(name)?;
+        let result = storage
+            .write(fd, text.as_bytes())
+            .and_then(|_| storage.write(fd, b"\n"));
+        let result = result.and(storage.close(fd));
+        self.refresh_listing(volume);
+        result
+    }
+
+    // Copies a block at a time, so the two files can be on different
+    // volumes and neither has to fit in memory.
+    fn copy_file(
+        &mut self,
+        (from_volume, from): (usize, &str),
+        (to_volume, to): (usize, &str),
+    ) -> Result<(), FileSystemError> {
+        let source = self.mounts.volume(from_volume).open_read(from)?;
+        let target = match self.mounts.volume(to_volume).open_create(to) {
+            Ok(fd) => fd,
+            Err(e) => {
+                self.mounts.volume(from_volume).close(source)?;
+                return Err(e);
+            }
+        };
+        let mut buffer = [0; BLOCK_SIZE];
+        let result = loop {
+            match self.mounts.volume(from_volume).read(source, &mut buffer) {
+                Ok(0) => break Ok(()),
+                Ok(num_bytes) => {
+                    if let Err(e) = self
+                        .mounts
+                        .volume(to_volume)
+                        .write(target, &buffer[..num_bytes])
+                    {
+                        break Err(e);
+                    }
+                }
+                Err(e) => break Err(e),
+            }
+        };
+        let closed = self.mounts.volume(from_volume).close(source);
+        let closed = closed.and(self.mounts.volume(to_volume).close(target));
+        self.refresh_listing(to_volume);
+        result.and(closed)
+    }
+
+    #[cfg(all(feature = "shell", feature = "serial"))]
+    // Sends a file's bytes out of COM1 as they are, for capturing on the
+    // host; returns how many were sent.
+    fn send_file(&mut self, (volume, name): (usize, &str)) -> Result<usize, FileSystemError> {
+        let storage = self.mounts.volume(volume);
+        let fd = storage.open_read(name)?;
+        let mut buffer = [0; BLOCK_SIZE];
+        let mut sent = 0;
+        let result = loop {
+            match storage.read(fd, &mut buffer) {
+                Ok(0) => break Ok(sent),
+                Ok(num_bytes) => {
+                    buffer[..num_bytes].iter().copied().for_each(serial::send);
+                    sent += num_bytes;
+                }
+                Err(e) => break Err(e),
+            }
+        };
+        let closed = storage.close(fd);
+        result.and_then(|sent| closed.map(|_| sent))
+    }
+
+    #[cfg(feature = "shell")]
+    // Starts a program in the background, with its input and output
+    // redirected to files if asked. It shows up in the jobs window, where it
+    // can be attached to a window.
+    fn shell_run(
+        &mut self,
+        program: &str,
+        input: Option<&str>,
+        output: Option<&str>,
+        window: usize,
+        shell: &mut Shell,
+    ) -> Result<(), FileSystemError> {
+        let Some((volume, name)) = self.shell_path(program, window, shell) else {
+            return Ok(());
+        };
+        let input = match input.map(|path| self.shell_path(path, window, shell)) {
+            Some(None) => return Ok(()),
+            input => input.flatten(),
+        };
+        let output = match output.map(|path| self.shell_path(path, window, shell)) {
+            Some(None) => return Ok(()),
+            output => output.flatten(),
+        };
+        let Some(pid) = self.spawn(volume, name_bytes(name), None)? else {
+            writeln!(shell, "Too many processes").unwrap();
+            return Ok(());
+        };
+        match self.redirect(pid, input, output) {
+            Ok(()) => {
+                writeln!(shell, "[{}] {}", pid, name).unwrap();
+                Ok(())
+            }
+            Err(e) => {
+                self.kill(pid);
+                Err(e)
+            }
+        }
+    }
+
+    #[cfg(feature = "shell")]
+    fn redirect(
+        &mut self,
+        pid: usize,
+        input: Option<(usize, &str)>,
+        output: Option<(usize, &str)>,
+    ) -> Result<(), FileSystemError> {
+        if let Some((volume, name)) = input {
+            let fd = self.mounts.volume(volume).open_read(name)?;
+            if let Some(process) = self.processes.get_mut(pid) {
+                process.stdin = Some((volume, fd));
+            }
+        }
+        if let Some((volume, name)) = output {
+            let storage = self.mounts.volume(volume);
+            let fd = storage.open_create(name)?;
+            self.refresh_listing(volume);
+            if let Some(process) = self.processes.get_mut(pid) {
+                process.log = Some((volume, fd));
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "shell")]
+    fn shell_print_file<'a>(
+        &mut self,
+        command: &str,
+        mut words: impl Iterator<Item = &'a str>,
+        window: usize,
+        shell: &mut Shell,
+    ) {
+        let mut num_lines = if command == "cat" { None } else { Some(10) };
+        let mut path = words.next();
+        if num_lines.is_some() && path == Some("-n") {
+            num_lines = words.next().and_then(|n| n.parse().ok());
+            path = words.next();
+        }
+        let volume = self.windows[window].volume;
+        let (volume, name) = match path.and_then(|path| parse_path(path, volume)) {
+            Some(parsed) if command == "cat" || num_lines.is_some() => parsed,
+            _ => {
+                writeln!(shell, "usage: {} [-n N] file", command).unwrap();
+                return;
+            }
+        };
+        let storage = self.mounts.volume(volume);
+        let range = match (command, num_lines) {
+            _ if name.len() > MAX_PATH_BYTES => Err(FileSystemError::FilenameTooLong),
+            ("tail", Some(n)) => FileReader::open(storage, name).and_then(|mut reader| {
+                let total = reader.count_lines(storage);
+                reader.close(storage)?;
+                total.map(|total| (total.saturating_sub(n), total))
+            }),
+            (_, Some(n)) => Ok((0, n)),
+            _ => Ok((0, usize::MAX)),
+        };
+        match range {
+            Ok((start, end)) => {
+                shell.page = Some(Page::new(volume, name, start, end));
+                self.print_page(shell);
+            }
+            Err(e) => writeln!(shell, "{}", e).unwrap(),
+        }
+    }
+
+    #[cfg(feature = "shell")]
+    // Prints the next page of the file the shell is paging through, leaving
+    // a prompt if any of it is still to come.
+    fn print_page(&mut self, shell: &mut Shell) {
+        if let Some(page) = shell.page.take() {
+            match page.print(self.mounts.volume(page.volume), shell) {
+                Ok(Some(rest)) => {
+                    writeln!(shell, "-- more: space, q to stop --").unwrap();
+                    shell.page = Some(rest);
+                }
+                Ok(None) => {}
+                Err(e) => writeln!(shell, "{}", e).unwrap(),
+            }
+        }
+    }
+
+    #[cfg(feature = "shell")]
+    fn handle_shell_key(&mut self, key: char) {
+        let window = self.focused_editor;
+        let paging = self.windows[window]
+            .shell
+            .as_ref()
+            .is_some_and(|shell| shell.page.is_some());
+        match key {
+            ' ' | '\n' if paging => {
+                if let Some(mut shell) = self.windows[window].shell.take() {
+                    self.print_page(&mut shell);
+                    self.windows[window].shell = Some(shell);
+                }
+            }
+            'q' if paging => {
+                if let Some(shell) = &mut self.windows[window].shell {
+                    shell.page = None;
+                }
+            }
+            _ if paging => {}
+            '\n' => self.run_shell_command(),
+            '\t' => self.complete_shell_word(),
+            k => {
+                if k == '\u{0008}' || is_drawable(k) {
+                    if let Some(shell) = &mut self.windows[window].shell {
+                        shell.push_char(k);
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "shell")]
+    fn complete_shell_word(&mut self) {
+        let window = self.focused_editor;
+        if let Some(mut shell) = self.windows[window].shell.take() {
+            let (word, is_command) = shell.completion_word();
+            let word = word.as_str().unwrap();
+            if is_command {
+                shell.complete(0, shell::COMMANDS.iter().copied());
+            } else if let Some((volume, name)) = parse_path(word, self.windows[window].volume) {
+                if let Ok((num_files, files)) = self.mounts.volume(volume).list_directory() {
+                    let names = files[..num_files].iter().filter_map(name_str);
+                    shell.complete(word.len() - name.len(), names);
+                }
+            }
+            self.windows[window].shell = Some(shell);
+        }
+    }
+
+    #[cfg(feature = "shell")]
+    fn shell_volume(&self, arg: Option<&str>, window: usize, shell: &mut Shell) -> Option<usize> {
+        match arg {
+            None => Some(self.windows[window].volume),
+            Some(arg) => match parse_path(arg, self.windows[window].volume) {
+                Some((volume, "")) => Some(volume),
+                _ => {
+                    writeln!(shell, "bad volume: {}", arg).unwrap();
+                    None
+                }
+            },
+        }
+    }
+
+    fn flush_log(&mut self, pid: usize, close: bool) {
+        if let Some(process) = self.processes.get_mut(pid) {
+            if let Some((volume, fd)) = process.log {
+                let storage = self.mounts.volume(volume);
+                let mut result = Ok(());
+                let (log, dropped) = process.take_log();
+                if !log.is_empty() {
+                    result = storage.write(fd, log);
+                }
+                // The log is written a tick at a time; one tick printing more
+                // than a block overruns the buffer, and the rest is lost.
+                if dropped > 0 {
+                    writeln!(process.output, "log: {} bytes not written", dropped).unwrap();
+                }
+                if close {
+                    process.log = None;
+                    result = result.and(storage.close(fd));
+                }
+                if let Err(e) = result {
+                    writeln!(process.output, "{}", e).unwrap();
+                }
+            }
+        }
+    }
+
+    // Answers an input request from the process's input file, if it has one.
+    // Once the file runs out the process goes back to waiting on the keyboard.
+    fn feed_input(&mut self, pid: usize) {
+        if let Some(process) = self.processes.get_mut(pid) {
+            if let (ProcessState::AwaitingInput, Some((volume, fd)), Some(interpreter)) =
+                (process.state, process.stdin, process.interpreter.as_mut())
+            {
+                let storage = self.mounts.volume(volume);
+                match stream::read_line::<10>(storage, fd) {
+                    Ok(Some(line)) => {
+                        let line = line.as_str().unwrap();
+                        writeln!(process.output, "{}", line).unwrap();
+                        process.state = ProcessState::Running;
+                        if let Err(e) = interpreter.provide_input(line) {
+                            writeln!(Printer(process), "{}", e).unwrap();
+                        }
+                    }
+                    result => {
+                        process.stdin = None;
+                        if let Err(e) = result.and(storage.close(fd)) {
+                            writeln!(process.output, "{}", e).unwrap();
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn close_input(&mut self, pid: usize) {
+        if let Some(process) = self.processes.get_mut(pid) {
+            if let Some((volume, fd)) = process.stdin.take() {
+                if let Err(e) = self.mounts.volume(volume).close(fd) {
+                    writeln!(process.output, "{}", e).unwrap();
+                }
+            }
+        }
+    }
+
+    fn focused_num_files(&mut self) -> usize {
+        let window = &self.windows[self.focused_editor];
+        window
+            .listing(self.mounts.volume(window.volume))
+            .map_or(0, |(n, _)| n)
+    }
+
+    // Recounts a volume's files after anything that may add or remove one,
+    // so the arrow keys reach every file, and wipes the lists showing the
+    // volume so a file that went away doesn't stay drawn.
+    fn refresh_listing(&mut self, volume: usize) {
+        let num_files = self
+            .mounts
+            .volume(volume)
+            .list_directory()
+            .map_or(0, |(n, _)| n);
+        self.num_files[volume] = num_files;
+        for window in self.windows.iter_mut().filter(|w| w.volume == volume) {
+            window.focused_file = wrapping_nav::clamp(window.focused_file, num_files);
+            window.preview = None;
+            if window.state == WindowState::Listing {
+                window.clear_window();
+            }
+        }
+    }
+
+    // Reads the config file, if the first volume has one, and starts its
+    // autoexec programs. Lines that can't be understood are skipped, and the
+    // first of them is reported on the status bar.
+    fn load_config(&mut self) {
+        let storage = self.mounts.volume(0);
+        let Ok(fd) = storage.open_read(settings::CONFIG_FILE) else {
+            return;
+        };
+        let mut bad_line = None;
+        let mut line_number = 0;
+        let result = loop {
+            match stream::read_line::<80>(storage, fd) {
+                Ok(Some(line)) => {
+                    line_number += 1;
+                    if !self.settings.apply(line.as_str().unwrap()) && bad_line.is_none() {
+                        bad_line = Some(line_number);
+                    }
+                }
+                Ok(None) => break storage.close(fd),
+                Err(e) => {
+                    storage.close(fd).ok();
+                    break Err(e);
+                }
+            }
+        };
+        self.set_theme(self.settings.theme);
+        if self.settings.read_only {
+            self.mounts.set_read_only();
+        }
+        if self.settings.pit_divisor != timer::DEFAULT_DIVISOR {
+            timer::set_divisor(self.settings.pit_divisor);
+        }
+        match (result, bad_line) {
+            (Err(e), _) => self.set_filename_message(format_args!("config: {}", e)),
+            (Ok(()), Some(line)) => {
+                self.set_filename_message(format_args!("bad config line {}", line))
+            }
+            (Ok(()), None) => self.set_filename_message(""),
+        }
+        match self.settings.session {
+            Session::Record => match SessionLog::start(self.mounts.volume(0), 0) {
+                Ok(log) => self.session_log = Some(log),
+                Err(e) => self.set_filename_message(format_args!("session: {}", e)),
+            },
+            Session::Replay => self.session_replay = Some(SessionReplay::new(0)),
+            Session::Off => {}
+        }
+        let settings = self.settings;
+        for (window, path) in settings.autoexec[..settings.num_autoexec]
+            .iter()
+            .enumerate()
+        {
+            if let Err(e) = self.run_in_window(window, path.as_str().unwrap()) {
+                self.set_filename_message(format_args!("autoexec: {}", e));
+            }
+        }
+    }
+
+    // Steps the option selected in the focused settings window, puts the
+    // change into effect and saves every setting back to the config file.
+    fn change_option(&mut self, forward: bool) {
+        let Some(options) = self.windows[self.focused_editor].options else {
+            return;
+        };
+        if self.refuse_read_only("change settings") {
+            return;
+        }
+        self.settings.step(options.selected(), forward);
+        self.set_theme(self.settings.theme);
+        self.running_countdown = 0;
+        let mut config: ArrayString<384> = ArrayString::default();
+        self.settings.write_config(&mut config).unwrap();
+        let storage = self.mounts.volume(0);
+        let result = storage.open_create(settings::CONFIG_FILE).and_then(|fd| {
+            let written = storage.write(fd, config.as_str().unwrap().as_bytes());
+            written.and(storage.close(fd))
+        });
+        self.refresh_listing(0);
+        if let Err(e) = result {
+            self.print_error(self.focused_editor, e);
+        }
+    }
+
+    // In read-only mode, says on a toast that `what` can't be done and
+    // returns true.
+    fn refuse_read_only(&mut self, what: &str) -> bool {
+        if self.settings.read_only {
+            let mut toast: ArrayString<WIN_REGION_WIDTH> = ArrayString::default();
+            write!(toast, "read-only mode: can't {}", what).unwrap();
+            self.notifier.notify(toast.as_str().unwrap(), false);
+        }
+        self.settings.read_only
+    }
+
+    // The windows on the focused window's page.
+    fn page(&self) -> core::ops::Range<usize> {
+        let start = self.focused_editor - self.focused_editor % PAGE_WINDOWS;
+        start..N.min(start + PAGE_WINDOWS)
+    }
+
+    // F1-F4 focus the window in that slot of the page on screen. Pressed
+    // again, the key goes on to the same slot of the next page that has one.
+    fn focus_slot(&mut self, slot: usize) {
+        let pages = N.div_ceil(PAGE_WINDOWS);
+        let first = self.page().start + slot;
+        let turn = usize::from(first == self.focused_editor);
+        if let Some(window) = (turn..pages + turn)
+            .map(|turn| wrapping_nav::offset(first, turn * PAGE_WINDOWS, pages * PAGE_WINDOWS))
+            .find(|window| *window < N)
+        {
+            self.focus_window(window);
+        }
+    }
+
+    fn focus_window(&mut self, window: usize) {
+        let turned = window / PAGE_WINDOWS != self.focused_editor / PAGE_WINDOWS;
+        self.return_focus = None;
+        self.passphrase = None;
+        self.renaming = None;
+        self.choosing_window = false;
+        self.jump = None;
+        self.panel_selected = None;
+        if window != self.focused_editor {
+            self.previous_focus = self.focused_editor;
+            self.tour_saw(tour::Action::Focused);
+        }
+        self.windows[self.focused_editor].set_focus(false);
+        self.focused_editor = window;
+        self.windows[self.focused_editor].set_focus(true);
+        self.windows[self.focused_editor].activity = false;
+        if turned {
+            self.redraw_all();
+        } else if self.settings.dim_unfocused || self.settings.zoom {
+            self.set_theme(self.settings.theme);
+        }
+    }
+
+    fn blank_screen(&self) {
+        for row in 0..BUFFER_HEIGHT {
+            for col in 0..BUFFER_WIDTH {
+                plot(' ', col, row, self.palette.blank);
+            }
+        }
+    }
+
+    #[cfg(feature = "serial")]
+    // A byte from COM1 goes to every serial terminal window.
+    fn serial_received(&mut self, byte: u8) {
+        for window in self.windows.iter_mut() {
+            if let Some(terminal) = &mut window.terminal {
+                terminal.receive(byte);
+            }
+        }
+    }
+
+    // Wipes the screen and has everything drawn afresh.
+    fn redraw_all(&mut self) {
+        self.blank_screen();
+        self.set_theme(self.settings.theme);
+    }
+
+    // Gives every window the theme, greyed out for all but the focused one
+    // when unfocused windows are dimmed and held steady when motion is
+    // reduced, and redraws them in it.
+    fn set_theme(&mut self, theme: Theme) {
+        self.settings.theme = theme;
+        let steady = self.settings.reduce_motion;
+        let palette = |theme: Theme| match steady {
+            true => theme.palette().steady(),
+            false => theme.palette(),
+        };
+        self.palette = palette(theme);
+        for (i, window) in self.windows.iter_mut().enumerate() {
+            window.dimmed = self.settings.dim_unfocused && i != self.focused_editor;
+            let shade = if window.dimmed { theme.dimmed() } else { theme };
+            let shade = if window.bright {
+                shade.brightened()
+            } else {
+                shade
+            };
+            window.palette = palette(shade);
+            window.drawn_header = None;
+            if let Some(viewer) = &mut window.viewer {
+                viewer.mark_dirty();
+            }
+        }
+        for pid in 0..MAX_PROCESSES {
+            if let Some(process) = self.processes.get_mut(pid) {
+                process.output.mark_dirty();
+            }
+        }
+    }
+
+    // Collapsing blanks the window up to its header; expanding draws it all
+    // again.
+    fn toggle_collapsed(&mut self, window: usize) {
+        self.windows[window].collapsed = !self.windows[window].collapsed;
+        let rect = layout::window_rect(window, false);
+        for row in rect.y..=rect.bottom() {
+            for col in rect.x..=rect.right() {
+                plot(' ', col, row, self.windows[window].palette.blank);
+            }
+        }
+        self.set_theme(self.settings.theme);
+    }
+
+    fn set_filename_message(&mut self, message: impl core::fmt::Display) {
+        self.filename_message = ArrayString::default();
+        write!(self.filename_message, "{}", message).unwrap();
+        self.overwrite_pending = false;
+    }
+
+    fn submit_filename(&mut self) {
+        let path = self.filename_input;
+        let (volume, name) = match parse_path(
+            path.as_str().unwrap(),
+            self.windows[self.focused_editor].volume,
+        ) {
+            Some(parsed) => parsed,
+            None => {
+                self.set_filename_message("unknown volume");
+                return;
+            }
+        };
+        if name.is_empty() {
+            self.set_filename_message("name is empty");
+            return;
+        }
+        // A plain name goes in the directory the list is in.
+        let window = &self.windows[self.focused_editor];
+        let mut full_name: ArrayString<{ MAX_FILENAME_BYTES + MAX_PATH_BYTES }> =
+            ArrayString::default();
+        match window.dir.as_ref().and_then(name_str) {
+            Some(dir) if volume == window.volume && !name.contains('/') => {
+                write!(full_name, "{}{}", dir, name).unwrap()
+            }
+            _ => write!(full_name, "{}", name).unwrap(),
+        }
+        let name = full_name.as_str().unwrap();
+        if name.len() > MAX_FILENAME_BYTES {
+            self.set_filename_message("name too long");
+            return;
+        }
+        if self.mounts.volume(volume).exists(name) && !self.overwrite_pending {
+            self.set_filename_message("exists: Enter overwrites");
+            self.overwrite_pending = true;
+            return;
+        }
+        let quota = Quota::check(self.mounts.volume(volume), name, 0);
+        if quota.refuses() {
+            self.set_filename_message("no free file slots");
+            return;
+        } else if quota == Quota::LastSlot {
+            let mut toast: ArrayString<WIN_REGION_WIDTH> = ArrayString::default();
+            write!(toast, "{}: {}", name, quota).unwrap();
+            self.notifier.notify(toast.as_str().unwrap(), false);
+        }
+        let mut filesystem_operations = || -> Result<(), FileSystemError> {
+            let storage = self.mounts.volume(volume);
+            let fd = storage.open_create(name)?;
+            storage.close(fd)
+        };
+        let result = filesystem_operations();
+        self.refresh_listing(volume);
+        match result {
+            Ok(()) => {
+                self.creating_file = false;
+                self.filename_input = ArrayString::default();
+                self.set_filename_message("");
+            }
+            Err(e) => {
+                self.filename_message = ArrayString::default();
+                write!(self.filename_message, "{}", e).unwrap();
+                self.overwrite_pending = false;
+            }
+        }
+    }
+
+    fn toggle_star(&mut self) {
+        let volume = self.windows[self.focused_editor].volume;
+        let mut filesystem_operations = || -> Result<(), FileSystemError> {
+            let storage = self.mounts.volume(volume);
+            let window = &self.windows[self.focused_editor];
+            let (num_files, files) = window.listing(storage)?;
+            let focused = window.focused_file;
+            if focused < num_files && !dirs::is_dir(&files[focused]) {
+                let filename = files[focused];
+                storage.toggle_flag(
+                    core::str::from_utf8(&filename)
+                        .unwrap()
+                        .trim_end_matches('\0'),
+                    STARRED,
+                )?;
+                let (num_files, files) = self.windows[self.focused_editor].listing(storage)?;
+                if let Some(i) = files[..num_files].iter().position(|f| *f == filename) {
+                    self.windows[self.focused_editor].focused_file = i;
+                }
+            }
+            Ok(())
+        };
+        let result = filesystem_operations();
+        for window in self.windows.iter_mut().filter(|w| w.volume == volume) {
+            window.marked = [false; MAX_FILES_STORED];
+        }
+        if let Err(e) = result {
+            self.print_error(self.focused_editor, e);
+        }
+    }
+
+    // Files another window is editing or running are kept: the editor's
+    // next save would otherwise bring the file back.
+    fn delete_marked(&mut self) {
+        let volume = self.windows[self.focused_editor].volume;
+        let targets = self.windows[self.focused_editor].delete_targets();
+        let storage = self.mounts.volume(volume);
+        let window = &self.windows[self.focused_editor];
+        let open_files = &self.open_files;
+        let mut kept = 0;
+        let mut kept_name = [0; MAX_FILENAME_BYTES];
+        let mut filesystem_operations = || -> Result<(), FileSystemError> {
+            let (_, files) = window.listing(storage)?;
+            for (i, _) in targets.iter().enumerate().filter(|(_, t)| **t) {
+                if dirs::is_dir(&files[i]) {
+                    continue;
+                }
+                if open_files.window_for(volume, &files[i]).is_some() {
+                    kept += 1;
+                    kept_name = files[i];
+                    continue;
+                }
+                storage.delete(name_str(&files[i]).unwrap_or(""))?;
+            }
+            Ok(())
+        };
+        let result = filesystem_operations();
+        self.refresh_listing(volume);
+        for window in self.windows.iter_mut().filter(|w| w.volume == volume) {
+            window.marked = [false; MAX_FILES_STORED];
+        }
+        if kept > 0 {
+            let mut toast: ArrayString<WIN_REGION_WIDTH> = ArrayString::default();
+            match kept {
+                1 => write!(
+                    toast,
+                    "delete: {} is open",
+                    name_str(&kept_name).unwrap_or("")
+                ),
+                _ => write!(toast, "delete: kept {} open files", kept),
+            }
+            .unwrap();
+            self.notifier.notify(toast.as_str().unwrap(), false);
+        }
+        if let Err(e) = result {
+            self.print_error(self.focused_editor, e);
+        }
+    }
+
+    // Opens `path` (optionally prefixed with a volume, as in `b:notes`) for
+    // editing in `window`, closing whatever the window had open first.
+    pub fn open_in_window(&mut self, window: usize, path: &str) -> Result<(), FileSystemError> {
+        let (volume, filename) = self.resolve_path(window, path)?;
+        self.close_window(window);
+        self.edit_file(window, volume, filename)
+    }
+
+    // Like open_in_window, but runs the program instead of editing it.
+    pub fn run_in_window(&mut self, window: usize, path: &str) -> Result<(), FileSystemError> {
+        let (volume, filename) = self.resolve_path(window, path)?;
+        self.close_window(window);
+        self.run_file(window, volume, filename, false)
+    }
+
+    fn resolve_path(
+        &self,
+        window: usize,
+        path: &str,
+    ) -> Result<(usize, [u8; MAX_FILENAME_BYTES]), FileSystemError> {
+        match parse_path(path, self.windows[window].volume) {
+            Some((_, name)) if name.len() > MAX_FILENAME_BYTES => {
+                Err(FileSystemError::FilenameTooLong)
+            }
+            Some((volume, name)) => Ok((volume, name_bytes(name))),
+            None => Err(FileSystemError::FileNotFound),
+        }
+    }
+
+    // Acts on the focused file of a listing, first asking for its
+    // passphrase if it is protected and hasn't been unlocked, or always
+    // when its protection is to change.
+    fn open_listing_file(&mut self, action: FileAction) {
+        let window = self.focused_editor;
+        let volume = self.windows[window].volume;
+        let filename = match self.focused_listing_file() {
+            Ok(filename) => filename,
+            Err(e) => return self.print_error(window, e),
+        };
+        let storage = self.mounts.volume(volume);
+        let name = name_str(&filename).unwrap_or("");
+        let action = match action {
+            FileAction::Protect if storage.flags(name) & PROTECTED != 0 => FileAction::Unprotect,
+            action => action,
+        };
+        if storage.is_locked(name) || matches!(action, FileAction::Protect | FileAction::Unprotect)
+        {
+            self.passphrase = Some(PassphrasePrompt {
+                action,
+                volume,
+                filename,
+                typed: ArrayString::default(),
+            });
+        } else if let Err(e) = self.do_file_action(window, volume, filename, action) {
+            self.print_error(window, e);
+        }
+    }
+
+    fn submit_passphrase(&mut self) {
+        let Some(prompt) = self.passphrase.take() else {
+            return;
+        };
+        let window = self.focused_editor;
+        let storage = self.mounts.volume(prompt.volume);
+        let name = name_str(&prompt.filename).unwrap_or("");
+        let passphrase = prompt.typed.as_str().unwrap();
+        let result = match prompt.action {
+            FileAction::Protect if passphrase.is_empty() => {
+                return self.set_filename_message("passphrase is empty");
+            }
+            FileAction::Protect => storage.protect(name, passphrase),
+            _ if !storage.unlock(name, passphrase) => {
+                return self.set_filename_message("wrong passphrase");
+            }
+            action => self.do_file_action(window, prompt.volume, prompt.filename, action),
+        };
+        if let Err(e) = result {
+            self.print_error(window, e);
+        }
+    }
+
+    fn do_file_action(
+        &mut self,
+        window: usize,
+        volume: usize,
+        filename: [u8; MAX_FILENAME_BYTES],
+        action: FileAction,
+    ) -> Result<(), FileSystemError> {
+        match action {
+            FileAction::Edit => self.edit_file(window, volume, filename),
+            FileAction::Run if self.windows[window].shows_split() => {
+                self.run_under(window, volume, filename)
+            }
+            FileAction::Run => self.run_file(window, volume, filename, false),
+            FileAction::RunIn(target) => self.launch(target, volume, filename),
+            FileAction::RunEverywhere => self.clone_run(volume, filename),
+            FileAction::RunLogged => self.run_file(window, volume, filename, true),
+            FileAction::View => {
+                let window = &mut self.windows[window];
+                window.clear_window();
+                window.viewer = Some(Viewer::new(filename));
+                window.current_file = filename;
+                window.state = WindowState::Viewing;
+                Ok(())
+            }
+            FileAction::Play => {
+                match Playback::open(self.mounts.volume(volume), volume, filename)? {
+                    Some(playback) => self.playback = Some(playback),
+                    None => self.set_filename_message("too many overlays"),
+                }
+                Ok(())
+            }
+            FileAction::Protect => Ok(()),
+            FileAction::Unprotect => self
+                .mounts
+                .volume(volume)
+                .unprotect(name_str(&filename).unwrap_or("")),
+        }
+    }
+
+    // Goes into the directory focused in the list, or back up for `..`.
+    fn enter_directory(&mut self) {
+        let volume = self.windows[self.focused_editor].volume;
+        let window = &mut self.windows[self.focused_editor];
+        let Ok((num_files, files)) = window.listing(self.mounts.volume(volume)) else {
+            return;
+        };
+        window.follow_focus(&files[..num_files]);
+        match files[..num_files].get(window.focused_file) {
+            Some(entry) if *entry == dirs::PARENT => window.dir = None,
+            Some(entry) if dirs::is_dir(entry) => window.dir = Some(*entry),
+            _ => return,
+        }
+        window.select(0);
+        window.marked = [false; MAX_FILES_STORED];
+        window.clear_window();
+    }
+
+    fn focused_listing_file(&mut self) -> Result<[u8; MAX_FILENAME_BYTES], FileSystemError> {
+        let window = &mut self.windows[self.focused_editor];
+        let (num_files, files) = window.listing(self.mounts.volume(window.volume))?;
+        window.follow_focus(&files[..num_files]);
+        if window.focused_file < num_files && !dirs::is_dir(&files[window.focused_file]) {
+            Ok(files[window.focused_file])
+        } else {
+            Err(FileSystemError::FileNotFound)
+        }
+    }
+
+    fn close_window(&mut self, window: usize) {
+        match self.windows[window].state {
+            WindowState::Editing => {
+                self.save_editor(window);
+                self.keep_place(window);
+                self.open_files.close(window);
+                self.windows[window].editor = None;
+                self.windows[window].split = false;
+                if let Some(pid) = self.windows[window].run {
+                    self.kill(pid);
+                }
+            }
+            WindowState::Running => {
+                if let Some(pid) = self.window_pid(window) {
+                    self.kill(pid);
+                }
+            }
+            WindowState::Viewing => self.windows[window].viewer = None,
+            WindowState::Jobs => self.windows[window].jobs = None,
+            WindowState::Settings => self.windows[window].options = None,
+            WindowState::Tour => self.windows[window].tour = None,
+            WindowState::FileManager => self.windows[window].manager = None,
+            #[cfg(feature = "apps")]
+            WindowState::CharMap => self.windows[window].charmap = None,
+            #[cfg(feature = "apps")]
+            WindowState::Calculator => self.windows[window].calculator = None,
+            #[cfg(feature = "apps")]
+            WindowState::Life => self.windows[window].life = None,
+            #[cfg(feature = "apps")]
+            WindowState::Typing => self.windows[window].typing = None,
+            #[cfg(feature = "serial")]
+            WindowState::Terminal => self.windows[window].terminal = None,
+            #[cfg(feature = "shell")]
+            WindowState::Shell => self.windows[window].shell = None,
+            WindowState::BlockMap | WindowState::Listing => {}
+        }
+        self.windows[window].state = WindowState::Listing;
+        self.windows[window].activity = false;
+        self.windows[window].clear_window();
+        self.release_descriptors(window);
+    }
+
+    #[cfg(feature = "shell")]
+    // Returns a window to the file list whatever it was doing, dropping any
+    // half-finished prompt along with it.
+    fn reset_window(&mut self, window: usize) {
+        self.close_window(window);
+        if window == self.focused_editor {
+            self.creating_file = false;
+            self.confirming_delete = false;
+            self.choosing_window = false;
+            self.filename_input = ArrayString::default();
+            self.set_filename_message("");
+        }
+        self.windows[window].marked = [false; MAX_FILES_STORED];
+        self.windows[window].dir = None;
+        self.windows[window].select(0);
+    }
+
+    // Empties the scrollback of a shell or running program. Other windows
+    // have no scrollback to clear.
+    fn clear_output(&mut self, window: usize) {
+        match self.windows[window].state {
+            #[cfg(feature = "shell")]
+            WindowState::Shell => {
+                if let Some(shell) = &mut self.windows[window].shell {
+                    shell.clear();
+                }
+            }
+            WindowState::Running => {
+                if let Some(pid) = self.window_pid(window) {
+                    if let Some(process) = self.processes.get_mut(pid) {
+                        process.output = Output::default();
+                        process.unscroll();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn edit_file(
+        &mut self,
+        window: usize,
+        volume: usize,
+        filename: [u8; MAX_FILENAME_BYTES],
+    ) -> Result<(), FileSystemError> {
+        self.windows[window].clear_window();
+        self.windows[window].volume = volume;
+        self.windows[window].state = WindowState::Editing;
+        let storage = self.mounts.volume(volume);
+        let mut reader = FileReader::open(storage, core::str::from_utf8(&filename).unwrap())?;
+        let mut buffer = [0; MAX_FILE_BYTES];
+        let result = reader.read_all(storage, &mut buffer);
+        reader.close(storage)?;
+        let num_bytes = result?;
+        let place = storage.editor_place(name_str(&filename).unwrap_or(""));
+        self.windows[window].edit_file(&buffer[0..num_bytes], filename);
+        if let Some(editor) = &mut self.windows[window].editor {
+            editor.restore_place(place.row as usize, place.col as usize, place.top as usize);
+        }
+        self.open_files
+            .open(window, volume, filename, OpenMode::Editing);
+        self.tour_saw(tour::Action::Edited);
+        Ok(())
+    }
+
+    // Remembers where the editor in `window` is, for the file's next editor.
+    // A read-only volume just doesn't remember it.
+    fn keep_place(&mut self, window: usize) {
+        let Some(editor) = self.windows[window].editor else {
+            return;
+        };
+        let byte = |n: usize| n.min(u8::MAX as usize) as u8;
+        let (row, col, top) = editor.place();
+        let place = EditorPlace {
+            row: byte(row),
+            col: byte(col),
+            top: byte(top),
+        };
+        let name = self.windows[window].current_file;
+        let storage = self.mounts.volume(self.windows[window].volume);
+        let _ = storage.record_place(name_str(&name).unwrap_or(""), place);
+    }
+
+    fn kill(&mut self, pid: usize) {
+        if self
+            .processes
+            .get(pid)
+            .is_some_and(|p| p.state != ProcessState::Finished)
+        {
+            self.record_run(pid, RunStatus::Killed);
+        }
+        self.flush_log(pid, true);
+        self.close_input(pid);
+        for window in self.windows.iter_mut().filter(|w| w.run == Some(pid)) {
+            window.run = None;
+        }
+        if let Some(window) = self.processes.remove(pid).and_then(|p| p.window) {
+            self.release_window(window);
+        }
+    }
+
+    // Takes `pid` out of its window, leaving it running in the background.
+    fn detach(&mut self, pid: usize) {
+        if let Some(window) = self.processes.get_mut(pid).and_then(|p| p.window.take()) {
+            self.release_window(window);
+        }
+    }
+
+    // Shows `pid` in `window` in place of whatever the window had open.
+    fn attach(&mut self, pid: usize, window: usize) {
+        self.detach(pid);
+        if self.windows[window].state != WindowState::Listing {
+            self.close_window(window);
+        }
+        if let Some(process) = self.processes.get_mut(pid) {
+            process.window = Some(window);
+            process.output.mark_dirty();
+            self.windows[window].volume = process.volume;
+            self.windows[window].current_file = process.file;
+            self.windows[window].state = WindowState::Running;
+            self.open_files
+                .open(window, process.volume, process.file, OpenMode::Running);
+        }
+    }
+
+    // Returns a window whose process has gone to the listing.
+    fn release_window(&mut self, window: usize) {
+        self.open_files.close(window);
+        if self.windows[window].state == WindowState::Running {
+            self.windows[window].state = WindowState::Listing;
+            self.windows[window].watch = false;
+            self.windows[window].clear_window();
+        }
+    }
+
+    // Errors go to the output of the window's process if it has one, and to
+    // the status bar otherwise.
+    fn print_error(&mut self, window: usize, e: FileSystemError) {
+        match self
+            .window_pid(window)
+            .and_then(|pid| self.processes.get_mut(pid))
+        {
+            Some(process) => writeln!(process.output, "{}", e).unwrap(),
+            None => self.set_filename_message(e),
+        }
+    }
+
+    fn run_file(
+        &mut self,
+        window: usize,
+        volume: usize,
+        filename: [u8; MAX_FILENAME_BYTES],
+        append_output: bool,
+    ) -> Result<(), FileSystemError> {
+        let Some(pid) = self.spawn(volume, filename, Some(window))? else {
+            self.set_filename_message("Too many processes");
+            return Ok(());
+        };
+        self.windows[window].clear_window();
+        self.windows[window].volume = volume;
+        self.windows[window].state = WindowState::Running;
+        self.windows[window].current_file = filename;
+        self.open_files
+            .open(window, volume, filename, OpenMode::Running);
+        if append_output {
+            let base = name_str(&filename).unwrap_or("");
+            let end = base
+                .char_indices()
+                .map(|(i, c)| i + c.len_utf8())
+                .take_while(|end| *end <= MAX_FILENAME_BYTES - OUTPUT_SUFFIX.len())
+                .last()
+                .unwrap_or(0);
+            let mut output: ArrayString<MAX_FILENAME_BYTES> = ArrayString::default();
+            write!(output, "{}{}", &base[..end], OUTPUT_SUFFIX).unwrap();
+            let storage = self.mounts.volume(volume);
+            let log = storage.open_append(output.as_str().unwrap())?;
+            self.refresh_listing(volume);
+            if let Some(process) = self.processes.get_mut(pid) {
+                process.log = Some((volume, log));
+            }
+        }
+        self.tour_saw(tour::Action::Ran);
+        Ok(())
+    }
+
+    // Tells any open tour what was just done, so it can move on.
+    fn tour_saw(&mut self, action: tour::Action) {
+        for tour in self.windows.iter_mut().filter_map(|w| w.tour.as_mut()) {
+            tour.saw(action);
+        }
+    }
+
+    // Runs a file from one window's listing in another, which is cleared
+    // for it unless a program there is still going. Focus stays on the
+    // listing, so it can go on launching.
+    fn launch(
+        &mut self,
+        target: usize,
+        volume: usize,
+        filename: [u8; MAX_FILENAME_BYTES],
+    ) -> Result<(), FileSystemError> {
+        if target == self.focused_editor {
+            return self.run_file(target, volume, filename, false);
+        }
+        if self.window_busy(target) {
+            let mut message: ArrayString<32> = ArrayString::default();
+            write!(message, "window {} is busy", target + 1).unwrap();
+            self.notifier.notify(message.as_str().unwrap(), false);
+            return Ok(());
+        }
+        self.close_window(target);
+        self.run_file(target, volume, filename, false)
+    }
+
+    // Runs a file from the focused listing in every other window that is
+    // free for it: one showing a list, or the output of a program that has
+    // finished. Each copy is its own process, so identical workloads can be
+    // watched sharing the CPU.
+    fn clone_run(
+        &mut self,
+        volume: usize,
+        filename: [u8; MAX_FILENAME_BYTES],
+    ) -> Result<(), FileSystemError> {
+        let group = self.free_group();
+        let mut copies = 0;
+        for window in 0..N {
+            let free = match self.windows[window].state {
+                WindowState::Listing => true,
+                WindowState::Running => !self.window_busy(window),
+                _ => false,
+            };
+            if window == self.focused_editor || !free {
+                continue;
+            }
+            self.close_window(window);
+            self.run_file(window, volume, filename, false)?;
+            let Some(pid) = self.window_pid(window) else {
+                // The process table is full, and run_file has said so.
+                break;
+            };
+            if let Some(process) = self.processes.get_mut(pid) {
+                process.group = Some(group);
+            }
+            copies += 1;
+        }
+        let mut toast: ArrayString<40> = ArrayString::default();
+        match copies {
+            0 => write!(toast, "no free window"),
+            _ => write!(
+                toast,
+                "{} running in {} windows",
+                name_str(&filename).unwrap_or("?"),
+                copies
+            ),
+        }
+        .unwrap();
+        self.notifier.notify(toast.as_str().unwrap(), false);
+        Ok(())
+    }
+
+    // The lowest group number no live process has. There are never more
+    // groups than processes, so one is always free.
+    fn free_group(&self) -> usize {
+        (1..=MAX_PROCESSES)
+            .find(|g| self.processes.iter().all(|(_, p)| p.group != Some(*g)))
+            .unwrap_or(1)
+    }
+
+    // Whether a window has a program that hasn't finished yet.
+    fn window_busy(&self, window: usize) -> bool {
+        self.window_process(window)
+            .is_some_and(|p| p.state != ProcessState::Finished)
+    }
+
+    // Loads a program into a new process, returning None if the process
+    // table is full.
+    fn spawn(
+        &mut self,
+        volume: usize,
+        filename: [u8; MAX_FILENAME_BYTES],
+        window: Option<usize>,
+    ) -> Result<Option<usize>, FileSystemError> {
+        let storage = self.mounts.volume(volume);
+        let mut reader = FileReader::open(storage, core::str::from_utf8(&filename).unwrap())?;
+        let mut buffer = [0; MAX_FILE_BYTES];
+        let result = reader.read_all(storage, &mut buffer);
+        reader.close(storage)?;
+        let num_bytes = result?;
+        Ok(self.spawn_program(volume, filename, window, &buffer[0..num_bytes]))
+    }
+
+    // Starts `source`, as code page 437 bytes, as a run of `filename`.
+    fn spawn_program(
+        &mut self,
+        volume: usize,
+        filename: [u8; MAX_FILENAME_BYTES],
+        window: Option<usize>,
+        source: &[u8],
+    ) -> Option<usize> {
+        let vruntime = self.min_vruntime().0;
+        let mut text = [0; 2 * MAX_FILE_BYTES];
+        let program = cp437_to_utf8(source, &mut text);
+        let mut process = Process::new(program, filename, volume, window, vruntime);
+        process.started = self.metrics.frames;
+        self.processes.spawn(process)
+    }
+
+    // Runs an editor's text as it stands, saved or not, in the background,
+    // and splits the editor to show the output. A run still going from the
+    // last F5 is killed first.
+    fn run_from_editor(&mut self, window: usize) {
+        let Some(editor) = self.windows[window].editor else {
+            return;
+        };
+        if let Some(pid) = self.windows[window].run {
+            self.kill(pid);
+        }
+        let (volume, file) = (
+            self.windows[window].volume,
+            self.windows[window].current_file,
+        );
+        let contents = editor.get_file_contents();
+        match self.spawn_program(volume, file, None, contents.buffer_slice()) {
+            Some(pid) => {
+                self.windows[window].run = Some(pid);
+                self.windows[window].split = true;
+            }
+            None => self.set_filename_message("Too many processes"),
+        }
+    }
+
+    // Runs a file from a split listing in the background and shows its
+    // output under the list, killing the last run started there.
+    fn run_under(
+        &mut self,
+        window: usize,
+        volume: usize,
+        filename: [u8; MAX_FILENAME_BYTES],
+    ) -> Result<(), FileSystemError> {
+        if let Some(pid) = self.windows[window].run {
+            self.kill(pid);
+        }
+        match self.spawn(volume, filename, None)? {
+            Some(pid) => self.windows[window].run = Some(pid),
+            None => self.set_filename_message("Too many processes"),
+        }
+        Ok(())
+    }
+
+    // Keeps how `pid`'s run went with its file. A read-only volume just
+    // doesn't remember it.
+    fn record_run(&mut self, pid: usize, status: RunStatus) {
+        if let Some(process) = self.processes.get_mut(pid) {
+            process.stopped = Some(self.metrics.frames);
+            let run = RunStats {
+                ticks: process.wall_ticks(self.metrics.frames) as u32,
+                instructions: process.ticks as u32,
+                status,
+            };
+            let file = process.file;
+            let storage = self.mounts.volume(process.volume);
+            let _ = storage.record_run(name_str(&file).unwrap_or(""), run);
+        }
+    }
+
+    // Saves are checked against the volume's free blocks and directory
+    // slots before anything is written, so one that can't fit is refused
+    // with the reason instead of failing halfway.
+    fn save_editor(&mut self, window: usize) {
+        if self.refuse_read_only("save") {
+            return;
+        }
+        if let Some(editor) = self.windows[window].editor {
+            let filename = self.windows[window].current_file;
+            let name = name_str(&filename).unwrap_or("");
+            let storage = self.mounts.volume(self.windows[window].volume);
+            let quota = Quota::check(storage, name, editor.get_file_contents().len());
+            if quota != Quota::Fits {
+                let mut toast: ArrayString<WIN_REGION_WIDTH> = ArrayString::default();
+                write!(toast, "{}: {}", name, quota).unwrap();
+                self.notifier.notify(toast.as_str().unwrap(), false);
+            }
+            if quota.refuses() {
+                return;
+            }
+            if let Some(mut pending) = self.saves[window].take() {
+                pending.cancel(self.mounts.volume(pending.volume));
+            }
+            // Protected files are written in place, since a scratch copy
+            // would hold them in the clear, and so is a save with no room
+            // for a second copy.
+            let scratch = save::scratch_name(window);
+            let size = editor.get_file_contents().len();
+            let storage = self.mounts.volume(self.windows[window].volume);
+            let scratch = (storage.flags(name) & PROTECTED == 0
+                && Quota::room_for_scratch(storage, name, name_str(&scratch).unwrap_or(""), size))
+            .then_some(scratch);
+            self.saves[window] = Some(SaveTask::new(
+                self.windows[window].volume,
+                self.windows[window].current_file,
+                scratch,
+                editor.get_file_contents(),
+            ));
+        }
+    }
+
+    fn toggle_watch(&mut self, window: usize) {
+        let watch = !self.windows[window].watch;
+        self.windows[window].watch = watch;
+        let name = name_str(&self.windows[window].current_file).unwrap_or("");
+        let mut toast: ArrayString<WIN_REGION_WIDTH> = ArrayString::default();
+        match watch {
+            true => write!(toast, "{}: runs again on each save", name),
+            false => write!(toast, "{}: no longer watched", name),
+        }
+        .unwrap();
+        self.notifier.notify(toast.as_str().unwrap(), false);
+    }
+
+    // Each window watching the file just saved kills its run, finished or
+    // not, and starts the file afresh.
+    fn rerun_watching(&mut self, volume: usize, file: [u8; MAX_FILENAME_BYTES]) {
+        for window in 0..N {
+            let w = &self.windows[window];
+            if !w.watch
+                || w.state != WindowState::Running
+                || (w.volume, w.current_file) != (volume, file)
+            {
+                continue;
+            }
+            self.close_window(window);
+            if let Err(e) = self.run_file(window, volume, file, false) {
+                self.print_error(window, e);
+            }
+            self.windows[window].watch = self.windows[window].state == WindowState::Running;
+        }
+    }
+
+    fn advance_saves(&mut self) {
+        for window in 0..N {
+            if let Some(mut save) = self.saves[window] {
+                self.mounts.set_opener(Some(window));
+                match save.step(self.mounts.volume(save.volume)) {
+                    Ok(true) => {
+                        self.saves[window] = None;
+                        self.refresh_listing(save.volume);
+                        self.rerun_watching(save.volume, save.name());
+                    }
+                    Ok(false) => self.saves[window] = Some(save),
+                    Err(e) => {
+                        self.saves[window] = None;
+                        let name = name_str(&self.windows[window].current_file).unwrap_or("");
+                        let mut toast: ArrayString<WIN_REGION_WIDTH> = ArrayString::default();
+                        write!(toast, "{}: not saved: {}", name, e).unwrap();
+                        self.notifier.notify(toast.as_str().unwrap(), false);
+                    }
+                }
+            }
+        }
+    }
+
+    // A descriptor still open at the end of a tick that no program or save
+    // holds was left open by whatever opened it. The window that did is
+    // named on a toast, once, and the descriptor is closed when its volume
+    // next runs out.
+    fn find_leaks(&mut self) {
+        for volume in 0..NUM_VOLUMES {
+            let (processes, saves) = (&self.processes, &self.saves);
+            let held = |fd| fd_held(processes, saves, volume, fd);
+            if let Some((fd, window)) = self.mounts.volume(volume).find_leak(held) {
+                let mut toast: ArrayString<WIN_REGION_WIDTH> = ArrayString::default();
+                match window {
+                    Some(window) => write!(toast, "window {} left fd {} open", window + 1, fd),
+                    None => write!(toast, "kernel left fd {} open", fd),
+                }
+                .unwrap();
+                self.notifier.notify(toast.as_str().unwrap(), false);
+            }
+        }
     }
 
-    pub fn draw_processes(&mut self) {
-        for i in 0..4 {
-            plot(
-                'F',
-                WIN_REGION_WIDTH,
-                i * 2,
-                ColorCode::new(Color::LightCyan, Color::Black),
-            );
-            plot(
-                (i + 49) as u8 as char,
-                WIN_REGION_WIDTH + 1,
-                i * 2,
-                ColorCode::new(Color::LightCyan, Color::Black),
-            );
-
-            plot_num_right_justified(
-                10,
-                self.windows[i].vruntime as isize,
-                WIN_REGION_WIDTH,
-                i * 2 + 1,
-                ColorCode::new(Color::LightCyan, Color::Black),
-            );
+    // Closes what a window still has open once it is reset or its program
+    // crashes, so runs cut short can't use up the descriptors between them.
+    fn release_descriptors(&mut self, window: usize) {
+        for volume in 0..NUM_VOLUMES {
+            let (processes, saves) = (&self.processes, &self.saves);
+            let held = |fd| fd_held(processes, saves, volume, fd);
+            self.mounts.volume(volume).close_window(window, held);
         }
     }
 
-    pub fn key(&mut self, key: DecodedKey) {
-        match key {
-            DecodedKey::RawKey(code) => self.handle_raw(code),
-            DecodedKey::Unicode(c) => self.handle_unicode(c),
+    #[cfg(feature = "apps")]
+    // Running Life boards move on a generation every LIFE_PERIOD frames,
+    // whichever window has focus.
+    fn advance_life(&mut self) {
+        if !self.metrics.frames.is_multiple_of(LIFE_PERIOD) {
+            return;
+        }
+        for window in self.windows.iter_mut() {
+            if let Some(life) = &mut window.life {
+                if life.running {
+                    life.step();
+                }
+            }
         }
     }
 
-    fn handle_raw(&mut self, key: KeyCode) {
+    fn handle_raw(&mut self, key: KeyCode, modifiers: Modifiers) {
         match key {
             KeyCode::F1 => {
                 if !self.creating_file {
-                    self.windows[self.focused_editor].set_focus(false);
-                    self.focused_editor = 0;
-                    self.windows[self.focused_editor].set_focus(true);
+                    self.focus_slot(0);
                 }
             }
             KeyCode::F2 => {
                 if !self.creating_file {
-                    self.windows[self.focused_editor].set_focus(false);
-                    self.focused_editor = 1;
-                    self.windows[self.focused_editor].set_focus(true);
+                    self.focus_slot(1);
                 }
             }
             KeyCode::F3 => {
                 if !self.creating_file {
-                    self.windows[self.focused_editor].set_focus(false);
-                    self.focused_editor = 2;
-                    self.windows[self.focused_editor].set_focus(true);
+                    self.focus_slot(2);
                 }
             }
             KeyCode::F4 => {
                 if !self.creating_file {
-                    self.windows[self.focused_editor].set_focus(false);
-                    self.focused_editor = 3;
-                    self.windows[self.focused_editor].set_focus(true);
+                    self.focus_slot(3);
                 }
             }
             KeyCode::F5 => match self.windows[self.focused_editor].state {
-                WindowState::Editing => {}
+                WindowState::Editing => self.run_from_editor(self.focused_editor),
                 WindowState::Running => {}
+                WindowState::Viewing => {}
+                #[cfg(feature = "shell")]
+                WindowState::Shell => {}
+                WindowState::BlockMap => {}
+                WindowState::Jobs => {}
+                WindowState::Settings => {}
+                WindowState::Tour => {}
+                WindowState::FileManager => {}
+                #[cfg(feature = "apps")]
+                WindowState::CharMap => {}
+                #[cfg(feature = "apps")]
+                WindowState::Calculator => {}
+                #[cfg(feature = "apps")]
+                WindowState::Life => {}
+                #[cfg(feature = "apps")]
+                WindowState::Typing => {}
+                #[cfg(feature = "serial")]
+                WindowState::Terminal => {}
                 WindowState::Listing => {
-                    if !self.creating_file {
+                    if !self.creating_file && !self.refuse_read_only("create files") {
                         self.creating_file = true;
                     }
                 }
             },
+            KeyCode::F7 => self.move_panel_selection(false),
+            KeyCode::F8 => self.move_panel_selection(true),
             KeyCode::F6 => match self.windows[self.focused_editor].state {
-                WindowState::Editing => {
-                    if let Some(editor) = self.windows[self.focused_editor].editor {
-                        let file = editor.get_file_contents();
-
-                        let mut filesystem_operations = || -> Result<(), FileSystemError> {
-                            let fd = self.filesystem.open_create(
-                                core::str::from_utf8(&self.windows[self.focused_editor].current_file).unwrap()
-                            )?;
-                            self.filesystem.write(fd, file.as_str().unwrap().as_bytes())?;
-                            self.filesystem.close(fd)?;
-                            Ok(())
-                        };
-                        filesystem_operations().unwrap_or_else(|e| {
-                            let mut err: ArrayString<80> = ArrayString::default();
-                            write!(err, "{}", e).unwrap();
-                            self.windows[self.focused_editor].print(err.as_str().unwrap().as_bytes());
-                        });
-                    }
-                    self.windows[self.focused_editor].clear_window();
-                    self.windows[self.focused_editor].editor = None;
-                    self.windows[self.focused_editor].state = WindowState::Listing;
-                }
-                WindowState::Running => {
-                    self.windows[self.focused_editor].interpreter = None;
-                    self.windows[self.focused_editor].interpreter_print_loc = 0;
-                    self.windows[self.focused_editor].vruntime = 0;
-                    self.windows[self.focused_editor].state = WindowState::Listing;
-                    self.windows[self.focused_editor].clear_window();
-                }
                 WindowState::Listing => {
                     if self.creating_file {
                         self.creating_file = false;
                         self.filename_input = ArrayString::default();
+                        self.set_filename_message("");
                     }
                 }
+                _ => self.close_window(self.focused_editor),
             },
             KeyCode::ArrowUp => {
                 if let Some(mut editor) = self.windows[self.focused_editor].editor {
+                    editor.set_selecting(modifiers.shift);
                     editor.move_cursor_up();
                     self.windows[self.focused_editor].editor = Some(editor);
                 }
+                if let Some(viewer) = &mut self.windows[self.focused_editor].viewer {
+                    viewer.scroll_up();
+                }
+                if let Some(jobs) = &mut self.windows[self.focused_editor].jobs {
+                    jobs.up();
+                }
+                if let Some(options) = &mut self.windows[self.focused_editor].options {
+                    options.up();
+                }
+                if let Some(manager) = &mut self.windows[self.focused_editor].manager {
+                    manager.up();
+                }
+                #[cfg(feature = "apps")]
+                if let Some(charmap) = &mut self.windows[self.focused_editor].charmap {
+                    charmap.up();
+                }
+                #[cfg(feature = "apps")]
+                if let Some(life) = &mut self.windows[self.focused_editor].life {
+                    life.up();
+                }
+                #[cfg(feature = "shell")]
+                if let Some(shell) = &mut self.windows[self.focused_editor].shell {
+                    shell.history_back();
+                }
+                if let Some(process) = self.focused_output() {
+                    process.select_up();
+                }
             }
             KeyCode::ArrowRight => {
                 match self.windows[self.focused_editor].state {
                     WindowState::Editing => {
                         if let Some(mut editor) = self.windows[self.focused_editor].editor {
+                            editor.set_selecting(modifiers.shift);
                             editor.move_cursor_right();
                             self.windows[self.focused_editor].editor = Some(editor);
                         }
                     },
-                    WindowState::Running => (),
-                    WindowState::Listing => {
-                        self.windows[self.focused_editor].focused_file =
-                            (self.windows[self.focused_editor].focused_file + 1)
-                                .mod_floor(&self.num_files);
+                    #[cfg(feature = "shell")]
+                    WindowState::Shell => {
+                        if let Some(shell) = &mut self.windows[self.focused_editor].shell {
+                            shell.move_cursor_right();
+                        }
+                    }
+                    WindowState::Settings => self.change_option(true),
+                    WindowState::FileManager => {
+                        if let Some(manager) = &mut self.windows[self.focused_editor].manager {
+                            manager.step_volume(true);
+                        }
+                    }
+                    #[cfg(feature = "apps")]
+                    WindowState::CharMap => {
+                        if let Some(charmap) = &mut self.windows[self.focused_editor].charmap {
+                            charmap.right();
+                        }
+                    }
+                    #[cfg(feature = "apps")]
+                    WindowState::Life => {
+                        if let Some(life) = &mut self.windows[self.focused_editor].life {
+                            life.right();
+                        }
+                    }
+                    WindowState::Running
+                    | WindowState::Viewing
+                    | WindowState::Jobs
+                    | WindowState::Tour => (),
+                    #[cfg(feature = "apps")]
+                    WindowState::Calculator | WindowState::Typing => (),
+                    #[cfg(feature = "serial")]
+                    WindowState::Terminal => (),
+                    WindowState::Listing | WindowState::BlockMap => {
+                        let num_files = self.focused_num_files();
+                        let window = &mut self.windows[self.focused_editor];
+                        window.select(wrapping_nav::next(window.focused_file, num_files));
+                        self.tour_saw(tour::Action::Browsed);
                     }
                 }
                 //self.windows[self.focused_editor].move_cursor_right();
             }
             KeyCode::ArrowDown => {
                 if let Some(mut editor) = self.windows[self.focused_editor].editor {
+                    editor.set_selecting(modifiers.shift);
                     editor.move_cursor_down();
                     self.windows[self.focused_editor].editor = Some(editor);
                 }
+                if let Some(viewer) = &mut self.windows[self.focused_editor].viewer {
+                    viewer.scroll_down();
+                }
+                if let Some(jobs) = &mut self.windows[self.focused_editor].jobs {
+                    jobs.down(&self.processes);
+                }
+                if let Some(options) = &mut self.windows[self.focused_editor].options {
+                    options.down();
+                }
+                if let Some(manager) = &mut self.windows[self.focused_editor].manager {
+                    manager.down();
+                }
+                #[cfg(feature = "apps")]
+                if let Some(charmap) = &mut self.windows[self.focused_editor].charmap {
+                    charmap.down();
+                }
+                #[cfg(feature = "apps")]
+                if let Some(life) = &mut self.windows[self.focused_editor].life {
+                    life.down();
+                }
+                #[cfg(feature = "shell")]
+                if let Some(shell) = &mut self.windows[self.focused_editor].shell {
+                    shell.history_forward();
+                }
+                if let Some(process) = self.focused_output() {
+                    process.select_down();
+                }
             }
             KeyCode::ArrowLeft => {
                 match self.windows[self.focused_editor].state {
                     WindowState::Editing => {
                         if let Some(mut editor) = self.windows[self.focused_editor].editor {
+                            editor.set_selecting(modifiers.shift);
                             editor.move_cursor_left();
                             self.windows[self.focused_editor].editor = Some(editor);
                         }
                     },
-                    WindowState::Running => (),
-                    WindowState::Listing => {
-                        if self.num_files > 0 {
-                            self.windows[self.focused_editor].focused_file =
-                                (self.windows[self.focused_editor].focused_file + self.num_files
-                                    - 1)
-                                .mod_floor(&self.num_files);
+                    #[cfg(feature = "shell")]
+                    WindowState::Shell => {
+                        if let Some(shell) = &mut self.windows[self.focused_editor].shell {
+                            shell.move_cursor_left();
+                        }
+                    }
+                    WindowState::Settings => self.change_option(false),
+                    WindowState::FileManager => {
+                        if let Some(manager) = &mut self.windows[self.focused_editor].manager {
+                            manager.step_volume(false);
+                        }
+                    }
+                    #[cfg(feature = "apps")]
+                    WindowState::CharMap => {
+                        if let Some(charmap) = &mut self.windows[self.focused_editor].charmap {
+                            charmap.left();
+                        }
+                    }
+                    #[cfg(feature = "apps")]
+                    WindowState::Life => {
+                        if let Some(life) = &mut self.windows[self.focused_editor].life {
+                            life.left();
                         }
                     }
+                    WindowState::Running
+                    | WindowState::Viewing
+                    | WindowState::Jobs
+                    | WindowState::Tour => (),
+                    #[cfg(feature = "apps")]
+                    WindowState::Calculator | WindowState::Typing => (),
+                    #[cfg(feature = "serial")]
+                    WindowState::Terminal => (),
+                    WindowState::Listing | WindowState::BlockMap => {
+                        let num_files = self.focused_num_files();
+                        let window = &mut self.windows[self.focused_editor];
+                        window.select(wrapping_nav::prev(window.focused_file, num_files));
+                        self.tour_saw(tour::Action::Browsed);
+                    }
                 }
                 //self.windows[self.focused_editor].move_cursor_left();
             }
+            KeyCode::PageUp => {
+                if let Some(process) = self.focused_output() {
+                    process.page_up();
+                }
+            }
+            KeyCode::PageDown => {
+                if let Some(process) = self.focused_output() {
+                    process.page_down();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_jobs_key(&mut self, key: char) {
+        let window = self.focused_editor;
+        let Some(pid) = self.windows[window]
+            .jobs
+            .and_then(|jobs| jobs.selected_pid(&self.processes))
+        else {
+            return;
+        };
+        match key {
+            'a' => self.attach(pid, window),
+            'd' => self.detach(pid),
+            _ => self.control_process(pid, key),
+        }
+    }
+
+    // Kills, pauses or renices a process for the jobs window and the task
+    // panel.
+    fn control_process(&mut self, pid: usize, key: char) {
+        // Shifted, kill and pause act on every process in the selected one's
+        // group, or on it alone if it has none.
+        let group = self.processes.get(pid).and_then(|p| p.group);
+        let mut members = [None; MAX_PROCESSES];
+        for (slot, (other, process)) in members.iter_mut().zip(self.processes.iter()) {
+            if other == pid || (group.is_some() && process.group == group) {
+                *slot = Some(other);
+            }
+        }
+        match key {
+            'K' => {
+                for other in members.into_iter().flatten() {
+                    self.kill(other);
+                }
+            }
+            'P' => {
+                let resume = self
+                    .processes
+                    .get(pid)
+                    .is_some_and(|p| p.state == ProcessState::Paused);
+                for other in members.into_iter().flatten() {
+                    if let Some(process) = self.processes.get_mut(other) {
+                        process.state = match (process.state, resume) {
+                            (ProcessState::Running, false) => ProcessState::Paused,
+                            (ProcessState::Paused, true) => ProcessState::Running,
+                            (state, _) => state,
+                        }
+                    }
+                }
+            }
+            'k' => self.kill(pid),
+            _ => {}
+        }
+        if let Some(process) = self.processes.get_mut(pid) {
+            match key {
+                'p' => {
+                    process.state = match process.state {
+                        ProcessState::Running => ProcessState::Paused,
+                        ProcessState::Paused => ProcessState::Running,
+                        state => state,
+                    }
+                }
+                '-' => process.nice = (process.nice + 1).min(MAX_NICE),
+                '+' => process.nice = process.nice.saturating_sub(1),
+                _ => {}
+            }
+        }
+    }
+
+    // A digit typed in a list adds to the number of the file to pick, shown
+    // beside each file meanwhile, and Enter picks it. Digits that would
+    // number past the last file are dropped.
+    fn type_index(&mut self, index: usize, key: char) {
+        let num_files = self.focused_num_files();
+        let window = &mut self.windows[self.focused_editor];
+        window.typed_index = match key {
+            '\n' => {
+                if (1..=num_files).contains(&index) {
+                    window.select(index - 1);
+                }
+                None
+            }
+            '\u{0008}' => Some(index / 10),
+            '0'..='9' => {
+                let typed = index * 10 + key.to_digit(10).unwrap_or(0) as usize;
+                Some(if typed <= num_files { typed } else { index })
+            }
+            _ => None,
+        };
+    }
+
+    // Focuses the first entry in the focused list whose name starts with
+    // `prefix`, whatever its case, returning false if there is none.
+    fn jump_to_prefix(&mut self, prefix: &str) -> bool {
+        let window = self.focused_editor;
+        let storage = self.mounts.volume(self.windows[window].volume);
+        let Ok((num_files, files)) = self.windows[window].listing(storage) else {
+            return false;
+        };
+        let dir = self.windows[window].dir;
+        let found = files[..num_files].iter().position(|file| {
+            let name = dirs::display_name(file, dir).as_bytes();
+            name.len() >= prefix.len()
+                && name[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+        });
+        if let Some(file) = found {
+            self.windows[window].select(file);
+        }
+        found.is_some()
+    }
+
+    fn handle_manager_key(&mut self, key: char) {
+        let window = self.focused_editor;
+        match key {
+            '\t' => {
+                if let Some(manager) = &mut self.windows[window].manager {
+                    manager.switch_pane();
+                }
+            }
+            'c' => self.transfer_file(window, false),
+            'm' => self.transfer_file(window, true),
             _ => {}
         }
     }
 
+    // Copies the file picked in a file manager's active pane to the other
+    // pane's volume under the same name, deleting the original if it is
+    // being moved. A file of that name already there is left alone, and
+    // protected files stay where they are so they are never written out
+    // in the clear.
+    fn transfer_file(&mut self, window: usize, remove: bool) {
+        let Some(manager) = self.windows[window].manager else {
+            return;
+        };
+        let verb = if remove { "move" } else { "copy" };
+        if self.refuse_read_only(if remove { "move files" } else { "copy files" }) {
+            return;
+        }
+        let (from, to) = manager.volumes();
+        let mut toast: ArrayString<WIN_REGION_WIDTH> = ArrayString::default();
+        match manager.selected_file(self.mounts.volume(from)) {
+            Ok(_) if from == to => write!(toast, "{}: both panes are {}:", verb, volume_letter(to)),
+            Ok(file) => {
+                let name = name_str(&file).unwrap_or("");
+                if self.mounts.volume(from).flags(name) & PROTECTED != 0 {
+                    write!(toast, "{}: {} is protected", verb, name)
+                } else if self.mounts.volume(to).exists(name) {
+                    write!(toast, "{}: {}:{} exists", verb, volume_letter(to), name)
+                } else if remove && self.open_files.window_for(from, &file).is_some() {
+                    write!(toast, "{}: {} is open", verb, name)
+                } else {
+                    let result = self.copy_file((from, name), (to, name));
+                    let result = match remove {
+                        true => result.and_then(|_| self.mounts.volume(from).delete(name)),
+                        false => result,
+                    };
+                    match result {
+                        Ok(()) => Ok(()),
+                        Err(e) => write!(toast, "{}: {}", verb, e),
+                    }
+                }
+            }
+            Err(e) => write!(toast, "{}: {}", verb, e),
+        }
+        .unwrap();
+        if remove {
+            self.refresh_listing(from);
+        }
+        if toast.len() > 0 {
+            self.notifier.notify(toast.as_str().unwrap(), false);
+        }
+    }
+
     fn handle_unicode(&mut self, key: char) {
         match self.windows[self.focused_editor].state {
+            WindowState::Editing if self.renaming.is_some() => match key {
+                '\n' => self.submit_rename(),
+                '\u{1b}' => self.renaming = None,
+                k if k == '\u{0008}' || k.is_ascii_alphanumeric() || k == '_' => {
+                    if let Some(renaming) = &mut self.renaming {
+                        renaming.typed.push_char(k);
+                    }
+                }
+                _ => {}
+            },
             WindowState::Editing => {
                 if let Some(mut editor) = self.windows[self.focused_editor].editor {
                     match key {
@@ -670,7 +4088,7 @@ impl SwimInterface {
                         '\u{0008}' => editor.backspace_char(),
                         '\u{007F}' => editor.delete_char(),
                         k => {
-                            if is_drawable(k) {
+                            if self.settings.typeable(k) {
                                 editor.push_char(key);
                             }
                         }
@@ -679,118 +4097,258 @@ impl SwimInterface {
                 }
             },
             WindowState::Running => {
-                if self.windows[self.focused_editor].taking_input {
-                    if let Some(mut interpreter) = self.windows[self.focused_editor].interpreter {
+                let pid = self.window_pid(self.focused_editor);
+                if let Some(process) = pid.and_then(|pid| self.processes.get_mut(pid)) {
+                    if process.state == ProcessState::More {
+                        process.more();
+                    } else if let (Some(line), '\n') = (process.selected_line(), key) {
+                        self.clipboard = copy_line(line);
+                        process.selected = None;
+                        process.output.mark_dirty();
+                    } else if process.state == ProcessState::AwaitingInput {
                         match key {
-                            '\n' => {
-                                self.windows[self.focused_editor].vruntime = self.min_vruntime().0;
-                                interpreter
-                                    .provide_input(
-                                        self.windows[self.focused_editor]
-                                            .input_buffer
-                                            .as_str()
-                                            .unwrap(),
-                                    )
-                                    .unwrap_or_else(|e| {
-                                        let mut err: ArrayString<80> = Default::default();
-                                        write!(err, "{}", e).unwrap();
-                                        self.windows[self.focused_editor]
-                                            .print(err.as_str().unwrap().as_bytes());
-                                    });
-                                self.windows[self.focused_editor].interpreter_print_loc += 1;
-                                self.windows[self.focused_editor].taking_input = false;
-                            }
-                            '\u{0008}' => self.windows[self.focused_editor]
-                                .input_buffer
-                                .push_char('\u{0008}'),
-                            k => {
-                                if is_drawable(k) {
-                                    self.windows[self.focused_editor].input_buffer.push_char(k);
-                                }
+                            '\n' if process.input_kind != InputKind::Lines => self.answer_input(),
+                            '\u{0008}' => {
+                                process.input.push_char('\u{0008}');
+                                process.output.mark_dirty();
                             }
+                            k => process.type_char(k),
                         }
-                        self.windows[self.focused_editor].interpreter = Some(interpreter);
                     }
                 }
             }
+            WindowState::Viewing | WindowState::BlockMap | WindowState::Tour => {}
+            WindowState::Jobs => self.handle_jobs_key(key),
+            WindowState::FileManager => self.handle_manager_key(key),
+            WindowState::Settings => {
+                if key == '\n' {
+                    self.change_option(true);
+                }
+            }
+            #[cfg(feature = "apps")]
+            WindowState::CharMap => {
+                if key == '\n' {
+                    self.insert_char();
+                }
+            }
+            #[cfg(feature = "apps")]
+            WindowState::Typing => {
+                if let Some(test) = &mut self.windows[self.focused_editor].typing {
+                    test.type_char(key, self.metrics.frames);
+                }
+            }
+            #[cfg(feature = "serial")]
+            WindowState::Terminal => {
+                if key.is_ascii() {
+                    serial::send(key as u8);
+                }
+            }
+            #[cfg(feature = "apps")]
+            WindowState::Life => {
+                if let Some(life) = &mut self.windows[self.focused_editor].life {
+                    match key {
+                        ' ' => life.toggle(),
+                        'p' => life.running = !life.running,
+                        'n' => life.step(),
+                        'c' => life.clear(),
+                        _ => {}
+                    }
+                }
+            }
+            #[cfg(feature = "apps")]
+            WindowState::Calculator => {
+                if let Some(calculator) = &mut self.windows[self.focused_editor].calculator {
+                    match key {
+                        '\n' => calculator.evaluate(),
+                        k if k == '\u{0008}' || is_drawable(k) => calculator.push_char(k),
+                        _ => {}
+                    }
+                }
+            }
+            #[cfg(feature = "shell")]
+            WindowState::Shell => self.handle_shell_key(key),
             WindowState::Listing => {
-                if !self.creating_file {
+                if let Some(prompt) = &mut self.passphrase {
+                    match key {
+                        '\n' => self.submit_passphrase(),
+                        '\u{0008}' => prompt.typed.push_char(key),
+                        k if k.is_ascii_graphic() && prompt.typed.len() < MAX_PASSPHRASE => {
+                            prompt.typed.push_char(k)
+                        }
+                        _ => {}
+                    }
+                } else if self.confirming_delete {
+                    self.confirming_delete = false;
+                    if key == 'y' {
+                        self.delete_marked();
+                    }
+                } else if let Some(index) = self.windows[self.focused_editor].typed_index {
+                    self.type_index(index, key);
+                } else if let Some((mut prefix, _)) = self.jump {
+                    // Keys that don't lead to a name are dropped, so the
+                    // prefix always matches something.
+                    self.jump = match key {
+                        '\n' | '\u{1b}' => None,
+                        '\u{0008}' => {
+                            prefix.push_char(key);
+                            Some((prefix, self.metrics.frames))
+                        }
+                        k if is_drawable(k) && prefix.len() < MAX_FILENAME_BYTES => {
+                            prefix.push_char(k);
+                            if !self.jump_to_prefix(prefix.as_str().unwrap()) {
+                                prefix.push_char('\u{0008}');
+                            }
+                            Some((prefix, self.metrics.frames))
+                        }
+                        _ => Some((prefix, self.metrics.frames)),
+                    };
+                } else if self.choosing_window {
+                    self.choosing_window = false;
+                    match key.to_digit(10).map(|d| d as usize) {
+                        Some(slot) if (1..=N).contains(&slot) => {
+                            self.open_listing_file(FileAction::RunIn(slot - 1))
+                        }
+                        _ => {}
+                    }
+                } else if !self.creating_file {
                     match key {
-                        'e' => {
-                            self.windows[self.focused_editor].clear_window();
-                            self.windows[self.focused_editor].state = WindowState::Editing;
-                            let mut filesystem_operations = || -> Result<(), FileSystemError> {
-                                let (_, files) = self.filesystem.list_directory()?;
-                                let filename =
-                                    files[self.windows[self.focused_editor].focused_file];
-                                let fd = self
-                                    .filesystem
-                                    .open_read(core::str::from_utf8(&filename).unwrap())?;
-                                let mut buffer = [0; MAX_FILE_BYTES];
-                                let num_bytes = self.filesystem.read(fd, &mut buffer)?;
-                                let file = core::str::from_utf8(&buffer[0..num_bytes]).unwrap();
-                                self.windows[self.focused_editor].edit_file(file, filename);
-                                self.filesystem.close(fd).unwrap();
-                                Ok(())
-                            };
-                            if let Err(e) = filesystem_operations() {
-                                let mut err: ArrayString<80> = ArrayString::default();
-                                write!(err, "{}", e).unwrap();
-                                self.windows[self.focused_editor].print(err.as_str().unwrap().as_bytes());
+                        ' ' => {
+                            let num_files = self.focused_num_files();
+                            let window = &mut self.windows[self.focused_editor];
+                            if window.focused_file < num_files {
+                                window.marked[window.focused_file] =
+                                    !window.marked[window.focused_file];
                             }
                         }
-                        'r' => {
-                            self.windows[self.focused_editor].clear_window();
-                            self.windows[self.focused_editor].vruntime = self.min_vruntime().0;
-                            self.windows[self.focused_editor].state = WindowState::Running;
-                            let mut filesystem_operations = || -> Result<(), FileSystemError> {
-                                let (_, files) = self.filesystem.list_directory()?;
-                                let filename =
-                                    files[self.windows[self.focused_editor].focused_file];
-                                let fd = self
-                                    .filesystem
-                                    .open_read(core::str::from_utf8(&filename).unwrap())?;
-                                let mut buffer = [0; MAX_FILE_BYTES];
-                                let num_bytes = self.filesystem.read(fd, &mut buffer)?;
-                                let program = core::str::from_utf8(&buffer[0..num_bytes]).unwrap();
-                                self.windows[self.focused_editor].run_program(program, filename);
-                                self.filesystem.close(fd)?;
-                                Ok(())
-                            };
-                            if let Err(e) = filesystem_operations() {
-                                let mut err: ArrayString<80> = ArrayString::default();
-                                write!(err, "{}", e).unwrap();
-                                self.windows[self.focused_editor].print(err.as_str().unwrap().as_bytes());
+                        'd' => {
+                            if self.focused_num_files() > 0 && !self.refuse_read_only("delete") {
+                                self.confirming_delete = true;
                             }
                         }
+                        '*' if !self.refuse_read_only("star files") => self.toggle_star(),
+                        #[cfg(feature = "shell")]
+                        's' => {
+                            let window = &mut self.windows[self.focused_editor];
+                            window.clear_window();
+                            window.shell = Some(Shell::default());
+                            window.current_file = *b"shell\0\0\0\0\0";
+                            window.state = WindowState::Shell;
+                        }
+                        'v' => self.open_listing_file(FileAction::View),
+                        'f' => self.open_listing_file(FileAction::Play),
+                        'p' if !self.refuse_read_only("protect files") => {
+                            self.open_listing_file(FileAction::Protect)
+                        }
+                        'j' => {
+                            let window = &mut self.windows[self.focused_editor];
+                            window.clear_window();
+                            window.jobs = Some(Jobs::default());
+                            window.current_file = *b"jobs\0\0\0\0\0\0";
+                            window.state = WindowState::Jobs;
+                        }
+                        'm' => {
+                            let window = &mut self.windows[self.focused_editor];
+                            window.clear_window();
+                            window.manager = Some(FileManager::new(window.volume));
+                            window.current_file = *b"files\0\0\0\0\0";
+                            window.state = WindowState::FileManager;
+                        }
+                        'o' => {
+                            let window = &mut self.windows[self.focused_editor];
+                            window.clear_window();
+                            window.options = Some(Options::default());
+                            window.current_file = *b"settings\0\0";
+                            window.state = WindowState::Settings;
+                        }
+                        '?' => {
+                            let window = &mut self.windows[self.focused_editor];
+                            window.clear_window();
+                            window.tour = Some(Tour::default());
+                            window.current_file = *b"tour\0\0\0\0\0\0";
+                            window.state = WindowState::Tour;
+                        }
+                        #[cfg(feature = "apps")]
+                        'c' => {
+                            let target = self.previous_focus;
+                            let window = &mut self.windows[self.focused_editor];
+                            window.clear_window();
+                            window.charmap = Some(CharMap::new(target));
+                            window.current_file = *b"chars\0\0\0\0\0";
+                            window.state = WindowState::CharMap;
+                        }
+                        #[cfg(feature = "apps")]
+                        '=' => {
+                            let window = &mut self.windows[self.focused_editor];
+                            window.clear_window();
+                            window.calculator = Some(Calculator::default());
+                            window.current_file = *b"calc\0\0\0\0\0\0";
+                            window.state = WindowState::Calculator;
+                        }
+                        #[cfg(feature = "apps")]
+                        'l' => {
+                            let window = &mut self.windows[self.focused_editor];
+                            window.clear_window();
+                            window.life = Some(Life::default());
+                            window.current_file = *b"life\0\0\0\0\0\0";
+                            window.state = WindowState::Life;
+                        }
+                        #[cfg(feature = "apps")]
+                        't' => {
+                            let window = &mut self.windows[self.focused_editor];
+                            window.clear_window();
+                            window.typing = Some(TypingTest::default());
+                            window.current_file = *b"typing\0\0\0\0";
+                            window.state = WindowState::Typing;
+                        }
+                        #[cfg(feature = "serial")]
+                        'u' => {
+                            let window = &mut self.windows[self.focused_editor];
+                            window.clear_window();
+                            window.terminal = Some(Terminal::default());
+                            window.current_file = *b"com1\0\0\0\0\0\0";
+                            window.state = WindowState::Terminal;
+                        }
+                        'b' => {
+                            let window = &mut self.windows[self.focused_editor];
+                            window.clear_window();
+                            window.current_file = *b"blocks\0\0\0\0";
+                            window.state = WindowState::BlockMap;
+                        }
+                        '\t' => {
+                            let window = &mut self.windows[self.focused_editor];
+                            window.volume = wrapping_nav::next(window.volume, NUM_VOLUMES);
+                            window.dir = None;
+                            window.select(0);
+                            window.marked = [false; MAX_FILES_STORED];
+                            window.clear_window();
+                        }
+                        '\n' => self.enter_directory(),
+                        'e' => self.open_listing_file(FileAction::Edit),
+                        'r' => self.open_listing_file(FileAction::Run),
+                        'R' if self.focused_num_files() > 0 => self.choosing_window = true,
+                        'C' => self.open_listing_file(FileAction::RunEverywhere),
+                        '/' => self.jump = Some((ArrayString::default(), self.metrics.frames)),
+                        '1'..='9' => self.type_index(0, key),
+                        'a' if !self.refuse_read_only("log output") => {
+                            self.open_listing_file(FileAction::RunLogged)
+                        }
                         _ => (),
                     }
                 } else {
                     match key {
-                        '\n' => {
-                            let mut filesystem_operations = || -> Result<(), FileSystemError> {
-                                let fd = self.filesystem.open_create(self.filename_input.as_str().unwrap())?;
-                                self.filesystem.close(fd)?;
-                                self.num_files = self.filesystem.list_directory()?.0;
-                                Ok(())
-                            };
-                            match filesystem_operations() {
-                                Ok(()) => {
-                                    self.creating_file = false;
-                                    self.filename_input = ArrayString::default();
-                                },
-                                Err(e) => {
-                                    let mut err: ArrayString<80> = ArrayString::default();
-                                    write!(err, "{}", e).unwrap();
-                                    self.windows[self.focused_editor].print(err.as_str().unwrap().as_bytes());
-                                },
-                            }
-
+                        '\n' => self.submit_filename(),
+                        '\u{0008}' => {
+                            self.filename_input.push_char('\u{0008}');
+                            self.set_filename_message("");
                         }
-                        '\u{0008}' => self.filename_input.push_char('\u{0008}'),
                         k => {
-                            if is_drawable(k) {
+                            if !is_drawable(k) {
+                                self.set_filename_message("invalid character");
+                            } else if self.filename_input.len() == MAX_FILENAME_BYTES {
+                                self.set_filename_message("name too long");
+                            } else {
                                 self.filename_input.push_char(k);
+                                self.set_filename_message("");
                             }
                         }
                     }
@@ -810,53 +4368,231 @@ impl SwimInterface {
     }
 }
 
-#[derive(Default, Eq, PartialEq)]
+// What to do with a file from a listing, once any passphrase it needs has
+// been given.
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum FileAction {
+    Edit,
+    Run,
+    RunIn(usize),
+    RunEverywhere,
+    RunLogged,
+    View,
+    Play,
+    Protect,
+    Unprotect,
+}
+
+#[derive(Copy, Clone)]
+struct PassphrasePrompt {
+    action: FileAction,
+    volume: usize,
+    filename: [u8; MAX_FILENAME_BYTES],
+    typed: ArrayString<MAX_PASSPHRASE>,
+}
+
+#[derive(Copy, Clone)]
+struct RenamePrompt {
+    from: ArrayString<LINE_WIDTH>,
+    uses: usize,
+    typed: ArrayString<LINE_WIDTH>,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 enum WindowState {
     Editing,
     Running,
+    Viewing,
+    #[cfg(feature = "shell")]
+    Shell,
+    BlockMap,
+    Jobs,
+    Settings,
+    Tour,
+    FileManager,
+    #[cfg(feature = "apps")]
+    CharMap,
+    #[cfg(feature = "apps")]
+    Calculator,
+    #[cfg(feature = "apps")]
+    Life,
+    #[cfg(feature = "apps")]
+    Typing,
+    #[cfg(feature = "serial")]
+    Terminal,
     #[default]
     Listing,
 }
 
+impl WindowState {
+    // The few keys that matter most in each state, shown in the header.
+    fn key_hints(&self) -> &'static str {
+        match self {
+            WindowState::Listing => "e:edit r:run v:view d:del",
+            WindowState::Editing => "^S:save F6:close",
+            WindowState::Running => "^L:clear F6:kill",
+            WindowState::Viewing => "up/dn F6:close",
+            #[cfg(feature = "shell")]
+            WindowState::Shell => "tab ^C F6:close",
+            WindowState::BlockMap => "<-/-> F6:close",
+            WindowState::Jobs | WindowState::Settings | WindowState::Tour => "F6:close",
+            WindowState::FileManager => "tab c:copy m:move F6:close",
+            #[cfg(feature = "apps")]
+            WindowState::CharMap => "enter:insert F6:close",
+            #[cfg(feature = "apps")]
+            WindowState::Calculator => "enter:eval F6:close",
+            #[cfg(feature = "apps")]
+            WindowState::Life => "spc p:run n:step F6:close",
+            #[cfg(feature = "apps")]
+            WindowState::Typing => "type the line F6:close",
+            #[cfg(feature = "serial")]
+            WindowState::Terminal => "keys go to COM1 F6:close",
+        }
+    }
+}
+
+// What a window's header was last drawn showing.
+#[derive(Copy, Clone, Eq, PartialEq)]
+struct Header {
+    focused: bool,
+    state: WindowState,
+    volume: usize,
+    dir: Option<[u8; MAX_FILENAME_BYTES]>,
+    file: [u8; 10],
+    activity: bool,
+    // A program in the window hasn't finished, so its progress is shown.
+    running: bool,
+    // Its program is waiting for input or crashed, so the title blinks.
+    alert: bool,
+    collapsed: bool,
+    watch: bool,
+}
+
+impl Header {
+    // The window number, its volume or file, and the keys for its state.
+    // May be wider than the header; draw_header clips it.
+    fn title(&self, window: usize) -> ArrayString<64> {
+        let mut title = ArrayString::default();
+        write!(title, "F{} ", window + 1).unwrap();
+        match self.state {
+            WindowState::Listing => write!(
+                title,
+                "{}:{}",
+                volume_letter(self.volume),
+                self.dir.as_ref().and_then(name_str).unwrap_or("")
+            ),
+            _ => write!(title, "{}", name_str(&self.file).unwrap_or("?")),
+        }
+        .unwrap();
+        if self.watch {
+            write!(title, " (watch)").unwrap();
+        }
+        write!(title, " {}", self.state.key_hints()).unwrap();
+        title
+    }
+}
+
 struct Window {
     editor: Option<TextEditor<LINE_WIDTH, DOCUMENT_LENGTH>>,
-    interpreter: Option<
-        Interpreter<
-            MAX_TOKENS,
-            MAX_LITERAL_CHARS,
-            STACK_DEPTH,
-            MAX_LOCAL_VARS,
-            WIN_WIDTH,
-            GenerationalHeap<HEAP_SIZE, MAX_HEAP_BLOCKS, 2>,
-        >,
-    >,
-    interpreter_print_loc: usize,
+    #[cfg(feature = "shell")]
+    shell: Option<Shell>,
+    viewer: Option<Viewer>,
+    jobs: Option<Jobs>,
+    options: Option<Options>,
+    tour: Option<Tour>,
+    manager: Option<FileManager>,
+    #[cfg(feature = "apps")]
+    charmap: Option<CharMap>,
+    #[cfg(feature = "apps")]
+    calculator: Option<Calculator>,
+    #[cfg(feature = "apps")]
+    life: Option<Life>,
+    #[cfg(feature = "apps")]
+    typing: Option<TypingTest>,
+    #[cfg(feature = "serial")]
+    terminal: Option<Terminal>,
     current_file: [u8; 10],
     state: WindowState,
     window_x: usize,
     window_y: usize,
     focused: bool,
+    volume: usize,
     focused_file: usize,
-    vruntime: usize,
-    taking_input: bool,
-    input_buffer: ArrayString<10>,
+    // The name of the focused file when the listing was last drawn; see
+    // follow_focus.
+    focused_name: Option<[u8; MAX_FILENAME_BYTES]>,
+    // The directory the list is in, or None at the top; see dirs.
+    dir: Option<[u8; MAX_FILENAME_BYTES]>,
+    preview: Option<Preview>,
+    // The number being typed to pick a file, while the list shows each
+    // file's number before its name, cut short to make room.
+    typed_index: Option<usize>,
+    marked: [bool; MAX_FILES_STORED],
+    palette: Palette,
+    dimmed: bool,
+    // Drawn at full intensity, toggled with Ctrl+B.
+    bright: bool,
+    // Its editor has a row of column numbers over the text, toggled with
+    // Ctrl+U.
+    ruler: bool,
+    // Shown as its header line alone, toggled with Ctrl+W. Whatever it is
+    // running carries on.
+    collapsed: bool,
+    // Its program printed or finished while the window was unfocused.
+    activity: bool,
+    drawn_header: Option<Header>,
+    // An editor showing the output of its file's last run under it,
+    // toggled with Ctrl+O, and the process F5 started for that run.
+    split: bool,
+    run: Option<usize>,
+    // Runs its file again whenever a save of it finishes, toggled with
+    // Ctrl+E while it is running.
+    watch: bool,
 }
 
 impl Default for Window {
     fn default() -> Self {
         Self {
             editor: None,
-            interpreter: None,
-            interpreter_print_loc: Default::default(),
+            #[cfg(feature = "shell")]
+            shell: None,
+            viewer: None,
+            jobs: None,
+            manager: None,
+            options: None,
+            tour: None,
+            #[cfg(feature = "apps")]
+            charmap: None,
+            #[cfg(feature = "apps")]
+            calculator: None,
+            #[cfg(feature = "apps")]
+            life: None,
+            #[cfg(feature = "apps")]
+            typing: None,
+            #[cfg(feature = "serial")]
+            terminal: None,
             current_file: Default::default(),
             state: Default::default(),
             window_x: Default::default(),
             window_y: Default::default(),
             focused: Default::default(),
+            volume: 0,
             focused_file: Default::default(),
-            vruntime: Default::default(),
-            taking_input: false,
-            input_buffer: Default::default(),
+            focused_name: None,
+            dir: None,
+            preview: None,
+            typed_index: None,
+            marked: [false; MAX_FILES_STORED],
+            palette: Theme::default().palette(),
+            dimmed: false,
+            bright: false,
+            ruler: false,
+            collapsed: false,
+            activity: false,
+            drawn_header: None,
+            split: false,
+            run: None,
+            watch: false,
         }
     }
 }
@@ -865,96 +4601,314 @@ impl Window {
     pub fn make(x: usize, y: usize) -> Self {
         Self {
             editor: None,
-            interpreter: None,
             window_x: x,
             window_y: y,
             ..Default::default()
         }
     }
 
-    pub fn draw_window(
+    fn header(&self, focused: bool) -> Header {
+        Header {
+            focused,
+            state: self.state,
+            volume: self.volume,
+            dir: self.dir,
+            file: self.current_file,
+            activity: self.activity,
+            running: false,
+            alert: false,
+            collapsed: self.collapsed,
+            watch: self.watch,
+        }
+    }
+
+    pub fn draw_window<const N: usize>(
         &mut self,
-        filesystem: &mut FileSystem<
-            MAX_OPEN,
-            BLOCK_SIZE,
-            NUM_BLOCKS,
-            MAX_FILE_BLOCKS,
-            MAX_FILE_BYTES,
-            MAX_FILES_STORED,
-            MAX_FILENAME_BYTES,
-        >,
-    ) {
+        storage: &mut Storage,
+        open_files: &OpenFiles<N>,
+        cursor: bool,
+        guide: Option<usize>,
+    ) -> Result<(), FileSystemError> {
         match self.state {
             WindowState::Editing => {
                 if let Some(mut editor) = self.editor {
-                    editor.draw_window(self.window_x + 1, self.window_y + 1);
+                    let (x, mut y, mut rows) =
+                        (self.window_x + 1, self.window_y + 1, self.content_rows());
+                    if self.ruler {
+                        editor.draw_ruler(x, y, guide, self.palette.border);
+                        (y, rows) = (y + 1, rows - 1);
+                    }
+                    editor.draw_window(
+                        x,
+                        y,
+                        rows,
+                        cursor,
+                        guide,
+                        self.text_color(ColorCode::new(Color::LightCyan, Color::Black)),
+                    );
                     self.editor = Some(editor);
                 }
             },
-            WindowState::Running => {
-                if self.taking_input {
-                    plot_str(
-                        self.input_buffer.as_str().unwrap(),
+            WindowState::Running
+            | WindowState::Jobs
+            | WindowState::Settings
+            | WindowState::Tour
+            | WindowState::FileManager => {}
+            #[cfg(feature = "apps")]
+            WindowState::CharMap | WindowState::Life | WindowState::Typing => {}
+            #[cfg(feature = "serial")]
+            WindowState::Terminal => {
+                if let Some(terminal) = &self.terminal {
+                    terminal.draw(
                         self.window_x + 1,
-                        self.window_y + 1 + self.interpreter_print_loc,
-                        ColorCode::new(Color::LightCyan, Color::Black),
+                        self.window_y + 1,
+                        cursor && self.focused,
+                        self.text_color(ColorCode::new(Color::LightCyan, Color::Black)),
                     );
-                    for i in self.input_buffer.len()..10 {
-                        plot(
-                            ' ',
-                            self.window_x + 1 + i,
-                            self.window_y + 1 + self.interpreter_print_loc,
-                            ColorCode::new(Color::LightCyan, Color::Black),
-                        );
-                    }
                 }
             }
-            WindowState::Listing => match filesystem.list_directory() {
+            #[cfg(feature = "shell")]
+            WindowState::Shell => {
+                if let Some(shell) = &self.shell {
+                    shell.draw(
+                        self.window_x + 1,
+                        self.window_y + 1,
+                        cursor,
+                        self.text_color(ColorCode::new(Color::LightCyan, Color::Black)),
+                    );
+                }
+            }
+            #[cfg(feature = "apps")]
+            WindowState::Calculator => {
+                if let Some(calculator) = &self.calculator {
+                    calculator.draw(
+                        self.window_x + 1,
+                        self.window_y + 1,
+                        cursor,
+                        self.text_color(ColorCode::new(Color::LightCyan, Color::Black)),
+                    );
+                }
+            }
+            WindowState::Viewing => {
+                if let Some(mut viewer) = self.viewer {
+                    let result = viewer.draw(
+                        storage,
+                        self.window_x + 1,
+                        self.window_y + 1,
+                        self.text_color(ColorCode::new(Color::White, Color::Black)),
+                    );
+                    self.viewer = Some(viewer);
+                    result?;
+                }
+            }
+            WindowState::BlockMap => {
+                let highlight = match self.listing(storage) {
+                    Ok((num_files, files)) => {
+                        self.follow_focus(&files[..num_files]);
+                        files[..num_files].get(self.focused_file).copied()
+                    }
+                    _ => None,
+                };
+                storage.block_map().draw(
+                    self.window_x + 1,
+                    self.window_y + 1,
+                    highlight.as_ref(),
+                    self.text_color(ColorCode::new(Color::LightCyan, Color::Black)),
+                );
+            }
+            WindowState::Listing => match self.listing(storage) {
                 Ok((num_files, files)) => {
-                    for i in 0..num_files {
+                    self.follow_focus(&files[..num_files]);
+                    // A list taller than a split window's top pane scrolls to
+                    // keep the focused file in sight.
+                    let rows = self.content_rows();
+                    let first = (self.focused_file / 3 + 1).saturating_sub(rows) * 3;
+                    for i in first..first + rows * 3 {
+                        let x = self.window_x + 1 + (i % 3 * LISTING_CELL_WIDTH);
+                        let y = self.window_y + 1 + (i - first) / 3;
+                        if i >= num_files {
+                            for col in 0..LISTING_CELL_WIDTH {
+                                plot(' ', x + col, y, self.palette.text);
+                            }
+                            continue;
+                        }
+                        let color = if i == self.focused_file && self.dimmed {
+                            ColorCode::new(Color::Black, Color::DarkGray)
+                        } else if i == self.focused_file && self.marked[i] {
+                            ColorCode::new(Color::Black, Color::Yellow)
+                        } else if i == self.focused_file {
+                            ColorCode::new(Color::Black, Color::LightCyan)
+                        } else if self.marked[i] {
+                            self.text_color(ColorCode::new(Color::Yellow, Color::Black))
+                        } else {
+                            self.palette.text
+                        };
+                        if self.typed_index.is_some() {
+                            let mut number: ArrayString<3> = ArrayString::default();
+                            write!(number, "{:>2}", i + 1).unwrap();
+                            let number_color = ColorCode::new(Color::Yellow, Color::Black);
+                            plot_str(
+                                number.as_str().unwrap(),
+                                x,
+                                y,
+                                self.text_color(number_color),
+                            );
+                            plot(' ', x + 2, y, self.palette.text);
+                            let mut name = dirs::display_name(&files[i], self.dir).chars();
+                            for c in 2..MAX_FILENAME_BYTES {
+                                plot(name.next().unwrap_or(' '), x + 1 + c, y, color);
+                            }
+                            continue;
+                        }
+                        if let Some((window, mode)) = open_files.window_for(self.volume, &files[i])
+                        {
+                            plot(
+                                (window as u8 + b'1') as char,
+                                x,
+                                y,
+                                self.text_color(match mode {
+                                    OpenMode::Editing => {
+                                        ColorCode::new(Color::LightGreen, Color::Black)
+                                    }
+                                    OpenMode::Running => {
+                                        ColorCode::new(Color::LightRed, Color::Black)
+                                    }
+                                }),
+                            );
+                        } else if storage.file_flags(&files[i]) & PROTECTED != 0 {
+                            plot(
+                                '#',
+                                x,
+                                y,
+                                self.text_color(ColorCode::new(Color::Pink, Color::Black)),
+                            );
+                        } else if storage.file_flags(&files[i]) & STARRED != 0 {
+                            plot(
+                                '*',
+                                x,
+                                y,
+                                self.text_color(ColorCode::new(Color::Yellow, Color::Black)),
+                            );
+                        } else {
+                            plot(' ', x, y, ColorCode::new(Color::Yellow, Color::Black));
+                        }
+                        let mut name = dirs::display_name(&files[i], self.dir).chars();
                         for c in 0..MAX_FILENAME_BYTES {
-                            if i == self.focused_file {
-                                plot(
-                                    files[i][c] as char,
-                                    self.window_x + 1 + c + (i % 3 * MAX_FILENAME_BYTES),
-                                    self.window_y + 1 + i / 3,
-                                    ColorCode::new(Color::Black, Color::LightCyan),
-                                );
-                            } else {
-                                plot(
-                                    files[i][c] as char,
-                                    self.window_x + 1 + c + (i % 3 * MAX_FILENAME_BYTES),
-                                    self.window_y + 1 + i / 3,
-                                    ColorCode::new(Color::LightCyan, Color::Black),
-                                );
+                            plot(name.next().unwrap_or(' '), x + 1 + c, y, color);
+                        }
+                    }
+                    // The focused file's first line goes on the bottom row
+                    // when the grid leaves it free.
+                    if !self.split && num_files <= 3 * (WINDOW_ROWS - 1) {
+                        let file = files[..num_files].get(self.focused_file).filter(|file| {
+                            !dirs::is_dir(file) && storage.file_flags(file) & PROTECTED == 0
+                        });
+                        if let Some(file) = file {
+                            if !self.preview.is_some_and(|preview| preview.is_of(file)) {
+                                self.preview = Some(Preview::read(storage, file));
                             }
                         }
+                        let line = file.and(self.preview.as_ref()).map_or("", Preview::line);
+                        let color = self.text_color(ColorCode::new(Color::DarkGray, Color::Black));
+                        let mut chars = line.chars();
+                        for col in 0..LINE_WIDTH {
+                            let c = chars.next().unwrap_or(' ');
+                            plot(
+                                c,
+                                self.window_x + 1 + col,
+                                self.window_y + WINDOW_ROWS,
+                                color,
+                            );
+                        }
                     }
                 }
-                Err(e) => {
-                    let mut err: ArrayString<80> = ArrayString::default();
-                    write!(err, "{}", e).unwrap();
-                    self.print(err.as_str().unwrap().as_bytes());
-                }
+                Err(e) => return Err(e),
             },
         }
+        Ok(())
+    }
+
+    // The part inside the border.
+    fn interior(&self) -> Rect {
+        Rect {
+            x: self.window_x + 1,
+            y: self.window_y + 1,
+            width: LINE_WIDTH,
+            height: WINDOW_ROWS,
+        }
+    }
+
+    // Whether the window is cut in two with a run's output under it.
+    fn shows_split(&self) -> bool {
+        self.split && matches!(self.state, WindowState::Editing | WindowState::Listing)
+    }
+
+    // The rows an editor or listing has, fewer when split.
+    fn content_rows(&self) -> usize {
+        match self.shows_split() {
+            true => layout::split_panes(self.interior()).0.height,
+            false => WINDOW_ROWS,
+        }
+    }
+
+    // The files and directories the list shows.
+    fn listing(&self, storage: &mut Storage) -> Result<dirs::Entries, FileSystemError> {
+        let (num_files, files) = storage.list_directory()?;
+        Ok(dirs::entries(&files[..num_files], self.dir))
+    }
+
+    // Text the window draws in its own colors is greyed out along with the
+    // theme while the window is dimmed.
+    fn text_color(&self, color: ColorCode) -> ColorCode {
+        if self.dimmed || self.bright {
+            self.palette.text
+        } else {
+            color
+        }
+    }
+
+    // Moves the listing's focus by hand; follow_focus picks up the name it
+    // lands on.
+    fn select(&mut self, file: usize) {
+        self.focused_file = file;
+        self.focused_name = None;
+    }
+
+    // Keeps the listing on the file it last had focused, wherever another
+    // window adding, deleting or starring files has moved it since, or
+    // while this window was editing or running something. Marks go by
+    // position, so they are dropped when the files move under them.
+    fn follow_focus(&mut self, files: &[[u8; MAX_FILENAME_BYTES]]) {
+        if let Some(name) = self.focused_name {
+            if files.get(self.focused_file) != Some(&name) {
+                self.focused_file = files
+                    .iter()
+                    .position(|file| *file == name)
+                    .unwrap_or(wrapping_nav::clamp(self.focused_file, files.len()));
+                self.marked = [false; MAX_FILES_STORED];
+            }
+        }
+        self.focused_name = files.get(self.focused_file).copied();
+    }
+
+    pub fn delete_targets(&self) -> [bool; MAX_FILES_STORED] {
+        let mut targets = self.marked;
+        if !targets.contains(&true) {
+            targets[self.focused_file] = true;
+        }
+        targets
     }
 
     pub fn set_focus(&mut self, focused: bool) {
         self.focused = focused;
+        self.typed_index = None;
         if let Some(mut editor) = self.editor {
             editor.focused = focused;
             self.editor = Some(editor);
         }
     }
 
-    pub fn run_program(&mut self, program: &str, filename: [u8; 10]) {
-        let interpreter = Interpreter::new(program);
-        self.interpreter = Some(interpreter);
-        self.current_file = filename;
-    }
-
-    pub fn edit_file(&mut self, file: &str, filename: [u8; 10]) {
+    pub fn edit_file(&mut self, file: &[u8], filename: [u8; 10]) {
         let editor = TextEditor::new(file, true);
         self.editor = Some(editor);
         self.current_file = filename;
@@ -962,52 +4916,9 @@ impl Window {
 
     pub fn clear_window(&mut self) {
         for col in self.window_x + 1..self.window_x + WIN_WIDTH {
-            for row in self.window_y + 1..self.window_y + 11 {
-                plot(' ', col, row, ColorCode::new(Color::Black, Color::Black));
-            }
-        }
-    }
-}
-
-impl InterpreterOutput for Window {
-    fn print(&mut self, chars: &[u8]) {
-        if self.interpreter_print_loc == 10 {
-            for row in self.window_y + 1..self.interpreter_print_loc + self.window_y {
-                for col in self.window_x + 1..WIN_WIDTH + self.window_x {
-                    let (c, color) = peek(col, row + 1);
-                    plot(c, col, row, color);
-                }
-            }
-            for col in self.window_x + 1..WIN_WIDTH + self.window_x - 1 {
-                plot(
-                    ' ',
-                    col,
-                    self.interpreter_print_loc + self.window_y,
-                    ColorCode::new(Color::Black, Color::Black),
-                );
-            }
-            self.interpreter_print_loc -= 1;
-        }
-        if chars.len() > WIN_WIDTH - 2 {
-            for i in 0..WIN_WIDTH - 2 {
-                plot(
-                    chars[i] as char,
-                    i + self.window_x + 1,
-                    self.interpreter_print_loc + self.window_y + 1,
-                    ColorCode::new(Color::LightCyan, Color::Black),
-                );
-            }
-            self.print(&chars[WIN_WIDTH - 2..]);
-        } else if chars.len() != 0 {
-            for i in 0..chars.len() - 1 {
-                plot(
-                    chars[i] as char,
-                    i + self.window_x + 1,
-                    self.interpreter_print_loc + self.window_y + 1,
-                    ColorCode::new(Color::LightCyan, Color::Black),
-                );
+            for row in self.window_y + 1..self.window_y + 1 + WINDOW_ROWS {
+                plot(' ', col, row, self.palette.blank);
             }
         }
-        self.interpreter_print_loc += 1;
     }
 }
@@ -0,0 +1,120 @@
+use core::fmt::Write;
+
+use simple_interp::ArrayString;
+
+use crate::screen::plot;
+use crate::theme::Palette;
+use crate::wrapping_nav;
+use crate::LINE_WIDTH;
+
+const LIFE_ROWS: usize = 9;
+// Frames between generations while the simulation runs.
+pub const LIFE_PERIOD: u64 = 4;
+const ALIVE: char = 219u8 as char;
+const DEAD: char = 250u8 as char;
+const GLIDER: [(usize, usize); 5] = [(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)];
+
+// Conway's Game of Life on a board the size of a window, wrapping at the
+// edges. The arrows move a cursor whose cell space toggles; it runs as a
+// system task alongside the programs, see SwimInterface::advance_life.
+#[derive(Copy, Clone)]
+pub struct Life {
+    cells: [[bool; LINE_WIDTH]; LIFE_ROWS],
+    row: usize,
+    col: usize,
+    pub running: bool,
+    generation: usize,
+}
+
+impl Default for Life {
+    fn default() -> Self {
+        let mut life = Self {
+            cells: [[false; LINE_WIDTH]; LIFE_ROWS],
+            row: 0,
+            col: 0,
+            running: false,
+            generation: 0,
+        };
+        for (row, col) in GLIDER {
+            life.cells[row][col] = true;
+        }
+        life
+    }
+}
+
+impl Life {
+    pub fn up(&mut self) {
+        self.row = wrapping_nav::prev(self.row, LIFE_ROWS);
+    }
+
+    pub fn down(&mut self) {
+        self.row = wrapping_nav::next(self.row, LIFE_ROWS);
+    }
+
+    pub fn left(&mut self) {
+        self.col = wrapping_nav::prev(self.col, LINE_WIDTH);
+    }
+
+    pub fn right(&mut self) {
+        self.col = wrapping_nav::next(self.col, LINE_WIDTH);
+    }
+
+    pub fn toggle(&mut self) {
+        self.cells[self.row][self.col] = !self.cells[self.row][self.col];
+    }
+
+    pub fn clear(&mut self) {
+        self.cells = [[false; LINE_WIDTH]; LIFE_ROWS];
+        self.generation = 0;
+    }
+
+    pub fn step(&mut self) {
+        let mut next = [[false; LINE_WIDTH]; LIFE_ROWS];
+        for (row, cells) in next.iter_mut().enumerate() {
+            for (col, cell) in cells.iter_mut().enumerate() {
+                let neighbors = self.neighbors(row, col);
+                *cell = neighbors == 3 || (neighbors == 2 && self.cells[row][col]);
+            }
+        }
+        self.cells = next;
+        self.generation += 1;
+    }
+
+    fn neighbors(&self, row: usize, col: usize) -> usize {
+        let mut count = 0;
+        for dr in [LIFE_ROWS - 1, 0, 1] {
+            for dc in [LINE_WIDTH - 1, 0, 1] {
+                if (dr, dc) != (0, 0) && self.cells[(row + dr) % LIFE_ROWS][(col + dc) % LINE_WIDTH]
+                {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    pub fn draw(&self, x: usize, y: usize, cursor: bool, palette: &Palette) {
+        for (row, cells) in self.cells.iter().enumerate() {
+            for (col, alive) in cells.iter().enumerate() {
+                let color = if cursor && (row, col) == (self.row, self.col) {
+                    palette.highlight
+                } else {
+                    palette.text
+                };
+                plot(if *alive { ALIVE } else { DEAD }, x + col, y + row, color);
+            }
+        }
+        let mut status: ArrayString<LINE_WIDTH> = ArrayString::default();
+        let state = if self.running { "run " } else { "stop" };
+        write!(status, "gen {:<5} {} p n c spc", self.generation, state).unwrap();
+        let mut chars = status.as_str().unwrap().chars();
+        for col in 0..LINE_WIDTH {
+            plot(
+                chars.next().unwrap_or(' '),
+                x + col,
+                y + LIFE_ROWS,
+                palette.border,
+            );
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use simple_interp::ArrayString;
+
+use crate::overlay::{self, Overlay};
+use crate::screen::{draw_box, plot, plot_str, BUFFER_HEIGHT, BUFFER_WIDTH};
+use crate::settings::MAX_PASSCODE;
+use crate::theme::Palette;
+
+const WIDTH: usize = 32;
+const HEIGHT: usize = 5;
+
+// The screen lock: what has been typed of the passcode so far, shown only
+// as stars. It is an overlay over the whole screen, so programs keep
+// drawing underneath and what they drew shows once it is unlocked.
+#[derive(Copy, Clone)]
+pub struct Lock {
+    typed: ArrayString<MAX_PASSCODE>,
+    wrong: bool,
+    overlay: Overlay,
+}
+
+impl Lock {
+    // Blanks the screen, or returns None if no overlay is free.
+    pub fn new(palette: &Palette) -> Option<Self> {
+        let overlay = overlay::open(0, 0, BUFFER_WIDTH, BUFFER_HEIGHT)?;
+        overlay::draw_on(overlay, || {
+            for row in 0..BUFFER_HEIGHT {
+                for col in 0..BUFFER_WIDTH {
+                    plot(' ', col, row, palette.blank);
+                }
+            }
+        });
+        Some(Self {
+            typed: ArrayString::default(),
+            wrong: false,
+            overlay,
+        })
+    }
+
+    pub fn unlock(self) {
+        overlay::close(self.overlay);
+    }
+
+    // Takes a key, returning true once Enter is pressed on the passcode.
+    pub fn key(&mut self, c: char, passcode: &str) -> bool {
+        match c {
+            '\n' => {
+                let unlocked = self.typed.as_str().unwrap() == passcode;
+                self.wrong = !unlocked;
+                self.typed = ArrayString::default();
+                unlocked
+            }
+            '\u{0008}' => {
+                self.typed.push_char(c);
+                false
+            }
+            c if c.is_ascii_graphic() => {
+                self.typed.push_char(c);
+                self.wrong = false;
+                false
+            }
+            _ => false,
+        }
+    }
+
+    pub fn draw(&self, palette: &Palette) {
+        overlay::draw_on(self.overlay, || self.draw_box(palette));
+    }
+
+    fn draw_box(&self, palette: &Palette) {
+        let x = (BUFFER_WIDTH - WIDTH) / 2;
+        let y = (BUFFER_HEIGHT - HEIGHT) / 2;
+        draw_box(x, y, WIDTH, HEIGHT, palette.border);
+        plot_str("Locked", x + 2, y + 1, palette.text);
+        plot_str("passcode:", x + 2, y + 2, palette.text);
+        for i in 0..MAX_PASSCODE {
+            let c = if i < self.typed.len() { '*' } else { ' ' };
+            plot(c, x + 12 + i, y + 2, palette.text);
+        }
+        let message = if self.wrong { "wrong passcode" } else { "" };
+        for (i, c) in message.chars().chain([' '; 14]).take(14).enumerate() {
+            plot(c, x + 2 + i, y + 3, palette.error);
+        }
+    }
+}
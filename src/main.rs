@@ -1,10 +1,11 @@
 #![no_std]
 #![no_main]
 
-use crossbeam::atomic::AtomicCell;
 use pc_keyboard::DecodedKey;
 use pluggable_interrupt_os::{vga_buffer::clear_screen, HandlerTable};
-use simple_swim_template::SwimInterface;
+#[cfg(feature = "serial")]
+use simple_swim_template::{init_serial, send_memory_report};
+use simple_swim_template::{paint_stack, Events, SwimInterface, DEFAULT_FILES, SAMPLE_FILES};
 
 #[no_mangle]
 pub extern "C" fn _start() -> ! {
@@ -16,32 +17,57 @@ pub extern "C" fn _start() -> ! {
         .start()
 }
 
-static LAST_KEY: AtomicCell<Option<DecodedKey>> = AtomicCell::new(None);
-static TICKED: AtomicCell<bool> = AtomicCell::new(false);
+static EVENTS: Events = Events::new();
+// How many windows the interface has, on pages of four.
+const WINDOWS: usize = 4;
 
 fn cpu_loop() -> ! {
-    let mut kernel = SwimInterface::default();
+    let builder = SwimInterface::builder()
+        .files(DEFAULT_FILES)
+        .samples(SAMPLE_FILES)
+        .splash()
+        .windows::<WINDOWS>();
+    let builder = if cfg!(feature = "self_test") {
+        builder.self_test()
+    } else {
+        builder
+    };
+    let mut kernel = builder.build();
     loop {
-        if let Ok(_) = TICKED.compare_exchange(true, false) {
-            kernel.tick();
+        if kernel.drain(&EVENTS) || !kernel.is_idle() {
+            continue;
         }
-
-        if let Ok(k) = LAST_KEY.fetch_update(|k| if k.is_some() { Some(None) } else { None }) {
-            if let Some(k) = k {
-                kernel.key(k);
-            }
+        // An interrupt landing between the check above and `hlt` would sleep
+        // until the next one. With interrupts off, the rings are checked
+        // again; `sti` only takes effect after the instruction that follows
+        // it, so one that is pending then wakes the `hlt` at once.
+        unsafe { core::arch::asm!("cli") };
+        if EVENTS.is_empty() {
+            unsafe { core::arch::asm!("sti; hlt") };
+        } else {
+            unsafe { core::arch::asm!("sti") };
         }
     }
 }
 
 fn key(key: DecodedKey) {
-    LAST_KEY.store(Some(key));
+    EVENTS.push_key(key);
 }
 
 fn tick() {
-    TICKED.store(true);
+    EVENTS.tick();
+    #[cfg(feature = "serial")]
+    EVENTS.poll_serial();
 }
 
 fn startup() {
+    if cfg!(feature = "stack_audit") {
+        paint_stack();
+    }
     clear_screen();
+    #[cfg(feature = "serial")]
+    {
+        init_serial();
+        send_memory_report::<WINDOWS>();
+    }
 }
@@ -0,0 +1,196 @@
+use core::fmt::Write;
+
+use file_system_solution::FileSystemError;
+use simple_interp::ArrayString;
+
+use crate::config::{MAX_FILES_STORED, NUM_BLOCKS};
+use crate::format::{Compact, Percent};
+use crate::mount::{volume_letter, MountTable, NUM_VOLUMES};
+use crate::screen::{plot, ColorCode};
+use crate::storage::{name_str, Storage, PROTECTED, STARRED};
+use crate::theme::Palette;
+use crate::wrapping_nav;
+use crate::{LINE_WIDTH, MAX_FILENAME_BYTES};
+
+const PANE_WIDTH: usize = (LINE_WIDTH - 1) / 2;
+const FILE_ROWS: usize = 7;
+const HELP: &str = "tab c:copy m:move <-/->:vol";
+
+type Files = (usize, [[u8; MAX_FILENAME_BYTES]; MAX_FILES_STORED]);
+
+// One side of the file manager: a volume and the file picked on it.
+#[derive(Copy, Clone)]
+struct Pane {
+    volume: usize,
+    selected: usize,
+}
+
+// Two file lists side by side, each on its own volume, with Tab moving
+// between them. Files are copied or moved from the active pane's volume to
+// the other's; see SwimInterface::transfer_file.
+#[derive(Copy, Clone)]
+pub struct FileManager {
+    panes: [Pane; 2],
+    active: usize,
+}
+
+impl FileManager {
+    // Starts on `volume` with the next volume opposite.
+    pub fn new(volume: usize) -> Self {
+        Self {
+            panes: [
+                Pane {
+                    volume,
+                    selected: 0,
+                },
+                Pane {
+                    volume: wrapping_nav::next(volume, NUM_VOLUMES),
+                    selected: 0,
+                },
+            ],
+            active: 0,
+        }
+    }
+
+    pub fn switch_pane(&mut self) {
+        self.active = 1 - self.active;
+    }
+
+    pub fn up(&mut self) {
+        let pane = &mut self.panes[self.active];
+        pane.selected = pane.selected.saturating_sub(1);
+    }
+
+    // draw keeps the selection on a file once it knows how many there are.
+    pub fn down(&mut self) {
+        self.panes[self.active].selected += 1;
+    }
+
+    pub fn step_volume(&mut self, forward: bool) {
+        let pane = &mut self.panes[self.active];
+        pane.volume = wrapping_nav::step(pane.volume, NUM_VOLUMES, forward);
+        pane.selected = 0;
+    }
+
+    // The volumes files go from and to.
+    pub fn volumes(&self) -> (usize, usize) {
+        (
+            self.panes[self.active].volume,
+            self.panes[1 - self.active].volume,
+        )
+    }
+
+    pub fn selected_file(
+        &self,
+        storage: &mut Storage,
+    ) -> Result<[u8; MAX_FILENAME_BYTES], FileSystemError> {
+        let (num_files, files) = storage.list_directory()?;
+        files[..num_files]
+            .get(self.panes[self.active].selected)
+            .copied()
+            .ok_or(FileSystemError::FileNotFound)
+    }
+
+    pub fn draw(
+        &mut self,
+        mounts: &mut MountTable,
+        x: usize,
+        y: usize,
+        palette: &Palette,
+    ) -> Result<(), FileSystemError> {
+        for side in 0..2 {
+            let storage = mounts.volume(self.panes[side].volume);
+            let files = storage.list_directory()?;
+            let pane = &mut self.panes[side];
+            pane.selected = wrapping_nav::clamp(pane.selected, files.0);
+            self.draw_pane(
+                side,
+                storage,
+                files,
+                x + side * (PANE_WIDTH + 1),
+                y,
+                palette,
+            )?;
+        }
+        for row in 0..FILE_ROWS + 2 {
+            plot(179u8 as char, x + PANE_WIDTH, y + row, palette.border);
+        }
+        draw_text(HELP, x, y + FILE_ROWS + 2, LINE_WIDTH, palette.border);
+        Ok(())
+    }
+
+    fn draw_pane(
+        &self,
+        side: usize,
+        storage: &mut Storage,
+        (num_files, files): Files,
+        x: usize,
+        y: usize,
+        palette: &Palette,
+    ) -> Result<(), FileSystemError> {
+        let pane = self.panes[side];
+        let active = side == self.active;
+        let mut line: ArrayString<LINE_WIDTH> = ArrayString::default();
+        write!(line, "{}:", volume_letter(pane.volume)).unwrap();
+        let color = if active {
+            palette.highlight
+        } else {
+            palette.border
+        };
+        draw_text(line.as_str().unwrap(), x, y, PANE_WIDTH, color);
+        let first = pane.selected - pane.selected % FILE_ROWS;
+        for row in 0..FILE_ROWS {
+            let i = first + row;
+            let mut line: ArrayString<LINE_WIDTH> = ArrayString::default();
+            let mut color = palette.text;
+            if let Some(file) = files[..num_files].get(i) {
+                let flags = storage.file_flags(file);
+                let mark = if flags & PROTECTED != 0 {
+                    '#'
+                } else if flags & STARRED != 0 {
+                    '*'
+                } else {
+                    ' '
+                };
+                write!(line, "{}{}", mark, name_str(file).unwrap_or("?")).unwrap();
+                if i == pane.selected {
+                    color = if active {
+                        palette.highlight
+                    } else {
+                        palette.border
+                    };
+                }
+            }
+            draw_text(line.as_str().unwrap(), x, y + 1 + row, PANE_WIDTH, color);
+        }
+        // The selected file's size and how full the volume is.
+        let size = match files[..num_files].get(pane.selected) {
+            Some(file) => storage.file_size(name_str(file).unwrap_or(""))?,
+            None => 0,
+        };
+        let used = (NUM_BLOCKS - storage.block_map().num_free()) as u64;
+        let mut line: ArrayString<LINE_WIDTH> = ArrayString::default();
+        write!(
+            line,
+            "{:>4}B {:>4} used",
+            Compact(size as u64),
+            Percent(used, NUM_BLOCKS as u64)
+        )
+        .unwrap();
+        draw_text(
+            line.as_str().unwrap(),
+            x,
+            y + 1 + FILE_ROWS,
+            PANE_WIDTH,
+            palette.text,
+        );
+        Ok(())
+    }
+}
+
+fn draw_text(text: &str, x: usize, y: usize, width: usize, color: ColorCode) {
+    let mut chars = text.chars();
+    for col in 0..width {
+        plot(chars.next().unwrap_or(' '), x + col, y, color);
+    }
+}
@@ -0,0 +1,171 @@
+use core::fmt::Write;
+
+use crate::format::{Bar, Compact, Percent};
+
+#[derive(Copy, Clone, Default)]
+pub struct Frame {
+    pub cells_plotted: usize,
+    pub cells_written: usize,
+    pub draw_cycles: u64,
+    pub interp_cycles: u64,
+}
+
+// What each tick cost: how many cells it plotted, how many of those changed
+// what was on screen, and how many cycles it
+// spent drawing versus running the interpreter. Keeps the last frame and a
+// running total for averages.
+#[derive(Copy, Clone, Default)]
+pub struct FrameMetrics {
+    pub last: Frame,
+    total: Frame,
+    pub frames: u64,
+    pub keys: KeyLatency,
+}
+
+impl FrameMetrics {
+    pub fn record(&mut self, frame: Frame) {
+        self.last = frame;
+        self.total.cells_plotted += frame.cells_plotted;
+        self.total.cells_written += frame.cells_written;
+        self.total.draw_cycles += frame.draw_cycles;
+        self.total.interp_cycles += frame.interp_cycles;
+        self.frames += 1;
+    }
+
+    pub fn average(&self) -> Frame {
+        let frames = self.frames.max(1);
+        Frame {
+            cells_plotted: self.total.cells_plotted / frames as usize,
+            cells_written: self.total.cells_written / frames as usize,
+            draw_cycles: self.total.draw_cycles / frames,
+            interp_cycles: self.total.interp_cycles / frames,
+        }
+    }
+
+    pub fn write_report<W: Write>(&self, out: &mut W) -> core::fmt::Result {
+        let last = self.last;
+        let average = self.average();
+        writeln!(out, "frames {}", Compact(self.frames))?;
+        writeln!(out, "         last / avg")?;
+        writeln!(
+            out,
+            "cells   {:>5} / {}",
+            Compact(last.cells_plotted as u64),
+            Compact(average.cells_plotted as u64)
+        )?;
+        writeln!(
+            out,
+            "written {:>5} / {}",
+            Compact(last.cells_written as u64),
+            Compact(average.cells_written as u64)
+        )?;
+        writeln!(
+            out,
+            "draw    {:>5} / {} cyc",
+            Compact(last.draw_cycles),
+            Compact(average.draw_cycles)
+        )?;
+        writeln!(
+            out,
+            "interp  {:>5} / {} cyc",
+            Compact(last.interp_cycles),
+            Compact(average.interp_cycles)
+        )?;
+        if let (Some(p50), Some(p95)) = (self.keys.percentile(50), self.keys.percentile(95)) {
+            writeln!(
+                out,
+                "key p50 {:>5} / p95 {} cyc",
+                Compact(p50),
+                Compact(p95)
+            )?;
+        }
+        let busy = average.draw_cycles + average.interp_cycles;
+        let interp = Bar {
+            part: average.interp_cycles,
+            whole: busy,
+            width: 16,
+        };
+        writeln!(
+            out,
+            "interp  {} {}",
+            interp,
+            Percent(average.interp_cycles, busy)
+        )
+    }
+}
+
+// Ticks run a second, worked out each time a second's worth of timer
+// interrupts has come in. It falls below the timer's rate when frames take
+// so long that missed ticks are dropped rather than made up.
+#[derive(Copy, Clone, Default)]
+pub struct TickRate {
+    arrived: u32,
+    ran: u32,
+    pub per_second: Option<u32>,
+}
+
+impl TickRate {
+    pub fn count(&mut self, arrived: usize, ran: usize, hz: u32) {
+        self.arrived += arrived as u32;
+        self.ran += ran as u32;
+        if self.arrived >= hz {
+            self.per_second = Some(self.ran * hz / self.arrived);
+            self.arrived = 0;
+            self.ran = 0;
+        }
+    }
+}
+
+const PENDING_KEYS: usize = 16;
+const LATENCY_SAMPLES: usize = 64;
+
+// How long the last LATENCY_SAMPLES keys took from being queued to being
+// on screen, in cycles. A key is held as pending while its tick handles it,
+// and measured once that tick has finished drawing.
+#[derive(Copy, Clone)]
+pub struct KeyLatency {
+    pending: [u64; PENDING_KEYS],
+    num_pending: usize,
+    samples: [u64; LATENCY_SAMPLES],
+    num_samples: usize,
+    next: usize,
+}
+
+impl Default for KeyLatency {
+    fn default() -> Self {
+        Self {
+            pending: [0; PENDING_KEYS],
+            num_pending: 0,
+            samples: [0; LATENCY_SAMPLES],
+            num_samples: 0,
+            next: 0,
+        }
+    }
+}
+
+impl KeyLatency {
+    pub fn handled(&mut self, arrived: u64) {
+        if self.num_pending < PENDING_KEYS {
+            self.pending[self.num_pending] = arrived;
+            self.num_pending += 1;
+        }
+    }
+
+    pub fn drawn(&mut self, now: u64) {
+        for arrived in self.pending[..self.num_pending].iter() {
+            self.samples[self.next] = now.saturating_sub(*arrived);
+            self.next = (self.next + 1) % LATENCY_SAMPLES;
+            self.num_samples = (self.num_samples + 1).min(LATENCY_SAMPLES);
+        }
+        self.num_pending = 0;
+    }
+
+    // The latency `percent` of the sampled keys came in at or under.
+    pub fn percentile(&self, percent: usize) -> Option<u64> {
+        let mut sorted = self.samples;
+        let sorted = &mut sorted[..self.num_samples];
+        sorted.sort_unstable();
+        let last = sorted.len().checked_sub(1)?;
+        Some(sorted[last * percent / 100])
+    }
+}
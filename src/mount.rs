@@ -0,0 +1,51 @@
+use crate::storage::Storage;
+
+pub const NUM_VOLUMES: usize = 2;
+
+pub struct MountTable {
+    volumes: [Storage; NUM_VOLUMES],
+}
+
+impl Default for MountTable {
+    fn default() -> Self {
+        Self {
+            volumes: [Storage::default(), Storage::default()],
+        }
+    }
+}
+
+impl MountTable {
+    pub fn volume(&mut self, volume: usize) -> &mut Storage {
+        &mut self.volumes[volume]
+    }
+
+    pub fn set_read_only(&mut self) {
+        self.volumes.iter_mut().for_each(Storage::set_read_only);
+    }
+
+    pub fn set_opener(&mut self, window: Option<usize>) {
+        for volume in self.volumes.iter_mut() {
+            volume.set_opener(window);
+        }
+    }
+}
+
+pub fn volume_letter(volume: usize) -> char {
+    (b'a' + volume as u8) as char
+}
+
+// Splits an optional `x:` volume prefix off a path, falling back to
+// `default_volume` when there is none.
+pub fn parse_path(path: &str, default_volume: usize) -> Option<(usize, &str)> {
+    let bytes = path.as_bytes();
+    if bytes.len() >= 2 && bytes[1] == b':' {
+        let volume = bytes[0].wrapping_sub(b'a') as usize;
+        if volume < NUM_VOLUMES {
+            Some((volume, &path[2..]))
+        } else {
+            None
+        }
+    } else {
+        Some((default_volume, path))
+    }
+}
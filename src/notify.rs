@@ -0,0 +1,95 @@
+use simple_interp::ArrayString;
+
+use crate::overlay::{self, Overlay};
+use crate::screen::{plot, Color, ColorCode};
+#[cfg(feature = "sound")]
+use crate::speaker;
+use crate::WIN_REGION_WIDTH;
+
+// About three seconds at the timer's 18.2 Hz.
+const TOAST_TICKS: usize = 54;
+#[cfg(feature = "sound")]
+const BEEP_TICKS: usize = 3;
+#[cfg(feature = "sound")]
+const BEEP_HZ: u32 = 880;
+const QUEUE: usize = 4;
+
+// Short messages shown one at a time over the status bar, each for
+// TOAST_TICKS, optionally with a beep. Toasts arriving while the queue is
+// full are dropped.
+#[derive(Default)]
+pub struct Notifier {
+    queue: [ArrayString<WIN_REGION_WIDTH>; QUEUE],
+    len: usize,
+    shown: usize,
+    // Covers the status bar while there are toasts to show.
+    overlay: Option<Overlay>,
+    #[cfg(feature = "sound")]
+    beep: usize,
+}
+
+impl Notifier {
+    // Without the `sound` feature there is nothing to beep with.
+    #[cfg_attr(not(feature = "sound"), allow(unused_variables))]
+    pub fn notify(&mut self, text: &str, beep: bool) {
+        if self.len < QUEUE {
+            let mut toast = ArrayString::default();
+            for c in text.chars().take(WIN_REGION_WIDTH) {
+                toast.push_char(c);
+            }
+            self.queue[self.len] = toast;
+            self.len += 1;
+        }
+        #[cfg(feature = "sound")]
+        if beep {
+            speaker::play(BEEP_HZ);
+            self.beep = BEEP_TICKS;
+        }
+    }
+
+    // Ages the shown toast and any beep.
+    pub fn tick(&mut self) {
+        #[cfg(feature = "sound")]
+        if self.beep > 0 {
+            self.beep -= 1;
+            if self.beep == 0 {
+                speaker::stop();
+            }
+        }
+        if self.len == 0 {
+            return;
+        }
+        self.shown += 1;
+        if self.shown < TOAST_TICKS {
+            return;
+        }
+        self.shown = 0;
+        self.queue.rotate_left(1);
+        self.len -= 1;
+    }
+
+    // Draws the current toast over the status bar, giving the status bar
+    // back once the last one has gone.
+    pub fn draw(&mut self) {
+        if self.len == 0 {
+            if let Some(overlay) = self.overlay.take() {
+                overlay::close(overlay);
+            }
+            return;
+        }
+        if self.overlay.is_none() {
+            self.overlay = overlay::open(0, 0, WIN_REGION_WIDTH, 1);
+        }
+        let Some(overlay) = self.overlay else {
+            return;
+        };
+        let toast = self.queue[0].as_str().unwrap();
+        let color = ColorCode::new(Color::Black, Color::Yellow);
+        overlay::draw_on(overlay, || {
+            let mut chars = toast.chars();
+            for col in 0..WIN_REGION_WIDTH {
+                plot(chars.next().unwrap_or(' '), col, 0, color);
+            }
+        });
+    }
+}
@@ -0,0 +1,54 @@
+use crate::MAX_FILENAME_BYTES;
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum OpenMode {
+    Editing,
+    Running,
+}
+
+#[derive(Copy, Clone)]
+struct OpenFile {
+    volume: usize,
+    name: [u8; MAX_FILENAME_BYTES],
+    mode: OpenMode,
+}
+
+pub struct OpenFiles<const N: usize> {
+    windows: [Option<OpenFile>; N],
+}
+
+impl<const N: usize> Default for OpenFiles<N> {
+    fn default() -> Self {
+        Self { windows: [None; N] }
+    }
+}
+
+impl<const N: usize> OpenFiles<N> {
+    pub fn open(
+        &mut self,
+        window: usize,
+        volume: usize,
+        name: [u8; MAX_FILENAME_BYTES],
+        mode: OpenMode,
+    ) {
+        self.windows[window] = Some(OpenFile { volume, name, mode });
+    }
+
+    pub fn close(&mut self, window: usize) {
+        self.windows[window] = None;
+    }
+
+    pub fn window_for(
+        &self,
+        volume: usize,
+        name: &[u8; MAX_FILENAME_BYTES],
+    ) -> Option<(usize, OpenMode)> {
+        self.windows
+            .iter()
+            .enumerate()
+            .find_map(|(i, open)| match open {
+                Some(open) if open.volume == volume && &open.name == name => Some((i, open.mode)),
+                _ => None,
+            })
+    }
+}
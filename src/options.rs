@@ -0,0 +1,65 @@
+use core::fmt::Write;
+
+use simple_interp::ArrayString;
+
+use crate::screen::{plot, ColorCode};
+use crate::settings::{Settings, NUM_OPTIONS};
+use crate::theme::Palette;
+use crate::LINE_WIDTH;
+
+const OPTION_ROWS: usize = 9;
+const HELP: &str = "left/right/enter: change";
+
+// The settings window: one row per option with its current value, scrolled
+// to keep the selected one in view. Changes are made through
+// SwimInterface::change_option, which applies them at once and saves them to
+// the config file.
+#[derive(Copy, Clone, Default)]
+pub struct Options {
+    selected: usize,
+}
+
+impl Options {
+    pub fn up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn down(&mut self) {
+        if self.selected + 1 < NUM_OPTIONS {
+            self.selected += 1;
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn draw(&self, settings: &Settings, x: usize, y: usize, palette: &Palette) {
+        let first = (self.selected + 1).saturating_sub(OPTION_ROWS);
+        for row in 0..OPTION_ROWS {
+            let option = first + row;
+            let mut line: ArrayString<LINE_WIDTH> = ArrayString::default();
+            let mut color = palette.text;
+            if option < NUM_OPTIONS {
+                write!(line, "{:<13}", settings.option_name(option)).unwrap();
+                settings.write_value(option, &mut line).unwrap();
+                if option == self.selected {
+                    color = palette.highlight;
+                }
+            } else if let Some(path) =
+                settings.autoexec[..settings.num_autoexec].get(option - NUM_OPTIONS)
+            {
+                write!(line, "{:<13}{}", "autoexec", path.as_str().unwrap()).unwrap();
+            }
+            draw_row(line.as_str().unwrap(), x, y + row, color);
+        }
+        draw_row(HELP, x, y + OPTION_ROWS, palette.border);
+    }
+}
+
+fn draw_row(text: &str, x: usize, y: usize, color: ColorCode) {
+    let mut chars = text.chars();
+    for col in 0..LINE_WIDTH {
+        plot(chars.next().unwrap_or(' '), x + col, y, color);
+    }
+}
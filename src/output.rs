@@ -0,0 +1,154 @@
+use core::fmt::Write;
+
+use simple_interp::{ArrayString, InterpreterOutput};
+
+use crate::LINE_WIDTH;
+
+pub const SCROLLBACK: usize = 40;
+
+// Text written to a window, kept as lines so the window can be redrawn from
+// it at any time instead of plotting once and scrolling the screen. The
+// newest line is the one being written; it is only committed once a newline
+// arrives, long lines wrap at the window width, and the oldest lines drop
+// off once SCROLLBACK is full. Lines are stored as UTF-8, so a code page
+// 437 character above ASCII takes two of a line's LINE_WIDTH bytes.
+pub struct Output {
+    lines: [ArrayString<LINE_WIDTH>; SCROLLBACK],
+    newest: usize,
+    num_lines: usize,
+    dirty: bool,
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self {
+            lines: [ArrayString::default(); SCROLLBACK],
+            newest: 0,
+            num_lines: 1,
+            dirty: true,
+        }
+    }
+}
+
+impl Output {
+    pub fn num_lines(&self) -> usize {
+        self.num_lines
+    }
+
+    // The line `age` lines before the newest, which is age 0.
+    pub fn line(&self, age: usize) -> &str {
+        if age < self.num_lines {
+            self.lines[(self.newest + SCROLLBACK - age) % SCROLLBACK]
+                .as_str()
+                .unwrap()
+        } else {
+            ""
+        }
+    }
+
+    // Bytes that read as UTF-8, as a program's output does, are taken as
+    // such; otherwise each byte is one code page 437 character.
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        match core::str::from_utf8(bytes) {
+            Ok(text) => text.chars().for_each(|c| self.push(c)),
+            Err(_) => bytes.iter().for_each(|b| self.push(*b as char)),
+        }
+    }
+
+    // The scrollback as text, oldest line first.
+    pub fn write_text<W: Write + ?Sized>(&self, out: &mut W) -> core::fmt::Result {
+        for age in (0..self.num_lines).rev() {
+            let line = self.line(age);
+            if age > 0 || !line.is_empty() {
+                writeln!(out, "{}", line)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    // Reports whether anything changed since the last call.
+    pub fn take_dirty(&mut self) -> bool {
+        core::mem::replace(&mut self.dirty, false)
+    }
+
+    // Empties the line being written, or if it has yet to start, the line
+    // before it, which is then written again.
+    pub fn rewrite_line(&mut self) {
+        if self.lines[self.newest].len() == 0 && self.num_lines > 1 {
+            self.newest = (self.newest + SCROLLBACK - 1) % SCROLLBACK;
+            self.num_lines -= 1;
+        }
+        self.lines[self.newest] = ArrayString::default();
+        self.dirty = true;
+    }
+
+    // Starts a new line unless the newest is still empty, for a note that
+    // must not run on from a program's unfinished line.
+    pub fn end_line(&mut self) {
+        if self.lines[self.newest].len() > 0 {
+            self.new_line();
+            self.dirty = true;
+        }
+    }
+
+    fn push(&mut self, c: char) {
+        if c == '\n' {
+            self.new_line();
+        } else {
+            if self.lines[self.newest].len() + c.len_utf8() > LINE_WIDTH {
+                self.new_line();
+            }
+            let mut encoded = [0; 4];
+            for b in c.encode_utf8(&mut encoded).bytes() {
+                self.lines[self.newest].push_char(b as char);
+            }
+        }
+        self.dirty = true;
+    }
+
+    fn new_line(&mut self) {
+        self.newest = (self.newest + 1) % SCROLLBACK;
+        self.lines[self.newest] = ArrayString::default();
+        self.num_lines = (self.num_lines + 1).min(SCROLLBACK);
+    }
+}
+
+impl Write for Output {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for c in s.chars() {
+            self.push(c);
+        }
+        Ok(())
+    }
+}
+
+// Copies an output line byte for byte. Formatting it into an ArrayString
+// would keep only the low byte of each character above ASCII.
+pub fn copy_line(line: &str) -> ArrayString<LINE_WIDTH> {
+    let mut copy = ArrayString::default();
+    for b in line.bytes() {
+        copy.push_char(b as char);
+    }
+    copy
+}
+
+// Formats into whatever a program prints to, so text written on its behalf,
+// such as an error its input caused, goes everywhere its own prints do: the
+// window, any log file and the serial mirror. Writing to an Output directly
+// only reaches the window.
+pub struct Printer<'a, O: InterpreterOutput>(pub &'a mut O);
+
+impl<O: InterpreterOutput> Write for Printer<'_, O> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.0.print(s.as_bytes());
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use core::cell::RefCell;
+
+use crate::screen::{self, ColorCode, BUFFER_HEIGHT, BUFFER_WIDTH};
+
+// The compositor. An overlay is a rectangle drawn above the windows, such as
+// the lock screen or a toast. When one opens, the cells under it are saved;
+// while it is up, anything plotted under it goes into that saved copy
+// instead of onto the screen, so closing it puts back what is there now
+// rather than what was there then. Overlays stack: the one opened last is on
+// top, and plots go to the lowest overlay above the drawer that covers the
+// cell.
+const MAX_OVERLAYS: usize = 3;
+const CELLS: usize = BUFFER_WIDTH * BUFFER_HEIGHT;
+
+type Cell = Option<(char, ColorCode)>;
+
+// Names an open overlay; see open.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct Overlay(usize);
+
+#[derive(Copy, Clone)]
+struct Rect {
+    id: usize,
+    col: usize,
+    row: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Rect {
+    fn covers(&self, col: usize, row: usize) -> bool {
+        (self.col..self.col + self.width).contains(&col)
+            && (self.row..self.row + self.height).contains(&row)
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.row..self.row + self.height)
+            .flat_map(move |row| (self.col..self.col + self.width).map(move |col| (col, row)))
+    }
+}
+
+// The open overlays, bottom first, each with the cells under it.
+pub struct Layers {
+    rects: [Option<Rect>; MAX_OVERLAYS],
+    under: [[Cell; CELLS]; MAX_OVERLAYS],
+    len: usize,
+    next_id: usize,
+    // How many layers the one being drawn is above the windows: 0 for the
+    // windows themselves.
+    drawing: usize,
+}
+
+impl Layers {
+    const fn new() -> Self {
+        Self {
+            rects: [None; MAX_OVERLAYS],
+            under: [[None; CELLS]; MAX_OVERLAYS],
+            len: 0,
+            next_id: 0,
+            drawing: 0,
+        }
+    }
+
+    fn position(&self, overlay: Overlay) -> Option<usize> {
+        self.rects[..self.len]
+            .iter()
+            .position(|rect| rect.is_some_and(|rect| rect.id == overlay.0))
+    }
+
+    // Puts a cell on the screen, or under the lowest layer from `above` up
+    // that covers it.
+    fn place(&mut self, above: usize, c: char, col: usize, row: usize, color: ColorCode) {
+        match (above..self.len).find(|i| self.rects[*i].is_some_and(|r| r.covers(col, row))) {
+            Some(i) => self.under[i][row * BUFFER_WIDTH + col] = Some((c, color)),
+            None => screen::put(c, col, row, color),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    static LAYERS: RefCell<Layers> = const { RefCell::new(Layers::new()) };
+}
+
+#[cfg(feature = "std")]
+fn with_layers<R>(f: impl FnOnce(&mut Layers) -> R) -> R {
+    LAYERS.with(|layers| f(&mut layers.borrow_mut()))
+}
+
+// The kernel only draws from its main loop, never from an interrupt
+// handler, so the layers are never in use from two places at once.
+#[cfg(not(feature = "std"))]
+struct Shared(RefCell<Layers>);
+
+#[cfg(not(feature = "std"))]
+unsafe impl Sync for Shared {}
+
+#[cfg(not(feature = "std"))]
+static LAYERS: Shared = Shared(RefCell::new(Layers::new()));
+
+#[cfg(not(feature = "std"))]
+fn with_layers<R>(f: impl FnOnce(&mut Layers) -> R) -> R {
+    f(&mut LAYERS.0.borrow_mut())
+}
+
+// Where screen::plot sends every cell.
+pub fn route(c: char, col: usize, row: usize, color: ColorCode) {
+    if col < BUFFER_WIDTH && row < BUFFER_HEIGHT {
+        with_layers(|layers| layers.place(layers.drawing, c, col, row, color));
+    }
+}
+
+// Opens an overlay on top of the others, clipped to the screen. Returns
+// None when MAX_OVERLAYS are already open.
+pub fn open(col: usize, row: usize, width: usize, height: usize) -> Option<Overlay> {
+    with_layers(|layers| {
+        if layers.len == MAX_OVERLAYS || col >= BUFFER_WIDTH || row >= BUFFER_HEIGHT {
+            return None;
+        }
+        let rect = Rect {
+            id: layers.next_id,
+            col,
+            row,
+            width: width.min(BUFFER_WIDTH - col),
+            height: height.min(BUFFER_HEIGHT - row),
+        };
+        layers.next_id += 1;
+        let i = layers.len;
+        for (col, row) in rect.cells() {
+            layers.under[i][row * BUFFER_WIDTH + col] = Some(screen::peek(col, row));
+        }
+        layers.rects[i] = Some(rect);
+        layers.len += 1;
+        Some(Overlay(rect.id))
+    })
+}
+
+// Closes an overlay, giving its cells back to whatever is now under them.
+pub fn close(overlay: Overlay) {
+    with_layers(|layers| {
+        let Some(i) = layers.position(overlay) else {
+            return;
+        };
+        let rect = layers.rects[i].take().unwrap();
+        for (col, row) in rect.cells() {
+            if let Some((c, color)) = layers.under[i][row * BUFFER_WIDTH + col] {
+                layers.place(i + 1, c, col, row, color);
+            }
+        }
+        layers.rects[i..layers.len].rotate_left(1);
+        layers.under[i..layers.len].rotate_left(1);
+        layers.len -= 1;
+    })
+}
+
+// Runs `draw` with its plots going onto `overlay`, under any opened after
+// it.
+pub fn draw_on<R>(overlay: Overlay, draw: impl FnOnce() -> R) -> R {
+    let outer = with_layers(|layers| {
+        let drawing = layers.position(overlay).map_or(layers.drawing, |i| i + 1);
+        core::mem::replace(&mut layers.drawing, drawing)
+    });
+    let result = draw();
+    with_layers(|layers| layers.drawing = outer);
+    result
+}
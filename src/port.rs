@@ -0,0 +1,17 @@
+// x86 I/O port access, for the devices the kernel drives itself.
+
+pub unsafe fn outb(port: u16, value: u8) {
+    core::arch::asm!("out dx, al", in("dx") port, in("al") value, options(nomem, nostack));
+}
+
+#[cfg(any(feature = "serial", feature = "sound"))]
+pub unsafe fn inb(port: u16) -> u8 {
+    let value;
+    core::arch::asm!("in al, dx", out("al") value, in("dx") port, options(nomem, nostack));
+    value
+}
+
+#[cfg(feature = "shell")]
+pub unsafe fn outw(port: u16, value: u16) {
+    core::arch::asm!("out dx, ax", in("dx") port, in("ax") value, options(nomem, nostack));
+}
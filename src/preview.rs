@@ -0,0 +1,55 @@
+use simple_interp::ArrayString;
+
+use crate::dirs::Name;
+use crate::storage::{name_str, Storage};
+use crate::stream::FileReader;
+use crate::LINE_WIDTH;
+
+// The first line of a file with anything but spaces on it, shown under a
+// file list so a program can be told apart without opening it. A window
+// keeps the one for its focused file, and reads the file again only once
+// the focus moves or the listing is refreshed.
+#[derive(Copy, Clone)]
+pub struct Preview {
+    file: Name,
+    line: ArrayString<LINE_WIDTH>,
+}
+
+impl Preview {
+    // Reads no further than the line it shows. A file that can't be read
+    // shows nothing.
+    pub fn read(storage: &mut Storage, file: &Name) -> Self {
+        let mut line = ArrayString::default();
+        if let Ok(mut reader) = FileReader::open(storage, name_str(file).unwrap_or("")) {
+            let mut blank = true;
+            while let Ok(Some(byte)) = reader.next_byte(storage) {
+                if byte == b'\n' || line.len() == LINE_WIDTH {
+                    if !blank {
+                        break;
+                    }
+                    if byte == b'\n' {
+                        line.clear();
+                    }
+                    continue;
+                }
+                // Tabs and bytes past ASCII show as spaces.
+                let c = if byte.is_ascii_graphic() { byte } else { b' ' };
+                blank &= c == b' ';
+                line.push_char(c as char);
+            }
+            reader.close(storage).ok();
+            if blank {
+                line.clear();
+            }
+        }
+        Self { file: *file, line }
+    }
+
+    pub fn is_of(&self, file: &Name) -> bool {
+        self.file == *file
+    }
+
+    pub fn line(&self) -> &str {
+        self.line.as_str().unwrap()
+    }
+}
@@ -0,0 +1,602 @@
+use gc_heap_template::GenerationalHeap;
+use simple_interp::{ArrayString, Interpreter, InterpreterOutput};
+
+use crate::layout::Rect;
+use crate::output::{copy_line, Output};
+use crate::screen::{is_drawable, plot_clipped, Color, ColorCode};
+#[cfg(feature = "serial")]
+use crate::serial;
+use crate::theme::Palette;
+use crate::{
+    BLOCK_SIZE, HEAP_SIZE, LINE_WIDTH, MAX_FILENAME_BYTES, MAX_HEAP_BLOCKS, MAX_LITERAL_CHARS,
+    MAX_LOCAL_VARS, MAX_TOKENS, STACK_DEPTH, WIN_WIDTH,
+};
+
+pub const MAX_PROCESSES: usize = 8;
+pub const MAX_NICE: u32 = 3;
+const OUTPUT_ROWS: usize = 10;
+const MAX_INPUT: usize = 10;
+const FIELD_ROWS: usize = 3;
+// Lines a program prints before paging stops it, leaving the bottom row for
+// the `--More--` that says so.
+const PAGE_LINES: usize = OUTPUT_ROWS - 1;
+const MAX_LINES_INPUT: usize = FIELD_ROWS * LINE_WIDTH;
+const FLASH_FRAMES: usize = 3;
+
+pub type Program = Interpreter<
+    MAX_TOKENS,
+    MAX_LITERAL_CHARS,
+    STACK_DEPTH,
+    MAX_LOCAL_VARS,
+    WIN_WIDTH,
+    GenerationalHeap<HEAP_SIZE, MAX_HEAP_BLOCKS, 2>,
+>;
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum ProcessState {
+    Running,
+    AwaitingInput,
+    Paused,
+    // Stopped after a page of output, until a key lets it go on.
+    More,
+    Finished,
+}
+
+impl ProcessState {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProcessState::Running => "run",
+            ProcessState::AwaitingInput => "wait",
+            ProcessState::Paused => "stop",
+            ProcessState::More => "more",
+            ProcessState::Finished => "done",
+        }
+    }
+}
+
+// What a program asking for input will accept, as its prompt asks. A prompt
+// ending in "#" takes a number: digits, an optional leading minus sign and
+// one decimal point. A prompt ending in "..." takes several lines, with
+// Enter starting a new one and Ctrl+Enter finishing.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum InputKind {
+    Text,
+    Number,
+    Lines,
+}
+
+impl InputKind {
+    fn for_prompt(prompt: &str) -> Self {
+        let prompt = prompt.trim_end();
+        if prompt.ends_with("...") {
+            InputKind::Lines
+        } else if prompt.ends_with('#') {
+            InputKind::Number
+        } else {
+            InputKind::Text
+        }
+    }
+}
+
+// How a program's prints are laid out. By lines, each print is a line of
+// its own. By chars, a print carries on the line the last one left and
+// only an empty print ends it, for output such as a row of progress dots.
+// A program asks for chars with `#chars` as its first line.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum PrintMode {
+    Lines,
+    Chars,
+}
+
+impl PrintMode {
+    // The mode `program` asks for, and the program without the request.
+    // Its line is left blank, so the program's lines keep their numbers.
+    fn for_program(program: &str) -> (Self, &str) {
+        match program.strip_prefix("#chars") {
+            Some(rest) if rest.is_empty() || rest.starts_with(['\n', '\r']) => {
+                (PrintMode::Chars, rest)
+            }
+            _ => (PrintMode::Lines, program),
+        }
+    }
+}
+
+// A running program and everything it owns. Windows only show processes, so
+// a process keeps running and collecting output while no window shows it.
+pub struct Process {
+    pub interpreter: Option<Program>,
+    pub state: ProcessState,
+    pub file: [u8; MAX_FILENAME_BYTES],
+    pub volume: usize,
+    pub vruntime: usize,
+    // Interpreter ticks run so far, unweighted by nice.
+    pub ticks: usize,
+    // Each tick adds 2^nice to vruntime, so a nicer process gets a smaller
+    // share of the CPU under the fair scheduler.
+    pub nice: u32,
+    pub output: Output,
+    pub input: ArrayString<MAX_LINES_INPUT>,
+    pub input_kind: InputKind,
+    print_mode: PrintMode,
+    // Frames left to show the input row in the error color after a rejected
+    // key.
+    flash: usize,
+    // The line the program printed before asking for `input`, kept with it
+    // so the question stays in view however the output scrolls or is cleared.
+    pub prompt: ArrayString<LINE_WIDTH>,
+    pub window: Option<usize>,
+    // (volume, fd) of the file output is copied to, and of the file input
+    // requests are answered from.
+    pub log: Option<(usize, usize)>,
+    pub stdin: Option<(usize, usize)>,
+    // The output line highlighted for copying, by age as in Output::line.
+    pub selected: Option<usize>,
+    // How many lines PgUp has taken the view back from the newest.
+    scroll: usize,
+    // Lines printed since a key last let it go on, for paging.
+    page_lines: usize,
+    // Set whenever the program prints, for the scheduler to notice and clear.
+    pub printed: bool,
+    // Whether output is copied to the serial port, kept in step with the
+    // `mirror` setting by the scheduler.
+    pub mirror: bool,
+    // Stopped by the timeout rather than finishing.
+    pub crashed: bool,
+    // The frame it was spawned on, and the one it finished or was killed
+    // on, for how long its run took.
+    pub started: u64,
+    pub stopped: Option<u64>,
+    // Set on the processes one action launched together, so the jobs
+    // window can kill, pause or resume them as one.
+    pub group: Option<usize>,
+    #[cfg(feature = "serial")]
+    mirror_line_start: bool,
+    log_buffer: [u8; BLOCK_SIZE],
+    log_len: usize,
+    // Bytes printed past a full log_buffer since it was last written out,
+    // which the log never gets.
+    log_dropped: usize,
+}
+
+impl Process {
+    pub fn new(
+        program: &str,
+        file: [u8; MAX_FILENAME_BYTES],
+        volume: usize,
+        window: Option<usize>,
+        vruntime: usize,
+    ) -> Self {
+        let (print_mode, program) = PrintMode::for_program(program);
+        Self {
+            interpreter: Some(Interpreter::new(program)),
+            state: ProcessState::Running,
+            file,
+            volume,
+            vruntime,
+            ticks: 0,
+            nice: 0,
+            output: Output::default(),
+            input: ArrayString::default(),
+            input_kind: InputKind::Text,
+            print_mode,
+            flash: 0,
+            prompt: ArrayString::default(),
+            window,
+            log: None,
+            stdin: None,
+            selected: None,
+            scroll: 0,
+            page_lines: 0,
+            printed: false,
+            mirror: false,
+            crashed: false,
+            started: 0,
+            stopped: None,
+            group: None,
+            #[cfg(feature = "serial")]
+            mirror_line_start: true,
+            log_buffer: [0; BLOCK_SIZE],
+            log_len: 0,
+            log_dropped: 0,
+        }
+    }
+
+    // Frames from the one it was spawned in to the one it stopped in, or
+    // this one, both counted: the wall time its `ticks` of CPU time were
+    // spread over.
+    pub fn wall_ticks(&self, now: u64) -> u64 {
+        self.stopped.unwrap_or(now) - self.started + 1
+    }
+
+    // Whether the window's header should blink to ask for attention.
+    pub fn alerting(&self) -> bool {
+        self.crashed || matches!(self.state, ProcessState::AwaitingInput | ProcessState::More)
+    }
+
+    // With `paging` on, stops a program shown in a window once it has
+    // printed a page since it last went on. A program out of sight has
+    // no one to let it go on, so it runs freely.
+    pub fn page(&mut self, paging: bool) {
+        if !paging || self.window.is_none() {
+            self.page_lines = 0;
+        } else if self.state == ProcessState::Running && self.page_lines >= PAGE_LINES {
+            self.state = ProcessState::More;
+            self.output.mark_dirty();
+        }
+    }
+
+    // Lets a program stopped by paging print another page.
+    pub fn more(&mut self) {
+        if self.state == ProcessState::More {
+            self.state = ProcessState::Running;
+            self.page_lines = 0;
+            self.output.mark_dirty();
+        }
+    }
+
+    // Starts waiting for a line of input, taking the last line printed, or
+    // the one in progress, as the prompt.
+    pub fn await_input(&mut self) {
+        let newest = if self.output.line(0).is_empty() { 1 } else { 0 };
+        self.prompt = copy_line(self.output.line(newest));
+        self.input_kind = InputKind::for_prompt(self.prompt.as_str().unwrap());
+        self.input = ArrayString::default();
+        self.page_lines = 0;
+        self.state = ProcessState::AwaitingInput;
+        self.output.mark_dirty();
+    }
+
+    // Adds a typed character to the input, or flashes the input row if the
+    // field is full or the character doesn't fit the kind of input wanted.
+    pub fn type_char(&mut self, c: char) {
+        let (valid, max_len) = match self.input_kind {
+            InputKind::Text => (is_drawable(c), MAX_INPUT),
+            InputKind::Number => {
+                let typed = self.input.as_str().unwrap();
+                let valid = match c {
+                    '-' => typed.is_empty(),
+                    '.' => !typed.contains('.'),
+                    c => c.is_ascii_digit(),
+                };
+                (valid, MAX_INPUT)
+            }
+            InputKind::Lines => (is_drawable(c) || c == '\n', MAX_LINES_INPUT),
+        };
+        if valid && self.input.len() < max_len {
+            self.input.push_char(c);
+        } else {
+            self.flash = FLASH_FRAMES;
+        }
+        self.output.mark_dirty();
+    }
+
+    // Moves the highlight to an older output line, starting at the newest
+    // finished one.
+    pub fn select_up(&mut self) {
+        let oldest = self.output.num_lines() - 1;
+        self.selected = Some(match self.selected {
+            Some(age) => (age + 1).min(oldest),
+            None => self.newest_line().min(oldest),
+        });
+        self.output.mark_dirty();
+    }
+
+    // Moves the highlight to a newer line, dropping it past the newest.
+    pub fn select_down(&mut self) {
+        self.selected = self
+            .selected
+            .filter(|age| *age > self.newest_line())
+            .map(|age| age - 1);
+        self.output.mark_dirty();
+    }
+
+    // PgUp and PgDn move the view a window's height at a time, no further
+    // back than the oldest line kept and no further on than the newest.
+    pub fn page_up(&mut self) {
+        let oldest = self.output.num_lines().saturating_sub(OUTPUT_ROWS);
+        self.scroll = (self.scroll + OUTPUT_ROWS).min(oldest);
+        self.selected = None;
+        self.output.mark_dirty();
+    }
+
+    pub fn page_down(&mut self) {
+        self.scroll = self.scroll.saturating_sub(OUTPUT_ROWS);
+        self.selected = None;
+        self.output.mark_dirty();
+    }
+
+    // Back to following the newest output, as after the output is cleared.
+    pub fn unscroll(&mut self) {
+        self.scroll = 0;
+        self.output.mark_dirty();
+    }
+
+    pub fn selected_line(&self) -> Option<&str> {
+        self.selected.map(|age| self.output.line(age))
+    }
+
+    // The age of the newest line worth showing: the line in progress, unless
+    // it is empty and no input is being typed after it.
+    fn newest_line(&self) -> usize {
+        if self.state == ProcessState::AwaitingInput || !self.output.line(0).is_empty() {
+            0
+        } else {
+            1
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.state == ProcessState::Running && self.interpreter.is_some()
+    }
+
+    // The output logged since the last call, to be written to `log`, and
+    // how many bytes after it didn't fit.
+    pub fn take_log(&mut self) -> (&[u8], usize) {
+        let log_len = core::mem::take(&mut self.log_len);
+        let dropped = core::mem::take(&mut self.log_dropped);
+        (&self.log_buffer[..log_len], dropped)
+    }
+
+    // Draws the tail of the output, with any input being typed following its
+    // prompt on the newest line. The newest line is otherwise left out when
+    // it is empty, so the last full line sits at the bottom once the window
+    // has filled. A cursor follows the input when `cursor` is set, and the
+    // view scrolls back to show a line selected for copying.
+    // Draws into `area`, the inside of the window, clipped to it.
+    // `spacing` is the screen rows each line takes; see layout::line_spacing.
+    pub fn draw(&mut self, area: Rect, palette: &Palette, cursor: bool, spacing: usize) {
+        if !self.output.take_dirty() {
+            return;
+        }
+        let (x, y) = (area.x, area.y);
+        let flashing = self.flash > 0;
+        if flashing {
+            self.flash -= 1;
+            self.output.mark_dirty();
+        }
+        let field_color = if flashing {
+            palette.flash
+        } else {
+            palette.text
+        };
+        let taking_input = self.state == ProcessState::AwaitingInput;
+        let field_rows = if taking_input && self.input_kind == InputKind::Lines {
+            let field = Rect {
+                y: y + OUTPUT_ROWS - FIELD_ROWS,
+                height: FIELD_ROWS,
+                ..area
+            };
+            self.draw_field(field, field_color, cursor);
+            FIELD_ROWS
+        } else {
+            0
+        };
+        let taking_input = taking_input && field_rows == 0;
+        let more = self.state == ProcessState::More;
+        if more {
+            let mut chars = "--More--".chars();
+            for col in 0..LINE_WIDTH {
+                let c = chars.next().unwrap_or(' ');
+                plot_clipped(c, x + col, y + PAGE_LINES, palette.highlight, area);
+            }
+        }
+        let height = OUTPUT_ROWS - field_rows - more as usize;
+        let rows = height / spacing;
+        // Scroll back far enough to show the highlighted line, or as far as
+        // PgUp went. Scrolled back, a prompt keeps the bottom row to itself.
+        let newest = match (self.newest_line(), self.selected) {
+            (newest, Some(age)) if age >= newest + rows => age + 1 - rows,
+            (newest, _) => (newest + self.scroll).min(self.output.num_lines() - 1),
+        };
+        let anchored = taking_input && newest > 0;
+        let shown = (self.output.num_lines() - newest).min(rows - anchored as usize);
+        for screen_row in 0..height {
+            let row = screen_row / spacing;
+            let mut cursor_col = LINE_WIDTH;
+            let mut text = palette.text;
+            let age = match row {
+                _ if screen_row % spacing != 0 || row >= rows => None,
+                _ if anchored && row == rows - 1 => Some(0),
+                row if row < shown => Some(newest + shown - 1 - row),
+                _ => None,
+            };
+            let (line, gap, input) = if let Some(age) = age {
+                if self.selected == Some(age) {
+                    text = palette.highlight;
+                }
+                if age == 0 && taking_input {
+                    let prompt = self.prompt.as_str().unwrap();
+                    let gap = if prompt.is_empty() || prompt.ends_with(' ') {
+                        ""
+                    } else {
+                        " "
+                    };
+                    if cursor {
+                        cursor_col = prompt.chars().count() + gap.len() + self.input.len();
+                    }
+                    text = field_color;
+                    (prompt, gap, self.input.as_str().unwrap())
+                } else {
+                    (self.output.line(age), "", "")
+                }
+            } else {
+                ("", "", "")
+            };
+            let mut chars = line.chars().chain(gap.chars()).chain(input.chars());
+            for col in 0..LINE_WIDTH {
+                let color = if col == cursor_col {
+                    palette.highlight
+                } else {
+                    text
+                };
+                plot_clipped(
+                    chars.next().unwrap_or(' '),
+                    x + col,
+                    y + screen_row,
+                    color,
+                    area,
+                );
+            }
+        }
+    }
+
+    // Draws the last lines of output that fit in `area`, without the input
+    // field, for a program shown beside something else.
+    pub fn draw_tail(&self, area: Rect, color: ColorCode) {
+        let (x, y, rows) = (area.x, area.y, area.height);
+        let newest = self.newest_line();
+        let shown = (self.output.num_lines() - newest).min(rows);
+        for row in 0..rows {
+            let line = if row < shown {
+                self.output.line(newest + shown - 1 - row)
+            } else {
+                ""
+            };
+            let mut chars = line.chars();
+            for col in 0..LINE_WIDTH {
+                plot_clipped(chars.next().unwrap_or(' '), x + col, y + row, color, area);
+            }
+        }
+    }
+
+    // Draws the last FIELD_ROWS rows of a multi-line input, wrapping lines
+    // wider than the window.
+    fn draw_field(&self, area: Rect, color: ColorCode, cursor: bool) {
+        let (x, y) = (area.x, area.y);
+        let input = self.input.as_str().unwrap();
+        let (end_row, end_col) = field_end(input);
+        let first = (end_row + 1).saturating_sub(FIELD_ROWS);
+        for row in 0..FIELD_ROWS {
+            for col in 0..LINE_WIDTH {
+                plot_clipped(' ', x + col, y + row, color, area);
+            }
+        }
+        let (mut row, mut col) = (0, 0);
+        for c in input.chars() {
+            if c != '\n' && row >= first {
+                plot_clipped(c, x + col, y + row - first, color, area);
+            }
+            (row, col) = next_position(row, col, c);
+        }
+        if cursor {
+            plot_clipped(
+                ' ',
+                x + end_col,
+                y + end_row - first,
+                ColorCode::new(Color::Black, Color::LightCyan),
+                area,
+            );
+        }
+    }
+
+    // Each mirrored line starts with the window number, or `-` for a
+    // detached program, so lines from different programs can be told apart.
+    #[cfg(feature = "serial")]
+    fn mirror_output(&mut self, chars: &[u8]) {
+        if !self.mirror {
+            return;
+        }
+        for &byte in chars {
+            if self.mirror_line_start {
+                let label = self.window.map_or(b'-', |window| b'1' + window as u8);
+                [label, b':', b' '].into_iter().for_each(serial::send);
+            }
+            serial::send(byte);
+            self.mirror_line_start = byte == b'\n';
+        }
+    }
+
+    fn log_output(&mut self, chars: &[u8]) {
+        if self.log.is_some() {
+            let num_bytes = chars.len().min(BLOCK_SIZE - self.log_len);
+            self.log_buffer[self.log_len..self.log_len + num_bytes]
+                .copy_from_slice(&chars[..num_bytes]);
+            self.log_len += num_bytes;
+            self.log_dropped += chars.len() - num_bytes;
+        }
+    }
+}
+
+// Where the next character of a multi-line input goes, as a row counting
+// wrapped lines and a column.
+fn field_end(input: &str) -> (usize, usize) {
+    input
+        .chars()
+        .fold((0, 0), |(row, col), c| next_position(row, col, c))
+}
+
+fn next_position(row: usize, col: usize, c: char) -> (usize, usize) {
+    if c == '\n' || col + 1 == LINE_WIDTH {
+        (row + 1, 0)
+    } else {
+        (row, col + 1)
+    }
+}
+
+impl InterpreterOutput for Process {
+    // By chars, the newline ending each print is held back, unless it is
+    // all there is. A print led by `\r` rewrites the line the last one
+    // wrote, so a counter can update in place without using up the
+    // scrollback.
+    fn print(&mut self, chars: &[u8]) {
+        let chars = match (self.print_mode, chars) {
+            (PrintMode::Chars, [text @ .., b'\n']) if !text.is_empty() => text,
+            _ => chars,
+        };
+        self.printed = true;
+        self.log_output(chars);
+        #[cfg(feature = "serial")]
+        self.mirror_output(chars);
+        match chars {
+            [b'\r', text @ ..] => {
+                self.output.rewrite_line();
+                self.output.write_bytes(text);
+            }
+            _ => {
+                self.page_lines += chars.iter().filter(|c| **c == b'\n').count();
+                self.output.write_bytes(chars);
+            }
+        }
+    }
+}
+
+// Process IDs are slots in the table, so an ID is reused once its process
+// has been removed.
+pub struct ProcessTable {
+    processes: [Option<Process>; MAX_PROCESSES],
+}
+
+impl Default for ProcessTable {
+    fn default() -> Self {
+        Self {
+            processes: [const { None }; MAX_PROCESSES],
+        }
+    }
+}
+
+impl ProcessTable {
+    pub fn spawn(&mut self, process: Process) -> Option<usize> {
+        let pid = self.processes.iter().position(|p| p.is_none())?;
+        self.processes[pid] = Some(process);
+        Some(pid)
+    }
+
+    pub fn get(&self, pid: usize) -> Option<&Process> {
+        self.processes.get(pid)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, pid: usize) -> Option<&mut Process> {
+        self.processes.get_mut(pid)?.as_mut()
+    }
+
+    pub fn remove(&mut self, pid: usize) -> Option<Process> {
+        self.processes.get_mut(pid)?.take()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Process)> {
+        self.processes
+            .iter()
+            .enumerate()
+            .filter_map(|(pid, p)| p.as_ref().map(|p| (pid, p)))
+    }
+}
@@ -0,0 +1,88 @@
+use core::fmt::{self, Display, Formatter};
+
+use crate::storage::{name_bytes, Storage};
+use crate::{BLOCK_SIZE, MAX_FILES_STORED, NUM_BLOCKS};
+
+// Saves that leave the disk at least this full are warned about.
+const WARN_PERCENT: usize = 90;
+
+// How a save of `size` bytes would leave a volume, worked out before any of
+// it is written. Rewriting a file frees its old blocks first, and a file the
+// directory already has (even deleted) keeps its slot.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Quota {
+    Fits,
+    NearlyFull { percent: usize },
+    LastSlot,
+    NoSpace { needed: usize, free: usize },
+    NoSlot,
+}
+
+impl Quota {
+    pub fn check(storage: &mut Storage, filename: &str, size: usize) -> Quota {
+        let name = name_bytes(filename);
+        let (num_entries, entries) = match storage.raw_directory() {
+            Ok(directory) => directory,
+            Err(_) => return Quota::Fits,
+        };
+        let new_entry = !entries[..num_entries].contains(&name);
+        if new_entry && num_entries == MAX_FILES_STORED {
+            return Quota::NoSlot;
+        }
+        let blocks = storage.block_map();
+        let free = blocks.num_free() + blocks.num_blocks(&name);
+        let needed = size.div_ceil(BLOCK_SIZE);
+        if needed > free {
+            return Quota::NoSpace { needed, free };
+        }
+        let percent = (NUM_BLOCKS - (free - needed)) * 100 / NUM_BLOCKS;
+        if percent >= WARN_PERCENT {
+            Quota::NearlyFull { percent }
+        } else if new_entry && num_entries + 1 == MAX_FILES_STORED {
+            Quota::LastSlot
+        } else {
+            Quota::Fits
+        }
+    }
+
+    // Whether a save of `size` bytes can go to `scratch` first and then be
+    // copied over the file, which needs room for both copies at once.
+    pub fn room_for_scratch(
+        storage: &mut Storage,
+        filename: &str,
+        scratch: &str,
+        size: usize,
+    ) -> bool {
+        let (num_entries, entries) = match storage.raw_directory() {
+            Ok(directory) => directory,
+            Err(_) => return false,
+        };
+        let new_entries = [name_bytes(filename), name_bytes(scratch)]
+            .iter()
+            .filter(|name| !entries[..num_entries].contains(name))
+            .count();
+        let blocks = storage.block_map();
+        let free = blocks.num_free()
+            + blocks.num_blocks(&name_bytes(filename))
+            + blocks.num_blocks(&name_bytes(scratch));
+        num_entries + new_entries <= MAX_FILES_STORED && 2 * size.div_ceil(BLOCK_SIZE) <= free
+    }
+
+    pub fn refuses(&self) -> bool {
+        matches!(self, Quota::NoSpace { .. } | Quota::NoSlot)
+    }
+}
+
+impl Display for Quota {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Quota::Fits => write!(f, "saved"),
+            Quota::NearlyFull { percent } => write!(f, "disk {}% full", percent),
+            Quota::LastSlot => write!(f, "uses the last free file slot"),
+            Quota::NoSpace { needed, free } => {
+                write!(f, "not saved: needs {} blocks, {} free", needed, free)
+            }
+            Quota::NoSlot => write!(f, "not saved: no free file slots"),
+        }
+    }
+}
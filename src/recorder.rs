@@ -0,0 +1,157 @@
+use core::fmt::Write;
+
+use file_system_solution::FileSystemError;
+use simple_interp::ArrayString;
+
+use crate::overlay::{self, Overlay};
+use crate::screen::{plot, plot_str, BUFFER_HEIGHT, BUFFER_WIDTH};
+use crate::screenshot::{self, TEXT_BYTES};
+use crate::storage::{name_str, Storage};
+use crate::stream::FileReader;
+use crate::theme::Palette;
+use crate::{MAX_FILENAME_BYTES, MAX_FILE_BYTES};
+
+// Where Ctrl+R records to.
+pub const REEL: &str = "reel";
+// As many frames as fit in a file; older ones are dropped to make room.
+pub const MAX_FRAMES: usize = MAX_FILE_BYTES / TEXT_BYTES;
+
+// Records the screen's text every so many ticks into REEL, one frame after
+// another, each frame a screenshot's text. The file keeps the last
+// MAX_FRAMES of them.
+#[derive(Copy, Clone)]
+pub struct Recorder {
+    pub volume: usize,
+    frames: usize,
+    ticks: usize,
+}
+
+impl Recorder {
+    // Starts with an empty reel, so the first frame goes in on the first
+    // tick.
+    pub fn start(storage: &mut Storage, volume: usize) -> Result<Self, FileSystemError> {
+        let fd = storage.open_create(REEL)?;
+        storage.close(fd)?;
+        Ok(Self {
+            volume,
+            frames: 0,
+            ticks: 0,
+        })
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    pub fn tick(&mut self, storage: &mut Storage, every: usize) -> Result<(), FileSystemError> {
+        self.ticks += 1;
+        if self.ticks < every && self.frames > 0 {
+            return Ok(());
+        }
+        self.ticks = 0;
+        let drop = if self.frames == MAX_FRAMES {
+            TEXT_BYTES
+        } else {
+            self.frames += 1;
+            0
+        };
+        let fd = storage.open_append_dropping(REEL, drop)?;
+        let result = screenshot::write_text(storage, fd);
+        result.and(storage.close(fd))
+    }
+}
+
+// Steps through a recorded reel over the whole screen: Left and Right move
+// a frame, Esc closes it. Only the frame shown is read from the file.
+#[derive(Copy, Clone)]
+pub struct Playback {
+    pub volume: usize,
+    name: [u8; MAX_FILENAME_BYTES],
+    frame: usize,
+    frames: usize,
+    overlay: Overlay,
+    dirty: bool,
+}
+
+impl Playback {
+    // Returns None if no overlay is free.
+    pub fn open(
+        storage: &mut Storage,
+        volume: usize,
+        name: [u8; MAX_FILENAME_BYTES],
+    ) -> Result<Option<Self>, FileSystemError> {
+        let size = storage.file_size(name_str(&name).unwrap_or(""))?;
+        Ok(
+            overlay::open(0, 0, BUFFER_WIDTH, BUFFER_HEIGHT).map(|overlay| Self {
+                volume,
+                name,
+                frame: 0,
+                frames: size.div_ceil(TEXT_BYTES),
+                overlay,
+                dirty: true,
+            }),
+        )
+    }
+
+    pub fn step(&mut self, forward: bool) {
+        let frame = match forward {
+            true => (self.frame + 1).min(self.frames.saturating_sub(1)),
+            false => self.frame.saturating_sub(1),
+        };
+        self.dirty |= frame != self.frame;
+        self.frame = frame;
+    }
+
+    pub fn close(self) {
+        overlay::close(self.overlay);
+    }
+
+    pub fn draw(
+        &mut self,
+        storage: &mut Storage,
+        palette: &Palette,
+    ) -> Result<(), FileSystemError> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.dirty = false;
+        let mut reader = FileReader::open(storage, name_str(&self.name).unwrap_or(""))?;
+        let result = overlay::draw_on(self.overlay, || {
+            self.draw_frame(&mut reader, storage, palette)
+        });
+        reader.close(storage)?;
+        result
+    }
+
+    fn draw_frame(
+        &self,
+        reader: &mut FileReader,
+        storage: &mut Storage,
+        palette: &Palette,
+    ) -> Result<(), FileSystemError> {
+        let mut more = reader.skip_lines(storage, self.frame * BUFFER_HEIGHT)?;
+        for row in 0..BUFFER_HEIGHT {
+            let mut col = 0;
+            while more {
+                match reader.next_byte(storage)? {
+                    Some(b'\n') => break,
+                    Some(b) => {
+                        if col < BUFFER_WIDTH {
+                            plot(char::from(b), col, row, palette.text);
+                        }
+                        col += 1;
+                    }
+                    None => more = false,
+                }
+            }
+            for col in col..BUFFER_WIDTH {
+                plot(' ', col, row, palette.text);
+            }
+        }
+        let mut label: ArrayString<BUFFER_WIDTH> = ArrayString::default();
+        write!(label, " frame {}/{} ", self.frame + 1, self.frames.max(1)).unwrap();
+        let label = label.as_str().unwrap();
+        plot_str(label, BUFFER_WIDTH - label.len(), 0, palette.highlight);
+        Ok(())
+    }
+}
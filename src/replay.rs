@@ -0,0 +1,179 @@
+use core::fmt::Write;
+
+use pc_keyboard::{DecodedKey, KeyCode};
+
+use crate::SwimInterface;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Step {
+    Key(DecodedKey),
+    Tick,
+}
+
+// Anything that can stand in for the keyboard and timer: a fixed list of
+// steps in a test, or a script read from a file.
+pub trait InputSource {
+    fn next_step(&mut self) -> Option<Step>;
+}
+
+pub struct StepList<'a> {
+    steps: &'a [Step],
+    pos: usize,
+}
+
+impl<'a> StepList<'a> {
+    pub fn new(steps: &'a [Step]) -> Self {
+        Self { steps, pos: 0 }
+    }
+}
+
+impl InputSource for StepList<'_> {
+    fn next_step(&mut self) -> Option<Step> {
+        let step = self.steps.get(self.pos).copied();
+        self.pos += 1;
+        step
+    }
+}
+
+// A script in plain text. Characters are typed as they are; a name in
+// braces stands for a key with no character or for a timer tick:
+// `{F1}`..`{F8}`, `{Up}`, `{Down}`, `{Left}`, `{Right}`, `{PgUp}`, `{PgDn}`,
+// `{Home}`, `{End}`, `{Del}`, `{Ins}`, `{Enter}`, `{Tick}`, `{Tick 5}` for
+// five ticks, `{Ctrl}`
+// to hold Ctrl for the next key, `{Alt}` to hold Alt until the next tick,
+// and `{^s}` for Ctrl+S (any letter). `{{` types a single `{`. Unknown names are skipped.
+pub struct TextScript<'a> {
+    script: &'a str,
+    ticks: usize,
+}
+
+impl<'a> TextScript<'a> {
+    pub fn new(script: &'a str) -> Self {
+        Self { script, ticks: 0 }
+    }
+
+    // What is left of the script, not counting ticks still owed from a
+    // `{Tick N}` already read.
+    pub fn rest(&self) -> &'a str {
+        self.script
+    }
+
+    pub fn owed_ticks(&self) -> usize {
+        self.ticks
+    }
+}
+
+impl InputSource for TextScript<'_> {
+    fn next_step(&mut self) -> Option<Step> {
+        if self.ticks > 0 {
+            self.ticks -= 1;
+            return Some(Step::Tick);
+        }
+        loop {
+            let mut chars = self.script.chars();
+            let c = chars.next()?;
+            let rest = chars.as_str();
+            if c != '{' || rest.starts_with('{') {
+                self.script = if c == '{' { &rest[1..] } else { rest };
+                return Some(Step::Key(DecodedKey::Unicode(c)));
+            }
+            let (name, rest) = rest.split_once('}').unwrap_or((rest, ""));
+            self.script = rest;
+            if let Some(ticks) = name.strip_prefix("Tick ") {
+                if let Some(ticks) = ticks.parse::<usize>().ok().filter(|&t| t > 0) {
+                    self.ticks = ticks - 1;
+                    return Some(Step::Tick);
+                }
+            } else if let Some(step) = named_step(name) {
+                return Some(step);
+            }
+        }
+    }
+}
+
+// The keys with no character that scripts can name, besides `{^s}`'s
+// control letters.
+const NAMED_KEYS: [(&str, KeyCode); 25] = [
+    ("F1", KeyCode::F1),
+    ("F2", KeyCode::F2),
+    ("F3", KeyCode::F3),
+    ("F4", KeyCode::F4),
+    ("F5", KeyCode::F5),
+    ("F6", KeyCode::F6),
+    ("F7", KeyCode::F7),
+    ("F8", KeyCode::F8),
+    ("Up", KeyCode::ArrowUp),
+    ("Down", KeyCode::ArrowDown),
+    ("Left", KeyCode::ArrowLeft),
+    ("Right", KeyCode::ArrowRight),
+    ("PgUp", KeyCode::PageUp),
+    ("PgDn", KeyCode::PageDown),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("Del", KeyCode::Delete),
+    ("Ins", KeyCode::Insert),
+    ("Ctrl", KeyCode::LControl),
+    ("RCtrl", KeyCode::RControl),
+    ("Alt", KeyCode::LAlt),
+    ("AltGr", KeyCode::RAltGr),
+    ("Shift", KeyCode::LShift),
+    ("RShift", KeyCode::RShift),
+    ("Caps", KeyCode::CapsLock),
+];
+
+fn named_step(name: &str) -> Option<Step> {
+    if name == "Tick" {
+        return Some(Step::Tick);
+    }
+    if name == "Enter" {
+        return Some(Step::Key(DecodedKey::Unicode('\n')));
+    }
+    if let Some((_, code)) = NAMED_KEYS.iter().find(|(n, _)| *n == name) {
+        return Some(Step::Key(DecodedKey::RawKey(*code)));
+    }
+    match name.as_bytes() {
+        [b'^', letter] if letter.is_ascii_lowercase() => {
+            Some(Step::Key(DecodedKey::Unicode((letter - b'a' + 1) as char)))
+        }
+        _ => None,
+    }
+}
+
+// Writes `key` the way a script would give it, so that reading it back
+// gives the same key. Returns false for keys scripts have no way to name.
+pub fn write_key<W: Write>(out: &mut W, key: DecodedKey) -> Result<bool, core::fmt::Error> {
+    match key {
+        DecodedKey::Unicode('{') => out.write_str("{{")?,
+        DecodedKey::Unicode('\n') => out.write_str("{Enter}")?,
+        DecodedKey::Unicode(c @ '\u{1}'..='\u{1a}') => {
+            write!(out, "{{^{}}}", (c as u8 - 1 + b'a') as char)?
+        }
+        DecodedKey::Unicode(c) => out.write_char(c)?,
+        DecodedKey::RawKey(code) => match NAMED_KEYS.iter().find(|(_, c)| *c == code) {
+            Some((name, _)) => write!(out, "{{{}}}", name)?,
+            None => return Ok(false),
+        },
+    }
+    Ok(true)
+}
+
+impl<const N: usize> SwimInterface<N> {
+    // Delivers keys from `source` up to and including its next tick, returning
+    // false once the source has run out.
+    pub fn step<S: InputSource>(&mut self, source: &mut S) -> bool {
+        loop {
+            match source.next_step() {
+                Some(Step::Key(key)) => self.key(key),
+                Some(Step::Tick) => {
+                    self.tick();
+                    return true;
+                }
+                None => return false,
+            }
+        }
+    }
+
+    pub fn replay<S: InputSource>(&mut self, source: &mut S) {
+        while self.step(source) {}
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(feature = "shell")]
+use file_system_solution::FileSystemError;
+
+#[cfg(feature = "shell")]
+use crate::storage::Storage;
+
+// The course's sample programs, a directory per assignment (see dirs), put
+// on volume a: at boot by SwimInterfaceBuilder::samples.
+pub const SAMPLE_FILES: &[(&str, &str)] = &[
+    ("a1/hello", r#"print("Hello, world!")"#),
+    (
+        "a1/square",
+        r#"n := input("A number:")
+print((n * n))
+"#,
+    ),
+    (
+        "a2/count",
+        r#"i := 1
+while (i < 11) {
+    print(i)
+    i := (i + 1)
+}
+"#,
+    ),
+    (
+        "a2/average",
+        r#"sum := 0
+count := 0
+averaging := true
+while averaging {
+    num := input("Enter a number:")
+    if (num == "quit") {
+        averaging := false
+    } else {
+        sum := (sum + num)
+        count := (count + 1)
+    }
+}
+print((sum / count))
+"#,
+    ),
+];
+
+// Writes each sample back as it shipped, whether it was changed or
+// deleted since, and leaves every other file alone.
+#[cfg(feature = "shell")]
+pub fn restore(storage: &mut Storage, samples: &[(&str, &str)]) -> Result<(), FileSystemError> {
+    for (name, contents) in samples {
+        let fd = storage.open_create(name)?;
+        let result = storage.write(fd, contents.as_bytes());
+        result.and(storage.close(fd))?;
+    }
+    Ok(())
+}
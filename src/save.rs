@@ -0,0 +1,105 @@
+use core::fmt::Write;
+
+use file_system_solution::FileSystemError;
+use simple_interp::ArrayString;
+
+use crate::buffer::Contents;
+use crate::storage::{name_bytes, name_str, Storage, SAVE_SCRATCH};
+use crate::{BLOCK_SIZE, MAX_FILENAME_BYTES};
+
+// A save that runs as a system task: each step writes at most one block of
+// the document, so a long save is spread over several ticks instead of
+// holding up the one that asked for it.
+//
+// Given a scratch file, the steps write there, and the last one copies it
+// over the file, so a save cut short leaves the old contents whole. Without
+// one, as for protected files, the file is written in place.
+#[derive(Copy, Clone)]
+pub struct SaveTask {
+    pub volume: usize,
+    name: [u8; MAX_FILENAME_BYTES],
+    scratch: Option<[u8; MAX_FILENAME_BYTES]>,
+    contents: Contents,
+    fd: Option<usize>,
+    written: usize,
+}
+
+impl SaveTask {
+    pub fn new(
+        volume: usize,
+        name: [u8; MAX_FILENAME_BYTES],
+        scratch: Option<[u8; MAX_FILENAME_BYTES]>,
+        contents: Contents,
+    ) -> Self {
+        Self {
+            volume,
+            name,
+            scratch,
+            contents,
+            fd: None,
+            written: 0,
+        }
+    }
+
+    pub fn name(&self) -> [u8; MAX_FILENAME_BYTES] {
+        self.name
+    }
+
+    // The descriptor it is writing through, once it has started.
+    pub fn fd(&self) -> Option<usize> {
+        self.fd
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.contents.len() - self.written
+    }
+
+    // Returns true once the whole document is written and the file closed.
+    pub fn step(&mut self, storage: &mut Storage) -> Result<bool, FileSystemError> {
+        let fd = match self.fd {
+            Some(fd) => fd,
+            None => {
+                let target = self.scratch.as_ref().unwrap_or(&self.name);
+                let fd = storage.open_create(name_str(target).unwrap_or(""))?;
+                self.fd = Some(fd);
+                fd
+            }
+        };
+        // Not as_str: characters above 127 are single bytes, not UTF-8.
+        let bytes = self.contents.buffer_slice();
+        let end = bytes.len().min(self.written + BLOCK_SIZE);
+        if let Err(e) = storage.write(fd, &bytes[self.written..end]) {
+            self.cancel(storage);
+            return Err(e);
+        }
+        self.written = end;
+        if self.written == bytes.len() {
+            self.fd = None;
+            storage.close(fd)?;
+            if let Some(scratch) = &self.scratch {
+                let name = name_str(&self.name).unwrap_or("");
+                storage.replace(name_str(scratch).unwrap_or(""), name)?;
+            }
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    pub fn cancel(&mut self, storage: &mut Storage) {
+        if let Some(fd) = self.fd.take() {
+            storage.close(fd).unwrap_or(());
+            if let Some(scratch) = &self.scratch {
+                storage.truncate(name_str(scratch).unwrap_or("")).ok();
+            }
+        }
+    }
+}
+
+// The scratch file a window's saves are written to, one per window so saves
+// running side by side stay apart.
+pub fn scratch_name(window: usize) -> [u8; MAX_FILENAME_BYTES] {
+    let mut name: ArrayString<MAX_FILENAME_BYTES> = ArrayString::default();
+    write!(name, "{}{}", SAVE_SCRATCH, window + 1).unwrap();
+    name_bytes(name.as_str().unwrap())
+}
@@ -0,0 +1,27 @@
+use crate::SCHED_LATENCY;
+
+#[derive(Copy, Clone)]
+pub enum SchedulerPolicy {
+    // Runs the program with the least virtual runtime, splitting `latency`
+    // ticks evenly between the programs that are ready.
+    Fair { latency: usize },
+    // Takes ready programs in window order, `quantum` ticks each.
+    RoundRobin { quantum: usize },
+}
+
+impl Default for SchedulerPolicy {
+    fn default() -> Self {
+        Self::Fair {
+            latency: SCHED_LATENCY,
+        }
+    }
+}
+
+impl SchedulerPolicy {
+    pub fn time_slice(&self, num_programs: usize) -> usize {
+        match self {
+            Self::Fair { latency } => latency / num_programs,
+            Self::RoundRobin { quantum } => *quantum,
+        }
+    }
+}
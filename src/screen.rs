@@ -0,0 +1,312 @@
+use core::cell::RefCell;
+#[cfg(not(feature = "std"))]
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::layout::Rect;
+use crate::overlay;
+
+// Everything in the crate draws through this module rather than the VGA
+// buffer directly. Kernel builds forward to pluggable_interrupt_os; builds
+// with the `std` feature draw into a FakeScreen instead, so host tests can
+// read back what was drawn. Plots pass through the compositor in
+// overlay.rs on the way, which keeps what falls under an overlay, and then
+// land in an off-screen frame that present copies to the screen.
+pub trait Screen {
+    fn plot(&mut self, c: char, col: usize, row: usize, color: ColorCode);
+    fn peek(&self, col: usize, row: usize) -> (char, ColorCode);
+}
+
+pub fn plot(c: char, col: usize, row: usize, color: ColorCode) {
+    count_plot();
+    overlay::route(c, col, row, color);
+}
+
+// Into the frame being composed, for the compositor once it has placed a
+// cell.
+pub fn put(c: char, col: usize, row: usize, color: ColorCode) {
+    if col < BUFFER_WIDTH && row < BUFFER_HEIGHT {
+        with_frame(|frame| frame.back[row][col] = Some((c, color)));
+    }
+}
+
+// What the next present will show, falling back on the screen for cells
+// nothing has drawn yet.
+pub fn peek(col: usize, row: usize) -> (char, ColorCode) {
+    match with_frame(|frame| frame.back[row][col]) {
+        Some(cell) => cell,
+        None => with_screen(|screen| screen.peek(col, row)),
+    }
+}
+
+// Writes the cells that differ from the last presented frame to the
+// screen. A cell blanked and redrawn within a frame is never written at
+// all, so nothing flickers.
+pub fn present() {
+    with_frame(|frame| {
+        for row in 0..BUFFER_HEIGHT {
+            for col in 0..BUFFER_WIDTH {
+                let cell = frame.back[row][col];
+                if let Some((c, color)) = cell {
+                    if cell != frame.front[row][col] {
+                        with_screen(|screen| screen.plot(c, col, row, color));
+                        frame.front[row][col] = cell;
+                        frame.presented += 1;
+                    }
+                }
+            }
+        }
+    })
+}
+
+// A running count of cells present has written to the screen.
+pub fn cells_presented() -> usize {
+    with_frame(|frame| frame.presented)
+}
+
+// The frame being composed and the one last presented. A None cell in
+// `back` has not been drawn since startup; in `front`, not yet written.
+struct Frame {
+    back: [[Option<(char, ColorCode)>; BUFFER_WIDTH]; BUFFER_HEIGHT],
+    front: [[Option<(char, ColorCode)>; BUFFER_WIDTH]; BUFFER_HEIGHT],
+    presented: usize,
+}
+
+impl Frame {
+    const fn new() -> Self {
+        Self {
+            back: [[None; BUFFER_WIDTH]; BUFFER_HEIGHT],
+            front: [[None; BUFFER_WIDTH]; BUFFER_HEIGHT],
+            presented: 0,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    static FRAME: RefCell<Frame> = const { RefCell::new(Frame::new()) };
+}
+
+#[cfg(feature = "std")]
+fn with_frame<R>(f: impl FnOnce(&mut Frame) -> R) -> R {
+    FRAME.with(|frame| f(&mut frame.borrow_mut()))
+}
+
+// Like the overlay layers, only ever used from the kernel's main loop.
+#[cfg(not(feature = "std"))]
+struct Shared(RefCell<Frame>);
+
+#[cfg(not(feature = "std"))]
+unsafe impl Sync for Shared {}
+
+#[cfg(not(feature = "std"))]
+static FRAME: Shared = Shared(RefCell::new(Frame::new()));
+
+#[cfg(not(feature = "std"))]
+fn with_frame<R>(f: impl FnOnce(&mut Frame) -> R) -> R {
+    f(&mut FRAME.0.borrow_mut())
+}
+
+pub fn plot_str(s: &str, col: usize, row: usize, color: ColorCode) {
+    for (i, c) in s.chars().enumerate() {
+        plot(c, col + i, row, color);
+    }
+}
+
+// As plot and plot_str, but dropping whatever falls outside `clip`, so a
+// window drawing past its edge can't reach its neighbours or the task panel.
+pub fn plot_clipped(c: char, col: usize, row: usize, color: ColorCode, clip: Rect) {
+    if clip.contains(col, row) {
+        plot(c, col, row, color);
+    }
+}
+
+pub fn plot_str_clipped(s: &str, col: usize, row: usize, color: ColorCode, clip: Rect) {
+    for (i, c) in s.chars().enumerate() {
+        plot_clipped(c, col + i, row, color, clip);
+    }
+}
+
+// A double-lined box with its corners at (col, row) and (col + width - 1,
+// row + height - 1), blanking what is inside.
+pub fn draw_box(col: usize, row: usize, width: usize, height: usize, color: ColorCode) {
+    for y in row..row + height {
+        for x in col..col + width {
+            let c = match (x - col, y - row) {
+                (0, 0) => 201u8,
+                (0, r) if r == height - 1 => 200u8,
+                (c, 0) if c == width - 1 => 187u8,
+                (c, r) if c == width - 1 && r == height - 1 => 188u8,
+                (0, _) => 186u8,
+                (c, _) if c == width - 1 => 186u8,
+                (_, 0) => 205u8,
+                (_, r) if r == height - 1 => 205u8,
+                _ => b' ',
+            };
+            plot(c as char, x, y, color);
+        }
+    }
+}
+
+// Code page 437's characters above ASCII, which can't be typed but can be
+// picked from the character map.
+pub fn is_extended(c: char) -> bool {
+    ('\u{80}'..='\u{ff}').contains(&c)
+}
+
+// Reads text stored one byte per character, as the editor saves it, into
+// `out`, which needs room for two bytes for each byte above ASCII.
+pub fn cp437_to_utf8<'a>(bytes: &[u8], out: &'a mut [u8]) -> &'a str {
+    let mut len = 0;
+    for b in bytes {
+        len += (*b as char).encode_utf8(&mut out[len..]).len();
+    }
+    core::str::from_utf8(&out[..len]).unwrap()
+}
+
+// A running count of cells plotted, so a frame's cost can be measured by
+// reading it before and after.
+#[cfg(not(feature = "std"))]
+static CELLS_PLOTTED: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(not(feature = "std"))]
+fn count_plot() {
+    CELLS_PLOTTED.fetch_add(1, Ordering::Relaxed);
+}
+
+#[cfg(not(feature = "std"))]
+pub fn cells_plotted() -> usize {
+    CELLS_PLOTTED.load(Ordering::Relaxed)
+}
+
+#[cfg(not(feature = "std"))]
+pub use pluggable_interrupt_os::vga_buffer::{
+    is_drawable, Color, ColorCode, BUFFER_HEIGHT, BUFFER_WIDTH,
+};
+
+#[cfg(not(feature = "std"))]
+struct Vga;
+
+#[cfg(not(feature = "std"))]
+impl Screen for Vga {
+    fn plot(&mut self, c: char, col: usize, row: usize, color: ColorCode) {
+        pluggable_interrupt_os::vga_buffer::plot(c, col, row, color);
+    }
+
+    fn peek(&self, col: usize, row: usize) -> (char, ColorCode) {
+        pluggable_interrupt_os::vga_buffer::peek(col, row)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+fn with_screen<R>(f: impl FnOnce(&mut dyn Screen) -> R) -> R {
+    f(&mut Vga)
+}
+
+#[cfg(feature = "std")]
+pub use fake::*;
+
+#[cfg(feature = "std")]
+mod fake {
+    use std::cell::{Cell, RefCell};
+
+    use super::Screen;
+
+    pub const BUFFER_WIDTH: usize = 80;
+    pub const BUFFER_HEIGHT: usize = 25;
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub enum Color {
+        Black,
+        Blue,
+        Green,
+        Cyan,
+        Red,
+        Magenta,
+        Brown,
+        LightGray,
+        DarkGray,
+        LightBlue,
+        LightGreen,
+        LightCyan,
+        LightRed,
+        Pink,
+        Yellow,
+        White,
+    }
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+    pub struct ColorCode {
+        pub foreground: Color,
+        pub background: Color,
+    }
+
+    impl ColorCode {
+        pub fn new(foreground: Color, background: Color) -> Self {
+            Self {
+                foreground,
+                background,
+            }
+        }
+    }
+
+    pub struct FakeScreen {
+        cells: [[(char, ColorCode); BUFFER_WIDTH]; BUFFER_HEIGHT],
+    }
+
+    impl Default for FakeScreen {
+        fn default() -> Self {
+            Self {
+                cells: [[(' ', ColorCode::new(Color::Black, Color::Black)); BUFFER_WIDTH];
+                    BUFFER_HEIGHT],
+            }
+        }
+    }
+
+    impl FakeScreen {
+        pub fn row_text(&self, row: usize) -> String {
+            self.cells[row].iter().map(|(c, _)| *c).collect()
+        }
+
+        pub fn color(&self, col: usize, row: usize) -> ColorCode {
+            self.cells[row][col].1
+        }
+    }
+
+    impl Screen for FakeScreen {
+        fn plot(&mut self, c: char, col: usize, row: usize, color: ColorCode) {
+            if col < BUFFER_WIDTH && row < BUFFER_HEIGHT {
+                self.cells[row][col] = (c, color);
+            }
+        }
+
+        fn peek(&self, col: usize, row: usize) -> (char, ColorCode) {
+            self.cells[row][col]
+        }
+    }
+
+    thread_local! {
+        static SCREEN: RefCell<FakeScreen> = RefCell::new(FakeScreen::default());
+        static CELLS_PLOTTED: Cell<usize> = const { Cell::new(0) };
+    }
+
+    pub(super) fn count_plot() {
+        CELLS_PLOTTED.with(|count| count.set(count.get() + 1));
+    }
+
+    pub fn cells_plotted() -> usize {
+        CELLS_PLOTTED.with(|count| count.get())
+    }
+
+    pub(super) fn with_screen<R>(f: impl FnOnce(&mut dyn Screen) -> R) -> R {
+        SCREEN.with(|screen| f(&mut *screen.borrow_mut()))
+    }
+
+    // Each test thread has its own screen; this reads the calling thread's.
+    pub fn fake_screen<R>(f: impl FnOnce(&FakeScreen) -> R) -> R {
+        SCREEN.with(|screen| f(&screen.borrow()))
+    }
+
+    pub fn is_drawable(c: char) -> bool {
+        (' '..='~').contains(&c)
+    }
+}
@@ -0,0 +1,112 @@
+use core::fmt::Write;
+
+use file_system_solution::FileSystemError;
+use simple_interp::ArrayString;
+
+use crate::screen::{peek, Color, ColorCode, BUFFER_HEIGHT, BUFFER_WIDTH};
+use crate::storage::Storage;
+use crate::MAX_FILENAME_BYTES;
+
+// Screenshots go in `shot1` to `shot99`, each with a `.col` sidecar.
+const MAX_SHOTS: usize = 99;
+
+// How much of a file write_text writes: a line per row.
+pub const TEXT_BYTES: usize = (BUFFER_WIDTH + 1) * BUFFER_HEIGHT;
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+// The colors in VGA order, so a color's position is its attribute bits.
+const COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Blue,
+    Color::Green,
+    Color::Cyan,
+    Color::Red,
+    Color::Magenta,
+    Color::Brown,
+    Color::LightGray,
+    Color::DarkGray,
+    Color::LightBlue,
+    Color::LightGreen,
+    Color::LightCyan,
+    Color::LightRed,
+    Color::Pink,
+    Color::Yellow,
+    Color::White,
+];
+
+// The VGA attribute byte for a color code: background in the high nibble,
+// foreground in the low. ColorCode keeps its byte to itself in the kernel,
+// so this looks it up.
+fn attribute(color: ColorCode) -> u8 {
+    (0..=u8::MAX)
+        .find(|a| color == ColorCode::new(COLORS[(a & 0xF) as usize], COLORS[(a >> 4) as usize]))
+        .unwrap_or(0)
+}
+
+// A screen cell's character as the byte the editor stores for it.
+fn byte(c: char) -> u8 {
+    u8::try_from(c).unwrap_or(b'?')
+}
+
+fn shot_name(n: usize, suffix: &str) -> ArrayString<MAX_FILENAME_BYTES> {
+    let mut name = ArrayString::default();
+    write!(name, "shot{}{}", n, suffix).unwrap();
+    name
+}
+
+// Saves the screen as it is now into the first free `shotN`: its text a
+// line per row, and in `shotN.col` each cell's attribute byte as two hex
+// digits. Returns the name used.
+pub fn capture(storage: &mut Storage) -> Result<ArrayString<MAX_FILENAME_BYTES>, FileSystemError> {
+    let n = (1..=MAX_SHOTS)
+        .find(|n| !storage.exists(shot_name(*n, "").as_str().unwrap()))
+        .ok_or(FileSystemError::TooManyFiles)?;
+    let name = shot_name(n, "");
+    let fd = storage.open_create(name.as_str().unwrap())?;
+    let result = write_text(storage, fd);
+    finish(storage, fd, result)?;
+    let fd = storage.open_create(shot_name(n, ".col").as_str().unwrap())?;
+    let result = write_rows(storage, fd, |col, row| {
+        let a = attribute(peek(col, row).1);
+        [HEX[(a >> 4) as usize], HEX[(a & 0xF) as usize]]
+    });
+    finish(storage, fd, result)?;
+    Ok(name)
+}
+
+// Closes a shot's file however writing it went. On a full disk the close
+// can fail too; it is tried again at once, so the descriptor isn't left
+// open until an open runs short and closes the stale ones.
+fn finish(
+    storage: &mut Storage,
+    fd: usize,
+    result: Result<(), FileSystemError>,
+) -> Result<(), FileSystemError> {
+    let closed = storage.close(fd);
+    if closed.is_err() {
+        storage.close(fd).ok();
+    }
+    result.and(closed)
+}
+
+// Writes the screen's text to an open file, TEXT_BYTES of it.
+pub fn write_text(storage: &mut Storage, fd: usize) -> Result<(), FileSystemError> {
+    write_rows(storage, fd, |col, row| [byte(peek(col, row).0)])
+}
+
+// Writes BUFFER_HEIGHT lines, each of the cells' bytes in turn.
+fn write_rows<const W: usize>(
+    storage: &mut Storage,
+    fd: usize,
+    cell: impl Fn(usize, usize) -> [u8; W],
+) -> Result<(), FileSystemError> {
+    let mut line = [b'\n'; BUFFER_WIDTH * 2 + 1];
+    for row in 0..BUFFER_HEIGHT {
+        for col in 0..BUFFER_WIDTH {
+            line[col * W..col * W + W].copy_from_slice(&cell(col, row));
+        }
+        storage.write(fd, &line[..BUFFER_WIDTH * W + 1])?;
+    }
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use core::fmt::Write;
+
+use simple_interp::{ArrayString, TickStatus};
+
+use crate::process::Process;
+use crate::screen::{
+    draw_box, peek, plot, plot_str, Color, ColorCode, BUFFER_HEIGHT, BUFFER_WIDTH,
+};
+use crate::storage::Storage;
+use crate::theme::Palette;
+use crate::MAX_FILENAME_BYTES;
+
+const WIDTH: usize = 44;
+const HEIGHT: usize = 9;
+const SUBSYSTEMS: [&str; 3] = ["filesystem", "interpreter", "renderer"];
+
+// Made, read back and deleted again on the first volume.
+const TEMP_FILE: &str = "selftest";
+const TEMP_CONTENTS: &[u8] = b"swim self-test\n";
+// Run to the end and expected to print 42.
+const PROGRAM: &str = "print(42)";
+const PROGRAM_TICKS: usize = 100;
+// Plotted along the bottom row in two colors and peeked back.
+const PATTERN: &str = "SWIM self-test 0123456789";
+
+// The boot self-test, asked for with the builder's `self_test`. Each check
+// goes through the same code the interface does, so a change to a
+// dependency or a const that breaks one of them shows up before the
+// windows do. A check reports why it failed in a few words.
+#[derive(Copy, Clone)]
+pub struct SelfTest {
+    results: [Result<(), &'static str>; SUBSYSTEMS.len()],
+}
+
+impl SelfTest {
+    pub fn run(storage: &mut Storage) -> Self {
+        Self {
+            results: [
+                check_filesystem(storage),
+                check_interpreter(),
+                check_renderer(),
+            ],
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(Result::is_ok)
+    }
+
+    // The report, shown where the splash would be.
+    pub fn draw(&self, palette: &Palette) {
+        for row in 0..BUFFER_HEIGHT {
+            for col in 0..BUFFER_WIDTH {
+                plot(' ', col, row, palette.blank);
+            }
+        }
+        let x = (BUFFER_WIDTH - WIDTH) / 2;
+        let y = (BUFFER_HEIGHT - HEIGHT) / 2;
+        draw_box(x, y, WIDTH, HEIGHT, palette.border);
+        plot_str("Self-test", x + 3, y + 1, palette.text);
+        for (row, (name, result)) in SUBSYSTEMS.iter().zip(self.results).enumerate() {
+            let mut line: ArrayString<WIDTH> = ArrayString::default();
+            match result {
+                Ok(()) => write!(line, "{:<12}PASS", name).unwrap(),
+                Err(why) => write!(line, "{:<12}FAIL {}", name, why).unwrap(),
+            }
+            let color = if result.is_ok() {
+                palette.text
+            } else {
+                palette.alert
+            };
+            plot_str(line.as_str().unwrap(), x + 3, y + 3 + row, color);
+        }
+        let hint = if self.passed() {
+            "press any key"
+        } else {
+            "press any key to go on anyway"
+        };
+        plot_str(hint, x + 3, y + 7, palette.border);
+    }
+}
+
+fn check_filesystem(storage: &mut Storage) -> Result<(), &'static str> {
+    let fd = storage.open_create(TEMP_FILE).map_err(|_| "create")?;
+    let written = storage.write(fd, TEMP_CONTENTS);
+    storage.close(fd).map_err(|_| "close")?;
+    written.map_err(|_| "write")?;
+    let fd = storage.open_read(TEMP_FILE).map_err(|_| "open")?;
+    let mut buffer = [0; TEMP_CONTENTS.len() + 1];
+    let read = storage.read(fd, &mut buffer);
+    storage.close(fd).map_err(|_| "close")?;
+    if !read.is_ok_and(|n| buffer[..n] == *TEMP_CONTENTS) {
+        return Err("read back wrong");
+    }
+    storage.delete(TEMP_FILE).map_err(|_| "delete")?;
+    if storage.exists(TEMP_FILE) {
+        return Err("listed after delete");
+    }
+    Ok(())
+}
+
+fn check_interpreter() -> Result<(), &'static str> {
+    let mut process = Process::new(PROGRAM, [0; MAX_FILENAME_BYTES], 0, None, 0);
+    let Some(mut interpreter) = process.interpreter.take() else {
+        return Err("no interpreter");
+    };
+    for _ in 0..PROGRAM_TICKS {
+        match interpreter.tick(&mut process) {
+            TickStatus::Continuing => {}
+            TickStatus::AwaitInput => return Err("asked for input"),
+            TickStatus::Finished => {
+                let mut output: ArrayString<16> = ArrayString::default();
+                process.output.write_text(&mut output).ok();
+                return match output.as_str().unwrap().trim() {
+                    "42" => Ok(()),
+                    _ => Err("printed wrong"),
+                };
+            }
+        }
+    }
+    Err("never finished")
+}
+
+fn check_renderer() -> Result<(), &'static str> {
+    let colors = [
+        ColorCode::new(Color::Yellow, Color::Blue),
+        ColorCode::new(Color::Blue, Color::Yellow),
+    ];
+    let row = BUFFER_HEIGHT - 1;
+    for (col, c) in PATTERN.chars().enumerate() {
+        plot(c, col, row, colors[col % 2]);
+    }
+    for (col, c) in PATTERN.chars().enumerate() {
+        if peek(col, row) != (c, colors[col % 2]) {
+            return Err("peeked wrong");
+        }
+    }
+    Ok(())
+}
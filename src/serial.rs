@@ -0,0 +1,94 @@
+// COM1. Kernel builds drive the 16550 UART through its I/O ports; builds
+// with the `std` feature swap in a pair of queues, so host tests can feed it
+// bytes and read back what was sent.
+//
+// pluggable_interrupt_os owns the interrupt table and has no handler for
+// the UART's IRQ, so the receive interrupt stays off and the timer
+// interrupt polls instead (see Events::poll_serial). The 16-byte FIFO holds
+// about a tick's worth of typing; faster transfers lose bytes.
+
+#[cfg(not(feature = "std"))]
+const COM1: u16 = 0x3F8;
+// Status bits in the line status register.
+#[cfg(not(feature = "std"))]
+const DATA_READY: u8 = 0x01;
+#[cfg(not(feature = "std"))]
+const TRANSMIT_EMPTY: u8 = 0x20;
+// Polls of a full transmitter before a byte is dropped, so a port with
+// nothing attached can't hang the kernel.
+#[cfg(not(feature = "std"))]
+const SEND_TRIES: usize = 1000;
+
+#[cfg(not(feature = "std"))]
+use crate::port::{inb, outb};
+
+// 38400 baud, 8 data bits, no parity, one stop bit, FIFOs on.
+#[cfg(not(feature = "std"))]
+pub fn init() {
+    unsafe {
+        outb(COM1 + 1, 0x00);
+        outb(COM1 + 3, 0x80);
+        outb(COM1, 0x03);
+        outb(COM1 + 1, 0x00);
+        outb(COM1 + 3, 0x03);
+        outb(COM1 + 2, 0xC7);
+        outb(COM1 + 4, 0x0B);
+    }
+}
+
+#[cfg(not(feature = "std"))]
+pub fn receive() -> Option<u8> {
+    unsafe {
+        if inb(COM1 + 5) & DATA_READY != 0 {
+            Some(inb(COM1))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+pub fn send(byte: u8) {
+    unsafe {
+        for _ in 0..SEND_TRIES {
+            if inb(COM1 + 5) & TRANSMIT_EMPTY != 0 {
+                outb(COM1, byte);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+pub use fake::*;
+
+#[cfg(feature = "std")]
+mod fake {
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+
+    thread_local! {
+        static RECEIVED: RefCell<VecDeque<u8>> = const { RefCell::new(VecDeque::new()) };
+        static SENT: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub fn init() {}
+
+    pub fn receive() -> Option<u8> {
+        RECEIVED.with(|received| received.borrow_mut().pop_front())
+    }
+
+    pub fn send(byte: u8) {
+        SENT.with(|sent| sent.borrow_mut().push(byte));
+    }
+
+    // Bytes for the calling thread's port to receive, as if from the wire.
+    pub fn serial_arrive(bytes: &[u8]) {
+        RECEIVED.with(|received| received.borrow_mut().extend(bytes));
+    }
+
+    // Everything the calling thread's port has sent since the last call.
+    pub fn serial_sent() -> Vec<u8> {
+        SENT.with(|sent| sent.take())
+    }
+}
@@ -0,0 +1,210 @@
+use core::fmt::Write;
+
+use file_system_solution::FileSystemError;
+use pc_keyboard::DecodedKey;
+use simple_interp::ArrayString;
+
+use crate::replay::{write_key, InputSource, Step, TextScript};
+use crate::storage::Storage;
+use crate::stream::FileReader;
+use crate::MAX_FILE_BYTES;
+
+// Where `session=record` logs to and `session=replay` plays back from.
+pub const SESSION: &str = "session";
+// The longest line the log writes; a longer burst of keys is split.
+const LINE_BYTES: usize = 80;
+
+// What the kernel does with the session log from boot.
+#[derive(Copy, Clone, Default, Eq, PartialEq)]
+pub enum Session {
+    #[default]
+    Off,
+    Record,
+    Replay,
+}
+
+impl Session {
+    pub fn named(name: &str) -> Option<Session> {
+        match name {
+            "off" => Some(Session::Off),
+            "record" => Some(Session::Record),
+            "replay" => Some(Session::Replay),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Session::Off => "off",
+            Session::Record => "record",
+            Session::Replay => "replay",
+        }
+    }
+}
+
+// Logs every key and tick from boot as a script TextScript can read back:
+// a line for each burst of keys, and a `{Tick N}` line for each run of
+// ticks between them. Keys go out when the tick after them comes, and a
+// run of ticks when the next key ends it.
+#[derive(Copy, Clone)]
+pub struct SessionLog {
+    pub volume: usize,
+    line: ArrayString<LINE_BYTES>,
+    idle: usize,
+    bytes: usize,
+}
+
+impl SessionLog {
+    pub fn start(storage: &mut Storage, volume: usize) -> Result<Self, FileSystemError> {
+        let fd = storage.open_create(SESSION)?;
+        storage.close(fd)?;
+        Ok(Self {
+            volume,
+            line: ArrayString::default(),
+            idle: 0,
+            bytes: 0,
+        })
+    }
+
+    // Keys above ASCII can't come from the US-decoded keyboard, and keys
+    // scripts have no name for do nothing, so neither is logged.
+    pub fn key(&mut self, storage: &mut Storage, key: DecodedKey) -> Result<(), FileSystemError> {
+        let mut text: ArrayString<8> = ArrayString::default();
+        if matches!(key, DecodedKey::Unicode(c) if !c.is_ascii())
+            || !write_key(&mut text, key).unwrap_or(false)
+        {
+            return Ok(());
+        }
+        if self.idle > 0 {
+            let mut ticks: ArrayString<16> = ArrayString::default();
+            write_ticks(&mut ticks, self.idle);
+            self.write(storage, ticks.as_str().unwrap())?;
+            self.idle = 0;
+        }
+        if self.line.len() + text.len() > LINE_BYTES {
+            self.end_line(storage)?;
+        }
+        for c in text.as_str().unwrap().chars() {
+            self.line.push_char(c);
+        }
+        Ok(())
+    }
+
+    pub fn tick(&mut self, storage: &mut Storage) -> Result<(), FileSystemError> {
+        if self.line.len() > 0 {
+            self.end_line(storage)?;
+        }
+        self.idle += 1;
+        Ok(())
+    }
+
+    // Writes out the keys and ticks still held, for a log that stops here.
+    #[cfg(feature = "shell")]
+    pub fn finish(&mut self, storage: &mut Storage) -> Result<(), FileSystemError> {
+        if self.line.len() > 0 {
+            self.end_line(storage)?;
+        }
+        if self.idle > 0 {
+            let mut ticks: ArrayString<16> = ArrayString::default();
+            write_ticks(&mut ticks, self.idle);
+            self.write(storage, ticks.as_str().unwrap())?;
+            self.idle = 0;
+        }
+        Ok(())
+    }
+
+    fn end_line(&mut self, storage: &mut Storage) -> Result<(), FileSystemError> {
+        let line = self.line;
+        self.write(storage, line.as_str().unwrap())?;
+        self.write(storage, "\n")?;
+        self.line = ArrayString::default();
+        Ok(())
+    }
+
+    fn write(&mut self, storage: &mut Storage, text: &str) -> Result<(), FileSystemError> {
+        if self.bytes + text.len() > MAX_FILE_BYTES {
+            return Err(FileSystemError::FileTooBig);
+        }
+        let fd = storage.open_append(SESSION)?;
+        let result = storage.write(fd, text.as_bytes());
+        result.and(storage.close(fd))?;
+        self.bytes += text.len();
+        Ok(())
+    }
+}
+
+fn write_ticks(out: &mut ArrayString<16>, ticks: usize) {
+    match ticks {
+        1 => writeln!(out, "{{Tick}}"),
+        _ => writeln!(out, "{{Tick {}}}", ticks),
+    }
+    .unwrap();
+}
+
+// Plays a session log back a tick at a time. Only the line being played is
+// held; the file is opened again for each new line, as Playback reads only
+// the frame it shows.
+#[derive(Copy, Clone)]
+pub struct SessionReplay {
+    pub volume: usize,
+    line: ArrayString<LINE_BYTES>,
+    pos: usize,
+    ticks: usize,
+    lines: usize,
+}
+
+impl SessionReplay {
+    pub fn new(volume: usize) -> Self {
+        Self {
+            volume,
+            line: ArrayString::default(),
+            pos: 0,
+            ticks: 0,
+            lines: 0,
+        }
+    }
+
+    // The next key logged, or the tick that ends this frame's keys. None
+    // once the log has run out.
+    pub fn next_step(&mut self, storage: &mut Storage) -> Result<Option<Step>, FileSystemError> {
+        loop {
+            if self.ticks > 0 {
+                self.ticks -= 1;
+                return Ok(Some(Step::Tick));
+            }
+            let line = self.line;
+            let line = line.as_str().unwrap_or("");
+            let mut script = TextScript::new(&line[self.pos..]);
+            if let Some(step) = script.next_step() {
+                self.pos = line.len() - script.rest().len();
+                self.ticks = script.owed_ticks();
+                return Ok(Some(step));
+            }
+            if !self.next_line(storage)? {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn next_line(&mut self, storage: &mut Storage) -> Result<bool, FileSystemError> {
+        let mut reader = FileReader::open(storage, SESSION)?;
+        let mut line = ArrayString::default();
+        let result = reader.skip_lines(storage, self.lines).and_then(|more| {
+            if !more {
+                return Ok(false);
+            }
+            loop {
+                match reader.next_byte(storage)? {
+                    Some(b'\n') => return Ok(true),
+                    Some(b) => line.push_char(b as char),
+                    None => return Ok(line.len() > 0),
+                }
+            }
+        });
+        reader.close(storage)?;
+        self.line = line;
+        self.pos = 0;
+        self.lines += 1;
+        result
+    }
+}
@@ -0,0 +1,436 @@
+use core::fmt::Write;
+
+use simple_interp::ArrayString;
+
+use crate::input::{Keymap, KEYMAPS};
+use crate::scheduler::SchedulerPolicy;
+use crate::screen::{is_drawable, is_extended};
+use crate::session::Session;
+use crate::theme::{Theme, THEMES};
+use crate::timer;
+use crate::wrapping_nav;
+use crate::{LINE_WIDTH, MAX_PATH_BYTES, SCHED_LATENCY};
+
+pub const CONFIG_FILE: &str = "config";
+pub const MAX_AUTOEXEC: usize = 4;
+pub const MAX_TICKS_PER_FRAME: usize = 16;
+pub const NUM_OPTIONS: usize = 11;
+pub const CURSOR_BLINK: usize = 8;
+pub const MAX_PASSCODE: usize = 12;
+const DEFAULT_QUANTUM: usize = 4;
+const MAX_TIME_SLICE: usize = 99;
+const MAX_CURSOR_BLINK: usize = 99;
+const RECORD_EVERY: usize = 18;
+
+// Everything the `config` file can change. The file holds one `key=value`
+// per line, with `#` starting a comment:
+//
+//   theme=amber            one of the names in theme::THEMES; contrast
+//                          is white on black only
+//   scheduler=fair|rr
+//   latency=24             fair scheduler latency, in ticks
+//   quantum=4              round robin time slice, in ticks
+//   ticks_per_frame=2      interpreter ticks run on each timer tick
+//   keymap=us|dvorak|azerty
+//   cursor_blink=8         ticks between cursor blinks, 0 for a steady one
+//   dim=on|off             grey out every window but the focused one
+//   follow_input=on|off    focus a window when its program asks for input,
+//                          and return focus once it has been answered
+//   timeout=500            ticks a program may run before it is stopped,
+//                          0 for no limit
+//   mirror=on|off          copy every program's output to the serial port,
+//                          each line led by its window number
+//   beep=on|off            beep when a program in an unfocused window
+//                          finishes, as well as showing a toast
+//   more=on|off            stop a program in a window after each page of
+//                          output until a key is pressed in the window
+//   charset=cp437|ascii    whether the editor takes code page 437's
+//                          characters above ASCII, from Alt+code, dead keys
+//                          or the character map, or plain ASCII only
+//   motion=full|reduced    reduced keeps cursors steady and drops the
+//                          spinner, blinking titles and flashing input rows
+//   zoom=on|off            space the focused window's program output over
+//                          two rows a line, for projecting
+//   passcode=swordfish     what unlocks the screen after Ctrl+K; without
+//                          one the screen can't be locked
+//   read_only=on|off       refuse every change to the disk, leaving the
+//                          files as they were preloaded; never written
+//                          back, since nothing is saved while it is on
+//   record_every=18        ticks between the frames Ctrl+R records
+//   pit_divisor=65536      the PIT's input clock divided by this is the
+//                          timer rate, 18.2 Hz by default; read at boot
+//   autoexec=hello         run in the next free window at boot
+//   guide=15               shade the editor column after the first 15, so
+//                          lines can be kept that short; 15 is the longest
+//                          string literal, 0 for no guide
+//   session=off|record|replay
+//                          log every key and tick from boot into `session`,
+//                          or play that log back instead of the keyboard
+//
+// Keys the file leaves out keep the values the builder gave.
+#[derive(Copy, Clone)]
+pub struct Settings {
+    pub theme: Theme,
+    pub scheduler: SchedulerPolicy,
+    pub ticks_per_frame: usize,
+    pub keymap: Keymap,
+    pub cursor_blink: usize,
+    pub dim_unfocused: bool,
+    pub follow_input: bool,
+    pub timeout: usize,
+    pub mirror: bool,
+    pub extended_chars: bool,
+    pub reduce_motion: bool,
+    pub zoom: bool,
+    pub beep: bool,
+    pub more: bool,
+    pub read_only: bool,
+    pub passcode: ArrayString<MAX_PASSCODE>,
+    pub record_every: usize,
+    pub pit_divisor: u32,
+    pub autoexec: [ArrayString<MAX_PATH_BYTES>; MAX_AUTOEXEC],
+    pub num_autoexec: usize,
+    pub guide: usize,
+    pub session: Session,
+}
+
+impl Settings {
+    pub fn new(theme: Theme, scheduler: SchedulerPolicy) -> Self {
+        Self {
+            theme,
+            scheduler,
+            ticks_per_frame: 1,
+            keymap: Keymap::default(),
+            cursor_blink: CURSOR_BLINK,
+            dim_unfocused: false,
+            follow_input: false,
+            timeout: 0,
+            mirror: false,
+            extended_chars: true,
+            reduce_motion: false,
+            zoom: false,
+            beep: false,
+            more: false,
+            read_only: false,
+            passcode: ArrayString::default(),
+            record_every: RECORD_EVERY,
+            pit_divisor: timer::DEFAULT_DIVISOR,
+            autoexec: [ArrayString::default(); MAX_AUTOEXEC],
+            num_autoexec: 0,
+            guide: 0,
+            session: Session::Off,
+        }
+    }
+
+    // Applies one line of the config file, returning false if it could not
+    // be understood.
+    pub fn apply(&mut self, line: &str) -> bool {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            return true;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return false;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "theme" => match Theme::named(value) {
+                Some(theme) => self.theme = theme,
+                None => return false,
+            },
+            "scheduler" => match (value, self.scheduler) {
+                ("fair", SchedulerPolicy::Fair { .. })
+                | ("rr", SchedulerPolicy::RoundRobin { .. }) => {}
+                ("fair", _) => {
+                    self.scheduler = SchedulerPolicy::Fair {
+                        latency: SCHED_LATENCY,
+                    }
+                }
+                ("rr", _) => {
+                    self.scheduler = SchedulerPolicy::RoundRobin {
+                        quantum: DEFAULT_QUANTUM,
+                    }
+                }
+                _ => return false,
+            },
+            "latency" => match value.parse() {
+                Ok(latency) if latency > 0 => self.scheduler = SchedulerPolicy::Fair { latency },
+                _ => return false,
+            },
+            "quantum" => match value.parse() {
+                Ok(quantum) if quantum > 0 => {
+                    self.scheduler = SchedulerPolicy::RoundRobin { quantum }
+                }
+                _ => return false,
+            },
+            "ticks_per_frame" => match value.parse() {
+                Ok(ticks) if (1..=MAX_TICKS_PER_FRAME).contains(&ticks) => {
+                    self.ticks_per_frame = ticks
+                }
+                _ => return false,
+            },
+            "keymap" => match Keymap::named(value) {
+                Some(keymap) => self.keymap = keymap,
+                None => return false,
+            },
+            "cursor_blink" => match value.parse() {
+                Ok(ticks) if ticks <= MAX_CURSOR_BLINK => self.cursor_blink = ticks,
+                _ => return false,
+            },
+            "dim" => match value {
+                "on" => self.dim_unfocused = true,
+                "off" => self.dim_unfocused = false,
+                _ => return false,
+            },
+            "follow_input" => match value {
+                "on" => self.follow_input = true,
+                "off" => self.follow_input = false,
+                _ => return false,
+            },
+            "guide" => match value.parse() {
+                Ok(column) if column < LINE_WIDTH => self.guide = column,
+                _ => return false,
+            },
+            "timeout" => match value.parse() {
+                Ok(ticks) => self.timeout = ticks,
+                _ => return false,
+            },
+            "mirror" => match value {
+                "on" => self.mirror = true,
+                "off" => self.mirror = false,
+                _ => return false,
+            },
+            "beep" => match value {
+                "on" => self.beep = true,
+                "off" => self.beep = false,
+                _ => return false,
+            },
+            "more" => match value {
+                "on" => self.more = true,
+                "off" => self.more = false,
+                _ => return false,
+            },
+            "read_only" => match value {
+                "on" => self.read_only = true,
+                "off" => self.read_only = false,
+                _ => return false,
+            },
+            "charset" => match value {
+                "cp437" => self.extended_chars = true,
+                "ascii" => self.extended_chars = false,
+                _ => return false,
+            },
+            "motion" => match value {
+                "full" => self.reduce_motion = false,
+                "reduced" => self.reduce_motion = true,
+                _ => return false,
+            },
+            "zoom" => match value {
+                "on" => self.zoom = true,
+                "off" => self.zoom = false,
+                _ => return false,
+            },
+            "passcode" => {
+                if value.len() > MAX_PASSCODE || !value.chars().all(|c| c.is_ascii_graphic()) {
+                    return false;
+                }
+                self.passcode = ArrayString::default();
+                for c in value.chars() {
+                    self.passcode.push_char(c);
+                }
+            }
+            "record_every" => match value.parse() {
+                Ok(ticks) if ticks > 0 => self.record_every = ticks,
+                _ => return false,
+            },
+            "pit_divisor" => match value.parse() {
+                Ok(divisor) if (timer::MIN_DIVISOR..=timer::DEFAULT_DIVISOR).contains(&divisor) => {
+                    self.pit_divisor = divisor
+                }
+                _ => return false,
+            },
+            "session" => match Session::named(value) {
+                Some(session) => self.session = session,
+                None => return false,
+            },
+            "autoexec" => {
+                if self.num_autoexec == MAX_AUTOEXEC || value.len() > MAX_PATH_BYTES {
+                    return false;
+                }
+                let mut path = ArrayString::default();
+                for c in value.chars() {
+                    path.push_char(c);
+                }
+                self.autoexec[self.num_autoexec] = path;
+                self.num_autoexec += 1;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    // The options the settings window can change, in the order it lists
+    // them. Autoexec programs are only shown there, since a name can't be
+    // picked with the arrow keys.
+    pub fn option_name(&self, option: usize) -> &'static str {
+        match (option, self.scheduler) {
+            (0, _) => "theme",
+            (1, _) => "scheduler",
+            (2, SchedulerPolicy::Fair { .. }) => "latency",
+            (2, SchedulerPolicy::RoundRobin { .. }) => "quantum",
+            (3, _) => "ticks/frame",
+            (4, _) => "keymap",
+            (5, _) => "dim windows",
+            (6, _) => "follow input",
+            (7, _) => "mirror serial",
+            (8, _) => "charset",
+            (9, _) => "motion",
+            _ => "zoom",
+        }
+    }
+
+    pub fn write_value<W: Write>(&self, option: usize, out: &mut W) -> core::fmt::Result {
+        match (option, self.scheduler) {
+            (0, _) => write!(out, "{}", self.theme.name().unwrap_or("custom")),
+            (1, SchedulerPolicy::Fair { .. }) => write!(out, "fair"),
+            (1, SchedulerPolicy::RoundRobin { .. }) => write!(out, "rr"),
+            (2, SchedulerPolicy::Fair { latency }) => write!(out, "{}", latency),
+            (2, SchedulerPolicy::RoundRobin { quantum }) => write!(out, "{}", quantum),
+            (3, _) => write!(out, "{}", self.ticks_per_frame),
+            (4, _) => write!(out, "{}", self.keymap.name()),
+            (5, _) => write!(out, "{}", on_off(self.dim_unfocused)),
+            (6, _) => write!(out, "{}", on_off(self.follow_input)),
+            (7, _) => write!(out, "{}", on_off(self.mirror)),
+            (8, _) => write!(out, "{}", self.charset()),
+            (9, _) => write!(out, "{}", self.motion()),
+            _ => write!(out, "{}", on_off(self.zoom)),
+        }
+    }
+
+    // Moves an option to its next value, or its previous one.
+    pub fn step(&mut self, option: usize, forward: bool) {
+        match option {
+            0 => {
+                let current = THEMES.iter().position(|(_, theme)| *theme == self.theme);
+                let next = match (current, forward) {
+                    (None, _) => 0,
+                    (Some(i), forward) => wrapping_nav::step(i, THEMES.len(), forward),
+                };
+                self.theme = THEMES[next].1;
+            }
+            1 => {
+                self.scheduler = match self.scheduler {
+                    SchedulerPolicy::Fair { .. } => SchedulerPolicy::RoundRobin {
+                        quantum: DEFAULT_QUANTUM,
+                    },
+                    SchedulerPolicy::RoundRobin { .. } => SchedulerPolicy::Fair {
+                        latency: SCHED_LATENCY,
+                    },
+                }
+            }
+            2 => match &mut self.scheduler {
+                SchedulerPolicy::Fair { latency: slice }
+                | SchedulerPolicy::RoundRobin { quantum: slice } => {
+                    *slice = step_within(*slice, 1, MAX_TIME_SLICE, forward)
+                }
+            },
+            3 => {
+                self.ticks_per_frame =
+                    step_within(self.ticks_per_frame, 1, MAX_TICKS_PER_FRAME, forward)
+            }
+            4 => {
+                let i = KEYMAPS.iter().position(|k| *k == self.keymap).unwrap_or(0);
+                self.keymap = KEYMAPS[wrapping_nav::step(i, KEYMAPS.len(), forward)];
+            }
+            5 => self.dim_unfocused = !self.dim_unfocused,
+            6 => self.follow_input = !self.follow_input,
+            7 => self.mirror = !self.mirror,
+            8 => self.extended_chars = !self.extended_chars,
+            9 => self.reduce_motion = !self.reduce_motion,
+            _ => self.zoom = !self.zoom,
+        }
+    }
+
+    // Writes the settings back out in the config file's format.
+    pub fn write_config<W: Write>(&self, out: &mut W) -> core::fmt::Result {
+        if let Some(name) = self.theme.name() {
+            writeln!(out, "theme={}", name)?;
+        }
+        match self.scheduler {
+            SchedulerPolicy::Fair { latency } => writeln!(out, "latency={}", latency)?,
+            SchedulerPolicy::RoundRobin { quantum } => writeln!(out, "quantum={}", quantum)?,
+        }
+        writeln!(out, "ticks_per_frame={}", self.ticks_per_frame)?;
+        writeln!(out, "keymap={}", self.keymap.name())?;
+        writeln!(out, "cursor_blink={}", self.cursor_blink)?;
+        writeln!(out, "dim={}", on_off(self.dim_unfocused))?;
+        writeln!(out, "follow_input={}", on_off(self.follow_input))?;
+        writeln!(out, "timeout={}", self.timeout)?;
+        writeln!(out, "mirror={}", on_off(self.mirror))?;
+        writeln!(out, "beep={}", on_off(self.beep))?;
+        writeln!(out, "more={}", on_off(self.more))?;
+        writeln!(out, "charset={}", self.charset())?;
+        writeln!(out, "motion={}", self.motion())?;
+        writeln!(out, "zoom={}", on_off(self.zoom))?;
+        if self.passcode.len() > 0 {
+            writeln!(out, "passcode={}", self.passcode.as_str().unwrap())?;
+        }
+        writeln!(out, "record_every={}", self.record_every)?;
+        writeln!(out, "pit_divisor={}", self.pit_divisor)?;
+        if self.guide > 0 {
+            writeln!(out, "guide={}", self.guide)?;
+        }
+        if self.session != Session::Off {
+            writeln!(out, "session={}", self.session.name())?;
+        }
+        for path in self.autoexec[..self.num_autoexec].iter() {
+            writeln!(out, "autoexec={}", path.as_str().unwrap())?;
+        }
+        Ok(())
+    }
+
+    // Whether the editor takes a typed character.
+    pub fn typeable(&self, c: char) -> bool {
+        is_drawable(c) || (self.extended_chars && is_extended(c))
+    }
+
+    fn charset(&self) -> &'static str {
+        if self.extended_chars {
+            "cp437"
+        } else {
+            "ascii"
+        }
+    }
+
+    fn motion(&self) -> &'static str {
+        if self.reduce_motion {
+            "reduced"
+        } else {
+            "full"
+        }
+    }
+
+    // Whether text cursors are drawn on the given tick. They show for
+    // `cursor_blink` ticks, then hide for as many, unless motion is reduced.
+    pub fn cursor_shown(&self, tick: u64) -> bool {
+        self.reduce_motion
+            || self.cursor_blink == 0
+            || (tick / self.cursor_blink as u64).is_multiple_of(2)
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+fn step_within(value: usize, min: usize, max: usize, forward: bool) -> usize {
+    if forward {
+        (value + 1).min(max)
+    } else {
+        value.saturating_sub(1).max(min)
+    }
+}
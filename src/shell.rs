@@ -0,0 +1,331 @@
+use core::fmt::Write;
+
+use crate::screen::{plot, Color, ColorCode};
+use file_system_solution::FileSystemError;
+use simple_interp::ArrayString;
+
+use crate::output::Output;
+use crate::storage::Storage;
+use crate::stream::FileReader;
+use crate::wrapping_nav;
+use crate::{LINE_WIDTH, MAX_PATH_BYTES};
+
+const SHELL_ROWS: usize = 10;
+const PROMPT: &str = "> ";
+const HISTORY: usize = 10;
+const PAGE_LINES: usize = SHELL_ROWS - 2;
+const MAX_COMMAND: usize = LINE_WIDTH - PROMPT.len() - 1;
+pub const COMMANDS: &[&str] = &[
+    "help", "ls", "cat", "head", "tail", "echo", "run", "ps", "kill", "renice", "free", "df",
+    "fsck", "defrag", "stats", "mem", "keys", "shutdown", "reboot",
+    #[cfg(feature = "serial")]
+    "send",
+];
+
+// The word Tab is completing, remembered so that pressing it again moves on
+// to the next match. It only carries over while the line still holds the
+// last completion.
+#[derive(Copy, Clone)]
+struct Completion {
+    start: usize,
+    word: ArrayString<LINE_WIDTH>,
+    next: usize,
+    line: ArrayString<LINE_WIDTH>,
+    cursor: usize,
+}
+
+// Lines `line..end` of a file still waiting to be printed. The file is
+// streamed a page at a time and reopened for each page, the way the viewer
+// does, so nothing holds it open while the shell waits for a key.
+#[derive(Copy, Clone)]
+pub struct Page {
+    pub volume: usize,
+    name: ArrayString<MAX_PATH_BYTES>,
+    line: usize,
+    end: usize,
+}
+
+impl Page {
+    pub fn new(volume: usize, name: &str, line: usize, end: usize) -> Self {
+        let mut page_name = ArrayString::default();
+        write!(page_name, "{}", name).unwrap();
+        Self {
+            volume,
+            name: page_name,
+            line,
+            end,
+        }
+    }
+
+    // Prints the next page into `shell`, returning what is left to print.
+    pub fn print(
+        mut self,
+        storage: &mut Storage,
+        shell: &mut Shell,
+    ) -> Result<Option<Page>, FileSystemError> {
+        let mut reader = FileReader::open(storage, self.name.as_str().unwrap())?;
+        let result = self.print_lines(&mut reader, storage, shell);
+        reader.close(storage)?;
+        result.map(|more| if more { Some(self) } else { None })
+    }
+
+    fn print_lines(
+        &mut self,
+        reader: &mut FileReader,
+        storage: &mut Storage,
+        shell: &mut Shell,
+    ) -> Result<bool, FileSystemError> {
+        if !reader.skip_lines(storage, self.line)? {
+            return Ok(false);
+        }
+        let stop = self.end.min(self.line + PAGE_LINES);
+        let mut line_start = true;
+        while self.line < stop {
+            match reader.next_byte(storage)? {
+                Some(b) => {
+                    shell.write_char(b as char).unwrap();
+                    line_start = b == b'\n';
+                    if line_start {
+                        self.line += 1;
+                    }
+                }
+                None => {
+                    if !line_start {
+                        writeln!(shell).unwrap();
+                    }
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(self.line < self.end && reader.next_byte(storage)?.is_some())
+    }
+}
+
+#[derive(Default)]
+pub struct Shell {
+    command: ArrayString<LINE_WIDTH>,
+    cursor: usize,
+    history: [ArrayString<LINE_WIDTH>; HISTORY],
+    history_len: usize,
+    // How many commands back Up has gone; 0 while editing a new one.
+    recalled: usize,
+    completion: Option<Completion>,
+    // Set while a file is being paged, until the user asks for the rest.
+    pub page: Option<Page>,
+    output: Output,
+}
+
+impl Shell {
+    // Inserts at the cursor; a backspace deletes the character before it.
+    pub fn push_char(&mut self, c: char) {
+        let command = self.command;
+        let command = command.as_str().unwrap();
+        if c == '\u{0008}' {
+            if self.cursor > 0 {
+                self.command = splice(command, self.cursor - 1, self.cursor, "");
+                self.cursor -= 1;
+            }
+        } else if command.len() < MAX_COMMAND {
+            let mut inserted = [0; 4];
+            self.command = splice(
+                command,
+                self.cursor,
+                self.cursor,
+                c.encode_utf8(&mut inserted),
+            );
+            self.cursor += 1;
+        }
+    }
+
+    pub fn move_cursor_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_cursor_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.command.len());
+    }
+
+    pub fn history_back(&mut self) {
+        if self.recalled < self.history_len {
+            self.recalled += 1;
+            self.recall();
+        }
+    }
+
+    pub fn history_forward(&mut self) {
+        if self.recalled > 0 {
+            self.recalled -= 1;
+            self.recall();
+        }
+    }
+
+    pub fn cancel(&mut self) {
+        self.page = None;
+        let command = self.take_command();
+        writeln!(self, "{}{}^C", PROMPT, command.as_str().unwrap()).unwrap();
+    }
+
+    pub fn clear(&mut self) {
+        self.output = Output::default();
+    }
+
+    pub fn take_command(&mut self) -> ArrayString<LINE_WIDTH> {
+        let command = self.command;
+        self.command = ArrayString::default();
+        self.cursor = 0;
+        self.recalled = 0;
+        command
+    }
+
+    // Adds a command to the history, most recent first, unless it repeats
+    // the last one.
+    pub fn remember(&mut self, command: ArrayString<LINE_WIDTH>) {
+        if command.len() == 0 || (self.history_len > 0 && self.history[0] == command) {
+            return;
+        }
+        self.history.copy_within(..HISTORY - 1, 1);
+        self.history[0] = command;
+        self.history_len = (self.history_len + 1).min(HISTORY);
+    }
+
+    // The word before the cursor, and whether it is the command name rather
+    // than one of its arguments.
+    pub fn completion_word(&self) -> (ArrayString<LINE_WIDTH>, bool) {
+        let completion = self.pending_completion();
+        let before = &self.command.as_str().unwrap()[..completion.start];
+        (completion.word, before.trim().is_empty())
+    }
+
+    // Replaces the word before the cursor with the next of `names` that
+    // extends it, keeping the word's first `keep` bytes, such as a volume
+    // prefix, in front of the name.
+    pub fn complete<'a>(&mut self, keep: usize, names: impl Iterator<Item = &'a str> + Clone) {
+        let mut completion = self.pending_completion();
+        let word = completion.word;
+        let (kept, partial) = word.as_str().unwrap().split_at(keep);
+        let mut matches = names.filter(|name| name.starts_with(partial));
+        let num_matches = matches.clone().count();
+        if let Some(name) = matches.nth(wrapping_nav::wrap(completion.next, num_matches)) {
+            let mut replacement: ArrayString<LINE_WIDTH> = ArrayString::default();
+            write!(replacement, "{}{}", kept, name).unwrap();
+            let command = completion.line;
+            let command = command.as_str().unwrap();
+            let end = completion.cursor;
+            if command.len() - (end - completion.start) + replacement.len() <= MAX_COMMAND {
+                self.command = splice(
+                    command,
+                    completion.start,
+                    end,
+                    replacement.as_str().unwrap(),
+                );
+                self.cursor = completion.start + replacement.len();
+            }
+            completion.next += 1;
+            completion.line = self.command;
+            completion.cursor = self.cursor;
+            self.completion = Some(completion);
+        }
+    }
+
+    fn pending_completion(&self) -> Completion {
+        match self.completion {
+            Some(completion)
+                if completion.line == self.command && completion.cursor == self.cursor =>
+            {
+                completion
+            }
+            _ => {
+                let before = &self.command.as_str().unwrap()[..self.cursor];
+                let start = before.rfind(' ').map_or(0, |i| i + 1);
+                let mut word = ArrayString::default();
+                write!(word, "{}", &before[start..]).unwrap();
+                Completion {
+                    start,
+                    word,
+                    next: 0,
+                    line: self.command,
+                    cursor: self.cursor,
+                }
+            }
+        }
+    }
+
+    fn recall(&mut self) {
+        self.command = if self.recalled == 0 {
+            ArrayString::default()
+        } else {
+            self.history[self.recalled - 1]
+        };
+        self.cursor = self.command.len();
+    }
+
+    pub fn draw(&self, x: usize, y: usize, cursor: bool, text: ColorCode) {
+        let output_rows = SHELL_ROWS - 1;
+        for row in 0..output_rows {
+            draw_row(self.output.line(output_rows - row), "", x, y + row, text);
+        }
+        draw_row(
+            PROMPT,
+            self.command.as_str().unwrap(),
+            x,
+            y + output_rows,
+            text,
+        );
+        if !cursor {
+            return;
+        }
+        let cursor = self.command.as_str().unwrap()[self.cursor..]
+            .chars()
+            .next()
+            .unwrap_or(' ');
+        plot(
+            cursor,
+            x + PROMPT.len() + self.cursor,
+            y + output_rows,
+            ColorCode::new(Color::Black, Color::LightCyan),
+        );
+    }
+}
+
+// Splits a command line into the command and the files named by any
+// `< input` and `> output` after it. Returns None if a redirect is missing its
+// file or names more than one.
+pub fn split_redirects(line: &str) -> Option<(&str, Option<&str>, Option<&str>)> {
+    let (command, mut rest) = line.split_at(line.find(['<', '>']).unwrap_or(line.len()));
+    let mut input = None;
+    let mut output = None;
+    while let Some(op) = rest.chars().next() {
+        rest = &rest[1..];
+        let end = rest.find(['<', '>']).unwrap_or(rest.len());
+        let path = rest[..end].trim();
+        if path.is_empty() || path.contains(' ') {
+            return None;
+        }
+        if op == '<' {
+            input = Some(path);
+        } else {
+            output = Some(path);
+        }
+        rest = &rest[end..];
+    }
+    Some((command, input, output))
+}
+
+fn splice(text: &str, start: usize, end: usize, inserted: &str) -> ArrayString<LINE_WIDTH> {
+    let mut result = ArrayString::default();
+    write!(result, "{}{}{}", &text[..start], inserted, &text[end..]).unwrap();
+    result
+}
+
+fn draw_row(prefix: &str, text: &str, x: usize, y: usize, color: ColorCode) {
+    let mut chars = prefix.chars().chain(text.chars());
+    for col in 0..LINE_WIDTH {
+        plot(chars.next().unwrap_or(' '), x + col, y, color);
+    }
+}
+
+impl Write for Shell {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.output.write_str(s)
+    }
+}
@@ -0,0 +1,182 @@
+// The shell's `shutdown` and `reboot`: everything that would be lost is put
+// away, then a farewell screen is drawn and the machine is asked to power
+// off or reset. Kernel builds write QEMU's ACPI and debug-exit ports, or
+// the keyboard controller's reset line; builds with the `std` feature only
+// remember that they were asked, so host tests can check. A warm reboot
+// resets nothing but the interface, and the volumes come through it as
+// they were.
+
+use core::fmt::Write;
+
+use simple_interp::ArrayString;
+
+use crate::process::ProcessState;
+use crate::screen::{self, draw_box, plot, plot_str, BUFFER_HEIGHT, BUFFER_WIDTH};
+use crate::SwimInterface;
+
+#[cfg(not(feature = "std"))]
+use crate::port::{outb, outw};
+
+const WIDTH: usize = 44;
+const HEIGHT: usize = 9;
+
+// What putting everything away came to, for the farewell screen.
+#[derive(Default)]
+struct Summary {
+    counts: ArrayString<WIDTH>,
+    logged: bool,
+}
+
+impl<const N: usize> SwimInterface<N> {
+    // Powers off once everything is put away. Nothing runs after it: ticks
+    // and keys are ignored, which leaves the farewell screen up should the
+    // machine not turn off.
+    pub(crate) fn shut_down(&mut self) {
+        let summary = self.put_away();
+        self.shut_down = true;
+        self.draw_farewell(
+            "SWIM has shut down.",
+            &summary,
+            "It is safe to turn the machine off.",
+        );
+        screen::present();
+        power_off();
+    }
+
+    // Resets the machine once everything is put away, which boots the kernel
+    // image again with its files. A warm reboot instead starts the interface
+    // over in place, keeping both volumes as they are.
+    pub(crate) fn reboot(&mut self, warm: bool) {
+        let summary = self.put_away();
+        if warm {
+            let mounts = core::mem::take(&mut self.mounts);
+            let (theme, scheduler) = (self.settings.theme, self.settings.scheduler);
+            // The volumes stay read-only once made so; the settings follow.
+            let read_only = self.settings.read_only;
+            *self = Self::boot(mounts, self.samples, theme, scheduler);
+            self.settings.read_only |= read_only;
+            self.redraw_all();
+            self.notifier
+                .notify("warm boot: the files were kept", false);
+        } else {
+            self.shut_down = true;
+            self.draw_farewell(
+                "SWIM is restarting.",
+                &summary,
+                "The disk starts over from the image.",
+            );
+            screen::present();
+            reset();
+        }
+    }
+
+    // Finishes the saves under way, stops every program and writes out what
+    // the session log still holds. Returns what was done, for the farewell.
+    fn put_away(&mut self) -> Summary {
+        let saves = self.saves.iter().flatten().count();
+        while self.saves.iter().any(Option::is_some) {
+            self.advance_saves();
+        }
+        self.mounts.set_opener(None);
+        let mut programs = 0;
+        loop {
+            let Some((pid, running)) = self
+                .processes
+                .iter()
+                .next()
+                .map(|(pid, process)| (pid, process.state != ProcessState::Finished))
+            else {
+                break;
+            };
+            programs += usize::from(running);
+            self.kill(pid);
+        }
+        self.session_replay = None;
+        let logged = match self.session_log.take() {
+            Some(mut log) => log.finish(self.mounts.volume(log.volume)).is_ok(),
+            None => false,
+        };
+        let mut summary = Summary::default();
+        write!(
+            summary.counts,
+            "{} saves finished, {} programs stopped",
+            saves, programs
+        )
+        .unwrap();
+        summary.logged = logged;
+        summary
+    }
+
+    fn draw_farewell(&self, title: &str, summary: &Summary, advice: &str) {
+        for row in 0..BUFFER_HEIGHT {
+            for col in 0..BUFFER_WIDTH {
+                plot(' ', col, row, self.palette.blank);
+            }
+        }
+        let x = (BUFFER_WIDTH - WIDTH) / 2;
+        let y = (BUFFER_HEIGHT - HEIGHT) / 2;
+        draw_box(x, y, WIDTH, HEIGHT, self.palette.border);
+        plot_str(title, x + 3, y + 2, self.palette.text);
+        plot_str(
+            summary.counts.as_str().unwrap(),
+            x + 3,
+            y + 4,
+            self.palette.text,
+        );
+        if summary.logged {
+            plot_str("session log written", x + 3, y + 5, self.palette.text);
+        }
+        plot_str(advice, x + 3, y + 6, self.palette.text);
+    }
+}
+
+// Tries QEMU's ACPI power button on the port newer machine types use, then
+// the older Bochs one, then isa-debug-exit for a QEMU started with
+// `-device isa-debug-exit,iobase=0xf4,iosize=0x04`. On anything else none
+// of them does anything and the kernel just goes idle.
+#[cfg(not(feature = "std"))]
+fn power_off() {
+    unsafe {
+        outw(0x604, 0x2000);
+        outw(0xb004, 0x2000);
+        outb(0xf4, 0);
+    }
+}
+
+// Pulses the CPU reset line through the keyboard controller, which QEMU and
+// most PCs answer by booting again.
+#[cfg(not(feature = "std"))]
+fn reset() {
+    unsafe { outb(0x64, 0xfe) };
+}
+
+#[cfg(feature = "std")]
+pub use fake::*;
+
+#[cfg(feature = "std")]
+mod fake {
+    use std::cell::Cell;
+
+    thread_local! {
+        static POWERED_OFF: Cell<bool> = const { Cell::new(false) };
+        static RESET: Cell<bool> = const { Cell::new(false) };
+    }
+
+    pub(super) fn power_off() {
+        POWERED_OFF.with(|p| p.set(true));
+    }
+
+    pub(super) fn reset() {
+        RESET.with(|r| r.set(true));
+    }
+
+    // Whether the calling thread's machine was asked to power off.
+    pub fn powered_off() -> bool {
+        POWERED_OFF.with(|p| p.get())
+    }
+
+    // Whether the calling thread's machine was asked to reset.
+    pub fn was_reset() -> bool {
+        RESET.with(|r| r.get())
+    }
+}
@@ -0,0 +1,50 @@
+use crate::mount::NUM_VOLUMES;
+use crate::process::MAX_PROCESSES;
+use crate::{fd_held, SwimInterface};
+
+// What tests/soak.rs checks after every key and tick it feeds in: editor
+// cursors stay inside their documents, every open descriptor belongs to a
+// running program or a save, and no process's vruntime goes backwards.
+#[derive(Default)]
+pub struct Invariants {
+    // Each process's vruntime at the last check, by pid, with the frame it
+    // started on so a pid handed to a new process starts over.
+    vruntimes: [Option<(u64, usize)>; MAX_PROCESSES],
+    pub checks: usize,
+}
+
+impl Invariants {
+    pub fn check<const N: usize>(&mut self, swim: &mut SwimInterface<N>) -> Result<(), String> {
+        self.checks += 1;
+        for (i, window) in swim.windows.iter().enumerate() {
+            if window
+                .editor
+                .as_ref()
+                .is_some_and(|e| !e.cursor_in_bounds())
+            {
+                return Err(format!("window {}'s cursor left its document", i + 1));
+            }
+        }
+        for volume in 0..NUM_VOLUMES {
+            let (processes, saves) = (&swim.processes, &swim.saves);
+            let held = |fd| fd_held(processes, saves, volume, fd);
+            if let Some(fd) = swim.mounts.volume(volume).unheld(held) {
+                return Err(format!("fd {fd} on volume {volume} was left open"));
+            }
+        }
+        let mut seen = [None; MAX_PROCESSES];
+        for (pid, process) in swim.processes.iter() {
+            if let Some((started, vruntime)) = self.vruntimes[pid] {
+                if started == process.started && process.vruntime < vruntime {
+                    return Err(format!(
+                        "pid {pid}'s vruntime went from {vruntime} to {}",
+                        process.vruntime
+                    ));
+                }
+            }
+            seen[pid] = Some((process.started, process.vruntime));
+        }
+        self.vruntimes = seen;
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+// The PC speaker. Kernel builds drive it from channel 2 of the PIT, gated
+// through port 0x61; builds with the `std` feature only remember the tone,
+// so host tests can check what would be heard.
+
+#[cfg(not(feature = "std"))]
+use crate::port::{inb, outb};
+#[cfg(not(feature = "std"))]
+use crate::timer::{PIT_COMMAND, PIT_HZ};
+
+#[cfg(not(feature = "std"))]
+const PIT_CHANNEL_2: u16 = 0x42;
+#[cfg(not(feature = "std"))]
+const SPEAKER_GATE: u16 = 0x61;
+
+// Sounds `hz` until stopped.
+#[cfg(not(feature = "std"))]
+pub fn play(hz: u32) {
+    let divisor = PIT_HZ / hz;
+    unsafe {
+        // Channel 2, low byte then high byte, square wave.
+        outb(PIT_COMMAND, 0xB6);
+        outb(PIT_CHANNEL_2, divisor as u8);
+        outb(PIT_CHANNEL_2, (divisor >> 8) as u8);
+        outb(SPEAKER_GATE, inb(SPEAKER_GATE) | 0x03);
+    }
+}
+
+#[cfg(not(feature = "std"))]
+pub fn stop() {
+    unsafe {
+        outb(SPEAKER_GATE, inb(SPEAKER_GATE) & !0x03);
+    }
+}
+
+#[cfg(feature = "std")]
+pub use fake::*;
+
+#[cfg(feature = "std")]
+mod fake {
+    use std::cell::Cell;
+
+    thread_local! {
+        static TONE: Cell<Option<u32>> = const { Cell::new(None) };
+    }
+
+    pub fn play(hz: u32) {
+        TONE.with(|tone| tone.set(Some(hz)));
+    }
+
+    pub fn stop() {
+        TONE.with(|tone| tone.set(None));
+    }
+
+    // The tone the calling thread's speaker is sounding, if any.
+    pub fn speaker_tone() -> Option<u32> {
+        TONE.with(|tone| tone.get())
+    }
+}
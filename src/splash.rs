@@ -0,0 +1,38 @@
+use crate::screen::{draw_box, plot_str, BUFFER_HEIGHT, BUFFER_WIDTH};
+use crate::theme::Palette;
+
+// About two seconds at the timer's 18.2 Hz.
+pub const SPLASH_TICKS: usize = 36;
+const WIDTH: usize = 44;
+const HEIGHT: usize = 13;
+const LOGO: [&str; 4] = [
+    " ___ __      __ ___  __  __",
+    "/ __|\\ \\    / /|_ _||  \\/  |",
+    "\\__ \\ \\ \\/\\/ /  | | | |\\/| |",
+    "|___/  \\_/\\_/  |___||_|  |_|",
+];
+const HINTS: [&str; 2] = [
+    "F1-F4 windows  F5 new file",
+    "F6 close  e:edit r:run s:shell",
+];
+
+// The boot screen, shown until SPLASH_TICKS run out or a key is pressed.
+pub fn draw_splash(palette: &Palette) {
+    let x = (BUFFER_WIDTH - WIDTH) / 2;
+    let y = (BUFFER_HEIGHT - HEIGHT) / 2;
+    draw_box(x, y, WIDTH, HEIGHT, palette.border);
+    for (row, line) in LOGO.iter().enumerate() {
+        plot_str(line, x + 8, y + 1 + row, palette.text);
+    }
+    plot_str("Simple Window Interface", x + 3, y + 6, palette.text);
+    plot_str(
+        concat!("v", env!("CARGO_PKG_VERSION")),
+        x + 30,
+        y + 6,
+        palette.text,
+    );
+    for (row, line) in HINTS.iter().enumerate() {
+        plot_str(line, x + 3, y + 8 + row, palette.border);
+    }
+    plot_str("press any key, r: read-only", x + 3, y + 11, palette.text);
+}
@@ -0,0 +1,111 @@
+#[cfg(feature = "shell")]
+use core::fmt::Write;
+
+#[cfg(feature = "shell")]
+use crate::format::Compact;
+
+// The stack audit. Built with the `stack_audit` feature, the kernel paints
+// the AUDITED bytes below the boot stack pointer with a pattern at startup;
+// the lowest word since overwritten marks the deepest the stack has gone.
+// Interrupt handlers run on the same stack, so their frames count too. The
+// bottom word is the guard: once it is gone the stack has outgrown the
+// audit, and the figure is only a floor. Builds with the `std` feature keep
+// a depth that host tests set by hand.
+#[cfg(any(not(feature = "std"), feature = "shell"))]
+pub const AUDITED: usize = 128 * 1024;
+
+#[cfg(feature = "shell")]
+#[derive(Copy, Clone)]
+pub struct StackUse {
+    pub used: usize,
+    pub overflowed: bool,
+}
+
+#[cfg(not(feature = "std"))]
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(not(feature = "std"))]
+const PATTERN: u64 = 0xA5A5_A5A5_A5A5_A5A5;
+// Left unpainted below the caller's stack pointer, for paint's own frame.
+#[cfg(not(feature = "std"))]
+const SLACK: usize = 512;
+
+// The top of the painted region, or 0 before painting.
+#[cfg(not(feature = "std"))]
+static TOP: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(not(feature = "std"))]
+pub fn paint() {
+    let sp: usize;
+    unsafe { core::arch::asm!("mov {}, rsp", out(reg) sp) };
+    let top = (sp - SLACK) & !7;
+    for addr in (top - AUDITED..top).step_by(8) {
+        unsafe { core::ptr::write_volatile(addr as *mut u64, PATTERN) };
+    }
+    TOP.store(top, Ordering::Relaxed);
+}
+
+// How deep the stack has been since painting, or None if it never was.
+#[cfg(all(not(feature = "std"), feature = "shell"))]
+pub fn high_water() -> Option<StackUse> {
+    let top = TOP.load(Ordering::Relaxed);
+    if top == 0 {
+        return None;
+    }
+    let untouched = (top - AUDITED..top)
+        .step_by(8)
+        .take_while(|addr| unsafe { core::ptr::read_volatile(*addr as *const u64) } == PATTERN)
+        .count();
+    Some(StackUse {
+        used: AUDITED - untouched * 8,
+        overflowed: untouched == 0,
+    })
+}
+
+#[cfg(feature = "std")]
+pub use fake::*;
+
+#[cfg(feature = "std")]
+mod fake {
+    #[cfg(feature = "shell")]
+    use super::{StackUse, AUDITED};
+    use std::cell::Cell;
+
+    thread_local! {
+        static USED: Cell<Option<usize>> = const { Cell::new(None) };
+    }
+
+    pub fn paint() {
+        USED.with(|used| used.set(Some(0)));
+    }
+
+    #[cfg(feature = "shell")]
+    pub fn high_water() -> Option<StackUse> {
+        USED.with(|used| used.get()).map(|used| StackUse {
+            used: used.min(AUDITED),
+            overflowed: used >= AUDITED,
+        })
+    }
+
+    // Has the calling thread's painted stack reached `bytes` deep.
+    pub fn use_stack(bytes: usize) {
+        USED.with(|used| used.set(used.get().map(|used| used.max(bytes))));
+    }
+}
+
+// A line for the shell's `stats`, when the stack was painted.
+#[cfg(feature = "shell")]
+pub fn write_report<W: Write>(out: &mut W) -> core::fmt::Result {
+    match high_water() {
+        Some(stack) if stack.overflowed => {
+            writeln!(out, "stack   over {}, guard hit", Compact(AUDITED as u64))
+        }
+        Some(stack) => writeln!(
+            out,
+            "stack   {:>5} / {}",
+            Compact(stack.used as u64),
+            Compact(AUDITED as u64)
+        ),
+        None => Ok(()),
+    }
+}
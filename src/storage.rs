@@ -0,0 +1,973 @@
+use file_system_solution::{FileSystem, FileSystemError};
+use ramdisk::RamDisk;
+
+use crate::block_map::BlockMap;
+use crate::cipher::{self, Keystream};
+use crate::output::SCROLLBACK;
+use crate::{
+    BLOCK_SIZE, LINE_WIDTH, MAX_FILENAME_BYTES, MAX_FILES_STORED, MAX_FILE_BLOCKS, MAX_FILE_BYTES,
+    MAX_OPEN, NUM_BLOCKS,
+};
+
+pub const METADATA_FILE: &str = ".meta";
+const APPEND_SCRATCH: &str = ".append";
+pub const SAVE_SCRATCH: &str = ".save";
+// A name, its flags, a protected file's passphrase check, how its last
+// run went (a status byte, then ticks and instructions as four
+// little-endian bytes each), and where its editor was left: the cursor's
+// row and column and the top row in view, a byte each.
+const RECORD_SIZE: usize = MAX_FILENAME_BYTES + 14;
+// The metadata file starts with a header: a zero byte, which no name starts
+// with and so tells it from the bare records older builds wrote, then
+// "SWM", the format version and the size of a record. A later version only
+// adds fields to the end of a record, so a build reads the fields it knows
+// from records of any size and leaves the rest as if blank.
+const METADATA_HEADER: usize = 6;
+const METADATA_MAGIC: [u8; 4] = *b"\0SWM";
+// Version 1 is the bare records, from before there was a header.
+const METADATA_VERSION: u8 = 2;
+
+pub const DELETED: u8 = 1;
+pub const STARRED: u8 = 2;
+pub const PROTECTED: u8 = 4;
+
+// A window's output file each for the first MAX_WINDOW_FILES windows, and
+// room for a few generated ones.
+pub const MAX_WINDOW_FILES: usize = 8;
+const MAX_VIRTUAL: usize = MAX_WINDOW_FILES + 4;
+// Enough for a window's whole scrollback.
+const VIRTUAL_BYTES: usize = SCROLLBACK * (LINE_WIDTH + 1);
+// Virtual names may be longer than the disk's, such as `stats/sched`.
+const VIRTUAL_NAME_BYTES: usize = MAX_FILENAME_BYTES + 2;
+
+// `key` is a protected file's keystream seed once its passphrase has been
+// given; it is only ever held in memory.
+#[derive(Copy, Clone)]
+struct FileRecord {
+    name: [u8; MAX_FILENAME_BYTES],
+    flags: u8,
+    check: u8,
+    key: Option<u32>,
+    run: Option<RunStats>,
+    place: EditorPlace,
+}
+
+// Where an editor on a file was left, for the next one to open there. The
+// start of the file, the default, is never stored.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct EditorPlace {
+    pub row: u8,
+    pub col: u8,
+    pub top: u8,
+}
+
+// How a program's last run went, kept with its file; see record_run.
+// `ticks` are the timer ticks from start to end, and `instructions` the
+// interpreter ticks it was given, one statement each.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RunStats {
+    pub ticks: u32,
+    pub instructions: u32,
+    pub status: RunStatus,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RunStatus {
+    Finished,
+    TimedOut,
+    Killed,
+}
+
+impl FileRecord {
+    fn blank(name: [u8; MAX_FILENAME_BYTES]) -> Self {
+        Self {
+            name,
+            flags: 0,
+            check: 0,
+            key: None,
+            run: None,
+            place: EditorPlace::default(),
+        }
+    }
+}
+
+impl RunStatus {
+    // As stored in the metadata, where 0 means no run.
+    fn byte(self) -> u8 {
+        match self {
+            RunStatus::Finished => 1,
+            RunStatus::TimedOut => 2,
+            RunStatus::Killed => 3,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            1 => Some(RunStatus::Finished),
+            2 => Some(RunStatus::TimedOut),
+            3 => Some(RunStatus::Killed),
+            _ => None,
+        }
+    }
+}
+
+impl core::fmt::Display for RunStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.pad(match self {
+            RunStatus::Finished => "ok",
+            RunStatus::TimedOut => "timeout",
+            RunStatus::Killed => "killed",
+        })
+    }
+}
+
+// A read-only file kept in memory rather than on the disk, its contents
+// put in by publish. Characters are stored a byte each, as the editor does.
+// A generated file is only made when something is about to read it, so it
+// can be dropped to make room once nothing has it open.
+#[derive(Copy, Clone)]
+struct VirtualFile {
+    name: [u8; VIRTUAL_NAME_BYTES],
+    contents: [u8; VIRTUAL_BYTES],
+    len: usize,
+    generated: bool,
+}
+
+impl core::fmt::Write for VirtualFile {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for c in s.chars() {
+            if self.len < VIRTUAL_BYTES {
+                self.contents[self.len] = u8::try_from(c).unwrap_or(b'?');
+                self.len += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Writer {
+    fd: usize,
+    name: [u8; MAX_FILENAME_BYTES],
+    size: usize,
+}
+
+// The underlying FileSystem has no way to remove a directory entry, so a
+// deleted file is truncated to release its blocks and then hidden through
+// the metadata table. Creating a file with the same name brings it back.
+//
+// A protected file's bytes are obfuscated on the disk. Once its passphrase
+// is given, descriptors opened on it carry a keystream that reads and
+// writes pass through, so everything above Storage sees plain bytes; until
+// then they see what is stored, which is how defrag moves a locked file.
+//
+// Each descriptor the file system hands out remembers the window the kernel
+// was acting for when it was opened; see set_opener. One still open at the
+// end of a tick that nothing holds is stale, and is closed to make room if
+// an open later runs out of descriptors.
+//
+// Virtual files, such as `win1.out` with a window's output, sit in front of
+// the disk: reading one by name finds it before the file system is asked,
+// and nothing can be written under its name. Their descriptors start at
+// MAX_OPEN, above any the file system hands out. They are left out of
+// listings, since they take no blocks and have no metadata.
+pub struct Storage {
+    filesystem: FileSystem<
+        MAX_OPEN,
+        BLOCK_SIZE,
+        NUM_BLOCKS,
+        MAX_FILE_BLOCKS,
+        MAX_FILE_BYTES,
+        MAX_FILES_STORED,
+        MAX_FILENAME_BYTES,
+    >,
+    records: [Option<FileRecord>; MAX_FILES_STORED],
+    blocks: BlockMap,
+    writers: [Option<Writer>; MAX_OPEN],
+    streams: [Option<(usize, Keystream)>; MAX_OPEN],
+    virtuals: [Option<VirtualFile>; MAX_VIRTUAL],
+    // For each open virtual descriptor, which file and how far it has read.
+    virtual_reads: [Option<(usize, usize)>; MAX_OPEN],
+    openers: [Option<Opener>; MAX_OPEN],
+    opener: Option<usize>,
+    read_only: bool,
+}
+
+#[derive(Copy, Clone)]
+struct Opener {
+    window: Option<usize>,
+    stale: bool,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        let mut storage = Self {
+            filesystem: FileSystem::new(RamDisk::new()),
+            records: [None; MAX_FILES_STORED],
+            blocks: BlockMap::default(),
+            writers: [None; MAX_OPEN],
+            streams: [None; MAX_OPEN],
+            virtuals: [None; MAX_VIRTUAL],
+            virtual_reads: [None; MAX_OPEN],
+            openers: [None; MAX_OPEN],
+            opener: None,
+            read_only: false,
+        };
+        storage.load_metadata();
+        storage
+    }
+}
+
+impl Storage {
+    // Refuses, from then on, anything that would change the disk.
+    pub fn set_read_only(&mut self) {
+        self.read_only = true;
+    }
+
+    pub fn open_read(&mut self, filename: &str) -> Result<usize, FileSystemError> {
+        if let Some(i) = self.find_virtual(filename) {
+            let slot = self.virtual_reads.iter().position(Option::is_none);
+            let slot = slot.ok_or(FileSystemError::TooManyOpen)?;
+            self.virtual_reads[slot] = Some((i, 0));
+            return Ok(MAX_OPEN + slot);
+        }
+        if self.flags(filename) & DELETED != 0 {
+            return Err(FileSystemError::FileNotFound);
+        }
+        let fd = match self.filesystem.open_read(filename) {
+            Err(FileSystemError::TooManyOpen) if self.close_stale() => {
+                self.filesystem.open_read(filename)?
+            }
+            result => result?,
+        };
+        self.opened(fd);
+        self.attach_stream(filename, fd);
+        Ok(fd)
+    }
+
+    // Who the descriptors opened from now on are for: a window, or the
+    // kernel itself.
+    pub fn set_opener(&mut self, window: Option<usize>) {
+        self.opener = window;
+    }
+
+    fn opened(&mut self, fd: usize) {
+        self.openers[fd] = Some(Opener {
+            window: self.opener,
+            stale: false,
+        });
+    }
+
+    // Marks every open descriptor that `held` doesn't claim as stale, and
+    // returns the first one newly found, with the window it was opened for.
+    pub fn find_leak(&mut self, held: impl Fn(usize) -> bool) -> Option<(usize, Option<usize>)> {
+        let mut leak = None;
+        for (fd, opener) in self.openers.iter_mut().enumerate() {
+            if let Some(opener) = opener.as_mut().filter(|o| !o.stale && !held(fd)) {
+                opener.stale = true;
+                leak = leak.or(Some((fd, opener.window)));
+            }
+        }
+        leak
+    }
+
+    // The first open descriptor that `held` doesn't claim, leaving it
+    // unmarked, unlike find_leak.
+    #[cfg(feature = "soak")]
+    pub fn unheld(&self, held: impl Fn(usize) -> bool) -> Option<usize> {
+        (0..MAX_OPEN).find(|&fd| self.openers[fd].is_some() && !held(fd))
+    }
+
+    // Closes the descriptors opened for `window` that `held` doesn't claim,
+    // returning how many there were.
+    pub fn close_window(&mut self, window: usize, held: impl Fn(usize) -> bool) -> usize {
+        let mut closed = 0;
+        for fd in 0..MAX_OPEN {
+            if self.openers[fd].is_some_and(|o| o.window == Some(window)) && !held(fd) {
+                self.close(fd).ok();
+                closed += 1;
+            }
+        }
+        closed
+    }
+
+    // Closes the stale descriptors, returning whether there were any.
+    fn close_stale(&mut self) -> bool {
+        let mut closed = false;
+        for fd in 0..MAX_OPEN {
+            if self.openers[fd].is_some_and(|o| o.stale) {
+                self.close(fd).ok();
+                closed = true;
+            }
+        }
+        closed
+    }
+
+    pub fn open_create(&mut self, filename: &str) -> Result<usize, FileSystemError> {
+        let fd = self.create(filename)?;
+        if self.flags(filename) & DELETED != 0 {
+            self.set_flag(filename, DELETED, false)?;
+        }
+        self.attach_stream(filename, fd);
+        Ok(fd)
+    }
+
+    fn attach_stream(&mut self, filename: &str, fd: usize) {
+        let key = self
+            .find_record(&name_bytes(filename))
+            .and_then(|i| self.records[i])
+            .filter(|r| r.flags & PROTECTED != 0)
+            .and_then(|r| r.key);
+        if let (Some(key), Some(slot)) = (key, self.streams.iter_mut().find(|s| s.is_none())) {
+            *slot = Some((fd, Keystream::new(key)));
+        }
+    }
+
+    fn stream(&mut self, fd: usize) -> Option<&mut Keystream> {
+        self.streams
+            .iter_mut()
+            .flatten()
+            .find(|(stream_fd, _)| *stream_fd == fd)
+            .map(|(_, stream)| stream)
+    }
+
+    // True for a protected file whose passphrase hasn't been given yet.
+    pub fn is_locked(&self, filename: &str) -> bool {
+        match self
+            .find_record(&name_bytes(filename))
+            .and_then(|i| self.records[i])
+        {
+            Some(record) => record.flags & PROTECTED != 0 && record.key.is_none(),
+            None => false,
+        }
+    }
+
+    // Remembers a protected file's passphrase, if it is the right one.
+    pub fn unlock(&mut self, filename: &str, passphrase: &str) -> bool {
+        let key = cipher::key(passphrase);
+        match self.find_record(&name_bytes(filename)) {
+            Some(i) => match &mut self.records[i] {
+                Some(record) if record.flags & PROTECTED != 0 => {
+                    if record.check == cipher::check(key) {
+                        record.key = Some(key);
+                    }
+                    record.key == Some(key)
+                }
+                _ => false,
+            },
+            None => false,
+        }
+    }
+
+    // Obfuscates a plain file under a passphrase, rewriting it through a
+    // scratch copy the way appending does.
+    pub fn protect(&mut self, filename: &str, passphrase: &str) -> Result<(), FileSystemError> {
+        self.copy(filename, APPEND_SCRATCH)?;
+        let key = cipher::key(passphrase);
+        let flags = self.flags(filename) | PROTECTED;
+        self.write_record(filename, flags, cipher::check(key), Some(key))?;
+        let result = self.copy(APPEND_SCRATCH, filename);
+        self.truncate(APPEND_SCRATCH)?;
+        result
+    }
+
+    // Stores an unlocked protected file plainly again.
+    pub fn unprotect(&mut self, filename: &str) -> Result<(), FileSystemError> {
+        self.copy(filename, APPEND_SCRATCH)?;
+        self.set_flag(filename, PROTECTED, false)?;
+        let result = self.copy(APPEND_SCRATCH, filename);
+        self.truncate(APPEND_SCRATCH)?;
+        result
+    }
+
+    // FileSystem can only write a file from its start, so appending copies the
+    // old contents aside, recreates the file and writes them back, leaving the
+    // returned descriptor positioned at the end.
+    pub fn open_append(&mut self, filename: &str) -> Result<usize, FileSystemError> {
+        self.open_append_dropping(filename, 0)
+    }
+
+    // Appends as open_append does, but leaves the first `drop` bytes of the
+    // old contents out, for files kept to a rolling window.
+    pub fn open_append_dropping(
+        &mut self,
+        filename: &str,
+        drop: usize,
+    ) -> Result<usize, FileSystemError> {
+        if !self.exists(filename) {
+            return self.open_create(filename);
+        }
+        self.copy(filename, APPEND_SCRATCH)?;
+        let fd = self.open_create(filename)?;
+        let result = self.copy_into(APPEND_SCRATCH, fd, drop);
+        self.truncate(APPEND_SCRATCH)?;
+        match result {
+            Ok(()) => Ok(fd),
+            Err(e) => {
+                self.close(fd)?;
+                Err(e)
+            }
+        }
+    }
+
+    // Copies a finished scratch file over `filename` and empties it, so the
+    // file itself is rewritten in a single step.
+    pub fn replace(&mut self, scratch: &str, filename: &str) -> Result<(), FileSystemError> {
+        let result = self.copy(scratch, filename);
+        self.truncate(scratch)?;
+        result
+    }
+
+    // Empties the scratch files of saves that never finished.
+    pub fn clear_save_scratch(&mut self) {
+        let Ok((num_files, files)) = self.filesystem.list_directory() else {
+            return;
+        };
+        for file in files[..num_files].iter() {
+            let name = name_str(file).unwrap_or("");
+            if is_save_scratch(name) && self.file_size(name).is_ok_and(|size| size > 0) {
+                self.truncate(name).ok();
+            }
+        }
+    }
+
+    fn copy(&mut self, from: &str, to: &str) -> Result<(), FileSystemError> {
+        let fd = self.open_create(to)?;
+        let result = self.copy_into(from, fd, 0);
+        self.close(fd)?;
+        result
+    }
+
+    // Copies all but the first `skip` bytes of a file.
+    fn copy_into(&mut self, from: &str, to_fd: usize, skip: usize) -> Result<(), FileSystemError> {
+        let fd = self.open_read(from)?;
+        let mut buffer = [0; BLOCK_SIZE];
+        let mut skip = skip;
+        let result = loop {
+            match self.read(fd, &mut buffer) {
+                Ok(0) => break Ok(()),
+                Ok(num_bytes) => {
+                    let skipped = skip.min(num_bytes);
+                    skip -= skipped;
+                    if let Err(e) = self.write(to_fd, &buffer[skipped..num_bytes]) {
+                        break Err(e);
+                    }
+                }
+                Err(e) => break Err(e),
+            }
+        };
+        self.close(fd)?;
+        result
+    }
+
+    pub fn read(&mut self, fd: usize, buffer: &mut [u8]) -> Result<usize, FileSystemError> {
+        if fd >= MAX_OPEN {
+            let (i, pos) = self
+                .virtual_reads
+                .get_mut(fd - MAX_OPEN)
+                .and_then(Option::as_mut)
+                .ok_or(FileSystemError::FileNotOpen)?;
+            let Some(file) = &self.virtuals[*i] else {
+                return Ok(0);
+            };
+            let num_bytes = buffer.len().min(file.len.saturating_sub(*pos));
+            buffer[..num_bytes].copy_from_slice(&file.contents[*pos..*pos + num_bytes]);
+            *pos += num_bytes;
+            return Ok(num_bytes);
+        }
+        let num_bytes = self.filesystem.read(fd, buffer)?;
+        if let Some(stream) = self.stream(fd) {
+            stream.apply(&mut buffer[..num_bytes]);
+        }
+        Ok(num_bytes)
+    }
+
+    pub fn write(&mut self, fd: usize, buffer: &[u8]) -> Result<(), FileSystemError> {
+        if fd >= MAX_OPEN {
+            return Err(FileSystemError::NotOpenForWrite);
+        }
+        match self.stream(fd).copied() {
+            Some(mut stream) => {
+                let mut scratch = [0; BLOCK_SIZE];
+                for chunk in buffer.chunks(BLOCK_SIZE) {
+                    let scratch = &mut scratch[..chunk.len()];
+                    scratch.copy_from_slice(chunk);
+                    stream.apply(scratch);
+                    self.filesystem.write(fd, scratch)?;
+                }
+                *self.stream(fd).unwrap() = stream;
+            }
+            None => self.filesystem.write(fd, buffer)?,
+        }
+        if let Some(writer) = self.writers.iter_mut().flatten().find(|w| w.fd == fd) {
+            let blocks_before = (writer.size + BLOCK_SIZE - 1) / BLOCK_SIZE;
+            writer.size += buffer.len();
+            let blocks_after = (writer.size + BLOCK_SIZE - 1) / BLOCK_SIZE;
+            for _ in blocks_before..blocks_after {
+                self.blocks.allocate(&writer.name);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn close(&mut self, fd: usize) -> Result<(), FileSystemError> {
+        if fd >= MAX_OPEN {
+            let read = self.virtual_reads.get_mut(fd - MAX_OPEN);
+            return read
+                .and_then(Option::take)
+                .map(|_| ())
+                .ok_or(FileSystemError::FileNotOpen);
+        }
+        // A close that fails, as one writing out its last block onto a full
+        // disk can, leaves the descriptor stale for close_stale to retry.
+        // If it let the descriptor go anyway, the retry finds it not open
+        // and the bookkeeping is dropped here.
+        let closed = self.filesystem.close(fd);
+        if closed.is_err_and(|e| e != FileSystemError::FileNotOpen) {
+            if let Some(opener) = self.openers[fd].as_mut() {
+                opener.stale = true;
+            }
+            return closed;
+        }
+        self.openers[fd] = None;
+        for writer in self.writers.iter_mut() {
+            if writer.map_or(false, |w| w.fd == fd) {
+                *writer = None;
+            }
+        }
+        for stream in self.streams.iter_mut() {
+            if stream.is_some_and(|(stream_fd, _)| stream_fd == fd) {
+                *stream = None;
+            }
+        }
+        closed
+    }
+
+    // Replaces a virtual file's contents with what `write` writes, past
+    // VIRTUAL_BYTES being dropped, making the file if it is new. Readers
+    // that have it open carry on from where they were. Returns false when
+    // there is no room for it.
+    pub fn publish(
+        &mut self,
+        filename: &str,
+        write: impl FnOnce(&mut dyn core::fmt::Write) -> core::fmt::Result,
+    ) -> bool {
+        self.put_virtual(filename, false, write)
+    }
+
+    // Publishes a file that is made fresh for each read, making room by
+    // dropping an older generated file no one is reading.
+    #[cfg(feature = "shell")]
+    pub fn publish_generated(
+        &mut self,
+        filename: &str,
+        write: impl FnOnce(&mut dyn core::fmt::Write) -> core::fmt::Result,
+    ) -> bool {
+        self.put_virtual(filename, true, write)
+    }
+
+    fn put_virtual(
+        &mut self,
+        filename: &str,
+        generated: bool,
+        write: impl FnOnce(&mut dyn core::fmt::Write) -> core::fmt::Result,
+    ) -> bool {
+        let Some(name) = virtual_name(filename) else {
+            return false;
+        };
+        let free = self.virtuals.iter().position(Option::is_none);
+        let unread = (0..MAX_VIRTUAL).find(|i| {
+            self.virtuals[*i]
+                .as_ref()
+                .is_some_and(|file| file.generated)
+                && !self.virtual_reads.iter().flatten().any(|(j, _)| j == i)
+        });
+        let slot = match (self.find_virtual(filename), free, unread) {
+            (Some(i), _, _) | (None, Some(i), _) => i,
+            (None, None, Some(i)) if generated => i,
+            _ => return false,
+        };
+        let file = self.virtuals[slot].insert(VirtualFile {
+            name,
+            contents: [0; VIRTUAL_BYTES],
+            len: 0,
+            generated,
+        });
+        write(file).unwrap_or(());
+        true
+    }
+
+    fn find_virtual(&self, filename: &str) -> Option<usize> {
+        let name = virtual_name(filename)?;
+        self.virtuals
+            .iter()
+            .position(|file| file.as_ref().is_some_and(|file| file.name == name))
+    }
+
+    pub fn block_map(&self) -> &BlockMap {
+        &self.blocks
+    }
+
+    fn create(&mut self, filename: &str) -> Result<usize, FileSystemError> {
+        if self.read_only || self.find_virtual(filename).is_some() {
+            return Err(FileSystemError::NotOpenForWrite);
+        }
+        let fd = match self.filesystem.open_create(filename) {
+            Err(FileSystemError::TooManyOpen) if self.close_stale() => {
+                self.filesystem.open_create(filename)?
+            }
+            result => result?,
+        };
+        self.opened(fd);
+        let name = name_bytes(filename);
+        self.blocks.release(&name);
+        if let Some(writer) = self.writers.iter_mut().find(|w| w.is_none()) {
+            *writer = Some(Writer { fd, name, size: 0 });
+        }
+        Ok(fd)
+    }
+
+    pub fn list_directory(
+        &mut self,
+    ) -> Result<(usize, [[u8; MAX_FILENAME_BYTES]; MAX_FILES_STORED]), FileSystemError> {
+        let (num_files, files) = self.filesystem.list_directory()?;
+        let mut visible = [[0; MAX_FILENAME_BYTES]; MAX_FILES_STORED];
+        let mut num_visible = 0;
+        for starred in [true, false] {
+            for file in files[..num_files].iter() {
+                let flags = self.file_flags(file);
+                if !is_internal(file) && flags & DELETED == 0 && (flags & STARRED != 0) == starred {
+                    visible[num_visible] = *file;
+                    num_visible += 1;
+                }
+            }
+        }
+        Ok((num_visible, visible))
+    }
+
+    pub fn exists(&mut self, filename: &str) -> bool {
+        let name = name_bytes(filename);
+        match self.list_directory() {
+            Ok((num_files, files)) => files[..num_files].contains(&name),
+            Err(_) => false,
+        }
+    }
+
+    pub fn delete(&mut self, filename: &str) -> Result<(), FileSystemError> {
+        self.truncate(filename)?;
+        self.write_flags(filename, DELETED)
+    }
+
+    pub fn raw_directory(
+        &mut self,
+    ) -> Result<(usize, [[u8; MAX_FILENAME_BYTES]; MAX_FILES_STORED]), FileSystemError> {
+        self.filesystem.list_directory()
+    }
+
+    pub fn file_size(&mut self, filename: &str) -> Result<usize, FileSystemError> {
+        if let Some(i) = self.find_virtual(filename) {
+            return Ok(self.virtuals[i].as_ref().map_or(0, |file| file.len));
+        }
+        let fd = self.filesystem.open_read(filename)?;
+        let mut buffer = [0; BLOCK_SIZE];
+        let mut size = 0;
+        let result = loop {
+            match self.filesystem.read(fd, &mut buffer) {
+                Ok(0) => break Ok(size),
+                Ok(num_bytes) => size += num_bytes,
+                Err(e) => break Err(e),
+            }
+        };
+        self.filesystem.close(fd)?;
+        result
+    }
+
+    pub fn truncate(&mut self, filename: &str) -> Result<(), FileSystemError> {
+        let fd = self.create(filename)?;
+        self.close(fd)
+    }
+
+    #[cfg(feature = "shell")]
+    pub fn metadata_names(&self) -> impl Iterator<Item = [u8; MAX_FILENAME_BYTES]> + '_ {
+        self.records.iter().flatten().map(|r| r.name)
+    }
+
+    #[cfg(feature = "shell")]
+    pub fn remove_record(
+        &mut self,
+        name: &[u8; MAX_FILENAME_BYTES],
+    ) -> Result<(), FileSystemError> {
+        if let Some(i) = self.find_record(name) {
+            self.records[i] = None;
+        }
+        self.save_metadata()
+    }
+
+    pub fn flags(&self, filename: &str) -> u8 {
+        self.file_flags(&name_bytes(filename))
+    }
+
+    pub fn toggle_flag(&mut self, filename: &str, flag: u8) -> Result<(), FileSystemError> {
+        let on = self.flags(filename) & flag == 0;
+        self.set_flag(filename, flag, on)
+    }
+
+    pub fn set_flag(&mut self, filename: &str, flag: u8, on: bool) -> Result<(), FileSystemError> {
+        let flags = self.flags(filename);
+        if on {
+            self.write_flags(filename, flags | flag)
+        } else {
+            self.write_flags(filename, flags & !flag)
+        }
+    }
+
+    // Keeps a protected file's check and key while it stays protected.
+    fn write_flags(&mut self, filename: &str, flags: u8) -> Result<(), FileSystemError> {
+        let record = self
+            .find_record(&name_bytes(filename))
+            .and_then(|i| self.records[i])
+            .filter(|_| flags & PROTECTED != 0);
+        let (check, key) = record.map_or((0, None), |r| (r.check, r.key));
+        self.write_record(filename, flags, check, key)
+    }
+
+    // Keeps the file's last run and editor place unless it is being
+    // deleted.
+    fn write_record(
+        &mut self,
+        filename: &str,
+        flags: u8,
+        check: u8,
+        key: Option<u32>,
+    ) -> Result<(), FileSystemError> {
+        let kept = match flags & DELETED {
+            0 => self.record_of(filename),
+            _ => FileRecord::blank(name_bytes(filename)),
+        };
+        self.put_record(FileRecord {
+            flags,
+            check,
+            key,
+            ..kept
+        })
+    }
+
+    // The file's record, or a blank one if it has none.
+    fn record_of(&self, filename: &str) -> FileRecord {
+        let name = name_bytes(filename);
+        self.find_record(&name)
+            .and_then(|i| self.records[i])
+            .unwrap_or(FileRecord::blank(name))
+    }
+
+    // Remembers how a program's run went, for the file lists to show.
+    pub fn record_run(&mut self, filename: &str, run: RunStats) -> Result<(), FileSystemError> {
+        self.put_record(FileRecord {
+            run: Some(run),
+            ..self.record_of(filename)
+        })
+    }
+
+    // Remembers where an editor on the file was closed, for edit_file.
+    pub fn record_place(
+        &mut self,
+        filename: &str,
+        place: EditorPlace,
+    ) -> Result<(), FileSystemError> {
+        self.put_record(FileRecord {
+            place,
+            ..self.record_of(filename)
+        })
+    }
+
+    pub fn editor_place(&self, filename: &str) -> EditorPlace {
+        self.record_of(filename).place
+    }
+
+    pub fn run_stats(&self, filename: &str) -> Option<RunStats> {
+        self.find_record(&name_bytes(filename))
+            .and_then(|i| self.records[i])
+            .and_then(|r| r.run)
+    }
+
+    // A record that says nothing beyond a blank one is dropped.
+    fn put_record(&mut self, record: FileRecord) -> Result<(), FileSystemError> {
+        if self.read_only {
+            return Err(FileSystemError::NotOpenForWrite);
+        }
+        let empty =
+            record.flags == 0 && record.run.is_none() && record.place == EditorPlace::default();
+        match self.find_record(&record.name) {
+            Some(i) if empty => self.records[i] = None,
+            Some(i) => self.records[i] = Some(record),
+            None if empty => return Ok(()),
+            None => match self.records.iter().position(|r| r.is_none()) {
+                Some(i) => self.records[i] = Some(record),
+                None => return Err(FileSystemError::TooManyFiles),
+            },
+        }
+        self.save_metadata()
+    }
+
+    pub fn file_flags(&self, name: &[u8; MAX_FILENAME_BYTES]) -> u8 {
+        match self.find_record(name) {
+            Some(i) => self.records[i].map_or(0, |r| r.flags),
+            None => 0,
+        }
+    }
+
+    fn find_record(&self, name: &[u8; MAX_FILENAME_BYTES]) -> Option<usize> {
+        self.records
+            .iter()
+            .position(|r| r.map_or(false, |r| &r.name == name))
+    }
+
+    // Reads the records from the metadata file, of whatever version, and
+    // writes them back in this build's format if they were older. Done at
+    // boot once the files are on the disk.
+    pub fn load_metadata(&mut self) {
+        self.records = [None; MAX_FILES_STORED];
+        let Some((version, record_size)) = self.metadata_format() else {
+            return;
+        };
+        let Ok(fd) = self.filesystem.open_read(METADATA_FILE) else {
+            return;
+        };
+        let mut header = [0; METADATA_HEADER];
+        let skipped = match version {
+            1 => Ok(0),
+            _ => self.filesystem.read(fd, &mut header),
+        };
+        if skipped.is_ok() {
+            for i in 0..MAX_FILES_STORED {
+                let mut chunk = [0; u8::MAX as usize];
+                match self.filesystem.read(fd, &mut chunk[..record_size]) {
+                    Ok(n) if n == record_size => self.records[i] = Some(read_record(&chunk)),
+                    _ => break,
+                }
+            }
+        }
+        self.filesystem.close(fd).unwrap_or(());
+        if version < METADATA_VERSION && !self.read_only {
+            self.save_metadata().ok();
+        }
+    }
+
+    // The metadata file's version and record size, from its header or as
+    // version 1 if it has none. None if there is no file, or its header
+    // gives records too short to hold a name.
+    fn metadata_format(&mut self) -> Option<(u8, usize)> {
+        let fd = self.filesystem.open_read(METADATA_FILE).ok()?;
+        let mut header = [0; METADATA_HEADER];
+        let read = self.filesystem.read(fd, &mut header);
+        self.filesystem.close(fd).unwrap_or(());
+        match read {
+            Ok(METADATA_HEADER) if header[..4] == METADATA_MAGIC => {
+                Some((header[4], header[5] as usize)).filter(|&(_, size)| size > MAX_FILENAME_BYTES)
+            }
+            Ok(_) => Some((1, RECORD_SIZE)),
+            Err(_) => None,
+        }
+    }
+
+    // Whether the metadata file is `size` bytes of whole records after
+    // whatever header it has, for fsck.
+    #[cfg(feature = "shell")]
+    pub fn metadata_fits(&mut self, size: usize) -> bool {
+        match self.metadata_format() {
+            Some((1, record_size)) => size.is_multiple_of(record_size),
+            Some((_, record_size)) => {
+                size >= METADATA_HEADER && (size - METADATA_HEADER).is_multiple_of(record_size)
+            }
+            None => false,
+        }
+    }
+
+    pub fn save_metadata(&mut self) -> Result<(), FileSystemError> {
+        let mut buffer = [0; METADATA_HEADER + RECORD_SIZE * MAX_FILES_STORED];
+        buffer[..4].copy_from_slice(&METADATA_MAGIC);
+        buffer[4] = METADATA_VERSION;
+        buffer[5] = RECORD_SIZE as u8;
+        let mut num_bytes = METADATA_HEADER;
+        for record in self.records.iter().flatten() {
+            let chunk = &mut buffer[num_bytes..num_bytes + RECORD_SIZE];
+            chunk[..MAX_FILENAME_BYTES].copy_from_slice(&record.name);
+            chunk[MAX_FILENAME_BYTES] = record.flags;
+            chunk[MAX_FILENAME_BYTES + 1] = record.check;
+            if let Some(run) = record.run {
+                let run_bytes = &mut chunk[MAX_FILENAME_BYTES + 2..];
+                run_bytes[0] = run.status.byte();
+                run_bytes[1..5].copy_from_slice(&run.ticks.to_le_bytes());
+                run_bytes[5..9].copy_from_slice(&run.instructions.to_le_bytes());
+            }
+            let place = record.place;
+            chunk[MAX_FILENAME_BYTES + 11..].copy_from_slice(&[place.row, place.col, place.top]);
+            num_bytes += RECORD_SIZE;
+        }
+        let fd = self.create(METADATA_FILE)?;
+        let result = self.write(fd, &buffer[..num_bytes]);
+        result.and(self.close(fd))
+    }
+}
+
+// A record as stored, at least RECORD_SIZE bytes of it; see RECORD_SIZE.
+fn read_record(chunk: &[u8]) -> FileRecord {
+    let mut name = [0; MAX_FILENAME_BYTES];
+    name.copy_from_slice(&chunk[..MAX_FILENAME_BYTES]);
+    let number = |at: usize| {
+        let at = MAX_FILENAME_BYTES + at;
+        u32::from_le_bytes([chunk[at], chunk[at + 1], chunk[at + 2], chunk[at + 3]])
+    };
+    let run = RunStatus::from_byte(chunk[MAX_FILENAME_BYTES + 2]).map(|status| RunStats {
+        ticks: number(3),
+        instructions: number(7),
+        status,
+    });
+    let place = &chunk[MAX_FILENAME_BYTES + 11..];
+    FileRecord {
+        name,
+        flags: chunk[MAX_FILENAME_BYTES],
+        check: chunk[MAX_FILENAME_BYTES + 1],
+        key: None,
+        run,
+        place: EditorPlace {
+            row: place[0],
+            col: place[1],
+            top: place[2],
+        },
+    }
+}
+
+fn virtual_name(filename: &str) -> Option<[u8; VIRTUAL_NAME_BYTES]> {
+    let mut name = [0; VIRTUAL_NAME_BYTES];
+    name.get_mut(..filename.len())?
+        .copy_from_slice(filename.as_bytes());
+    Some(name)
+}
+
+pub fn name_bytes(filename: &str) -> [u8; MAX_FILENAME_BYTES] {
+    let mut name = [0; MAX_FILENAME_BYTES];
+    for (i, b) in filename.bytes().take(MAX_FILENAME_BYTES).enumerate() {
+        name[i] = b;
+    }
+    name
+}
+
+// The kernel's own files, kept out of listings. Other names starting with a
+// `.` are users' files and are listed like any other.
+fn is_internal(name: &[u8; MAX_FILENAME_BYTES]) -> bool {
+    let name = name_str(name).unwrap_or("");
+    name == METADATA_FILE || name == APPEND_SCRATCH || is_save_scratch(name)
+}
+
+// `.save` followed by a window number.
+fn is_save_scratch(name: &str) -> bool {
+    name.strip_prefix(SAVE_SCRATCH)
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+pub fn name_str(name: &[u8; MAX_FILENAME_BYTES]) -> Option<&str> {
+    core::str::from_utf8(name)
+        .ok()
+        .map(|name| name.trim_end_matches('\0'))
+}
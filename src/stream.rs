@@ -0,0 +1,110 @@
+use file_system_solution::FileSystemError;
+use simple_interp::ArrayString;
+
+use crate::storage::Storage;
+use crate::BLOCK_SIZE;
+
+// Reads a file one block at a time, so a reader never holds more than
+// BLOCK_SIZE bytes no matter how large the file is. The reader does not
+// borrow the storage it came from; every call takes it again.
+pub struct FileReader {
+    fd: usize,
+    buffer: [u8; BLOCK_SIZE],
+    len: usize,
+    pos: usize,
+}
+
+impl FileReader {
+    pub fn open(storage: &mut Storage, filename: &str) -> Result<Self, FileSystemError> {
+        Ok(Self {
+            fd: storage.open_read(filename)?,
+            buffer: [0; BLOCK_SIZE],
+            len: 0,
+            pos: 0,
+        })
+    }
+
+    pub fn next_byte(&mut self, storage: &mut Storage) -> Result<Option<u8>, FileSystemError> {
+        if self.pos == self.len {
+            self.len = storage.read(self.fd, &mut self.buffer)?;
+            self.pos = 0;
+            if self.len == 0 {
+                return Ok(None);
+            }
+        }
+        self.pos += 1;
+        Ok(Some(self.buffer[self.pos - 1]))
+    }
+
+    // Skips past the next `num_lines` newlines, returning false if the file
+    // ends first.
+    pub fn skip_lines(
+        &mut self,
+        storage: &mut Storage,
+        num_lines: usize,
+    ) -> Result<bool, FileSystemError> {
+        let mut skipped = 0;
+        while skipped < num_lines {
+            match self.next_byte(storage)? {
+                Some(b'\n') => skipped += 1,
+                Some(_) => {}
+                None => return Ok(false),
+            }
+        }
+        Ok(true)
+    }
+
+    // Counts the lines left in the file, including a last line with no
+    // newline after it.
+    #[cfg(feature = "shell")]
+    pub fn count_lines(&mut self, storage: &mut Storage) -> Result<usize, FileSystemError> {
+        let mut num_lines = 0;
+        let mut partial = false;
+        while let Some(b) = self.next_byte(storage)? {
+            partial = b != b'\n';
+            if !partial {
+                num_lines += 1;
+            }
+        }
+        Ok(num_lines + partial as usize)
+    }
+
+    pub fn read_all(
+        &mut self,
+        storage: &mut Storage,
+        out: &mut [u8],
+    ) -> Result<usize, FileSystemError> {
+        let mut num_bytes = 0;
+        while let Some(b) = self.next_byte(storage)? {
+            if num_bytes == out.len() {
+                return Err(FileSystemError::FileTooBig);
+            }
+            out[num_bytes] = b;
+            num_bytes += 1;
+        }
+        Ok(num_bytes)
+    }
+
+    pub fn close(self, storage: &mut Storage) -> Result<(), FileSystemError> {
+        storage.close(self.fd)
+    }
+}
+
+// Reads the next line of an open file a byte at a time, so the file is left
+// positioned at the start of the line after it. Characters past N are
+// dropped. Returns None once the file has run out.
+pub fn read_line<const N: usize>(
+    storage: &mut Storage,
+    fd: usize,
+) -> Result<Option<ArrayString<N>>, FileSystemError> {
+    let mut line = ArrayString::default();
+    let mut byte = [0];
+    loop {
+        match storage.read(fd, &mut byte)? {
+            0 if line.len() == 0 => return Ok(None),
+            0 => return Ok(Some(line)),
+            _ if byte[0] == b'\n' => return Ok(Some(line)),
+            _ => line.push_char(byte[0] as char),
+        }
+    }
+}
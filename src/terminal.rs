@@ -0,0 +1,46 @@
+use crate::output::Output;
+use crate::screen::{plot, Color, ColorCode};
+use crate::LINE_WIDTH;
+
+const TERMINAL_ROWS: usize = 10;
+
+// A terminal on COM1: bytes from the serial port are shown as they arrive
+// and typed keys go straight out. There is no local echo; the other end
+// echoes if it wants to.
+#[derive(Default)]
+pub struct Terminal {
+    output: Output,
+}
+
+impl Terminal {
+    // Output lines only hold printable ASCII, so anything else but a
+    // newline or tab is dropped.
+    pub fn receive(&mut self, byte: u8) {
+        match byte {
+            b'\n' | b' '..=b'~' => self.output.write_bytes(&[byte]),
+            b'\t' => self.output.write_bytes(b" "),
+            _ => {}
+        }
+    }
+
+    // The newest line, still being received, is at the bottom with the
+    // cursor after it.
+    pub fn draw(&self, x: usize, y: usize, cursor: bool, text: ColorCode) {
+        for row in 0..TERMINAL_ROWS {
+            let line = self.output.line(TERMINAL_ROWS - 1 - row);
+            let mut chars = line.chars();
+            for col in 0..LINE_WIDTH {
+                plot(chars.next().unwrap_or(' '), x + col, y + row, text);
+            }
+        }
+        let end = self.output.line(0).len();
+        if cursor && end < LINE_WIDTH {
+            plot(
+                ' ',
+                x + end,
+                y + TERMINAL_ROWS - 1,
+                ColorCode::new(Color::Black, Color::LightCyan),
+            );
+        }
+    }
+}
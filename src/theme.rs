@@ -0,0 +1,176 @@
+use crate::screen::{Color, ColorCode};
+
+// Colors for the window frames, status bar, listings and program output.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct Theme {
+    pub text: Color,
+    pub border: Color,
+    pub error: Color,
+    pub background: Color,
+}
+
+// The themes the config file can pick by name; the first is the default.
+pub const THEMES: [(&str, Theme); 5] = [
+    (
+        "classic",
+        Theme {
+            text: Color::LightCyan,
+            border: Color::Green,
+            error: Color::LightRed,
+            background: Color::Black,
+        },
+    ),
+    (
+        "mono",
+        Theme {
+            text: Color::White,
+            border: Color::LightGray,
+            error: Color::White,
+            background: Color::Black,
+        },
+    ),
+    (
+        "amber",
+        Theme {
+            text: Color::Yellow,
+            border: Color::Brown,
+            error: Color::LightRed,
+            background: Color::Black,
+        },
+    ),
+    (
+        "ocean",
+        Theme {
+            text: Color::White,
+            border: Color::LightCyan,
+            error: Color::Yellow,
+            background: Color::Blue,
+        },
+    ),
+    (
+        "contrast",
+        Theme {
+            text: Color::White,
+            border: Color::White,
+            error: Color::White,
+            background: Color::Black,
+        },
+    ),
+];
+
+// The high-contrast theme keeps to white on black, so nothing it shows is
+// told apart by color alone.
+const CONTRAST: Theme = THEMES[4].1;
+
+// A theme's color codes, kept by whatever draws in it.
+#[derive(Copy, Clone)]
+pub struct Palette {
+    pub text: ColorCode,
+    pub border: ColorCode,
+    pub error: ColorCode,
+    pub blank: ColorCode,
+    // A blinking title for a window wanting attention.
+    pub alert: ColorCode,
+    // The selected item or cursor in a list or field.
+    pub highlight: ColorCode,
+    // The input row for a few frames after a rejected key.
+    pub flash: ColorCode,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        THEMES[0].1
+    }
+}
+
+impl Theme {
+    pub fn named(name: &str) -> Option<Theme> {
+        THEMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, theme)| *theme)
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        THEMES
+            .iter()
+            .find(|(_, theme)| theme == self)
+            .map(|(n, _)| *n)
+    }
+
+    // Its colors as the codes the screen takes, worked out once here rather
+    // than on every plot.
+    pub fn palette(&self) -> Palette {
+        Palette {
+            text: ColorCode::new(self.text, self.background),
+            border: ColorCode::new(self.border, self.background),
+            error: ColorCode::new(self.error, self.background),
+            blank: ColorCode::new(self.background, self.background),
+            // Blinks on VGA, which reads a light background as the blink bit.
+            alert: ColorCode::new(brighten(self.error), brighten(self.background)),
+            highlight: if self.high_contrast() {
+                ColorCode::new(Color::Black, Color::LightGray)
+            } else {
+                ColorCode::new(Color::Black, Color::LightCyan)
+            },
+            flash: ColorCode::new(self.error, self.background),
+        }
+    }
+
+    pub fn high_contrast(&self) -> bool {
+        *self == CONTRAST
+    }
+
+    // The same theme at full intensity, for windows switched to bright.
+    pub fn brightened(&self) -> Theme {
+        Theme {
+            text: brighten(self.text),
+            border: brighten(self.border),
+            error: brighten(self.error),
+            background: self.background,
+        }
+    }
+
+    // The same theme greyed out, for windows drawn while unfocused. Grey is
+    // too faint for the high-contrast theme, which leaves the double-lined
+    // header to show focus.
+    pub fn dimmed(&self) -> Theme {
+        if self.high_contrast() {
+            return *self;
+        }
+        Theme {
+            text: Color::DarkGray,
+            border: Color::DarkGray,
+            error: Color::DarkGray,
+            background: self.background,
+        }
+    }
+}
+
+impl Palette {
+    // The same colors with nothing blinking or flashing, for motion=reduced:
+    // an alert holds the error color and a rejected key leaves the input row
+    // as it is.
+    pub fn steady(self) -> Palette {
+        Palette {
+            alert: self.error,
+            flash: self.text,
+            ..self
+        }
+    }
+}
+
+// The light half of the palette for a dark color; light colors are kept.
+pub fn brighten(color: Color) -> Color {
+    match color {
+        Color::Black => Color::DarkGray,
+        Color::Blue => Color::LightBlue,
+        Color::Green => Color::LightGreen,
+        Color::Cyan => Color::LightCyan,
+        Color::Red => Color::LightRed,
+        Color::Magenta => Color::Pink,
+        Color::Brown => Color::Yellow,
+        Color::LightGray => Color::White,
+        color => color,
+    }
+}
@@ -0,0 +1,54 @@
+// Channel 0 of the PIT, which raises the timer interrupt. Kernel builds
+// reprogram it when the config asks for a rate other than the BIOS's
+// 18.2 Hz; builds with the `std` feature only remember the divisor, so
+// host tests can check what would be set.
+
+#[cfg(not(feature = "std"))]
+use crate::port::outb;
+
+pub const PIT_HZ: u32 = 1_193_182;
+#[cfg(not(feature = "std"))]
+pub const PIT_COMMAND: u16 = 0x43;
+#[cfg(not(feature = "std"))]
+const PIT_CHANNEL_0: u16 = 0x40;
+// The BIOS's divisor, which is also the slowest the PIT can go, and the
+// smallest the config may ask for, about a kilohertz.
+pub const DEFAULT_DIVISOR: u32 = 65536;
+pub const MIN_DIVISOR: u32 = PIT_HZ / 1000;
+
+// Timer interrupts a second with `divisor`, rounded down.
+pub const fn hz(divisor: u32) -> u32 {
+    PIT_HZ / divisor
+}
+
+#[cfg(not(feature = "std"))]
+pub fn set_divisor(divisor: u32) {
+    unsafe {
+        // Channel 0, low byte then high byte, rate generator. A divisor of
+        // 65536 goes in as zero.
+        outb(PIT_COMMAND, 0x34);
+        outb(PIT_CHANNEL_0, divisor as u8);
+        outb(PIT_CHANNEL_0, (divisor >> 8) as u8);
+    }
+}
+
+#[cfg(feature = "std")]
+pub use fake::*;
+
+#[cfg(feature = "std")]
+mod fake {
+    use std::cell::Cell;
+
+    thread_local! {
+        static DIVISOR: Cell<u32> = const { Cell::new(super::DEFAULT_DIVISOR) };
+    }
+
+    pub fn set_divisor(divisor: u32) {
+        DIVISOR.with(|d| d.set(divisor));
+    }
+
+    // The divisor the calling thread's timer was last given.
+    pub fn timer_divisor() -> u32 {
+        DIVISOR.with(|d| d.get())
+    }
+}
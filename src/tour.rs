@@ -0,0 +1,121 @@
+use core::fmt::Write;
+
+use simple_interp::ArrayString;
+
+use crate::screen::{plot, ColorCode};
+use crate::theme::Palette;
+use crate::LINE_WIDTH;
+
+const TEXT_ROWS: usize = 7;
+
+// What the kernel tells a tour it has just done; see SwimInterface::tour_saw.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Action {
+    Focused,
+    Browsed,
+    Edited,
+    Ran,
+}
+
+// A step's text, and the action that moves the tour on from it. The last
+// step waits for nothing.
+struct Step {
+    text: [&'static str; TEXT_ROWS],
+    wants: Option<Action>,
+}
+
+const STEPS: [Step; 5] = [
+    Step {
+        text: [
+            "Welcome! Four windows share the",
+            "screen. Keys go to the one with",
+            "the double-lined header.",
+            "",
+            "Press F1 to F4 to move to",
+            "another window. This one keeps",
+            "up with what you do there.",
+        ],
+        wants: Some(Action::Focused),
+    },
+    Step {
+        text: [
+            "A window starts as a list of",
+            "the files on its volume; Tab",
+            "goes to the next volume.",
+            "",
+            "Press Right or Left to move",
+            "along the list.",
+            "",
+        ],
+        wants: Some(Action::Browsed),
+    },
+    Step {
+        text: [
+            "e opens the file under the",
+            "highlight in the editor, where",
+            "Ctrl+S saves and F6 closes.",
+            "",
+            "Press e to edit a file.",
+            "",
+            "",
+        ],
+        wants: Some(Action::Edited),
+    },
+    Step {
+        text: [
+            "r runs the file under the",
+            "highlight and shows its output",
+            "in the window; F6 stops it.",
+            "",
+            "Close the editor with F6, then",
+            "press r on hello to run it.",
+            "",
+        ],
+        wants: Some(Action::Ran),
+    },
+    Step {
+        text: [
+            "That's the tour. In a list, o",
+            "opens the settings, s a shell",
+            "and j the running jobs; the",
+            "README covers the rest.",
+            "",
+            "F6 closes this window.",
+            "",
+        ],
+        wants: None,
+    },
+];
+
+// A guided tour, opened with `?` in a file list. Each step says what to try
+// and moves on once the kernel reports doing it, whichever window it was
+// done in.
+#[derive(Copy, Clone, Default)]
+pub struct Tour {
+    step: usize,
+}
+
+impl Tour {
+    // Moves on if `action` is what the current step asks for.
+    pub fn saw(&mut self, action: Action) {
+        if STEPS[self.step].wants == Some(action) {
+            self.step += 1;
+        }
+    }
+
+    pub fn draw(&self, x: usize, y: usize, palette: &Palette) {
+        let mut progress: ArrayString<LINE_WIDTH> = ArrayString::default();
+        write!(progress, "Step {} of {}", self.step + 1, STEPS.len()).unwrap();
+        draw_row(progress.as_str().unwrap(), x, y, palette.border);
+        for (row, line) in STEPS[self.step].text.iter().enumerate() {
+            draw_row(line, x, y + 2 + row, palette.text);
+        }
+    }
+}
+
+fn draw_row(text: &str, x: usize, y: usize, color: ColorCode) {
+    let mut chars = text.chars();
+    for col in 0..LINE_WIDTH {
+        plot(chars.next().unwrap_or(' '), x + col, y, color);
+    }
+}
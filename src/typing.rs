@@ -0,0 +1,119 @@
+use core::fmt::Write;
+
+use simple_interp::ArrayString;
+
+use crate::format::Percent;
+use crate::screen::{is_drawable, plot, ColorCode};
+use crate::theme::Palette;
+use crate::wrapping_nav;
+use crate::LINE_WIDTH;
+
+const PHRASES: [&str; 4] = [
+    "the quick brown fox jumps over",
+    "a lazy dog naps in the sun",
+    "pack my box with five dozen",
+    "sphinx of black quartz judge",
+];
+// The timer interrupt runs at the PIT's default of about 18.2 Hz.
+const TICKS_PER_MINUTE: u64 = 1092;
+const HELP: &str = "enter: next line";
+
+// A typing-speed test: one phrase to copy, timed in frames from the first
+// key to the last character, with every mistyped key counted against
+// accuracy even if it is corrected.
+#[derive(Copy, Clone, Default)]
+pub struct TypingTest {
+    phrase: usize,
+    typed: ArrayString<LINE_WIDTH>,
+    keystrokes: u64,
+    mistakes: u64,
+    start: Option<u64>,
+    end: Option<u64>,
+}
+
+impl TypingTest {
+    fn target(&self) -> &'static str {
+        PHRASES[self.phrase]
+    }
+
+    fn finished(&self) -> bool {
+        self.end.is_some()
+    }
+
+    pub fn type_char(&mut self, c: char, frame: u64) {
+        if self.finished() {
+            if c == '\n' {
+                *self = Self {
+                    phrase: wrapping_nav::next(self.phrase, PHRASES.len()),
+                    ..Self::default()
+                };
+            }
+            return;
+        }
+        if c == '\u{0008}' {
+            self.typed.push_char(c);
+            return;
+        }
+        if !is_drawable(c) {
+            return;
+        }
+        self.start.get_or_insert(frame);
+        self.keystrokes += 1;
+        if self.target().chars().nth(self.typed.len()) != Some(c) {
+            self.mistakes += 1;
+        }
+        self.typed.push_char(c);
+        if self.typed.as_str().unwrap() == self.target() {
+            self.end = Some(frame);
+        }
+    }
+
+    fn elapsed(&self, frame: u64) -> u64 {
+        match self.start {
+            Some(start) => self.end.unwrap_or(frame) - start,
+            None => 0,
+        }
+    }
+
+    // Words of five characters per minute.
+    fn wpm(&self, frame: u64) -> u64 {
+        let words = self.typed.len() as u64 / 5;
+        (words * TICKS_PER_MINUTE)
+            .checked_div(self.elapsed(frame))
+            .unwrap_or(0)
+    }
+
+    pub fn draw(&self, x: usize, y: usize, frame: u64, cursor: bool, palette: &Palette) {
+        draw_row(self.target(), x, y, palette.border);
+        let target = self.target().as_bytes();
+        for col in 0..LINE_WIDTH {
+            let typed = self.typed.as_str().unwrap().as_bytes().get(col);
+            let color = match typed {
+                Some(c) if target.get(col) == Some(c) => palette.text,
+                Some(_) => palette.error,
+                None if cursor && col == self.typed.len() && !self.finished() => palette.highlight,
+                None => palette.text,
+            };
+            plot(typed.map_or(' ', |c| *c as char), x + col, y + 1, color);
+        }
+        let mut line: ArrayString<LINE_WIDTH> = ArrayString::default();
+        write!(line, "ticks    {}", self.elapsed(frame)).unwrap();
+        draw_row(line.as_str().unwrap(), x, y + 3, palette.text);
+        line = ArrayString::default();
+        write!(line, "wpm      {}", self.wpm(frame)).unwrap();
+        draw_row(line.as_str().unwrap(), x, y + 4, palette.text);
+        line = ArrayString::default();
+        let correct = self.keystrokes - self.mistakes;
+        write!(line, "accuracy {}", Percent(correct, self.keystrokes)).unwrap();
+        draw_row(line.as_str().unwrap(), x, y + 5, palette.text);
+        let help = if self.finished() { HELP } else { "" };
+        draw_row(help, x, y + 9, palette.border);
+    }
+}
+
+fn draw_row(text: &str, x: usize, y: usize, color: ColorCode) {
+    let mut chars = text.chars();
+    for col in 0..LINE_WIDTH {
+        plot(chars.next().unwrap_or(' '), x + col, y, color);
+    }
+}
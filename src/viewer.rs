@@ -0,0 +1,101 @@
+use crate::screen::{plot, Color, ColorCode};
+use file_system_solution::FileSystemError;
+
+use crate::storage::{name_str, Storage};
+use crate::stream::FileReader;
+use crate::{LINE_WIDTH, MAX_FILENAME_BYTES};
+
+const VIEW_ROWS: usize = 10;
+
+// A read-only pager. Only the visible lines are ever read: each redraw
+// streams the file from the start, skips to the top line and stops once the
+// window is full.
+#[derive(Copy, Clone)]
+pub struct Viewer {
+    name: [u8; MAX_FILENAME_BYTES],
+    top: usize,
+    at_end: bool,
+    dirty: bool,
+}
+
+impl Viewer {
+    pub fn new(name: [u8; MAX_FILENAME_BYTES]) -> Self {
+        Self {
+            name,
+            top: 0,
+            at_end: false,
+            dirty: true,
+        }
+    }
+
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn scroll_up(&mut self) {
+        if self.top > 0 {
+            self.top -= 1;
+            self.dirty = true;
+        }
+    }
+
+    pub fn scroll_down(&mut self) {
+        if !self.at_end {
+            self.top += 1;
+            self.dirty = true;
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        storage: &mut Storage,
+        x: usize,
+        y: usize,
+        text: ColorCode,
+    ) -> Result<(), FileSystemError> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.dirty = false;
+        let mut reader = FileReader::open(storage, name_str(&self.name).unwrap_or(""))?;
+        let result = self.draw_lines(&mut reader, storage, x, y, text);
+        reader.close(storage)?;
+        result
+    }
+
+    fn draw_lines(
+        &mut self,
+        reader: &mut FileReader,
+        storage: &mut Storage,
+        x: usize,
+        y: usize,
+        text: ColorCode,
+    ) -> Result<(), FileSystemError> {
+        let mut more = reader.skip_lines(storage, self.top)?;
+        for row in 0..VIEW_ROWS {
+            let mut col = 0;
+            while more {
+                match reader.next_byte(storage)? {
+                    Some(b'\n') => break,
+                    Some(b) => {
+                        if col < LINE_WIDTH {
+                            plot(b as char, x + col, y + row, text);
+                        }
+                        col += 1;
+                    }
+                    None => more = false,
+                }
+            }
+            for col in col.min(LINE_WIDTH)..LINE_WIDTH {
+                plot(
+                    ' ',
+                    x + col,
+                    y + row,
+                    ColorCode::new(Color::Black, Color::Black),
+                );
+            }
+        }
+        self.at_end = !more || reader.next_byte(storage)?.is_none();
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+// Moving a selection through `len` items. The wrapping moves go round from
+// the last item to the first and back; `clamp` stops at the last. With no
+// items every position is 0, where `% len` would divide by zero.
+
+pub fn wrap(index: usize, len: usize) -> usize {
+    match len {
+        0 => 0,
+        _ => index % len,
+    }
+}
+
+pub fn offset(index: usize, by: usize, len: usize) -> usize {
+    wrap(wrap(index, len) + wrap(by, len), len)
+}
+
+pub fn next(index: usize, len: usize) -> usize {
+    offset(index, 1, len)
+}
+
+pub fn prev(index: usize, len: usize) -> usize {
+    offset(index, len.saturating_sub(1), len)
+}
+
+pub fn step(index: usize, len: usize, forward: bool) -> usize {
+    if forward {
+        next(index, len)
+    } else {
+        prev(index, len)
+    }
+}
+
+// The nearest position to `index` that is one of the items.
+pub fn clamp(index: usize, len: usize) -> usize {
+    index.min(len.saturating_sub(1))
+}
@@ -0,0 +1,2202 @@
+#![cfg(feature = "std")]
+
+use pc_keyboard::{DecodedKey, KeyCode};
+use simple_swim_template::testing::*;
+use simple_swim_template::{
+    paint_stack, send_memory_report, wrapping_nav, Events, SchedulerPolicy, SwimInterface,
+    TextScript, Theme, SAMPLE_FILES,
+};
+
+fn screen_row(row: usize) -> String {
+    fake_screen(|screen| screen.row_text(row))
+}
+
+fn screen_text(col: usize, row: usize, len: usize) -> String {
+    screen_row(row).chars().skip(col).take(len).collect()
+}
+
+// The whole screen as text, one line per row with trailing blanks cut.
+fn screen_frame() -> String {
+    (0..25)
+        .map(|row| screen_row(row).trim_end().to_string() + "\n")
+        .collect()
+}
+
+// Compares the screen against tests/golden/<name>.txt. Running the tests
+// with UPDATE_GOLDEN set rewrites the file from the screen instead, so a
+// deliberate change to the layout can be checked in with its new frames.
+fn assert_frame(name: &str) {
+    let path = std::path::Path::new(file!())
+        .with_file_name("golden")
+        .join(format!("{name}.txt"));
+    let frame = screen_frame();
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, &frame).unwrap();
+        return;
+    }
+    let golden = std::fs::read_to_string(&path).unwrap();
+    assert!(
+        frame == golden,
+        "{name} no longer matches its golden frame:\n{frame}"
+    );
+}
+
+fn run_script<const N: usize>(swim: &mut SwimInterface<N>, script: &str) {
+    swim.replay(&mut TextScript::new(script));
+}
+
+fn visible_files(storage: &mut Storage) -> Vec<String> {
+    let (num_files, files) = storage.list_directory().unwrap();
+    files[..num_files]
+        .iter()
+        .map(|name| {
+            String::from_utf8_lossy(name)
+                .trim_end_matches('\0')
+                .to_string()
+        })
+        .collect()
+}
+
+// The editor writes out every row of its document, so trailing blank rows
+// show up as newlines.
+fn editor_text(editor: &Editor) -> String {
+    let contents = editor.get_file_contents();
+    contents
+        .as_str()
+        .unwrap()
+        .trim_end_matches('\n')
+        .to_string()
+}
+
+// xorshift64, for the editor's randomized properties; a seed always gives
+// the same cases.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+
+    // Printable ASCII in lines of up to `max_line` characters.
+    fn text(&mut self, max_line: usize) -> String {
+        let mut text = String::new();
+        for _ in 0..self.below(EDITOR_ROWS + 8) {
+            for _ in 0..self.below(max_line + 1) {
+                text.push((b' ' + self.below(95) as u8) as char);
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+fn create(storage: &mut Storage, name: &str, contents: &str) {
+    let fd = storage.open_create(name).unwrap();
+    storage.write(fd, contents.as_bytes()).unwrap();
+    storage.close(fd).unwrap();
+}
+
+#[test]
+fn editor_typing_and_backspace() {
+    let mut editor = Editor::new(b"", true);
+    for c in "abc".chars() {
+        editor.push_char(c);
+    }
+    editor.newline();
+    editor.push_char('d');
+    editor.push_char('x');
+    editor.backspace_char();
+    assert_eq!(editor_text(&editor), "abc\nd");
+}
+
+#[test]
+fn editor_starts_with_file_contents() {
+    let editor = Editor::new(b"print(1)\nprint(2)", true);
+    assert_eq!(editor_text(&editor), "print(1)\nprint(2)");
+}
+
+// What the editor keeps of a file: lines no wider than it and no more of
+// them than it has rows come back as they went in, save for the blank rows
+// written after them. Anything else loads as it was first saved, since a
+// full row and one that wrapped there read the same.
+#[test]
+fn editor_contents_round_trip() {
+    let mut rng = Rng(0x5eed);
+    for _ in 0..200 {
+        let text = rng.text(EDITOR_COLUMNS - 1);
+        let editor = Editor::new(text.as_bytes(), true);
+        let kept: Vec<_> = text.lines().take(EDITOR_ROWS).collect();
+        assert_eq!(editor_text(&editor), kept.join("\n").trim_end_matches('\n'));
+
+        let text = rng.text(EDITOR_COLUMNS * 2);
+        let saved = Editor::new(text.as_bytes(), true).get_file_contents();
+        let saved = saved.as_str().unwrap();
+        let resaved = Editor::new(saved.as_bytes(), true).get_file_contents();
+        assert_eq!(resaved.as_str().unwrap(), saved);
+    }
+}
+
+#[test]
+fn editing_keeps_the_cursor_in_the_document() {
+    let mut rng = Rng(0xed17);
+    for _ in 0..50 {
+        let text = rng.text(EDITOR_COLUMNS);
+        let mut editor = Editor::new(text.as_bytes(), true);
+        for _ in 0..400 {
+            match rng.below(10) {
+                0..=2 => editor.push_char((b' ' + rng.below(95) as u8) as char),
+                3 => editor.backspace_char(),
+                4 => editor.delete_char(),
+                5 => editor.newline(),
+                6 => editor.move_cursor_up(),
+                7 => editor.move_cursor_down(),
+                8 => editor.move_cursor_left(),
+                _ => editor.move_cursor_right(),
+            }
+            let (row, col, _) = editor.place();
+            assert!(row < EDITOR_ROWS && col < EDITOR_COLUMNS, "{row}, {col}");
+        }
+        let saved = editor.get_file_contents();
+        let resaved = Editor::new(saved.as_str().unwrap().as_bytes(), true).get_file_contents();
+        assert_eq!(resaved.as_str().unwrap(), saved.as_str().unwrap());
+    }
+}
+
+#[test]
+fn storage_round_trip() {
+    let mut storage = Storage::default();
+    create(&mut storage, "hello", "world");
+    assert!(storage.exists("hello"));
+    assert_eq!(storage.file_size("hello").unwrap(), 5);
+    let fd = storage.open_read("hello").unwrap();
+    let mut buffer = [0; 16];
+    let num_bytes = storage.read(fd, &mut buffer).unwrap();
+    storage.close(fd).unwrap();
+    assert_eq!(&buffer[..num_bytes], b"world");
+}
+
+#[test]
+fn storage_delete_hides_and_create_restores() {
+    let mut storage = Storage::default();
+    create(&mut storage, "a", "1");
+    create(&mut storage, "b", "2");
+    storage.delete("a").unwrap();
+    assert_eq!(visible_files(&mut storage), ["b"]);
+    assert!(storage.open_read("a").is_err());
+    assert_ne!(storage.flags("a") & DELETED, 0);
+
+    create(&mut storage, "a", "3");
+    assert_eq!(storage.flags("a") & DELETED, 0);
+    assert_eq!(visible_files(&mut storage), ["a", "b"]);
+}
+
+#[test]
+fn storage_lists_starred_files_first() {
+    let mut storage = Storage::default();
+    for name in ["a", "b", "c"] {
+        create(&mut storage, name, "x");
+    }
+    storage.toggle_flag("c", STARRED).unwrap();
+    assert_eq!(visible_files(&mut storage), ["c", "a", "b"]);
+    storage.toggle_flag("c", STARRED).unwrap();
+    assert_eq!(visible_files(&mut storage), ["a", "b", "c"]);
+}
+
+#[test]
+fn listings_hide_only_the_kernels_own_dotfiles() {
+    let mut storage = Storage::default();
+    for name in [".notes", ".append", ".save1", ".saves", "a"] {
+        create(&mut storage, name, "x");
+    }
+    storage.toggle_flag("a", STARRED).unwrap();
+    assert!(storage.file_size(".meta").is_ok());
+    assert_eq!(visible_files(&mut storage), ["a", ".notes", ".saves"]);
+    storage.clear_save_scratch();
+    assert_eq!(storage.file_size(".save1").unwrap(), 0);
+    assert_eq!(storage.file_size(".saves").unwrap(), 1);
+}
+
+#[test]
+fn storage_migrates_metadata_from_older_builds_and_reads_newer_ones() {
+    let mut storage = Storage::default();
+    create(&mut storage, "a", "x");
+    let write_meta = |storage: &mut Storage, bytes: &[u8]| {
+        let fd = storage.open_create(".meta").unwrap();
+        storage.write(fd, bytes).unwrap();
+        storage.close(fd).unwrap();
+    };
+    // Bare records, as builds before the header wrote them: `a` starred.
+    let mut record = [0; 24];
+    record[0] = b'a';
+    record[10] = STARRED;
+    write_meta(&mut storage, &record);
+    storage.load_metadata();
+    assert_eq!(storage.flags("a"), STARRED);
+    let fd = storage.open_read(".meta").unwrap();
+    let mut meta = [0; 64];
+    let num_bytes = storage.read(fd, &mut meta).unwrap();
+    storage.close(fd).unwrap();
+    assert_eq!(&meta[..6], b"\0SWM\x02\x18");
+    assert_eq!(&meta[6..num_bytes], &record);
+
+    // A later version whose records have grown fields this build lacks.
+    let mut newer = vec![0, b'S', b'W', b'M', 9, 28];
+    newer.extend_from_slice(&record);
+    newer.extend_from_slice(&[0xff; 4]);
+    write_meta(&mut storage, &newer);
+    storage.load_metadata();
+    assert_eq!(storage.flags("a"), STARRED);
+    assert_eq!(storage.file_size(".meta").unwrap(), newer.len());
+}
+
+#[test]
+fn storage_append_keeps_old_contents() {
+    let mut storage = Storage::default();
+    create(&mut storage, "log", "one\n");
+    let fd = storage.open_append("log").unwrap();
+    storage.write(fd, b"two\n").unwrap();
+    storage.close(fd).unwrap();
+    assert_eq!(storage.file_size("log").unwrap(), 8);
+}
+
+#[test]
+fn storage_replaces_a_file_from_its_save_scratch() {
+    let mut storage = Storage::default();
+    create(&mut storage, "notes", "old");
+    create(&mut storage, ".save1", "new text");
+    create(&mut storage, ".save2", "left over");
+    storage.replace(".save1", "notes").unwrap();
+    assert_eq!(storage.file_size("notes").unwrap(), 8);
+    assert_eq!(storage.file_size(".save1").unwrap(), 0);
+    storage.clear_save_scratch();
+    assert_eq!(storage.file_size(".save2").unwrap(), 0);
+    assert_eq!(visible_files(&mut storage), ["notes"]);
+}
+
+#[test]
+fn scheduler_time_slices() {
+    let fair = SchedulerPolicy::Fair { latency: 12 };
+    assert_eq!(fair.time_slice(1), 12);
+    assert_eq!(fair.time_slice(3), 4);
+    let round_robin = SchedulerPolicy::RoundRobin { quantum: 5 };
+    assert_eq!(round_robin.time_slice(1), 5);
+    assert_eq!(round_robin.time_slice(4), 5);
+}
+
+#[test]
+fn shell_opens_and_closes() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "s{Tick}");
+    assert!(screen_row(0).starts_with("Shell"));
+    run_script(&mut swim, "{F6}{Tick}");
+    assert!(!screen_row(0).starts_with("Shell"));
+}
+
+#[test]
+fn shell_recalls_and_edits_commands() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "sls\n{Up}{Tick}");
+    assert_eq!(screen_text(1, 11, 6), "> ls  ");
+    run_script(&mut swim, "{Left}x{Left}y{Right}z{Tick}");
+    assert_eq!(screen_text(1, 11, 7), "> lyxzs");
+    run_script(&mut swim, "{Down}{Tick}");
+    assert_eq!(screen_text(1, 11, 6), ">     ");
+}
+
+#[test]
+fn shell_tab_completes_commands_and_files() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("apple", ""), ("apricot", ""), ("banana", "")])
+        .build();
+    run_script(&mut swim, "sde\t{Tick}");
+    assert_eq!(screen_text(1, 11, 9), "> defrag ");
+    run_script(&mut swim, "{F6}s{Tick}");
+    run_script(&mut swim, "ls a:ap\t{Tick}");
+    assert_eq!(screen_text(1, 11, 14), "> ls a:apple  ");
+    run_script(&mut swim, "\t{Tick}");
+    assert_eq!(screen_text(1, 11, 14), "> ls a:apricot");
+    run_script(&mut swim, "\t{Tick}");
+    assert_eq!(screen_text(1, 11, 14), "> ls a:apple  ");
+}
+
+#[test]
+fn shell_pages_through_files() {
+    let lines: Vec<String> = (1..=20).map(|i| format!("l{}", i)).collect();
+    let contents = lines.join("\n");
+    let mut swim = SwimInterface::builder()
+        .files(&[("big", contents.as_str())])
+        .build();
+    run_script(&mut swim, "scat big\n{Tick}");
+    assert_eq!(screen_text(1, 2, 3), "l1 ");
+    assert_eq!(screen_text(1, 10, 7), "-- more");
+    run_script(&mut swim, " {Tick}");
+    assert_eq!(screen_text(1, 9, 3), "l16");
+    assert_eq!(screen_text(1, 10, 7), "-- more");
+    run_script(&mut swim, "qtail -n 2 big\n{Tick}");
+    assert_eq!(screen_text(1, 9, 3), "l19");
+    assert_eq!(screen_text(1, 10, 3), "l20");
+    run_script(&mut swim, "head -n 3 big\n{Tick}");
+    assert_eq!(screen_text(1, 10, 3), "l3 ");
+}
+
+#[test]
+fn shell_redirects_into_and_out_of_files() {
+    let mut swim = SwimInterface::builder()
+        .files(&[
+            ("ask", "a := input(\"a\")\nb := input(\"b\")\nprint(done)"),
+            ("answers", "red\nblue\n"),
+        ])
+        .build();
+    run_script(&mut swim, "secho hi  there > greet\ncat greet > b:copy\n");
+    run_script(&mut swim, "cat b:copy\n{Tick}");
+    assert_eq!(screen_text(1, 10, 9), "hi there ");
+    run_script(&mut swim, "run ask < answers > log\n");
+    run_script(&mut swim, &"{Tick}".repeat(30));
+    run_script(&mut swim, "cat log\n{Tick}");
+    assert_eq!(screen_text(1, 8, 4), "done");
+    assert_eq!(screen_text(1, 9, 7), "got red");
+    assert_eq!(screen_text(1, 10, 8), "got blue");
+}
+
+#[test]
+fn shell_lists_renices_and_kills_processes() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "a := input(\"a\")")])
+        .build();
+    run_script(&mut swim, "srun ask\n{Tick}{Tick}{Tick}ps\n{Tick}");
+    assert_eq!(screen_text(1, 10, 15), "0  ask        w");
+    run_script(&mut swim, "renice 0 9\n{Tick}");
+    assert_eq!(screen_text(1, 10, 13), "nice must be ");
+    run_script(&mut swim, "renice 0 2\n{F2}j{Tick}");
+    assert!(screen_text(36, 3, 30).trim_end().ends_with("-- 2"));
+    run_script(&mut swim, "{F1}kill 0\nps\n{Tick}");
+    assert_eq!(screen_text(1, 10, 4), "ID f");
+}
+
+#[test]
+fn shell_reports_disk_and_process_usage() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "a := input(\"a\")")])
+        .build();
+    run_script(&mut swim, "sdf\n{Tick}");
+    assert!(screen_text(1, 9, 31).trim_end().ends_with(", 1 files"));
+    assert!(screen_text(1, 10, 31).trim_end().ends_with(", 0 files"));
+    run_script(&mut swim, "run ask\nfree\n{Tick}");
+    assert!(screen_text(1, 9, 31).starts_with("procs 1/8"));
+    assert_eq!(screen_text(1, 10, 22), "heap use: not reported");
+}
+
+#[test]
+fn window_output_reads_as_a_virtual_file() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("nums", "print(257)\nprint(4)\n")])
+        .build();
+    swim.run_in_window(1, "nums").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}scat win2.out\n{Tick}");
+    assert_eq!(screen_text(1, 8, 3), "257");
+    assert_eq!(screen_text(1, 9, 3), "4  ");
+    assert_eq!(screen_text(1, 10, 6), "[DONE]");
+    // Writes are refused, and a window with no program reads as empty.
+    run_script(&mut swim, "echo hi > win2.out\nhead win1.out\n{Tick}");
+    assert!(screen_text(1, 9, 31).starts_with("NotOpenForWrite"));
+    assert_eq!(screen_text(1, 10, 31).trim_end(), "> head win1.out");
+}
+
+#[test]
+fn stats_files_are_generated_when_read() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "a := input(\"a\")")])
+        .build();
+    run_script(&mut swim, "srun ask\ncat stats/sched\n{Tick}");
+    assert_eq!(screen_text(1, 6, 11), "policy fair");
+    assert_eq!(screen_text(1, 9, 11), "processes 1");
+    assert_eq!(screen_text(1, 10, 13), "0 ask run 0 0");
+    // Each read sees the processes as they are then.
+    run_script(
+        &mut swim,
+        "renice 0 3\ncat stats/sched\ncat stats/heap0\n{Tick}",
+    );
+    assert_eq!(screen_text(1, 4, 13), "0 ask run 1 3");
+    assert_eq!(screen_text(1, 6, 8), "file ask");
+    assert_eq!(screen_text(1, 8, 13), "heap_size 256");
+    run_script(&mut swim, "echo hi > stats/sched\n{Tick}");
+    assert!(screen_text(1, 10, 31).starts_with("NotOpenForWrite"));
+}
+
+#[test]
+fn file_manager_copies_and_moves_between_volumes() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("alpha", "print(1)"), ("beta", "hi")])
+        .build();
+    run_script(&mut swim, "m{Tick}");
+    assert_eq!(screen_text(1, 2, 2), "a:");
+    assert_eq!(screen_text(17, 2, 2), "b:");
+    run_script(&mut swim, "c{Down}m{Tick}");
+    assert_eq!(screen_text(17, 3, 6), " alpha");
+    assert_eq!(screen_text(17, 4, 5), " beta");
+    assert_eq!(screen_text(1, 4, 5), "     ");
+    assert_eq!(screen_text(1, 10, 15), "   8B   1% used");
+    // Copying back the other way would overwrite alpha, so it is refused.
+    run_script(&mut swim, "\tc{Tick}");
+    assert!(screen_row(0).starts_with("copy: a:alpha exists"));
+}
+
+#[test]
+fn listing_groups_files_into_directories() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("top", "x"), ("d1/one", "a"), ("d1/two", "b")])
+        .build();
+    run_script(&mut swim, "{Tick}");
+    assert_eq!(screen_text(1, 2, 15), " d1/        top");
+    run_script(&mut swim, "\n{Tick}");
+    assert!(screen_text(3, 1, 8).starts_with("F1 a:d1/"));
+    assert_eq!(screen_text(1, 2, 26), " ..         one        two");
+    // A new file goes in the directory the list is in.
+    run_script(&mut swim, "{F5}new\n{Tick}");
+    assert_eq!(screen_text(1, 3, 4), " new");
+    run_script(&mut swim, "\n{Tick}");
+    assert_eq!(screen_text(1, 2, 26), " d1/        top           ");
+}
+
+#[test]
+fn reset_samples_restores_only_the_samples() {
+    let mut swim = SwimInterface::builder().samples(SAMPLE_FILES).build();
+    run_script(&mut swim, "{Tick}");
+    assert_eq!(screen_text(1, 2, 15), " a1/        a2/");
+    run_script(&mut swim, "secho oops > a1/hello\necho mine > a1/mine\n");
+    run_script(
+        &mut swim,
+        "reset samples\ncat a1/hello\ncat a1/mine\n{Tick}",
+    );
+    assert_eq!(screen_text(1, 6, 18), "restored 4 samples");
+    assert_eq!(screen_text(1, 8, 22), "print(\"Hello, world!\")");
+    assert_eq!(screen_text(1, 10, 5), "mine ");
+}
+
+#[test]
+fn clear_and_reset_windows() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "{F2}j{F1}secho hi\n{Tick}");
+    assert_eq!(screen_text(1, 10, 2), "hi");
+    run_script(&mut swim, "\x0c{Tick}");
+    assert_eq!(screen_text(1, 10, 2), "  ");
+    assert!(screen_text(35, 1, 35).contains("jobs"));
+    run_script(&mut swim, "reset 2\n{Tick}");
+    assert!(!screen_text(35, 1, 35).contains("jobs"));
+    run_script(&mut swim, "reset\n{Tick}");
+    assert!(!screen_row(0).starts_with("Shell"));
+}
+
+#[test]
+fn window_count_is_a_const_generic() {
+    let mut single = SwimInterface::builder().windows::<1>().build();
+    run_script(&mut single, "{F2}{Tick}");
+    assert_eq!(screen_text(3, 1, 2), "F1");
+    assert_eq!(screen_text(38, 1, 2), "  ");
+
+    let mut swim = SwimInterface::builder().windows::<6>().build();
+    run_script(&mut swim, "{Tick}{Tick}");
+    assert_eq!(screen_text(3, 13, 2), "F3");
+    run_script(&mut swim, "{F1}{Tick}");
+    assert_eq!(screen_text(3, 1, 2), "F5");
+    assert_eq!(screen_text(38, 1, 2), "F6");
+    assert_eq!(screen_text(3, 13, 2), "  ");
+    run_script(&mut swim, "{F3}{Tick}");
+    assert_eq!(screen_text(3, 13, 2), "  ");
+    run_script(&mut swim, "{Tick}");
+    assert_eq!(screen_text(3, 13, 2), "F3");
+    run_script(&mut swim, "{F2}{F2}{Tick}");
+    assert_eq!(screen_text(38, 1, 2), "F6");
+}
+
+#[test]
+fn listings_keep_their_focus_when_files_move() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "{Right}{Right}{Tick}{F2}{Right}{Right}{Right}*{Tick}");
+    run_script(&mut swim, "{F1}v{Tick}");
+    assert!(screen_row(0).starts_with("Viewing average"));
+    run_script(&mut swim, "{F2}{Right}dy{Tick}{F1}{F6}{Tick}v{Tick}");
+    assert!(screen_row(0).starts_with("Viewing average"));
+}
+
+#[test]
+fn batch_delete_keeps_files_open_in_other_windows() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "e{Tick}{F2}dy{Tick}");
+    assert!(screen_row(0).starts_with("delete: hello is open"));
+    // hello is still there to view.
+    run_script(&mut swim, "v{Tick}");
+    assert!(screen_row(1).contains("F2 hello up/dn"));
+}
+
+#[test]
+fn config_file_is_applied_at_boot() {
+    let mut swim = SwimInterface::builder()
+        .files(&[
+            ("hello", "print(hi)"),
+            (
+                "config",
+                "# boot settings\nkeymap=dvorak\nautoexec=hello\nbogus=1\n",
+            ),
+        ])
+        .build();
+    run_script(&mut swim, "{Tick}");
+    assert!(screen_row(0).starts_with("Running hello"));
+    run_script(&mut swim, "{F2}{Tick}");
+    assert!(screen_row(0).contains("bad config line 4"));
+    run_script(&mut swim, ";{Tick}");
+    // hello has finished out of sight, and its toast covers the status bar
+    // until it times out.
+    run_script(&mut swim, &"{Tick}".repeat(54));
+    assert!(screen_row(0).starts_with("Shell"));
+}
+
+// Boot problems are written straight onto the status bar; the last one
+// found is the one left showing.
+#[test]
+fn config_problems_are_reported_on_the_status_bar() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("config", "bogus=1\nautoexec=missing\n")])
+        .build();
+    run_script(&mut swim, "{Tick}");
+    assert!(screen_row(0).contains("autoexec: FileNotFound"));
+    let mut swim = SwimInterface::builder()
+        .files(&[("config", "theme=mono\n\nbogus=1\n")])
+        .build();
+    run_script(&mut swim, "{Tick}");
+    assert!(screen_row(0).contains("bad config line 3"));
+}
+
+#[test]
+fn settings_window_changes_and_saves_options() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "o{Tick}");
+    assert!(screen_row(0).starts_with("Settings"));
+    assert_eq!(screen_text(1, 2, 20).trim_end(), "theme        classic");
+    run_script(&mut swim, "{Right}{Down}\n{Tick}");
+    assert_eq!(screen_text(1, 2, 20).trim_end(), "theme        mono");
+    assert_eq!(screen_text(1, 3, 20).trim_end(), "scheduler    rr");
+    assert_eq!(screen_text(1, 4, 20).trim_end(), "quantum      4");
+    run_script(&mut swim, "{F2}scat config\n{Tick}");
+    assert_eq!(screen_text(36, 2, 10), "theme=mono");
+    assert_eq!(screen_text(36, 3, 9), "quantum=4");
+}
+
+#[test]
+fn windows_take_their_colors_from_the_theme() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("config", "theme=amber\n")])
+        .build();
+    let color = |col, row| fake_screen(|screen| screen.color(col, row));
+    run_script(&mut swim, "{Tick}");
+    let amber = Theme::named("amber").unwrap().palette();
+    assert_eq!(color(0, 5), amber.border);
+    assert_eq!(color(35, 5), amber.border);
+    assert_eq!(color(2, 2), amber.highlight);
+    // Stepping the theme on in the settings window recolors every window.
+    run_script(&mut swim, "o{Right}{Tick}{Tick}{Tick}");
+    let ocean = Theme::named("ocean").unwrap().palette();
+    assert_eq!(color(0, 5), ocean.border);
+    assert_eq!(color(35, 5), ocean.border);
+}
+
+// A theme switch repaints two windows a tick, so the bottom row of windows
+// changes a tick after the top one.
+#[test]
+fn theme_switches_repaint_a_few_windows_a_tick() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("config", "theme=amber\n")])
+        .build();
+    let borders = || {
+        [(0, 5), (35, 5), (0, 17), (35, 17)]
+            .map(|(col, row)| fake_screen(|screen| screen.color(col, row)))
+    };
+    let amber = Theme::named("amber").unwrap().palette().border;
+    let ocean = Theme::named("ocean").unwrap().palette().border;
+    run_script(&mut swim, "o{Tick}{Tick}{Tick}{Right}{Tick}");
+    assert_eq!(borders(), [ocean, ocean, amber, amber]);
+    run_script(&mut swim, "{Tick}");
+    assert_eq!(borders(), [ocean; 4]);
+}
+
+#[test]
+fn headers_show_keys_for_the_window_state() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "{Tick}");
+    assert!(screen_text(0, 1, 35).contains("a: e:edit r:run v:view d:del"));
+    run_script(&mut swim, "s{Tick}");
+    assert!(screen_text(0, 1, 35).contains(" tab ^C F6:close"));
+}
+
+#[test]
+fn text_cursors_blink() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("config", "cursor_blink=2\n")])
+        .build();
+    let cursor = ColorCode::new(Color::Black, Color::LightCyan);
+    let at_prompt = || fake_screen(|screen| screen.color(15, 0));
+    run_script(&mut swim, "{Tick}");
+    assert_eq!(at_prompt(), cursor);
+    run_script(&mut swim, "{Tick}{Tick}");
+    assert_ne!(at_prompt(), cursor);
+    run_script(&mut swim, "{Tick}{Tick}");
+    assert_eq!(at_prompt(), cursor);
+    run_script(&mut swim, "s{Tick}");
+    assert_eq!(fake_screen(|screen| screen.color(3, 11)), cursor);
+}
+
+#[test]
+fn unfocused_windows_are_dimmed() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("config", "dim=on\n")])
+        .build();
+    let grey = ColorCode::new(Color::DarkGray, Color::Black);
+    let color = |col, row| fake_screen(|screen| screen.color(col, row));
+    run_script(&mut swim, "{Tick}");
+    assert_ne!(color(0, 5), grey);
+    assert_eq!(color(35, 5), grey);
+    run_script(&mut swim, "{F2}{Tick}");
+    assert_eq!(color(0, 5), grey);
+    assert_ne!(color(35, 5), grey);
+    // Turning it off in the settings window saves the dim key.
+    run_script(&mut swim, &format!("o{}\n", "{Down}{Tick}".repeat(5)));
+    run_script(&mut swim, "{F1}scat config\n{Tick}");
+    assert_eq!(screen_text(1, 7, 8), "dim=off ");
+    assert_ne!(color(35, 5), grey);
+}
+
+#[test]
+fn headers_skip_name_padding_and_clip() {
+    let mut swim = SwimInterface::default();
+    swim.open_in_window(0, "nums").unwrap();
+    run_script(&mut swim, "{Tick}");
+    assert_eq!(screen_text(3, 1, 24), "F1 nums ^S:save F6:close");
+    let (text, cut) = clip("a long window title", 8);
+    assert_eq!((text.as_str().unwrap(), cut), ("a long ", true));
+    let (text, cut) = clip("short", 8);
+    assert_eq!((text.as_str().unwrap(), cut), ("short", false));
+}
+
+#[test]
+fn background_output_badges_the_header() {
+    let mut swim = SwimInterface::default();
+    swim.run_in_window(1, "nums").unwrap();
+    run_script(&mut swim, "{F1}{Tick}{Tick}");
+    assert_eq!(screen_text(37, 1, 1), "\u{7}");
+    run_script(&mut swim, "{F2}{Tick}");
+    assert_ne!(screen_text(37, 1, 1), "\u{7}");
+}
+
+#[test]
+fn follow_input_focuses_the_asking_window() {
+    let mut swim = SwimInterface::builder()
+        .files(&[
+            ("ask", "x := input(\"name?\")"),
+            ("config", "follow_input=on\n"),
+        ])
+        .build();
+    swim.run_in_window(2, "ask").unwrap();
+    run_script(&mut swim, "{F1}{Tick}{Tick}{Tick}");
+    assert!(screen_row(0).starts_with("Awaiting Input"));
+    run_script(&mut swim, "bo\n{Tick}{Tick}");
+    assert!(screen_row(0).starts_with("F5 - Filename"));
+    assert!((14..24).any(|row| screen_text(1, row, 31).starts_with("got bo")));
+}
+
+#[test]
+fn followed_input_gives_focus_back_once_entered() {
+    let mut swim = SwimInterface::builder()
+        .files(&[
+            ("ask", "x := input(\"a?\")\ny := input(\"b?\")"),
+            ("config", "follow_input=on\n"),
+        ])
+        .build();
+    run_script(&mut swim, "{F2}{Tick}");
+    swim.run_in_window(2, "ask").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}{Tick}");
+    assert!(screen_row(0).starts_with("Awaiting Input"));
+    run_script(&mut swim, "1\n{Tick}");
+    assert!(screen_row(0).starts_with("F5 - Filename"));
+    // Moving focus by hand while input is followed keeps it where it went.
+    run_script(&mut swim, "{Tick}{Tick}");
+    assert!(screen_row(0).starts_with("Awaiting Input"));
+    run_script(&mut swim, "{F4}{F3}2\n{Tick}");
+    assert!(!screen_row(0).starts_with("F5 - Filename"));
+}
+
+#[test]
+fn task_panel_names_where_keys_go() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "{Tick}");
+    assert_eq!(screen_text(70, 12, 10), "keys to F1");
+    assert_eq!(screen_text(70, 13, 10), "commands  ");
+    run_script(&mut swim, "{F2}s{Tick}");
+    assert_eq!(screen_text(70, 12, 10), "keys to F2");
+    assert_eq!(screen_text(70, 13, 10), "shell     ");
+    run_script(&mut swim, "{F1}{F5}{Tick}");
+    assert_eq!(screen_text(70, 13, 10), "filename  ");
+}
+
+#[test]
+fn running_programs_show_progress() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "x := input(\"name?\")")])
+        .build();
+    swim.run_in_window(0, "ask").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}{Tick}");
+    assert!(screen_text(0, 12, 35).contains("2 ticks"));
+    run_script(&mut swim, "bo\n{Tick}{Tick}{Tick}");
+    assert!(!screen_text(0, 12, 35).contains("ticks"));
+}
+
+#[test]
+fn listings_show_the_last_run_of_the_focused_file() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("alpha", "print(1)"), ("beta", "hi")])
+        .build();
+    run_script(&mut swim, "{Tick}");
+    assert!(!screen_text(0, 12, 35).contains("ticks"));
+    run_script(&mut swim, "r{Tick}{Tick}{Tick}{Tick}{F6}{Tick}");
+    assert!(screen_text(2, 12, 20).starts_with(" ok "));
+    assert!(screen_text(2, 12, 20).contains("2 ins"));
+    run_script(&mut swim, "{Right}{Tick}");
+    assert!(!screen_text(0, 12, 35).contains("ticks"));
+}
+
+#[test]
+fn editors_split_to_show_the_last_run() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("alpha", "print(1)")])
+        .build();
+    run_script(&mut swim, "e{F5}{Tick}{Tick}{Tick}{Tick}");
+    assert_eq!(screen_text(1, 2, 8), "print(1)");
+    assert_eq!(screen_text(1, 8, 8), " output ");
+    assert_eq!(screen_text(1, 9, 1), "1");
+    assert_eq!(screen_text(1, 10, 6), "[DONE]");
+    run_script(&mut swim, "{^o}{Tick}");
+    assert_eq!(screen_text(1, 8, 8), "        ");
+}
+
+// Run and edit read all of a file, not just the part the editor shows.
+#[test]
+fn files_longer_than_the_editor_run_and_edit() {
+    let program = format!("print(42)\n{}print(7)\n", "\n".repeat(1400));
+    let mut swim = SwimInterface::builder()
+        .files(&[("long", program.as_str())])
+        .build();
+    run_script(&mut swim, "{^o}r{Tick}{Tick}{Tick}{Tick}");
+    assert_eq!(screen_text(1, 9, 2), "42");
+    assert_eq!(screen_text(1, 10, 2), "7 ");
+    assert_eq!(screen_text(1, 11, 6), "[DONE]");
+    run_script(&mut swim, "{^o}e{Tick}");
+    assert!(!screen_row(0).contains("FileTooBig"));
+    assert_eq!(screen_text(1, 2, 9), "print(42)");
+}
+
+#[test]
+fn listings_split_to_show_the_run_under_them() {
+    let names: Vec<String> = (0..20).map(|i| format!("f{:02}", i)).collect();
+    let mut files: Vec<(&str, &str)> = names.iter().map(|name| (name.as_str(), "")).collect();
+    files.insert(0, ("alpha", "print(7)"));
+    let mut swim = SwimInterface::builder().files(&files).build();
+    run_script(&mut swim, "{^o}r{Tick}{Tick}{Tick}{Tick}");
+    assert_eq!(screen_text(1, 2, 6), " alpha");
+    assert_eq!(screen_text(1, 8, 8), " output ");
+    assert_eq!(screen_text(1, 9, 1), "7");
+    assert_eq!(screen_text(1, 10, 6), "[DONE]");
+    // Seven rows of files scroll in the six above the rule.
+    run_script(&mut swim, &"{Right}{Tick}".repeat(18));
+    assert_eq!(screen_text(1, 7, 4), " f17");
+    assert_eq!(screen_text(1, 8, 8), " output ");
+    run_script(&mut swim, "{^o}{Tick}");
+    assert_eq!(screen_text(1, 8, 4), " f17");
+}
+
+#[test]
+fn editor_jumps_to_a_definition_and_back() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("calc", "total := 0\n  step := 2\nprint((total + step))")])
+        .build();
+    // Onto `step` in the last line, then to its assignment and back.
+    run_script(&mut swim, "e{Down}{Tick}{Down}{Tick}");
+    run_script(&mut swim, &"{Right}{Tick}".repeat(16));
+    run_script(&mut swim, "{Ctrl}]X{Tick}");
+    assert_eq!(screen_text(1, 3, 11), "  Xtep := 2");
+    run_script(&mut swim, "{^t}Y{Tick}");
+    assert_eq!(screen_text(1, 4, 21), "print((total + sYep))");
+    run_script(&mut swim, "{^t}{Tick}");
+    assert!(screen_row(0).contains("no jump to go back from"));
+}
+
+// Ctrl counts as held for a few ticks after it was pressed, however many
+// other keys come in the meantime.
+#[test]
+fn ctrl_wears_off_while_other_keys_arrive() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("notes", "hello")])
+        .build();
+    run_script(&mut swim, "e{Tick}{Ctrl}s{Tick}");
+    run_script(&mut swim, &"{Up}{Tick}".repeat(4));
+    run_script(&mut swim, "z{Tick}");
+    assert_eq!(screen_text(1, 2, 5), "zello");
+}
+
+#[test]
+fn editor_highlights_other_uses_of_the_identifier() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("uses", "n := 1\nprint(n)\nnn := n")])
+        .build();
+    let used = ColorCode::new(Color::LightCyan, Color::DarkGray);
+    let color = |col, row| fake_screen(|screen| screen.color(col, row));
+    run_script(&mut swim, "e{Tick}");
+    assert_eq!(color(7, 3), used);
+    assert_eq!(color(7, 4), used);
+    assert_ne!(color(1, 4), used);
+    assert_ne!(color(2, 2), used);
+    // Off an identifier, nothing is picked out.
+    run_script(&mut swim, "{Right}{Tick}{Right}{Tick}{Right}{Tick}");
+    assert_ne!(color(7, 3), used);
+}
+
+#[test]
+fn editor_shades_the_guide_column_and_shows_a_ruler() {
+    let mut swim = SwimInterface::builder()
+        .files(&[
+            ("config", "guide=15\n"),
+            ("long", "print(\"a string too long\")"),
+        ])
+        .build();
+    let guide = ColorCode::new(Color::White, Color::DarkGray);
+    let color = |col, row| fake_screen(|screen| screen.color(col, row));
+    swim.open_in_window(0, "long").unwrap();
+    run_script(&mut swim, "{Tick}");
+    assert_eq!(color(16, 2), guide);
+    assert_eq!(color(16, 3), guide);
+    assert_ne!(color(15, 2), guide);
+    run_script(&mut swim, "{^u}{Right}{Right}{Tick}");
+    assert_eq!(screen_text(1, 2, 20), "....+....1....+....2");
+    assert_eq!(color(3, 2), ColorCode::new(Color::Black, Color::LightCyan));
+    assert_eq!(color(16, 2), guide);
+    assert_eq!(screen_text(1, 3, 8), "print(\"a");
+    run_script(&mut swim, "{^u}{Tick}");
+    assert_eq!(screen_text(1, 2, 8), "print(\"a");
+}
+
+#[test]
+fn editor_renames_a_variable_and_undoes_it() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("uses", "n := 1\nprint(n)\nnn := n")])
+        .build();
+    run_script(&mut swim, "e{^n}tot{Tick}");
+    assert!(screen_row(0).starts_with("Rename n (3 uses) to: tot "));
+    run_script(&mut swim, "al\n{Tick}");
+    assert!(screen_row(0).starts_with("renamed 3 uses of n, ^Z undoes"));
+    assert_eq!(screen_text(1, 2, 10), "total := 1");
+    assert_eq!(screen_text(1, 3, 12), "print(total)");
+    assert_eq!(screen_text(1, 4, 11), "nn := total");
+    run_script(&mut swim, "{^z}{Tick}");
+    assert_eq!(screen_text(1, 2, 10), "n := 1    ");
+    assert_eq!(screen_text(1, 4, 11), "nn := n    ");
+}
+
+#[test]
+fn editors_reopen_where_they_were_left() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("notes", "one\ntwo\nthree")])
+        .build();
+    run_script(&mut swim, "e{Down}{Tick}{Down}{Tick}{Right}{Tick}{Right}{Tick}{F6}{Tick}");
+    run_script(&mut swim, "eX{Tick}");
+    assert_eq!(screen_text(1, 4, 5), "thXee");
+}
+
+#[test]
+fn programs_stop_at_the_timeout() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "x := input(\"name?\")"), ("config", "timeout=1\n")])
+        .build();
+    swim.run_in_window(0, "ask").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}");
+    assert_eq!(screen_text(1, 2, 9), "timed out");
+}
+
+// Answers go to the interpreter where it is, and the run carries on from
+// one answer to the next.
+#[test]
+fn answers_carry_on_the_same_run() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "a := input(\"a\")\nb := input(\"b\")\nprint(end)")])
+        .build();
+    swim.run_in_window(0, "ask").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}ax\u{8}c\n{Tick}{Tick}de\n");
+    run_script(&mut swim, &"{Tick}".repeat(6));
+    let output: Vec<String> = (2..12).map(|row| screen_text(1, row, 6)).collect();
+    let answers: Vec<&String> = output.iter().filter(|l| l.starts_with("got")).collect();
+    assert_eq!(answers, ["got ac", "got de"]);
+    assert!(output.contains(&"[DONE]".to_string()));
+}
+
+#[test]
+fn the_task_panel_shows_what_a_program_is_asking() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "print(Your name?)\nx := input(\"\")")])
+        .build();
+    swim.run_in_window(1, "ask").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}{Tick}");
+    assert_eq!(screen_text(70, 2, 2), "F2");
+    assert_eq!(screen_text(70, 3, 10), "wait Your ");
+    assert_eq!(screen_text(70, 1, 10), "idle      ");
+}
+
+#[test]
+fn input_prompt_stays_with_the_field() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "print(Name?)\nx := input(\"\")")])
+        .build();
+    swim.run_in_window(0, "ask").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}bo{Tick}");
+    assert_eq!(screen_text(1, 3, 8), "Name? bo");
+    run_script(&mut swim, "\x0c{Tick}");
+    assert_eq!(screen_text(1, 2, 8), "Name? bo");
+}
+
+#[test]
+fn number_prompts_reject_other_keys() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "print(Number #)\nx := input(\"\")")])
+        .build();
+    swim.run_in_window(0, "ask").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}-4x.5.-2{Tick}");
+    assert_eq!(screen_text(1, 3, 14), "Number # -4.52");
+    let error = ColorCode::new(Color::LightRed, Color::Black);
+    assert_eq!(fake_screen(|screen| screen.color(1, 3)), error);
+    run_script(&mut swim, "{Tick}{Tick}{Tick}");
+    assert_ne!(fake_screen(|screen| screen.color(1, 3)), error);
+
+    // Only the suffix asks for a number, not the wording.
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "print(Number plate?)\nx := input(\"\")")])
+        .build();
+    swim.run_in_window(0, "ask").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}ab1{Tick}");
+    assert_eq!(screen_text(1, 3, 17), "Number plate? ab1");
+}
+
+#[test]
+fn multiline_input_ends_with_ctrl_enter() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("story", "print(Story...)\nx := input(\"\")")])
+        .build();
+    swim.run_in_window(0, "story").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}ab\ncd{Tick}");
+    assert_eq!(screen_text(1, 9, 3), "ab ");
+    assert_eq!(screen_text(1, 10, 3), "cd ");
+    assert!(screen_row(0).starts_with("Awaiting Input"));
+    run_script(&mut swim, "{Ctrl}\n{Tick}{Tick}");
+    assert!(screen_row(0).starts_with("Running"));
+}
+
+#[test]
+fn output_lines_copy_and_paste() {
+    let mut swim = SwimInterface::default();
+    swim.run_in_window(0, "nums").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}{Tick}{Up}x{Up}{Tick}");
+    let selected = ColorCode::new(Color::Black, Color::LightCyan);
+    assert_eq!(fake_screen(|screen| screen.color(1, 3)), selected);
+    run_script(&mut swim, "\n{F2}s{^v}{Tick}");
+    assert_ne!(fake_screen(|screen| screen.color(1, 3)), selected);
+    assert!(screen_text(36, 11, 31).starts_with("> 257"));
+}
+
+#[test]
+fn character_map_types_into_the_previous_window() {
+    let mut swim = SwimInterface::default();
+    swim.open_in_window(0, "nums").unwrap();
+    run_script(&mut swim, "{F2}c{Down}{Right}{Down}{Right}{Down}{Right}{Down}{Tick}");
+    assert!(screen_row(0).starts_with("Characters"));
+    assert!(screen_text(36, 10, 31).contains("147 0x93 into F1"));
+    run_script(&mut swim, "\n{Tick}");
+    assert_eq!(screen_text(1, 2, 1), "\u{93}");
+    run_script(&mut swim, "{F1}{F6}{Tick}{Tick}{Tick}");
+    swim.open_in_window(0, "nums").unwrap();
+    run_script(&mut swim, "{Tick}");
+    assert_eq!(screen_text(1, 2, 1), "\u{93}");
+}
+
+#[test]
+fn calculator_prints_each_expression() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "=2*x{^h}3\n{Tick}");
+    assert!(screen_row(0).starts_with("Calculator"));
+    assert_eq!(screen_text(1, 9, 6), "2*3   ");
+    assert_eq!(screen_text(1, 10, 6), "= 2*3 ");
+    assert_eq!(screen_text(1, 11, 3), "=  ");
+}
+
+#[test]
+fn life_steps_by_key_and_runs_on_the_timer() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "l{Tick}");
+    let alive = 219u8 as char;
+    assert_eq!(screen_row(2).chars().nth(2), Some(alive));
+    assert_eq!(screen_row(3).chars().nth(1), Some(250u8 as char));
+    run_script(&mut swim, "n{Tick}");
+    assert_eq!(screen_row(3).chars().nth(1), Some(alive));
+    assert!(screen_text(1, 11, 10).starts_with("gen 1 "));
+    run_script(&mut swim, "p{Tick}{Tick}{Tick}{Tick}{Tick}");
+    assert!(screen_text(1, 11, 10).starts_with("gen 2 "));
+}
+
+#[test]
+fn typing_test_reports_speed_and_accuracy() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "t{Tick}thx{^h}e quick{Tick}{Tick} brown fox jumps over{Tick}");
+    assert_eq!(screen_text(1, 2, 30), "the quick brown fox jumps over");
+    assert_eq!(screen_text(1, 3, 30), "the quick brown fox jumps over");
+    assert_eq!(screen_text(1, 5, 12).trim_end(), "ticks    2");
+    assert_eq!(screen_text(1, 6, 14).trim_end(), "wpm      3276");
+    assert_eq!(screen_text(1, 7, 12).trim_end(), "accuracy 96%");
+    run_script(&mut swim, "\n{Tick}");
+    assert!(screen_text(1, 2, 30).starts_with("a lazy dog"));
+}
+
+#[test]
+fn splash_shows_until_a_key_or_timeout() {
+    let mut swim = SwimInterface::builder().splash().build();
+    run_script(&mut swim, "{Tick}");
+    assert!(screen_row(17).contains("press any key"));
+    run_script(&mut swim, "s{Tick}");
+    assert!(!screen_row(17).contains("press any key"));
+    assert_eq!(screen_text(0, 0, 8), "F5 - Fil");
+
+    let mut swim = SwimInterface::builder().splash().build();
+    for _ in 0..36 {
+        run_script(&mut swim, "{Tick}");
+    }
+    run_script(&mut swim, "{Tick}");
+    assert!(screen_text(0, 1, 35).contains("e:edit"));
+}
+
+#[test]
+fn locked_screen_hides_output_until_the_passcode() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("config", "passcode=open\n"), ("nums", "print(257)\n")])
+        .build();
+    run_script(&mut swim, "{^k}");
+    swim.run_in_window(1, "nums").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}");
+    assert!(screen_row(12).contains("passcode:"));
+    assert!(!screen_text(36, 2, 31).contains("257"));
+    run_script(&mut swim, "nope\n{Tick}");
+    assert!(screen_row(13).contains("wrong passcode"));
+    run_script(&mut swim, "open\n{Tick}");
+    assert!(!screen_row(12).contains("passcode:"));
+    assert!(screen_text(36, 2, 31).starts_with("257"));
+}
+
+#[test]
+fn overlays_keep_what_is_drawn_under_them() {
+    let color = ColorCode::new(Color::White, Color::Black);
+    plot_str("base  ", 0, 24, color);
+    let popup = open_overlay(0, 24, 4, 1).unwrap();
+    draw_on(popup, || plot_str("pop", 0, 24, color));
+    let top = open_overlay(2, 24, 2, 1).unwrap();
+    draw_on(top, || plot_str("##", 2, 24, color));
+    plot_str("BASE!", 0, 24, color);
+    present();
+    assert_eq!(screen_text(0, 24, 5), "po##!");
+    draw_on(popup, || plot_str("POP", 0, 24, color));
+    present();
+    assert_eq!(screen_text(0, 24, 5), "PO##!");
+    close_overlay(popup);
+    present();
+    assert_eq!(screen_text(0, 24, 5), "BA##!");
+    close_overlay(top);
+    present();
+    assert_eq!(screen_text(0, 24, 5), "BASE!");
+}
+
+#[test]
+fn azerty_keymap_moves_letters_and_types_accents() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("config", "keymap=azerty\n"), ("notes", "")])
+        .build();
+    swim.open_in_window(0, "notes").unwrap();
+    run_script(&mut swim, "q2m{Tick}");
+    assert_eq!(screen_text(1, 2, 3), "a\u{82},");
+    assert_eq!(screen_text(70, 14, 10), "kbd azerty");
+}
+
+#[test]
+fn alt_codes_and_dead_keys_compose_accents() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("config", "keymap=azerty\n"), ("notes", "")])
+        .build();
+    swim.open_in_window(0, "notes").unwrap();
+    run_script(&mut swim, "{Alt}130{Tick}[q{{e[ x{Tick}");
+    assert_eq!(screen_text(1, 2, 5), "\u{82}\u{83}\u{89}^x");
+
+    let mut swim = SwimInterface::builder()
+        .files(&[("config", "charset=ascii\n"), ("notes", "")])
+        .build();
+    swim.open_in_window(0, "notes").unwrap();
+    run_script(&mut swim, "{Alt}130{Tick}x{Tick}");
+    assert_eq!(screen_text(1, 2, 2), "x ");
+}
+
+#[test]
+fn protected_files_need_their_passphrase() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("secret", "hello")])
+        .build();
+    run_script(&mut swim, "pswordfish\n{Tick}");
+    assert_eq!(screen_text(1, 2, 7), "#secret");
+    run_script(&mut swim, "pwrong\n{Tick}");
+    assert!(screen_row(0).contains("wrong passphrase"));
+    run_script(&mut swim, "e{Tick}");
+    assert_eq!(screen_text(1, 2, 5), "hello");
+
+    let mut storage = Storage::default();
+    let fd = storage.open_create("notes").unwrap();
+    storage.write(fd, b"plain text").unwrap();
+    storage.close(fd).unwrap();
+    storage.protect("notes", "swordfish").unwrap();
+    assert!(storage.flags("notes") & PROTECTED != 0);
+    assert!(!storage.unlock("notes", "guess"));
+    assert!(storage.unlock("notes", "swordfish"));
+    let mut buffer = [0; 16];
+    let fd = storage.open_read("notes").unwrap();
+    let num_bytes = storage.read(fd, &mut buffer).unwrap();
+    assert_eq!(&buffer[..num_bytes], b"plain text");
+}
+
+#[test]
+fn finishing_out_of_sight_shows_a_toast_and_beeps() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("config", "beep=on\n"), ("hi", "print(1)")])
+        .build();
+    swim.run_in_window(1, "hi").unwrap();
+    run_script(&mut swim, "{F1}{Tick}{Tick}{Tick}");
+    assert!(screen_row(0).starts_with("window 2: hi finished, "));
+    assert_eq!(speaker_tone(), Some(880));
+    run_script(&mut swim, &"{Tick}".repeat(60));
+    assert!(screen_row(0).starts_with("F5 - Filename"));
+    assert_eq!(speaker_tone(), None);
+}
+
+#[test]
+fn saves_that_would_fill_the_disk_are_warned_or_refused() {
+    let big = "x".repeat(16384);
+    let filler = "x".repeat(14592);
+    // Forty rows that the editor writes back as exactly four blocks, the
+    // first one empty so typing there grows the file.
+    let notes = format!(
+        "\n{}{}",
+        format!("{}\n", "x".repeat(25)).repeat(36),
+        format!("{}\n", "x".repeat(28)).repeat(3)
+    );
+    let mut swim = SwimInterface::builder()
+        .files(&[
+            ("a", &big),
+            ("b", &big),
+            ("c", &big),
+            ("d", &filler),
+            ("notes", &notes),
+        ])
+        .build();
+    swim.open_in_window(0, "notes").unwrap();
+    run_script(&mut swim, "{^s}{Tick}");
+    assert!(screen_row(0).starts_with("notes: disk 100% full"));
+    run_script(&mut swim, &"{Tick}".repeat(60));
+    run_script(&mut swim, "ab{^s}{Tick}");
+    assert!(screen_row(0).starts_with("notes: not saved: needs 5 blocks, 4 free"));
+}
+
+#[test]
+fn read_only_boot_refuses_changes() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("notes", "hello")])
+        .splash()
+        .build();
+    run_script(&mut swim, "r{Tick}");
+    assert_eq!(screen_text(70, 15, 9), "READ ONLY");
+    swim.open_in_window(0, "notes").unwrap();
+    run_script(&mut swim, "x{^s}{Tick}");
+    assert!(screen_row(0).starts_with("read-only mode: can't save"));
+
+    let mut swim = SwimInterface::builder()
+        .files(&[("config", "read_only=on\n"), ("notes", "hello")])
+        .build();
+    run_script(&mut swim, "s{Tick}echo hi > out\n{Tick}");
+    assert!((2..12).any(|row| screen_text(1, row, 31).starts_with("echo: read-only mode")));
+}
+
+#[test]
+fn wrapping_navigation_handles_empty_collections() {
+    assert_eq!(wrapping_nav::next(2, 3), 0);
+    assert_eq!(wrapping_nav::prev(0, 3), 2);
+    assert_eq!(wrapping_nav::step(1, 3, false), 0);
+    assert_eq!(wrapping_nav::offset(3, 7, 4), 2);
+    assert_eq!(wrapping_nav::wrap(5, 0), 0);
+    assert_eq!(wrapping_nav::next(0, 0), 0);
+    assert_eq!(wrapping_nav::prev(0, 0), 0);
+    assert_eq!(wrapping_nav::clamp(5, 3), 2);
+    assert_eq!(wrapping_nav::clamp(5, 0), 0);
+    // The second volume starts empty.
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "\t{Right}{Tick}{Left}{Tick}");
+    assert!(screen_text(3, 1, 5).starts_with("F1 b:"));
+}
+
+#[test]
+fn formatting_helpers() {
+    let compact = |n| Compact(n).to_string();
+    assert_eq!(compact(999), "999");
+    assert_eq!(compact(1234), "1.2k");
+    assert_eq!(compact(45_678), "45k");
+    assert_eq!(compact(3_400_000), "3.4M");
+    assert_eq!(Percent(1, 3).to_string(), "33%");
+    assert_eq!(Percent(5, 0).to_string(), "0%");
+    let bar = Bar {
+        part: 3,
+        whole: 4,
+        width: 8,
+    };
+    assert_eq!(bar.to_string(), "######..");
+    assert_eq!(right(Compact(1234), 6).as_str().unwrap(), "  1.2k");
+    assert_eq!(left("toolong", 4).as_str().unwrap(), "tool");
+}
+
+#[test]
+fn block_map_opens_from_listing() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "b{Tick}");
+    assert!(screen_row(0).starts_with("Block map (est.)"));
+}
+
+#[test]
+fn viewer_opens_from_listing() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "v{Tick}");
+    assert!(screen_row(0).starts_with("Viewing"));
+}
+
+#[test]
+fn switching_windows_moves_focus() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "{F2}s{Tick}");
+    assert!(screen_row(0).starts_with("Shell"));
+    run_script(&mut swim, "{F1}{Tick}");
+    assert!(!screen_row(0).starts_with("Shell"));
+}
+
+#[test]
+fn frame_metrics_count_plotted_cells() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "{Tick}{Tick}");
+    let metrics = swim.frame_metrics();
+    assert_eq!(metrics.frames, 2);
+    assert!(metrics.last.cells_plotted > 0);
+    assert_eq!(metrics.last.draw_cycles, 0);
+}
+
+#[test]
+fn unfocused_header_shows_its_own_file() {
+    let mut swim = SwimInterface::default();
+    swim.open_in_window(1, "nums").unwrap();
+    run_script(&mut swim, "{F1}{Tick}");
+    let header: Vec<char> = screen_row(1).chars().collect();
+    let left: String = header[..35].iter().collect();
+    let right: String = header[35..70].iter().collect();
+    assert!(!left.contains("nums"));
+    assert!(right.contains("nums"));
+}
+
+#[test]
+fn program_output_is_drawn_from_the_buffer() {
+    let mut swim = SwimInterface::default();
+    swim.run_in_window(0, "nums").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}{Tick}{Tick}");
+    let interior = |row: usize| screen_row(row).chars().skip(1).take(10).collect::<String>();
+    assert_eq!(interior(2), "1         ");
+    assert_eq!(interior(3), "257       ");
+    assert_eq!(interior(4), "[DONE]    ");
+}
+
+#[test]
+fn drain_delivers_queued_keys_before_ticks() {
+    let events = Events::new();
+    let mut swim = SwimInterface::default();
+    assert!(!swim.drain(&events));
+    assert!(events.is_empty());
+    events.push_key(DecodedKey::Unicode('s'));
+    assert!(!events.is_empty());
+    events.tick();
+    assert!(swim.drain(&events));
+    assert!(events.is_empty());
+    assert!(screen_row(0).starts_with("Shell"));
+    assert_eq!(events.dropped_keys(), 0);
+}
+
+#[test]
+fn held_arrows_stop_repeating_when_released() {
+    let events = Events::new();
+    let mut swim = SwimInterface::builder()
+        .files(&[("a", "x"), ("b", "x"), ("c", "x"), ("d", "x"), ("e", "x")])
+        .build();
+    run_script(&mut swim, "{Tick}");
+    // The second press is the keyboard's typematic repeat; the one after the
+    // release is a fresh press, and moves on once.
+    events.push_key(DecodedKey::RawKey(KeyCode::ArrowRight));
+    events.push_key(DecodedKey::RawKey(KeyCode::ArrowRight));
+    events.release_key(KeyCode::ArrowRight);
+    events.push_key(DecodedKey::RawKey(KeyCode::ArrowRight));
+    events.release_key(KeyCode::ArrowRight);
+    for _ in 0..5 {
+        events.tick();
+        swim.drain(&events);
+    }
+    run_script(&mut swim, "v{Tick}");
+    assert!(screen_row(0).starts_with("Viewing c"));
+}
+
+#[test]
+fn serial_terminal_shows_received_bytes_and_sends_keys() {
+    let events = Events::new();
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "u{Tick}");
+    serial_arrive(b"hello\r\nwor");
+    events.poll_serial();
+    events.tick();
+    assert!(swim.drain(&events));
+    assert_eq!(screen_text(1, 10, 6), "hello ");
+    assert_eq!(screen_text(1, 11, 4), "wor ");
+    run_script(&mut swim, "ls\n{Tick}");
+    assert_eq!(serial_sent(), b"ls\n");
+}
+
+#[test]
+fn screenshots_save_the_screen_and_send_over_serial() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("nums", "print(257)\n")])
+        .build();
+    swim.run_in_window(1, "nums").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}");
+    let screen: Vec<String> = (0..25).map(screen_row).collect();
+    run_script(&mut swim, "{^p}{Tick}");
+    run_script(&mut swim, "ssend shot1\n{Tick}");
+    // One byte per cell, as the screen's code page has them.
+    let sent: String = serial_sent().into_iter().map(char::from).collect();
+    assert_eq!(sent.lines().collect::<Vec<_>>(), screen);
+    assert!(sent.lines().nth(2).unwrap().contains("257"));
+    run_script(&mut swim, "send shot1.col\n{Tick}");
+    let colors = String::from_utf8(serial_sent()).unwrap();
+    assert_eq!(colors.lines().count(), 25);
+    assert!(colors.lines().all(|line| line.len() == 160));
+    // The status bar, light cyan on black.
+    assert!(colors.starts_with("0b0b"));
+}
+
+#[test]
+fn recorder_keeps_the_last_frames_for_playback() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("config", "record_every=1\n"), ("notes", "")])
+        .build();
+    run_script(&mut swim, "{^r}{Tick}");
+    swim.open_in_window(1, "notes").unwrap();
+    run_script(&mut swim, "{F2}");
+    for c in "abcdefghijkl".chars() {
+        run_script(&mut swim, &format!("{}{{Tick}}", c));
+    }
+    run_script(&mut swim, "{^r}{Tick}");
+    assert!(screen_row(0).starts_with("recorded 8 frames to reel"));
+    run_script(&mut swim, "{F1}{Right}{Tick}{Right}{Tick}f{Tick}");
+    assert!(screen_row(0).ends_with(" frame 1/8 "));
+    // The oldest frames were dropped; the first kept shows what was typed
+    // by then.
+    assert_eq!(screen_text(36, 2, 6), "abcde ");
+    run_script(&mut swim, "{Right}{Tick}");
+    assert!(screen_row(0).ends_with(" frame 2/8 "));
+    assert_eq!(screen_text(36, 2, 7), "abcdef ");
+    run_script(&mut swim, "q{Tick}");
+    assert_eq!(screen_text(36, 2, 13), "abcdefghijkl ");
+}
+
+#[test]
+fn mirror_copies_program_output_to_serial() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("config", "mirror=on\n"), ("nums", "print(257)\nprint(4)\n")])
+        .build();
+    serial_sent();
+    swim.run_in_window(1, "nums").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}");
+    assert_eq!(serial_sent(), b"2: 257\n2: 4\n");
+}
+
+#[test]
+fn idle_until_a_program_runs() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "{Tick}");
+    assert!(swim.is_idle());
+    assert!(screen_row(0).ends_with("idle"));
+    swim.run_in_window(0, "nums").unwrap();
+    assert!(!swim.is_idle());
+}
+
+#[test]
+fn each_window_shows_its_own_process() {
+    let mut swim = SwimInterface::default();
+    swim.run_in_window(0, "nums").unwrap();
+    swim.run_in_window(1, "hello").unwrap();
+    run_script(&mut swim, &"{Tick}".repeat(30));
+    let row: Vec<char> = screen_row(2).chars().collect();
+    let left: String = row[1..11].iter().collect();
+    let right: String = row[36..49].iter().collect();
+    assert_eq!(left, "1         ");
+    assert_eq!(right, "\"Hello, world");
+}
+
+#[test]
+fn jobs_window_lists_and_kills_processes() {
+    let mut swim = SwimInterface::default();
+    swim.run_in_window(1, "average").unwrap();
+    run_script(&mut swim, "j{Tick}");
+    assert!(screen_row(0).starts_with("Jobs"));
+    assert_eq!(screen_text(1, 3, 9), "0 average");
+    // Paused, it runs no more ticks, but the wall clock goes on.
+    run_script(&mut swim, "p{Tick}{Tick}{Tick}{Tick}");
+    assert!(screen_text(1, 10, 31).starts_with("cpu 1 wall 5  20%"));
+    run_script(&mut swim, "k{Tick}");
+    assert_eq!(screen_text(1, 3, 9), "         ");
+    assert!(swim.is_idle());
+}
+
+// Wall time counts the frames from start to finish, both included, and the
+// share is of the ticks_per_frame ticks each of them could have run.
+#[test]
+fn jobs_window_keeps_the_times_a_run_finished_with() {
+    let mut swim = SwimInterface::builder()
+        .files(&[
+            ("two", "print(1)\nprint(2)"),
+            ("config", "ticks_per_frame=2\n"),
+        ])
+        .build();
+    swim.run_in_window(1, "two").unwrap();
+    run_script(&mut swim, "j{Tick}{Tick}");
+    assert_eq!(screen_text(1, 3, 14), "0 two        d");
+    assert_eq!(screen_text(1, 10, 17), "cpu 2 wall 1 100%");
+    run_script(&mut swim, &"{Tick}".repeat(5));
+    assert_eq!(screen_text(1, 10, 17), "cpu 2 wall 1 100%");
+}
+
+#[test]
+fn bright_windows_and_blinking_alerts() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "x := input(\"name?\")")])
+        .build();
+    let color = |col, row| fake_screen(|screen| screen.color(col, row));
+    run_script(&mut swim, "{Tick}");
+    assert_eq!(color(0, 1), ColorCode::new(Color::Green, Color::Black));
+    run_script(&mut swim, "{^b}{Tick}");
+    assert_eq!(color(0, 1), ColorCode::new(Color::LightGreen, Color::Black));
+    run_script(&mut swim, "{^b}{Tick}");
+    assert_eq!(color(0, 1), ColorCode::new(Color::Green, Color::Black));
+
+    swim.run_in_window(1, "ask").unwrap();
+    run_script(&mut swim, "{F1}{Tick}{Tick}{Tick}");
+    let blinking = ColorCode::new(Color::LightRed, Color::DarkGray);
+    assert_eq!(color(38, 1), blinking);
+    run_script(&mut swim, "{F2}bo\n{Tick}{Tick}{Tick}{Tick}");
+    assert_ne!(color(38, 1), blinking);
+}
+
+#[test]
+fn high_contrast_and_reduced_motion_hold_still_in_white_on_black() {
+    let mut swim = SwimInterface::builder()
+        .files(&[
+            ("config", "theme=contrast\nmotion=reduced\ncursor_blink=2\n"),
+            ("ask", "x := input(\"name?\")"),
+        ])
+        .build();
+    let color = |col, row| fake_screen(|screen| screen.color(col, row));
+    let cursor = ColorCode::new(Color::Black, Color::LightGray);
+    run_script(&mut swim, "{Tick}");
+    assert_eq!(color(0, 1), ColorCode::new(Color::White, Color::Black));
+    assert_eq!(color(15, 0), cursor);
+    run_script(&mut swim, "{Tick}{Tick}");
+    assert_eq!(color(15, 0), cursor);
+
+    swim.run_in_window(1, "ask").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}{Tick}");
+    assert_eq!(screen_text(37, 1, 1), "!");
+    assert_eq!(color(38, 1), ColorCode::new(Color::White, Color::Black));
+    run_script(&mut swim, "o{Tick}");
+    run_script(&mut swim, &"{Down}{Tick}".repeat(9));
+    assert_eq!(screen_text(1, 10, 20).trim_end(), "motion       reduced");
+}
+
+#[test]
+fn stats_report_the_stack_high_water_mark_once_painted() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "sstats\n{Tick}");
+    assert!(screen_text(1, 10, 31).starts_with("interp "));
+    paint_stack();
+    use_stack(12_345);
+    run_script(&mut swim, "stats\n{Tick}");
+    assert_eq!(screen_text(1, 10, 31).trim_end(), "stack     12k / 131k");
+    use_stack(200_000);
+    run_script(&mut swim, "stats\n{Tick}");
+    assert_eq!(
+        screen_text(1, 10, 31).trim_end(),
+        "stack   over 131k, guard hit"
+    );
+}
+
+#[test]
+fn memory_budget_goes_to_the_shell_and_serial() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "smem\n{Tick}");
+    assert!(screen_text(1, 8, 31).starts_with("volume     2 x "));
+    assert!(screen_text(1, 9, 31).starts_with("open files 1 x "));
+    assert!(screen_text(1, 10, 31).starts_with("overlays   1 x "));
+    send_memory_report::<4>();
+    let sent = String::from_utf8(serial_sent()).unwrap();
+    assert!(sent.starts_with("memory budget\ninterface  1 x "));
+    assert_eq!(sent.lines().count(), 11);
+}
+
+#[test]
+fn task_panel_shows_ticks_run_per_second() {
+    let events = Events::new();
+    let mut swim = SwimInterface::builder()
+        .files(&[("config", "pit_divisor=11931\n")])
+        .build();
+    assert_eq!(timer_divisor(), 11931);
+    run_script(&mut swim, "{Tick}");
+    assert_eq!(screen_text(70, 11, 10), "    -- tps");
+    for _ in 0..100 {
+        events.tick();
+        swim.drain(&events);
+    }
+    run_script(&mut swim, "{Tick}");
+    assert_eq!(screen_text(70, 11, 10), "   100 tps");
+    for _ in 0..100 {
+        events.tick();
+    }
+    swim.drain(&events);
+    run_script(&mut swim, "{Tick}");
+    assert_eq!(screen_text(70, 11, 10), "     4 tps");
+}
+
+#[test]
+fn listings_launch_programs_into_other_windows() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "x := input(\"name?\")")])
+        .build();
+    run_script(&mut swim, "R{Tick}");
+    assert!(screen_row(0).starts_with("Run in window? (1-4)"));
+    run_script(&mut swim, "2{Tick}{Tick}");
+    assert!(screen_row(0).starts_with("F5 - Filename"));
+    assert!(screen_text(35, 1, 35).contains("ask"));
+    // A window whose program is still going is left alone.
+    run_script(&mut swim, "R2{Tick}");
+    assert!(screen_row(0).contains("window 2 is busy"));
+}
+
+#[test]
+fn clone_run_fills_every_free_window() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "x := input(\"name?\")")])
+        .build();
+    run_script(&mut swim, "{F4}e{Tick}{F1}C{Tick}{Tick}");
+    assert!(screen_row(0).contains("ask running in 2 windows"));
+    assert!(screen_text(35, 1, 35).contains("ask"));
+    assert!(screen_text(0, 13, 35).contains("ask"));
+    // The editor and the launcher keep what they had, so only two run.
+    run_script(&mut swim, "j{Tick}");
+    assert_eq!(screen_text(1, 3, 5), "0 ask");
+    assert_eq!(screen_text(1, 4, 5), "1 ask");
+    assert_eq!(screen_text(1, 5, 5), "     ");
+}
+
+#[test]
+fn jobs_pause_and_kill_a_clone_run_group_together() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "x := input(\"name?\")")])
+        .build();
+    swim.run_in_window(3, "ask").unwrap();
+    run_script(&mut swim, "{Tick}Cj{Down}P{Tick}");
+    assert_eq!(screen_text(1, 2, 31), "# file       stat   vrun w  n g");
+    assert!(!screen_text(1, 3, 31).ends_with(" 1"));
+    assert!(screen_text(1, 4, 31).ends_with(" 1"));
+    assert!(screen_text(1, 5, 31).ends_with(" 1"));
+    assert!(screen_text(1, 4, 31).contains("stop"));
+    assert!(screen_text(1, 5, 31).contains("stop"));
+    run_script(&mut swim, "K{Tick}");
+    assert!(screen_text(1, 3, 5).contains("ask"));
+    assert_eq!(screen_text(1, 4, 5), "     ");
+}
+
+#[test]
+fn stats_report_how_long_keys_wait_to_be_drawn() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "s{Tick}st");
+    advance(300);
+    run_script(&mut swim, "ats\n");
+    advance(100);
+    // The report comes out before the keys that typed it are drawn.
+    run_script(&mut swim, "{Tick}stats\n{Tick}");
+    assert_eq!(
+        screen_text(1, 9, 31).trim_end(),
+        "key p50   100 / p95 400 cyc"
+    );
+}
+
+#[test]
+fn stale_descriptors_are_reported_and_closed_to_make_room() {
+    let mut storage = Storage::default();
+    for i in 0..20 {
+        create(&mut storage, &format!("f{}", i), "data");
+    }
+    storage.set_opener(Some(2));
+    let leaked = (0..20)
+        .take_while(|i| storage.open_read(&format!("f{}", i)).is_ok())
+        .count();
+    assert!(leaked < 20);
+    assert_eq!(storage.find_leak(|fd| fd == 0), Some((1, Some(2))));
+    // Each leak is only reported the first time it is found.
+    assert_eq!(storage.find_leak(|_| false), Some((0, Some(2))));
+    assert_eq!(storage.find_leak(|_| false), None);
+    let fd = storage.open_read("f19").unwrap();
+    storage.close(fd).unwrap();
+}
+
+#[test]
+fn page_keys_scroll_back_through_program_output() {
+    let program: String = (1..=25).map(|n| format!("print({})\n", n)).collect();
+    let mut swim = SwimInterface::builder()
+        .files(&[("long", program.as_str())])
+        .build();
+    swim.run_in_window(0, "long").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}{Tick}{Tick}");
+    assert_eq!(screen_text(1, 11, 6), "[DONE]");
+    run_script(&mut swim, "{PgUp}{Tick}");
+    assert_eq!(screen_text(1, 11, 3), "15 ");
+    run_script(&mut swim, "{PgUp}{Tick}");
+    assert_eq!(screen_text(1, 2, 3), "1  ");
+    run_script(&mut swim, "{PgDn}{Tick}{PgDn}{Tick}");
+    assert_eq!(screen_text(1, 11, 6), "[DONE]");
+
+    // The line asking for input stays on the bottom row while the output
+    // above it scrolls.
+    let program = format!("{}x := input(\"name?\")\n", program);
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", program.as_str())])
+        .build();
+    swim.run_in_window(0, "ask").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}{Tick}{PgUp}{Tick}");
+    assert_eq!(screen_text(1, 10, 3), "15 ");
+    assert_eq!(screen_text(1, 11, 3), "24 ");
+}
+
+#[test]
+fn task_panel_pauses_and_kills_the_picked_window() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "x := input(\"name?\")")])
+        .build();
+    swim.run_in_window(1, "ask").unwrap();
+    swim.run_in_window(2, "ask").unwrap();
+    // The first to run keeps its time slice while it waits for input.
+    run_script(&mut swim, &"{Tick}".repeat(30));
+    assert_eq!(screen_text(70, 1, 10), "idle      ");
+    assert_eq!(screen_text(70, 3, 10), "wait   50%");
+    run_script(&mut swim, "{F7}{F8}{F8}{Tick}");
+    assert_eq!(screen_text(70, 13, 10), "tasks     ");
+    run_script(&mut swim, "k{Tick}");
+    assert_eq!(screen_text(70, 5, 10), "idle      ");
+    assert_eq!(screen_text(70, 3, 10), "wait  100%");
+    run_script(&mut swim, "\u{1b}{Tick}");
+    assert_eq!(screen_text(70, 13, 10), "commands  ");
+}
+
+#[test]
+fn resetting_a_window_closes_what_it_left_open() {
+    let mut storage = Storage::default();
+    for i in 0..20 {
+        create(&mut storage, &format!("f{}", i), "data");
+    }
+    storage.set_opener(Some(1));
+    let held = storage.open_read("f0").unwrap();
+    let opened = (1..20)
+        .take_while(|i| storage.open_read(&format!("f{}", i)).is_ok())
+        .count();
+    assert!(opened < 19);
+    assert!(storage.open_read("f19").is_err());
+    storage.set_opener(Some(2));
+    assert_eq!(storage.close_window(2, |_| false), 0);
+    assert_eq!(storage.close_window(1, |fd| fd == held), opened);
+    let fd = storage.open_read("f19").unwrap();
+    storage.close(fd).unwrap();
+    storage.close(held).unwrap();
+}
+
+#[test]
+fn listing_previews_the_focused_files_first_line() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("hello", "\n  \nprint(hi)\n"), ("notes", "\tshopping")])
+        .build();
+    run_script(&mut swim, "{Tick}");
+    assert_eq!(screen_text(1, 11, 12), "print(hi)   ");
+    run_script(&mut swim, "{Right}{Tick}");
+    assert_eq!(screen_text(1, 11, 12), " shopping   ");
+}
+
+#[test]
+fn typing_after_slash_jumps_to_a_matching_file() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("alpha", "a"), ("beta", "b"), ("bravo", "c"), ("carrot", "d")])
+        .build();
+    run_script(&mut swim, "/B{Tick}");
+    assert!(screen_row(0).starts_with("Jump to: B "));
+    assert_eq!(screen_text(1, 11, 2), "b ");
+    run_script(&mut swim, "rx{Tick}");
+    assert!(screen_row(0).starts_with("Jump to: Br "));
+    assert_eq!(screen_text(1, 11, 2), "c ");
+    run_script(&mut swim, &"{Tick}".repeat(20));
+    assert_eq!(screen_text(70, 13, 10), "commands  ");
+    run_script(&mut swim, "/c\n{Tick}");
+    assert_eq!(screen_text(1, 11, 2), "d ");
+    assert_eq!(screen_text(70, 13, 10), "commands  ");
+}
+
+#[test]
+fn typing_a_files_number_and_enter_picks_it() {
+    let files: Vec<(String, String)> = (0..12)
+        .map(|i| (format!("file{:02}", i), format!("contents {}", i)))
+        .collect();
+    let files: Vec<(&str, &str)> = files
+        .iter()
+        .map(|(n, c)| (n.as_str(), c.as_str()))
+        .collect();
+    let mut swim = SwimInterface::builder().files(&files).build();
+    run_script(&mut swim, "1{Tick}");
+    assert!(screen_row(0).starts_with("File number: 1 "));
+    assert_eq!(screen_text(1, 2, 11), " 1 file00  ");
+    // 13 is past the last file, so the 3 is dropped.
+    run_script(&mut swim, "32\n{Tick}");
+    assert_eq!(screen_text(1, 11, 11), "contents 11");
+    assert_eq!(screen_text(1, 2, 11), " file00    ");
+    assert_eq!(screen_text(70, 13, 10), "commands  ");
+}
+
+#[test]
+fn collapsed_windows_keep_their_header_and_program() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "x := input(\"name?\")")])
+        .build();
+    swim.run_in_window(0, "ask").unwrap();
+    run_script(&mut swim, "{^w}{Tick}{Tick}{Tick}");
+    assert!(screen_text(3, 1, 6).starts_with("F1 ask"));
+    for row in 2..13 {
+        assert_eq!(screen_text(0, row, 35).trim(), "");
+    }
+    assert_eq!(screen_text(70, 1, 10), "wait  100%");
+    run_script(&mut swim, "{^w}{Tick}");
+    assert_eq!(screen_row(12).chars().next(), Some(200u8 as char));
+}
+
+// Each window's outline sits on its cell of the grid: double lines for
+// the focused one, single for the rest. A collapsed window keeps only the
+// ends of its header row, and the window under it stays where it was.
+#[test]
+fn window_outlines_follow_their_rects() {
+    let mut swim = SwimInterface::default();
+    let at = |col, row| screen_row(row).chars().nth(col).unwrap() as u32;
+    let corners = |x, y| [at(x, y), at(x + 34, y), at(x, y + 11), at(x + 34, y + 11)];
+    run_script(&mut swim, "{Tick}{Tick}{Tick}");
+    assert_eq!(corners(0, 1), [201, 187, 200, 188]);
+    for (x, y) in [(35, 1), (0, 13), (35, 13)] {
+        assert_eq!(corners(x, y), [218, 191, 192, 217]);
+    }
+    run_script(&mut swim, "{F2}{^w}{Tick}{Tick}");
+    assert_eq!([at(35, 1), at(69, 1)], [201, 187]);
+    for row in 2..13 {
+        assert_eq!(screen_text(35, row, 35).trim(), "");
+    }
+    assert_eq!(corners(35, 13), [218, 191, 192, 217]);
+}
+
+#[test]
+fn clipped_plots_stay_inside_their_rect() {
+    let color = ColorCode::new(Color::White, Color::Black);
+    plot_str("........", 0, 5, color);
+    let rect = Rect {
+        x: 2,
+        y: 5,
+        width: 4,
+        height: 1,
+    };
+    plot_str_clipped("abcdefgh", 1, 5, color, rect);
+    plot_str_clipped("zz", 2, 6, color, rect);
+    present();
+    assert_eq!(screen_text(0, 5, 8), "..bcde..");
+    assert_ne!(screen_text(2, 6, 2), "zz");
+}
+
+#[test]
+fn frames_reach_the_screen_only_where_they_changed() {
+    let color = ColorCode::new(Color::White, Color::Black);
+    plot_str("steady", 0, 24, color);
+    present();
+    let written = cells_presented();
+    plot_str("      ", 0, 24, color);
+    plot_str("steady", 0, 24, color);
+    present();
+    assert_eq!(cells_presented(), written);
+    plot_str("st", 0, 24, color);
+    plot_str("ready", 0, 24, color);
+    assert_eq!(screen_text(0, 24, 6), "steady");
+    present();
+    assert_eq!(cells_presented(), written + 5);
+    assert_eq!(screen_text(0, 24, 6), "readyy");
+}
+
+#[test]
+fn golden_empty_boot_screen() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "{Tick}");
+    assert_frame("boot");
+}
+
+#[test]
+fn golden_listing_with_a_selection() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("hello", "print(1)\n"), ("notes", "todo\n"), ("sum", "")])
+        .build();
+    run_script(&mut swim, "{Right}{Tick}");
+    assert_frame("listing");
+}
+
+#[test]
+fn golden_editor_with_its_cursor() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("notes", "first\nsecond\n")])
+        .build();
+    swim.open_in_window(0, "notes").unwrap();
+    run_script(&mut swim, "{Down}new {Tick}");
+    assert_frame("editor");
+    let cursor = ColorCode::new(Color::Black, Color::LightCyan);
+    assert_eq!(fake_screen(|screen| screen.color(5, 3)), cursor);
+}
+
+#[test]
+fn golden_program_waiting_for_input() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "print(hi)\nx := input(\"name?\")")])
+        .build();
+    swim.run_in_window(0, "ask").unwrap();
+    run_script(&mut swim, &"{Tick}".repeat(30));
+    assert_frame("waiting");
+}
+
+#[test]
+fn a_recorded_session_replays_the_same() {
+    let files = [("notes", "todo\n"), ("zz", "")];
+    let mut recorded = files.to_vec();
+    recorded.push(("config", "session=record\n"));
+    let mut swim = SwimInterface::builder().files(&recorded).build();
+    run_script(
+        &mut swim,
+        "{Tick}{Tick}{Right}{Tick}{Tick}e{Tick}ab{Enter}{^s}{Tick}{Tick}",
+    );
+    let window: Vec<_> = (1..13).map(|row| screen_text(0, row, 35)).collect();
+
+    swim.open_in_window(1, "session").unwrap();
+    run_script(&mut swim, "{Tick}");
+    let log: Vec<_> = (2..8)
+        .map(|row| screen_text(36, row, 31).trim_end().to_string())
+        .collect();
+    assert_eq!(
+        log,
+        [
+            "{Tick 2}",
+            "{Right}",
+            "{Tick 2}",
+            "e",
+            "{Tick}",
+            "ab{Enter}{^s}"
+        ]
+    );
+
+    let log = log.join("\n") + "\n";
+    let mut replayed = files.to_vec();
+    replayed.extend([("config", "session=replay\n"), ("session", log.as_str())]);
+    let mut swim = SwimInterface::builder().files(&replayed).build();
+    run_script(&mut swim, "x{Tick}{Tick}{Tick}{Tick}{Tick}{Tick}{Tick}");
+    for (row, text) in (1..13).zip(window) {
+        assert_eq!(screen_text(0, row, 35), text);
+    }
+    assert!(screen_row(0).starts_with("replay finished"));
+}
+
+#[test]
+fn a_crash_writes_a_crashdump() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "x := input(\"name?\")"), ("config", "timeout=1\n")])
+        .build();
+    run_script(&mut swim, "{F2}{Tick}");
+    swim.run_in_window(1, "ask").unwrap();
+    run_script(&mut swim, "{Tick}{Tick}");
+    swim.open_in_window(2, "crashdump").unwrap();
+    run_script(&mut swim, "{Tick}");
+    // The viewer wraps at its width, so rows are joined before matching.
+    let dump: String = (14..24)
+        .map(|row| screen_text(1, row, 33).trim_end().to_string())
+        .collect();
+    assert!(dump.starts_with("crash: pid 0 in F2 a:ask after1 ticks"));
+    assert!(dump.contains("F2 Running a:ask"));
+    assert!(dump.contains("pid 0 done ticks 1"));
+}
+
+#[test]
+fn a_crashdump_on_the_disk_opens_at_boot() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("crashdump", "crash: pid 0 in F2 a:ask\n")])
+        .build();
+    run_script(&mut swim, "{Tick}{Tick}");
+    assert!(screen_row(0).starts_with("crashdump from the last run"));
+    assert!(screen_text(36, 14, 31).starts_with("crash: pid 0 in F2 a:ask"));
+}
+
+#[test]
+fn a_watched_run_starts_again_when_its_file_is_saved() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("show", "print(1)")])
+        .build();
+    swim.run_in_window(1, "show").unwrap();
+    run_script(&mut swim, "{F2}{^e}{Tick}{Tick}");
+    assert!(screen_row(0).starts_with("show: runs again on each save"));
+    assert!(screen_text(36, 1, 33).contains("(watch)"));
+    assert_eq!(screen_text(36, 2, 1), "1");
+    swim.open_in_window(0, "show").unwrap();
+    run_script(&mut swim, "{F1}print(2){Enter}{^s}{Tick}{Tick}{Tick}");
+    assert_eq!(screen_text(36, 2, 1), "2");
+    assert_eq!(screen_text(36, 3, 6), "[DONE]");
+    assert!(screen_text(36, 1, 33).contains("(watch)"));
+
+    run_script(&mut swim, "{F2}{^e}{F1}print(3){^s}{Tick}{Tick}{Tick}");
+    assert_eq!(screen_text(36, 3, 6), "[DONE]");
+    assert!(!screen_text(36, 1, 33).contains("(watch)"));
+}
+
+#[test]
+fn paging_holds_a_program_until_a_key() {
+    let count: String = (1..=20).map(|i| format!("print({})\n", i)).collect();
+    let mut swim = SwimInterface::builder()
+        .files(&[("config", "more=on\n"), ("count", &count)])
+        .build();
+    swim.run_in_window(0, "count").unwrap();
+    run_script(&mut swim, &"{Tick}".repeat(20));
+    assert_eq!(screen_text(1, 11, 8), "--More--");
+    assert_eq!(screen_text(1, 10, 2), "20");
+    assert!(!(2..12).any(|row| screen_text(1, row, 6) == "[DONE]"));
+    run_script(&mut swim, " {Tick}{Tick}");
+    assert_eq!(screen_text(1, 11, 6), "[DONE]");
+}
+
+#[test]
+fn programs_asking_for_chars_print_along_one_line() {
+    let mut swim = SwimInterface::builder()
+        .files(&[
+            (
+                "dots",
+                "#chars\nprint(.)\nprint(.)\nprint(.)\nprint()\nprint(done)",
+            ),
+            ("lines", "print(.)\nprint(.)"),
+        ])
+        .build();
+    swim.run_in_window(0, "dots").unwrap();
+    swim.run_in_window(1, "lines").unwrap();
+    run_script(&mut swim, &"{Tick}".repeat(40));
+    assert_eq!(screen_text(1, 2, 4), "... ");
+    assert_eq!(screen_text(1, 3, 5), "done ");
+    assert_eq!(screen_text(1, 4, 6), "[DONE]");
+    assert_eq!(screen_text(36, 2, 2), ". ");
+    assert_eq!(screen_text(36, 3, 2), ". ");
+}
+
+#[test]
+fn a_carriage_return_rewrites_the_last_line() {
+    let mut swim = SwimInterface::builder()
+        .files(&[
+            (
+                "count",
+                "print(first)\nprint(1%)\nprint(\r50%)\nprint(\r100%)",
+            ),
+            ("dots", "#chars\nprint(..)\nprint(\rdone)"),
+        ])
+        .build();
+    swim.run_in_window(0, "count").unwrap();
+    swim.run_in_window(1, "dots").unwrap();
+    run_script(&mut swim, &"{Tick}".repeat(40));
+    assert_eq!(screen_text(1, 2, 6), "first ");
+    assert_eq!(screen_text(1, 3, 5), "100% ");
+    assert_eq!(screen_text(1, 4, 6), "[DONE]");
+    assert_eq!(screen_text(36, 2, 5), "done ");
+    assert_eq!(screen_text(36, 3, 6), "[DONE]");
+}
+
+#[test]
+fn muting_keeps_unfocused_windows_from_drawing_output() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("one", "print(focused)"), ("two", "print(background)")])
+        .build();
+    run_script(&mut swim, "{^q}");
+    swim.run_in_window(0, "one").unwrap();
+    swim.run_in_window(1, "two").unwrap();
+    run_script(&mut swim, &"{Tick}".repeat(40));
+    assert_eq!(screen_text(1, 2, 7), "focused");
+    assert_eq!(screen_text(36, 2, 10), "          ");
+    assert_eq!(screen_text(70, 16, 5), "muted");
+    run_script(&mut swim, "{^q}{Tick}");
+    assert_eq!(screen_text(36, 2, 10), "background");
+    assert_eq!(screen_text(36, 3, 6), "[DONE]");
+    assert_eq!(screen_text(70, 16, 5), "     ");
+}
+
+#[test]
+fn zoom_spaces_the_focused_window_over_two_rows_a_line() {
+    let mut swim = SwimInterface::builder()
+        .files(&[
+            ("config", "zoom=on\n"),
+            ("abc", "print(a)\nprint(b)\nprint(c)"),
+        ])
+        .build();
+    swim.run_in_window(0, "abc").unwrap();
+    run_script(&mut swim, &"{Tick}".repeat(5));
+    assert_eq!(screen_text(1, 2, 2), "a ");
+    assert_eq!(screen_text(1, 3, 2), "  ");
+    assert_eq!(screen_text(1, 4, 2), "b ");
+    assert_eq!(screen_text(1, 6, 2), "c ");
+    assert_eq!(screen_text(1, 8, 6), "[DONE]");
+    run_script(&mut swim, "{F2}{Tick}");
+    assert_eq!(screen_text(1, 3, 2), "b ");
+    assert_eq!(screen_text(1, 5, 6), "[DONE]");
+}
+
+#[test]
+fn the_tour_moves_on_as_each_step_is_done() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "?{Tick}");
+    assert!(screen_row(0).starts_with("Guided tour"));
+    assert_eq!(screen_text(1, 2, 12), "Step 1 of 5 ");
+    run_script(&mut swim, "{F2}{Tick}");
+    assert_eq!(screen_text(1, 2, 12), "Step 2 of 5 ");
+    run_script(&mut swim, "{Right}{Tick}");
+    assert_eq!(screen_text(1, 2, 12), "Step 3 of 5 ");
+    run_script(&mut swim, "r{Tick}");
+    assert_eq!(screen_text(1, 2, 12), "Step 3 of 5 ");
+    run_script(&mut swim, "{F6}e{Tick}");
+    assert_eq!(screen_text(1, 2, 12), "Step 4 of 5 ");
+    run_script(&mut swim, "{F6}r{Tick}");
+    assert_eq!(screen_text(1, 2, 12), "Step 5 of 5 ");
+    assert_eq!(screen_text(1, 4, 16), "That's the tour.");
+}
+
+#[test]
+fn keys_writes_a_reference_of_every_binding() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "skeys\n{Tick}");
+    run_script(&mut swim, "head -n 1 keys.txt\n{Tick}");
+    run_script(&mut swim, "tail -n 1 keys.txt\n{Tick}");
+    let shell: Vec<String> = (2..12).map(|row| screen_text(1, row, 31)).collect();
+    assert!(shell.iter().any(|row| row.starts_with("wrote a:keys.txt")));
+    assert!(shell
+        .iter()
+        .any(|row| row.starts_with("Keys, read with the us keymap")));
+    assert!(shell
+        .iter()
+        .any(|row| row.starts_with("Ctrl+C     drop the line or st")));
+}
+
+#[test]
+fn shutdown_stops_everything_and_powers_off() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "a := input(\"a\")")])
+        .build();
+    swim.run_in_window(0, "ask").unwrap();
+    run_script(&mut swim, &"{Tick}".repeat(5));
+    run_script(&mut swim, "{F2}s{Tick}shutdown\n{Tick}");
+    assert!(powered_off());
+    assert_eq!(screen_text(21, 10, 19), "SWIM has shut down.");
+    assert_eq!(
+        screen_text(21, 12, 36),
+        "0 saves finished, 1 programs stopped"
+    );
+    let farewell = screen_frame();
+    run_script(&mut swim, "{F1}r{Tick}{Tick}");
+    assert_eq!(screen_frame(), farewell);
+}
+
+#[test]
+fn a_warm_reboot_keeps_the_files_and_a_cold_one_resets() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("ask", "a := input(\"a\")")])
+        .build();
+    swim.run_in_window(0, "ask").unwrap();
+    run_script(&mut swim, &"{Tick}".repeat(5));
+    assert_eq!(screen_text(70, 1, 4), "wait");
+    run_script(&mut swim, "{F2}secho kept > note\n{Tick}reboot warm\n{Tick}");
+    assert!(!was_reset());
+    assert_eq!(screen_text(70, 1, 4), "idle");
+    run_script(&mut swim, "{F2}scat note\n{Tick}");
+    let shell: Vec<String> = (2..12).map(|row| screen_text(36, row, 31)).collect();
+    assert!(shell.iter().any(|row| row.starts_with("kept")));
+
+    run_script(&mut swim, "reboot\n{Tick}");
+    assert!(was_reset());
+    assert_eq!(screen_text(21, 10, 19), "SWIM is restarting.");
+}
+
+#[test]
+fn a_warm_reboot_stays_read_only() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("notes", "hello")])
+        .splash()
+        .build();
+    run_script(&mut swim, "r{Tick}s{Tick}reboot warm\n{Tick}");
+    assert_eq!(screen_text(70, 15, 9), "READ ONLY");
+    run_script(&mut swim, "s{Tick}echo hi > out\n{Tick}");
+    assert!((2..12).any(|row| screen_text(1, row, 31).starts_with("echo: read-only mode")));
+}
+
+#[test]
+fn the_self_test_reports_each_subsystem_before_the_ui() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("hello", "print(hello)")])
+        .self_test()
+        .build();
+    run_script(&mut swim, "{Tick}");
+    assert_eq!(screen_text(21, 11, 16), "filesystem  PASS");
+    assert_eq!(screen_text(21, 12, 16), "interpreter PASS");
+    assert_eq!(screen_text(21, 13, 16), "renderer    PASS");
+    run_script(&mut swim, " {Tick}");
+    assert!(screen_row(2).contains("hello"));
+    assert!(!screen_frame().contains("selftest"));
+}
+
+#[test]
+fn defrag_lists_the_files_it_rewrote() {
+    let mut swim = SwimInterface::builder()
+        .files(&[("one", "1"), ("two", "2")])
+        .build();
+    run_script(&mut swim, "sdefrag\n{Tick}");
+    let shell: Vec<String> = (2..12).map(|row| screen_text(1, row, 31)).collect();
+    assert!(shell.iter().any(|row| row.starts_with("rewrote one")));
+    assert!(shell.iter().any(|row| row.starts_with("rewrote two")));
+    assert!(shell
+        .iter()
+        .any(|row| row.starts_with("2 files rewritten")));
+}
@@ -0,0 +1,27 @@
+#![cfg(all(feature = "std", not(feature = "shell"), not(feature = "apps")))]
+
+// A build with the optional subsystems left out, as `just test-minimal`
+// runs it: the listing keys that would open them do nothing.
+
+use simple_swim_template::testing::*;
+use simple_swim_template::{SwimInterface, TextScript};
+
+fn screen_row(row: usize) -> String {
+    fake_screen(|screen| screen.row_text(row))
+}
+
+fn run_script(swim: &mut SwimInterface, script: &str) {
+    swim.replay(&mut TextScript::new(script));
+}
+
+#[test]
+fn keys_for_left_out_subsystems_do_nothing() {
+    let mut swim = SwimInterface::default();
+    run_script(&mut swim, "{Tick}");
+    let header = screen_row(1);
+    assert!(header.contains("a: e:edit r:run"));
+    for key in ["s", "c", "=", "l", "t", "u"] {
+        run_script(&mut swim, &format!("{key}{{Tick}}"));
+        assert_eq!(screen_row(1), header, "{key} changed the window");
+    }
+}
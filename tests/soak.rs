@@ -0,0 +1,141 @@
+#![cfg(feature = "soak")]
+
+use std::collections::VecDeque;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use pc_keyboard::{DecodedKey, KeyCode};
+use simple_swim_template::testing::*;
+use simple_swim_template::{SwimInterface, SAMPLE_FILES};
+
+// How many keys and ticks each seed feeds in; SOAK_EVENTS overrides it and
+// SOAK_SEED runs just the one seed, to replay a failure.
+const EVENTS: usize = 5000;
+const SEEDS: [u64; 4] = [1, 2, 3, 4];
+// Runs that once failed, as a seed and how many events it took.
+const REGRESSIONS: [(u64, usize); 1] = [
+    // A screenshot's close failed with DiskFull and left its descriptor open.
+    (16, 20000),
+];
+// How many of the last events a failure reports.
+const HISTORY: usize = 24;
+
+const RAW_KEYS: [KeyCode; 17] = [
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::Delete,
+];
+
+const FILES: [(&str, &str); 3] = [
+    ("ask", "a := input(\"a\")\nprint(a)"),
+    ("count", "n := 0\nwhile n < 50 {\nn := n + 1\nprint(n)\n}"),
+    ("notes", "first\nsecond\n"),
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Event {
+    Tick,
+    Key(DecodedKey),
+}
+
+// xorshift64, so a seed always gives the same run without a dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn event(&mut self) -> Event {
+        let key = match self.below(20) {
+            0..=5 => return Event::Tick,
+            6..=10 => DecodedKey::Unicode((b' ' + self.below(95) as u8) as char),
+            11..=12 => DecodedKey::Unicode(['\n', '\u{8}', '\u{1b}', '\t'][self.below(4)]),
+            13..=14 => DecodedKey::Unicode((1 + self.below(26) as u8) as char),
+            _ => DecodedKey::RawKey(RAW_KEYS[self.below(RAW_KEYS.len())]),
+        };
+        Event::Key(key)
+    }
+}
+
+fn screen() -> String {
+    (0..25)
+        .map(|row| {
+            fake_screen(|screen| screen.row_text(row))
+                .trim_end()
+                .to_string()
+                + "\n"
+        })
+        .collect()
+}
+
+fn soak(seed: u64, events: usize) {
+    let files: Vec<_> = SAMPLE_FILES.iter().chain(FILES.iter()).copied().collect();
+    let mut swim = SwimInterface::builder().files(&files).build();
+    let mut invariants = Invariants::default();
+    let mut rng = Rng(seed);
+    let mut history = VecDeque::new();
+    for i in 0..events {
+        let event = rng.event();
+        if history.len() == HISTORY {
+            history.pop_front();
+        }
+        history.push_back(event);
+        let step = catch_unwind(AssertUnwindSafe(|| {
+            match event {
+                Event::Tick => swim.tick(),
+                Event::Key(key) => swim.key(key),
+            }
+            invariants.check(&mut swim)
+        }));
+        let failure = match step {
+            Ok(Ok(())) => continue,
+            Ok(Err(broken)) => broken,
+            Err(_) => "panicked".to_string(),
+        };
+        panic!(
+            "seed {seed}, event {i}: {failure}\nlast events: {history:?}\n{}",
+            screen()
+        );
+    }
+    assert_eq!(invariants.checks, events);
+}
+
+#[test]
+fn random_keys_keep_the_invariants() {
+    let events = std::env::var("SOAK_EVENTS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(EVENTS);
+    match std::env::var("SOAK_SEED").ok().and_then(|s| s.parse().ok()) {
+        Some(seed) => soak(seed, events),
+        None => SEEDS.iter().for_each(|&seed| soak(seed, events)),
+    }
+}
+
+#[test]
+fn past_failures_stay_fixed() {
+    REGRESSIONS
+        .iter()
+        .for_each(|&(seed, events)| soak(seed, events));
+}